@@ -23,11 +23,65 @@ pub struct Viewport<T: Default> {
     pub size: Extent2<T>,
     /// the viewport's focal length; higher means more zoomed in
     pub focal_len: T,
+    /// distance along the rays to the plane of perfect focus, if depth of field is in use
+    #[serde(default)]
+    pub focus_dist: Option<T>,
 }
 
+/// A single image to render; owns its viewport, so renders can outlive the scene file they were
+/// loaded from
+#[derive(Clone, Copy)]
 pub struct Render<T: Default> {
     pub width: usize,
     pub view: Viewport<T>,
+    pub foveation: Option<Foveation<T>>,
+    pub normal_smoothing: Option<NormalSmoothing<T>>,
+}
+
+/// Smooths the normal pass before it's used for shading, to calm the sparkle of estimators whose
+/// finite-difference normals are noisy; see `img::smooth_normals`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct NormalSmoothing<T> {
+    /// how many pixels out to average normals over
+    pub radius: usize,
+    /// how different in depth, as a fraction of a pixel's own depth, a neighbor can be and still
+    /// count as the same surface; typically around 0.01
+    pub depth_tolerance: T,
+}
+
+/// Spends rendering effort unevenly across the image: full quality within `radius` of a
+/// screen-space `center` (in the same coordinates as `Viewport::ray`), falling off linearly over
+/// `falloff` to `min_quality` in the periphery.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Foveation<T> {
+    pub center: Vec2<T>,
+    pub radius: T,
+    pub falloff: T,
+    /// fraction of full quality used at the edges of the image
+    pub min_quality: T,
+}
+
+impl<T> Foveation<T>
+where
+    T: Float + Sum,
+{
+    /// fraction of full quality to render `location` at, from 1 down to `min_quality`
+    pub fn quality(&self, location: Vec2<T>) -> T {
+        let dist = (location - self.center).magnitude();
+        let t = ((dist - self.radius) / self.falloff)
+            .max(T::zero())
+            .min(T::one());
+        T::one() - t * (T::one() - self.min_quality)
+    }
+
+    /// `full` (a sample or step count) scaled by the quality at `location`; always at least 1
+    pub fn scale(&self, location: Vec2<T>, full: usize) -> usize {
+        (T::from(full).unwrap() * self.quality(location))
+            .round()
+            .to_usize()
+            .unwrap_or(full)
+            .max(1)
+    }
 }
 
 impl<T> Viewport<T>
@@ -73,6 +127,47 @@ where
 
         (self.cam.origin + ray_on_viewport, ray_rot)
     }
+
+    /// This viewport moved `angle` radians around the vertical line through `center`, still facing
+    /// the same way relative to it; for turntables, which circle the geometry at a fixed height
+    pub fn orbit(&self, center: Vec3<T>, angle: T) -> Self
+    where
+        T: Float + Sum,
+    {
+        let (sin, cos) = angle.sin_cos();
+        // counterclockwise around y, seen from above
+        let turn = |v: Vec3<T>| Vec3::new(v.x * cos + v.z * sin, v.y, v.z * cos - v.x * sin);
+        Viewport {
+            cam: Ray::new(
+                center + turn(self.cam.origin - center),
+                turn(self.cam.direction),
+            ),
+            right: turn(self.right),
+            ..*self
+        }
+    }
+
+    /// The inverse of `ray`: the location on the viewport whose ray passes through `point`, or
+    /// `None` if `point` isn't in front of the viewport. Locations outside 0 to 1 are off screen.
+    pub fn project(&self, point: Vec3<T>) -> Option<Vec2<T>>
+    where
+        T: Float + Sum,
+    {
+        // every ray passes through this point behind the viewport
+        let eye = self.cam.origin - self.cam.direction * self.focal_len;
+        let to_point = point - eye;
+        let depth = to_point.dot(self.cam.direction);
+        if depth < self.focal_len {
+            return None;
+        }
+        let ray_on_viewport =
+            to_point * (self.focal_len / depth) - self.cam.direction * self.focal_len;
+        let half = T::from(0.5).unwrap();
+        Some(Vec2::new(
+            ray_on_viewport.dot(self.right) / self.size.w + half,
+            ray_on_viewport.dot(self.right.cross(self.cam.direction)) / self.size.h + half,
+        ))
+    }
 }
 
 impl<T: Default> Render<T> {
@@ -94,3 +189,38 @@ impl<T: Default> Render<T> {
         (T::from(self.width).unwrap() / self.aspect()).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use vek::Vec2;
+
+    use super::Foveation;
+
+    fn foveation() -> Foveation<f64> {
+        Foveation {
+            center: Vec2::new(0.5, 0.5),
+            radius: 0.1,
+            falloff: 0.2,
+            min_quality: 0.25,
+        }
+    }
+
+    #[test]
+    fn foveation_quality_test() {
+        let foveation = foveation();
+        // full quality within the radius, the minimum past the falloff, and linear between
+        assert_eq!(foveation.quality(Vec2::new(0.5, 0.5)), 1.0);
+        assert_eq!(foveation.quality(Vec2::new(0.55, 0.5)), 1.0);
+        assert_eq!(foveation.quality(Vec2::new(1.0, 1.0)), 0.25);
+        assert!((foveation.quality(Vec2::new(0.7, 0.5)) - 0.625).abs() < 1e-9);
+    }
+
+    #[test]
+    fn foveation_scale_test() {
+        let foveation = foveation();
+        assert_eq!(foveation.scale(Vec2::new(0.5, 0.5), 8), 8);
+        assert_eq!(foveation.scale(Vec2::new(1.0, 1.0), 8), 2);
+        // never down to nothing
+        assert_eq!(foveation.scale(Vec2::new(1.0, 1.0), 1), 1);
+    }
+}
@@ -1,20 +1,251 @@
 use std::iter::Sum;
+use std::sync::Arc;
 
 use num::Float;
-use vek::{Quaternion, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+use vek::{Extent2, Quaternion, Vec2, Vec3, Vec4};
+
+use crate::expr::{self, Expr};
+use crate::noise;
+use crate::quaternion;
 
 pub trait Estimator<T>: Sized
 where
     T: Float + Sum,
 {
     fn estimate(&self, pos: Vec3<T>) -> T;
+
+    /// The estimate at `pos` along with how close the point's orbit came to `trap`, for
+    /// estimators which iterate an orbit; others give `None`.
+    fn estimate_trapped(&self, pos: Vec3<T>, _trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        (self.estimate(pos), None)
+    }
+}
+
+/// A shape which the orbits of an iterated fractal are measured against; the closest an orbit
+/// comes to the trap varies smoothly over the surface, which makes it useful for coloring
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "trap", rename_all = "lowercase")]
+pub enum OrbitTrap<T> {
+    Point {
+        point: Vec3<T>,
+    },
+    /// the plane of points `p` where `p · normal = offset`
+    Plane {
+        normal: Vec3<T>,
+        offset: T,
+    },
+    /// the line through the origin along `direction`
+    Axis {
+        direction: Vec3<T>,
+    },
+}
+
+impl<T> OrbitTrap<T>
+where
+    T: Float + Sum,
+{
+    /// distance from `z` to the trap
+    pub fn distance(&self, z: Vec3<T>) -> T {
+        match self {
+            OrbitTrap::Point { point } => (z - *point).magnitude(),
+            OrbitTrap::Plane { normal, offset } => (z.dot(normal.normalized()) - *offset).abs(),
+            OrbitTrap::Axis { direction } => {
+                let direction = direction.normalized();
+                (z - direction * z.dot(direction)).magnitude()
+            }
+        }
+    }
+}
+
+/// the closest the orbit has come to `trap` so far, after visiting `z`
+fn update_trap<T>(closest: Option<T>, trap: Option<&OrbitTrap<T>>, z: Vec3<T>) -> Option<T>
+where
+    T: Float + Sum,
+{
+    trap.map(|trap| {
+        let dist = trap.distance(z);
+        closest.map_or(dist, |c| c.min(dist))
+    })
 }
 
+#[derive(Clone)]
 pub enum GeometryEstimator<T>
 where
     T: Float + Sum,
 {
     Julia(Julia<T>),
+    Mandelbox(Mandelbox<T>),
+    Sphere(Sphere<T>),
+    Cuboid(Cuboid<T>),
+    Torus(Torus<T>),
+    Plane(Plane<T>),
+    Tpms(Tpms<T>),
+    Capsule(Capsule<T>),
+    Cylinder(Cylinder<T>),
+    Heightfield(Heightfield<T>),
+    SdfGrid(SdfGrid<T>),
+    Sierpinski(Sierpinski<T>),
+    Kleinian(Kleinian<T>),
+    Ifs(Ifs<T>),
+    Apollonian(Apollonian<T>),
+    QuaternionMandelbrot(QuaternionMandelbrot<T>),
+    Hybrid(Hybrid<T>),
+    Formula(Formula<T>),
+    Union(Union<T>),
+    SmoothUnion(SmoothUnion<T>),
+    Morph(Morph<T>),
+    Intersection(Intersection<T>),
+    Difference(Difference<T>),
+    Twist(Twist<T>),
+    Bend(Bend<T>),
+    Taper(Taper<T>),
+    Elongate(Elongate<T>),
+    Mirror(Mirror<T>),
+    Symmetry(Symmetry<T>),
+    Repeat(Repeat<T>),
+    Onion(Onion<T>),
+    Round(Round<T>),
+    Transformed(Transformed<T>),
+    Displace(Displace<T>),
+    Warp(Warp<T>),
+    Bounded(Bounded<T>),
+}
+
+impl<T> GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    /// the iteration count of estimators for iterated fractals; see `iterations_mut`
+    pub fn iterations(&self) -> Option<usize> {
+        match self {
+            GeometryEstimator::Julia(julia) => Some(julia.iterations),
+            GeometryEstimator::Mandelbox(mandelbox) => Some(mandelbox.iterations),
+            GeometryEstimator::Sierpinski(sierpinski) => Some(sierpinski.iterations),
+            GeometryEstimator::Kleinian(kleinian) => Some(kleinian.iterations),
+            GeometryEstimator::Ifs(ifs) => Some(ifs.iterations),
+            GeometryEstimator::Apollonian(apollonian) => Some(apollonian.iterations),
+            GeometryEstimator::QuaternionMandelbrot(mandelbrot) => Some(mandelbrot.iterations),
+            GeometryEstimator::Hybrid(hybrid) => Some(hybrid.iterations),
+            GeometryEstimator::Formula(formula) => Some(formula.iterations),
+            GeometryEstimator::Sphere(_)
+            | GeometryEstimator::Cuboid(_)
+            | GeometryEstimator::Torus(_)
+            | GeometryEstimator::Plane(_)
+            | GeometryEstimator::Tpms(_)
+            | GeometryEstimator::Capsule(_)
+            | GeometryEstimator::Cylinder(_)
+            | GeometryEstimator::Heightfield(_)
+            | GeometryEstimator::SdfGrid(_)
+            | GeometryEstimator::Union(_)
+            | GeometryEstimator::SmoothUnion(_)
+            | GeometryEstimator::Morph(_)
+            | GeometryEstimator::Intersection(_)
+            | GeometryEstimator::Difference(_) => None,
+            GeometryEstimator::Twist(twist) => twist.de.iterations(),
+            GeometryEstimator::Bend(bend) => bend.de.iterations(),
+            GeometryEstimator::Taper(taper) => taper.de.iterations(),
+            GeometryEstimator::Elongate(elongate) => elongate.de.iterations(),
+            GeometryEstimator::Mirror(mirror) => mirror.de.iterations(),
+            GeometryEstimator::Symmetry(symmetry) => symmetry.de.iterations(),
+            GeometryEstimator::Repeat(repeat) => repeat.de.iterations(),
+            GeometryEstimator::Onion(onion) => onion.de.iterations(),
+            GeometryEstimator::Round(round) => round.de.iterations(),
+            GeometryEstimator::Transformed(transformed) => transformed.de.iterations(),
+            GeometryEstimator::Displace(displace) => displace.de.iterations(),
+            GeometryEstimator::Warp(warp) => warp.de.iterations(),
+            GeometryEstimator::Bounded(bounded) => bounded.de.iterations(),
+        }
+    }
+
+    /// the iteration count of estimators for iterated fractals
+    pub fn iterations_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            GeometryEstimator::Julia(julia) => Some(&mut julia.iterations),
+            GeometryEstimator::Mandelbox(mandelbox) => Some(&mut mandelbox.iterations),
+            GeometryEstimator::Sierpinski(sierpinski) => Some(&mut sierpinski.iterations),
+            GeometryEstimator::Kleinian(kleinian) => Some(&mut kleinian.iterations),
+            GeometryEstimator::Ifs(ifs) => Some(&mut ifs.iterations),
+            GeometryEstimator::Apollonian(apollonian) => Some(&mut apollonian.iterations),
+            GeometryEstimator::QuaternionMandelbrot(mandelbrot) => Some(&mut mandelbrot.iterations),
+            GeometryEstimator::Hybrid(hybrid) => Some(&mut hybrid.iterations),
+            GeometryEstimator::Formula(formula) => Some(&mut formula.iterations),
+            GeometryEstimator::Sphere(_)
+            | GeometryEstimator::Cuboid(_)
+            | GeometryEstimator::Torus(_)
+            | GeometryEstimator::Plane(_)
+            | GeometryEstimator::Tpms(_)
+            | GeometryEstimator::Capsule(_)
+            | GeometryEstimator::Cylinder(_)
+            | GeometryEstimator::Heightfield(_)
+            | GeometryEstimator::SdfGrid(_) => None,
+            // combinations have more than one iteration count
+            GeometryEstimator::Union(_)
+            | GeometryEstimator::SmoothUnion(_)
+            | GeometryEstimator::Morph(_)
+            | GeometryEstimator::Intersection(_)
+            | GeometryEstimator::Difference(_) => None,
+            GeometryEstimator::Twist(twist) => twist.de.iterations_mut(),
+            GeometryEstimator::Bend(bend) => bend.de.iterations_mut(),
+            GeometryEstimator::Taper(taper) => taper.de.iterations_mut(),
+            GeometryEstimator::Elongate(elongate) => elongate.de.iterations_mut(),
+            GeometryEstimator::Mirror(mirror) => mirror.de.iterations_mut(),
+            GeometryEstimator::Symmetry(symmetry) => symmetry.de.iterations_mut(),
+            GeometryEstimator::Repeat(repeat) => repeat.de.iterations_mut(),
+            GeometryEstimator::Onion(onion) => onion.de.iterations_mut(),
+            GeometryEstimator::Round(round) => round.de.iterations_mut(),
+            GeometryEstimator::Transformed(transformed) => transformed.de.iterations_mut(),
+            GeometryEstimator::Displace(displace) => displace.de.iterations_mut(),
+            GeometryEstimator::Warp(warp) => warp.de.iterations_mut(),
+            GeometryEstimator::Bounded(bounded) => bounded.de.iterations_mut(),
+        }
+    }
+
+    /// Which copy of a `Repeat` the surface near `pos` belongs to, looking through transforms
+    /// and wrappers which don't move points; `None` if the shape isn't repeated.
+    pub fn instance(&self, pos: Vec3<T>) -> Option<Instance> {
+        match self {
+            GeometryEstimator::Repeat(repeat) => Some(repeat.instance(pos)),
+            GeometryEstimator::Transformed(transformed) => {
+                transformed.de.instance(transformed.local(pos))
+            }
+            GeometryEstimator::Onion(onion) => onion.de.instance(pos),
+            GeometryEstimator::Round(round) => round.de.instance(pos),
+            GeometryEstimator::Displace(displace) => displace.de.instance(pos),
+            GeometryEstimator::Bounded(bounded) => bounded.de.instance(pos),
+            _ => None,
+        }
+    }
+
+    /// The distance along `rot` from `pos` to where the ray first meets the surface, worked out
+    /// exactly for primitives with a closed form (spheres, planes, sharp-edged boxes, and those
+    /// moved by `Transformed`) so they needn't be marched; a ray starting inside meets the
+    /// surface where it leaves. `None` for estimators which have to be marched, and `Some(None)`
+    /// for a ray which misses.
+    pub fn intersect(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<Option<T>> {
+        match self {
+            GeometryEstimator::Sphere(sphere) => Some(sphere.intersect(pos, rot)),
+            GeometryEstimator::Cuboid(cuboid) if cuboid.rounding == T::zero() => {
+                Some(cuboid.intersect(pos, rot))
+            }
+            GeometryEstimator::Plane(plane) => Some(plane.intersect(pos, rot)),
+            GeometryEstimator::Transformed(transformed) => transformed.intersect(pos, rot),
+            _ => None,
+        }
+    }
+}
+
+/// The first crossing at or ahead of the start of a ray of the surface of a closed region it
+/// enters and leaves at `span`, if it crosses it at all
+fn first_crossing<T: Float>(span: Option<(T, T)>) -> Option<T> {
+    let (enter, exit) = span?;
+    if enter >= T::zero() {
+        Some(enter)
+    } else if exit >= T::zero() {
+        Some(exit)
+    } else {
+        None
+    }
 }
 
 impl<T> From<Julia<T>> for GeometryEstimator<T>
@@ -26,116 +257,4191 @@ where
     }
 }
 
-impl<T> Estimator<T> for GeometryEstimator<T>
+impl<T> From<Mandelbox<T>> for GeometryEstimator<T>
 where
     T: Float + Sum,
 {
-    fn estimate(&self, pos: Vec3<T>) -> T {
-        match self {
-            GeometryEstimator::Julia(julia) => julia.estimate(pos),
-        }
+    fn from(mandelbox: Mandelbox<T>) -> Self {
+        GeometryEstimator::Mandelbox(mandelbox)
     }
 }
 
-pub struct Geometry<T>
+impl<T> From<Sphere<T>> for GeometryEstimator<T>
 where
     T: Float + Sum,
 {
-    pub max_steps: usize,
-    /// values smaller than ε are considered part of the geometry
-    pub epsilon: T,
-    /// rays which exceed this distance are assumed to be lost
-    pub cutoff: T,
-    /// sample size for estimating normals
-    pub sample_size: T,
-    pub de: GeometryEstimator<T>,
+    fn from(sphere: Sphere<T>) -> Self {
+        GeometryEstimator::Sphere(sphere)
+    }
 }
 
-impl<T> Estimator<T> for Geometry<T>
+impl<T> From<Cuboid<T>> for GeometryEstimator<T>
 where
     T: Float + Sum,
 {
-    fn estimate(&self, pos: Vec3<T>) -> T {
-        self.de.estimate(pos)
+    fn from(cuboid: Cuboid<T>) -> Self {
+        GeometryEstimator::Cuboid(cuboid)
     }
 }
 
-impl<T> Geometry<T>
+impl<T> From<Tpms<T>> for GeometryEstimator<T>
 where
     T: Float + Sum,
 {
-    pub fn estimate(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<Vec3<T>> {
-        let mut total_dist = T::from(0).unwrap();
-        for _ in 0..self.max_steps {
-            let measure_pos = pos + rot * total_dist;
-            let dist = self.de.estimate(measure_pos);
-            total_dist = total_dist + dist;
+    fn from(tpms: Tpms<T>) -> Self {
+        GeometryEstimator::Tpms(tpms)
+    }
+}
 
-            if dist <= self.epsilon {
-                return Some(measure_pos);
-            } else if total_dist >= self.cutoff || total_dist.is_infinite() {
-                return None;
-            }
-        }
-        None
+impl<T> From<Torus<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(torus: Torus<T>) -> Self {
+        GeometryEstimator::Torus(torus)
     }
+}
 
-    pub fn normal(&self, pos: Vec3<T>) -> Vec3<T>
-    where
-        T: Float + Sum,
-    {
-        let zero = T::zero();
-        let x = Vec3::new(self.sample_size, zero, zero);
-        let y = Vec3::new(zero, self.sample_size, zero);
-        let z = Vec3::new(zero, zero, self.sample_size);
-        Vec3::new(
-            self.de.estimate(pos + x) - self.de.estimate(pos - x),
-            self.de.estimate(pos + y) - self.de.estimate(pos - y),
-            self.de.estimate(pos + z) - self.de.estimate(pos - z),
-        )
-        .normalized()
+impl<T> From<Plane<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(plane: Plane<T>) -> Self {
+        GeometryEstimator::Plane(plane)
     }
 }
 
-pub struct Julia<T: Float + Sum> {
-    c: Quaternion<T>,
-    iterations: usize,
+impl<T> From<Capsule<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(capsule: Capsule<T>) -> Self {
+        GeometryEstimator::Capsule(capsule)
+    }
 }
 
-impl<T> Julia<T>
+impl<T> From<Cylinder<T>> for GeometryEstimator<T>
 where
     T: Float + Sum,
 {
-    pub fn new(c: Quaternion<T>, iterations: usize) -> Self {
-        Self { c, iterations }
+    fn from(cylinder: Cylinder<T>) -> Self {
+        GeometryEstimator::Cylinder(cylinder)
     }
 }
 
-impl<T> Estimator<T> for Julia<T>
+impl<T> From<Heightfield<T>> for GeometryEstimator<T>
 where
     T: Float + Sum,
 {
-    fn estimate(&self, pos: Vec3<T>) -> T {
-        // keep one component fixed to view a 3d "slice" of the 4d fractal
-        let mut q = Quaternion::from(Vec4::from(pos));
-        // q', running derviative of q
-        let mut qp: Quaternion<T> = Quaternion::from(Vec4::right());
+    fn from(heightfield: Heightfield<T>) -> Self {
+        GeometryEstimator::Heightfield(heightfield)
+    }
+}
 
-        let t2 = T::from(2).unwrap();
-        let t16 = T::from(16).unwrap();
+impl<T> From<SdfGrid<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(grid: SdfGrid<T>) -> Self {
+        GeometryEstimator::SdfGrid(grid)
+    }
+}
 
-        for _ in 0..self.iterations {
-            qp = (q * qp) * t2;
-            q = q * q + self.c;
-            if q.magnitude_squared() > t16 {
-                break;
-            }
-        }
+impl<T> From<Sierpinski<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(sierpinski: Sierpinski<T>) -> Self {
+        GeometryEstimator::Sierpinski(sierpinski)
+    }
+}
 
-        //            |q| log |q|
-        // distance = ───────────
-        //               2 |q′|
-        let mag_q = q.magnitude();
-        mag_q * mag_q.ln() / (t2 * qp.magnitude())
+impl<T> From<Kleinian<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(kleinian: Kleinian<T>) -> Self {
+        GeometryEstimator::Kleinian(kleinian)
+    }
+}
+
+impl<T> From<Ifs<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(ifs: Ifs<T>) -> Self {
+        GeometryEstimator::Ifs(ifs)
+    }
+}
+
+impl<T> From<Apollonian<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(apollonian: Apollonian<T>) -> Self {
+        GeometryEstimator::Apollonian(apollonian)
+    }
+}
+
+impl<T> From<QuaternionMandelbrot<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(mandelbrot: QuaternionMandelbrot<T>) -> Self {
+        GeometryEstimator::QuaternionMandelbrot(mandelbrot)
+    }
+}
+
+impl<T> From<Hybrid<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(hybrid: Hybrid<T>) -> Self {
+        GeometryEstimator::Hybrid(hybrid)
+    }
+}
+
+impl<T> From<Formula<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(formula: Formula<T>) -> Self {
+        GeometryEstimator::Formula(formula)
+    }
+}
+
+impl<T> From<Union<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(union: Union<T>) -> Self {
+        GeometryEstimator::Union(union)
+    }
+}
+
+impl<T> From<SmoothUnion<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(union: SmoothUnion<T>) -> Self {
+        GeometryEstimator::SmoothUnion(union)
+    }
+}
+
+impl<T> From<Morph<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(morph: Morph<T>) -> Self {
+        GeometryEstimator::Morph(morph)
+    }
+}
+
+impl<T> From<Intersection<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(intersection: Intersection<T>) -> Self {
+        GeometryEstimator::Intersection(intersection)
+    }
+}
+
+impl<T> From<Difference<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(difference: Difference<T>) -> Self {
+        GeometryEstimator::Difference(difference)
+    }
+}
+
+impl<T> From<Twist<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(twist: Twist<T>) -> Self {
+        GeometryEstimator::Twist(twist)
+    }
+}
+
+impl<T> From<Bend<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(bend: Bend<T>) -> Self {
+        GeometryEstimator::Bend(bend)
+    }
+}
+
+impl<T> From<Symmetry<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(symmetry: Symmetry<T>) -> Self {
+        GeometryEstimator::Symmetry(symmetry)
+    }
+}
+
+impl<T> From<Taper<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(taper: Taper<T>) -> Self {
+        GeometryEstimator::Taper(taper)
+    }
+}
+
+impl<T> From<Elongate<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(elongate: Elongate<T>) -> Self {
+        GeometryEstimator::Elongate(elongate)
+    }
+}
+
+impl<T> From<Mirror<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(mirror: Mirror<T>) -> Self {
+        GeometryEstimator::Mirror(mirror)
+    }
+}
+
+impl<T> From<Repeat<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(repeat: Repeat<T>) -> Self {
+        GeometryEstimator::Repeat(repeat)
+    }
+}
+
+impl<T> From<Onion<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(onion: Onion<T>) -> Self {
+        GeometryEstimator::Onion(onion)
+    }
+}
+
+impl<T> From<Round<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(round: Round<T>) -> Self {
+        GeometryEstimator::Round(round)
+    }
+}
+
+impl<T> From<Transformed<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(transformed: Transformed<T>) -> Self {
+        GeometryEstimator::Transformed(transformed)
+    }
+}
+
+impl<T> From<Warp<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(warp: Warp<T>) -> Self {
+        GeometryEstimator::Warp(warp)
+    }
+}
+
+impl<T> From<Displace<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(displace: Displace<T>) -> Self {
+        GeometryEstimator::Displace(displace)
+    }
+}
+
+impl<T> From<Bounded<T>> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(bounded: Bounded<T>) -> Self {
+        GeometryEstimator::Bounded(bounded)
+    }
+}
+
+impl<T> Estimator<T> for GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        match self {
+            GeometryEstimator::Julia(julia) => julia.estimate(pos),
+            GeometryEstimator::Mandelbox(mandelbox) => mandelbox.estimate(pos),
+            GeometryEstimator::Sphere(sphere) => sphere.estimate(pos),
+            GeometryEstimator::Cuboid(cuboid) => cuboid.estimate(pos),
+            GeometryEstimator::Torus(torus) => torus.estimate(pos),
+            GeometryEstimator::Plane(plane) => plane.estimate(pos),
+            GeometryEstimator::Tpms(tpms) => tpms.estimate(pos),
+            GeometryEstimator::Capsule(capsule) => capsule.estimate(pos),
+            GeometryEstimator::Cylinder(cylinder) => cylinder.estimate(pos),
+            GeometryEstimator::Heightfield(heightfield) => heightfield.estimate(pos),
+            GeometryEstimator::SdfGrid(grid) => grid.estimate(pos),
+            GeometryEstimator::Sierpinski(sierpinski) => sierpinski.estimate(pos),
+            GeometryEstimator::Kleinian(kleinian) => kleinian.estimate(pos),
+            GeometryEstimator::Ifs(ifs) => ifs.estimate(pos),
+            GeometryEstimator::Apollonian(apollonian) => apollonian.estimate(pos),
+            GeometryEstimator::QuaternionMandelbrot(mandelbrot) => mandelbrot.estimate(pos),
+            GeometryEstimator::Hybrid(hybrid) => hybrid.estimate(pos),
+            GeometryEstimator::Formula(formula) => formula.estimate(pos),
+            GeometryEstimator::Union(union) => union.estimate(pos),
+            GeometryEstimator::SmoothUnion(union) => union.estimate(pos),
+            GeometryEstimator::Morph(morph) => morph.estimate(pos),
+            GeometryEstimator::Intersection(intersection) => intersection.estimate(pos),
+            GeometryEstimator::Difference(difference) => difference.estimate(pos),
+            GeometryEstimator::Twist(twist) => twist.estimate(pos),
+            GeometryEstimator::Bend(bend) => bend.estimate(pos),
+            GeometryEstimator::Taper(taper) => taper.estimate(pos),
+            GeometryEstimator::Elongate(elongate) => elongate.estimate(pos),
+            GeometryEstimator::Mirror(mirror) => mirror.estimate(pos),
+            GeometryEstimator::Symmetry(symmetry) => symmetry.estimate(pos),
+            GeometryEstimator::Repeat(repeat) => repeat.estimate(pos),
+            GeometryEstimator::Onion(onion) => onion.estimate(pos),
+            GeometryEstimator::Round(round) => round.estimate(pos),
+            GeometryEstimator::Transformed(transformed) => transformed.estimate(pos),
+            GeometryEstimator::Displace(displace) => displace.estimate(pos),
+            GeometryEstimator::Warp(warp) => warp.estimate(pos),
+            GeometryEstimator::Bounded(bounded) => bounded.estimate(pos),
+        }
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        match self {
+            GeometryEstimator::Julia(julia) => julia.estimate_trapped(pos, trap),
+            GeometryEstimator::Mandelbox(mandelbox) => mandelbox.estimate_trapped(pos, trap),
+            GeometryEstimator::QuaternionMandelbrot(mandelbrot) => {
+                mandelbrot.estimate_trapped(pos, trap)
+            }
+            GeometryEstimator::Transformed(transformed) => transformed.estimate_trapped(pos, trap),
+            GeometryEstimator::Bounded(bounded) => bounded.estimate_trapped(pos, trap),
+            GeometryEstimator::Elongate(elongate) => elongate.estimate_trapped(pos, trap),
+            GeometryEstimator::Repeat(repeat) => repeat.estimate_trapped(pos, trap),
+            GeometryEstimator::Onion(onion) => onion.estimate_trapped(pos, trap),
+            GeometryEstimator::Round(round) => round.estimate_trapped(pos, trap),
+            GeometryEstimator::Union(union) => union.estimate_trapped(pos, trap),
+            GeometryEstimator::SmoothUnion(union) => union.estimate_trapped(pos, trap),
+            GeometryEstimator::Morph(morph) => morph.estimate_trapped(pos, trap),
+            GeometryEstimator::Intersection(intersection) => {
+                intersection.estimate_trapped(pos, trap)
+            }
+            GeometryEstimator::Difference(difference) => difference.estimate_trapped(pos, trap),
+            GeometryEstimator::Twist(twist) => twist.estimate_trapped(pos, trap),
+            GeometryEstimator::Bend(bend) => bend.estimate_trapped(pos, trap),
+            GeometryEstimator::Taper(taper) => taper.estimate_trapped(pos, trap),
+            GeometryEstimator::Mirror(mirror) => mirror.estimate_trapped(pos, trap),
+            GeometryEstimator::Symmetry(symmetry) => symmetry.estimate_trapped(pos, trap),
+            GeometryEstimator::Displace(displace) => displace.estimate_trapped(pos, trap),
+            GeometryEstimator::Warp(warp) => warp.estimate_trapped(pos, trap),
+            // shapes which don't iterate have no orbit; nor, for now, do hybrids, formulas, and
+            // the other iterated fractals
+            _ => (self.estimate(pos), None),
+        }
+    }
+}
+
+/// Rule for scaling a fractal's detail with zoom depth, so that deep zoom animations keep a
+/// constant level of detail on screen.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ZoomQuality<T> {
+    /// iterations added for each doubling of the zoom
+    pub iterations_per_octave: T,
+    /// factor ε (and the normal sample size) is multiplied by for each doubling of the zoom
+    pub epsilon_per_octave: T,
+}
+
+/// Rule for evaluating a fractal with fewer iterations far along a ray, where its detail is
+/// smaller than a pixel anyway; see `Geometry::lod_iterations`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DistanceLod<T> {
+    /// distance along the ray within which every iteration is used
+    pub near: T,
+    /// iterations removed for each doubling of the distance beyond `near`; the size of a pixel's
+    /// footprint doubles along with the distance
+    pub iterations_per_octave: T,
+    /// the fewest iterations to use, however far away
+    pub min_iterations: usize,
+}
+
+/// How surface normals are estimated from the distance field
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalMethod {
+    /// central differences along each axis; 6 estimates
+    Central,
+    /// forward differences at the corners of a tetrahedron; 4 estimates, slightly less accurate
+    Tetrahedral,
+}
+
+/// A simple region containing all of a scene's geometry, which rays can be clipped to exactly
+/// before marching
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "lowercase")]
+pub enum Bounds<T> {
+    Sphere {
+        center: Vec3<T>,
+        radius: T,
+    },
+    /// an axis-aligned box; `size` is the full width, height, and depth
+    Box {
+        center: Vec3<T>,
+        size: Vec3<T>,
+    },
+}
+
+impl<T> Bounds<T>
+where
+    T: Float + Sum,
+{
+    /// the distances along `rot` from `pos` where the ray enters and leaves the region, which
+    /// may be negative behind `pos`; `None` if the ray misses it
+    pub fn clip(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<(T, T)> {
+        match *self {
+            Bounds::Sphere { center, radius } => {
+                let offset = pos - center;
+                let a = rot.magnitude_squared();
+                let half_b = offset.dot(rot);
+                let c = offset.magnitude_squared() - radius * radius;
+                let discriminant = half_b * half_b - a * c;
+                if discriminant < T::zero() || a == T::zero() {
+                    return None;
+                }
+                let root = discriminant.sqrt();
+                Some(((-half_b - root) / a, (-half_b + root) / a))
+            }
+            Bounds::Box { center, size } => {
+                let half = size / T::from(2).unwrap();
+                let (mut enter, mut exit) = (T::neg_infinity(), T::infinity());
+                for axis in 0..3 {
+                    let (lo, hi) = (center[axis] - half[axis], center[axis] + half[axis]);
+                    if rot[axis] == T::zero() {
+                        // parallel to this pair of faces: either always between them or never
+                        if pos[axis] < lo || pos[axis] > hi {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let (a, b) = ((lo - pos[axis]) / rot[axis], (hi - pos[axis]) / rot[axis]);
+                    enter = enter.max(a.min(b));
+                    exit = exit.min(a.max(b));
+                }
+                if enter > exit {
+                    None
+                } else {
+                    Some((enter, exit))
+                }
+            }
+        }
+    }
+}
+
+/// One step of a ray march
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarchStep<T> {
+    /// where the estimate was taken
+    pub pos: Vec3<T>,
+    /// distance along the ray to `pos`
+    pub total: T,
+    /// the distance estimate at `pos`, negated if the ray started inside the surface, so that
+    /// it's positive until the ray reaches the surface either way
+    pub dist: T,
+    /// how far the march advanced from `pos`
+    pub step: T,
+    /// whether the step was lengthened to the geometry's `min_step`
+    pub floored: bool,
+}
+
+/// How much work a ray march took and how close it came to the surface
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarchStats<T> {
+    /// distance estimates marched with
+    pub steps: usize,
+    /// the smallest distance estimate along the ray; within ε for hits
+    pub closest: T,
+    /// steps lengthened to the geometry's `min_step`
+    pub floored: usize,
+    /// whether the march gave up for running out of `max_evaluations`
+    pub out_of_evaluations: bool,
+}
+
+/// How a march ended
+enum MarchEnd<T> {
+    Hit(Vec3<T>),
+    /// the ray left the geometry's bounds, passed its cutoff, or ran out of steps
+    Miss,
+    /// the ray ran out of `max_evaluations`
+    OutOfEvaluations,
+}
+
+impl<T> MarchEnd<T> {
+    fn hit(self) -> Option<Vec3<T>> {
+        match self {
+            MarchEnd::Hit(pos) => Some(pos),
+            MarchEnd::Miss | MarchEnd::OutOfEvaluations => None,
+        }
+    }
+}
+
+/// A distance estimate which came out infinite or NaN, and where it was taken
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NonFiniteEstimate<T> {
+    pub pos: Vec3<T>,
+    pub dist: T,
+}
+
+#[derive(Clone)]
+pub struct Geometry<T>
+where
+    T: Float + Sum,
+{
+    /// most steps to march along one ray
+    pub max_steps: usize,
+    /// most distance estimates to take along one ray, counting the samples over-relaxation
+    /// throws away, which don't count as steps; `None` for no limit but `max_steps`
+    pub max_evaluations: Option<usize>,
+    /// values smaller than ε are considered part of the geometry
+    pub epsilon: T,
+    /// how much ε grows per unit of distance along the ray, so the hit threshold covers about the
+    /// same fraction of a pixel near the camera and far from it; typically about the angle one
+    /// pixel subtends, `1 / width` radians for a focal length of 1. `None` for a fixed ε
+    pub epsilon_scale: Option<T>,
+    /// rays which exceed this distance are assumed to be lost
+    pub cutoff: T,
+    /// sample size for estimating normals
+    pub sample_size: T,
+    pub normal_method: NormalMethod,
+    /// factor each step is lengthened by for over-relaxed sphere tracing, typically 1.2 to 1.6;
+    /// `None` (or 1) for plain sphere tracing
+    pub relaxation: Option<T>,
+    /// factor distance estimates are multiplied by before marching with them, typically 0.5 to
+    /// 1, for estimators which overestimate and would otherwise step through the surface;
+    /// `None` (or 1) to trust the estimator
+    pub step_scale: Option<T>,
+    /// shortest step to take, as a fraction of ε, typically 0.1 to 1; rays grazing a surface
+    /// would otherwise creep along it in thousands of ever smaller steps. `None` for no floor
+    pub min_step: Option<T>,
+    /// most secant steps to take after a hit to move it from up to ε off the surface to much
+    /// closer to it, for better normals and shadows when ε is large; `None` (or 0) to leave hits
+    /// where the march stops
+    pub refine_steps: Option<usize>,
+    /// how detail scales in zoom animations; see `Geometry::at_zoom`
+    pub zoom_quality: Option<ZoomQuality<T>>,
+    /// how detail falls off with distance along the ray; see `Geometry::lod_iterations`
+    pub lod: Option<DistanceLod<T>>,
+    /// a region the geometry lies entirely inside; rays are only marched where they cross it
+    pub bounds: Option<Bounds<T>>,
+    pub de: GeometryEstimator<T>,
+}
+
+impl<T> Estimator<T> for Geometry<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(pos)
+    }
+}
+
+impl<T> Geometry<T>
+where
+    T: Float + Sum,
+{
+    pub fn estimate(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<Vec3<T>> {
+        self.estimate_from(pos, rot, T::zero())
+    }
+
+    /// Like `estimate`, but starts marching `start` units along the ray; `start` must be short
+    /// of the first surface along the ray or it will be skipped.
+    ///
+    /// A ray which starts inside the surface, where the estimate is negative, marches on the
+    /// negated estimate to where it leaves the surface, as for a camera inside a bulb or a
+    /// refracted ray finding its exit point; interior estimates are only as good as the
+    /// estimator's, and many fractals' are rough.
+    ///
+    /// Primitives with an exact intersection (see `GeometryEstimator::intersect`) are hit
+    /// directly instead of being marched; `march_trace` and `estimate_with_stats` always march,
+    /// since they're for seeing how the march goes. Marched hits are refined with
+    /// `refine_steps`.
+    pub fn estimate_from(&self, pos: Vec3<T>, rot: Vec3<T>, start: T) -> Option<Vec3<T>> {
+        match self.intersect_from(pos, rot, start) {
+            Some(hit) => hit,
+            None => self
+                .march(pos, rot, start, |_| ())
+                .hit()
+                .map(|hit| self.refine(pos, rot, hit)),
+        }
+    }
+
+    /// Moves `hit`, where a march along the ray from `pos` stopped within ε of the surface,
+    /// closer to where the estimate is zero with up to `refine_steps` secant steps along the ray.
+    /// Refinement stops at the first step which doesn't bring the estimate closer to zero, so a
+    /// rough estimator can't make a hit worse.
+    fn refine(&self, pos: Vec3<T>, rot: Vec3<T>, hit: Vec3<T>) -> Vec3<T> {
+        let steps = self.refine_steps.unwrap_or(0);
+        if steps == 0 {
+            return hit;
+        }
+        let at = |t: T| self.de.estimate(pos + rot * t);
+        let mut t1 = (hit - pos).magnitude() / rot.magnitude();
+        let mut d1 = at(t1);
+        // a sample a little way back along the ray to draw the first secant through
+        let mut t0 = t1 - self.epsilon_at(t1);
+        let mut d0 = at(t0);
+        for _ in 0..steps {
+            if d1 == T::zero() || d1 == d0 {
+                break;
+            }
+            let t = t1 - d1 * (t1 - t0) / (d1 - d0);
+            let d = at(t);
+            if d.is_nan() || d.abs() >= d1.abs() {
+                break;
+            }
+            t0 = t1;
+            d0 = d1;
+            t1 = t;
+            d1 = d;
+        }
+        pos + rot * t1
+    }
+
+    /// The exact hit from `start` units along the ray for primitives with one, over the same
+    /// span as a march and counting a start within ε of the surface as a hit there, as marching
+    /// does; `None` if the estimator has to be marched, or the ray misses the bounds and a march
+    /// would end before it began.
+    fn intersect_from(&self, pos: Vec3<T>, rot: Vec3<T>, start: T) -> Option<Option<Vec3<T>>> {
+        let (start, end) = self.span(pos, rot, start)?;
+        let origin = pos + rot * start;
+        let t = self.de.intersect(origin, rot)?;
+        if self.de.estimate(origin).abs() <= self.epsilon_at(start) {
+            return Some(Some(origin));
+        }
+        Some(
+            t.map(|t| start + t)
+                .filter(|&total| total <= end)
+                .map(|total| pos + rot * total),
+        )
+    }
+
+    /// Marches from `start` units along the ray, calling `visit` with each step, and returns how
+    /// the march ended.
+    ///
+    /// With `relaxation` set, this is over-relaxed sphere tracing (Keinert et al. 2014): each
+    /// step is lengthened by the relaxation factor, which is safe as long as the unbounding
+    /// spheres of consecutive samples overlap. When they don't, the step may have skipped a
+    /// surface, so the march falls back to the previous sample and continues unrelaxed.
+    fn march<F>(&self, pos: Vec3<T>, rot: Vec3<T>, start: T, mut visit: F) -> MarchEnd<T>
+    where
+        F: FnMut(MarchStep<T>),
+    {
+        let mut omega = self.relaxation.unwrap_or_else(T::one);
+        let scale = self.step_scale.unwrap_or_else(T::one);
+        let min_step = self.min_step.unwrap_or_else(T::zero);
+        let (start, end) = match self.span(pos, rot, start) {
+            Some(span) => span,
+            None => return MarchEnd::Miss,
+        };
+        let mut total_dist = start;
+        let (mut steps, mut evaluations) = (0, 0);
+        // 1 for rays starting outside the surface and -1 for rays starting inside it
+        let mut side: Option<T> = None;
+        // distance along the ray and estimate of the previous sample
+        let mut prev: Option<(T, T)> = None;
+        // the estimator with its iterations reduced by `lod`, rebuilt only when the count changes
+        let mut reduced: Option<(usize, GeometryEstimator<T>)> = None;
+        while steps < self.max_steps {
+            if matches!(self.max_evaluations, Some(max) if evaluations >= max) {
+                return MarchEnd::OutOfEvaluations;
+            }
+            evaluations += 1;
+            let measure_pos = pos + rot * total_dist;
+            let epsilon = self.epsilon_at(total_dist);
+            let dist = match self.lod_iterations(total_dist) {
+                Some(iterations) => {
+                    match &reduced {
+                        Some((n, _)) if *n == iterations => {}
+                        _ => {
+                            let mut de = self.de.clone();
+                            if let Some(n) = de.iterations_mut() {
+                                *n = iterations;
+                            }
+                            reduced = Some((iterations, de));
+                        }
+                    }
+                    reduced.as_ref().unwrap().1.estimate(measure_pos)
+                }
+                None => self.de.estimate(measure_pos),
+            } * scale;
+            let dist = dist * *side.get_or_insert_with(|| Self::side(dist, epsilon));
+
+            if omega > T::one() {
+                if let Some((prev_total, prev_dist)) = prev {
+                    // landing inside the surface also counts as overshooting, since the hit would
+                    // be up to a whole relaxed step too deep
+                    if dist < T::zero() || prev_dist.abs() + dist.abs() < total_dist - prev_total {
+                        omega = T::one();
+                        total_dist = prev_total + prev_dist;
+                        prev = None;
+                        continue;
+                    }
+                }
+            }
+
+            let step = dist * omega;
+            let floor = min_step * epsilon;
+            let floored = dist > epsilon && step < floor;
+            let step = if floored { floor } else { step };
+            visit(MarchStep {
+                pos: measure_pos,
+                total: total_dist,
+                dist,
+                step,
+                floored,
+            });
+            steps += 1;
+            prev = Some((total_dist, dist));
+            total_dist = total_dist + step;
+
+            if dist <= epsilon {
+                return MarchEnd::Hit(measure_pos);
+            } else if total_dist >= end || total_dist.is_infinite() {
+                return MarchEnd::Miss;
+            }
+        }
+        MarchEnd::Miss
+    }
+
+    /// which side of the surface a ray whose first estimate is `dist` starts on: -1 if it's
+    /// inside, and 1 if it's outside or within `epsilon` of the surface
+    fn side(dist: T, epsilon: T) -> T {
+        if dist < -epsilon {
+            -T::one()
+        } else {
+            T::one()
+        }
+    }
+
+    /// The stretch of the ray from `start` units along it to march over: up to `cutoff`, and
+    /// only where it crosses `bounds` if there are any. `None` if the ray misses the bounds
+    /// entirely, so there's nothing to march.
+    fn span(&self, pos: Vec3<T>, rot: Vec3<T>, start: T) -> Option<(T, T)> {
+        match self.bounds {
+            Some(bounds) => {
+                let (enter, exit) = bounds.clip(pos, rot)?;
+                let (start, end) = (start.max(enter), self.cutoff.min(exit));
+                if start > end {
+                    None
+                } else {
+                    Some((start, end))
+                }
+            }
+            None => Some((start, self.cutoff)),
+        }
+    }
+
+    /// Like `estimate`, but fails at the first estimate which isn't a finite number instead of
+    /// marching on with it; for safe renders (see `render::Acceleration::safe`) to catch
+    /// numerical problems where they start rather than as artifacts in the image. It never uses
+    /// `relaxation`, `lod`, `min_step`, or `refine_steps`.
+    pub fn estimate_checked(
+        &self,
+        pos: Vec3<T>,
+        rot: Vec3<T>,
+    ) -> Result<Option<Vec3<T>>, NonFiniteEstimate<T>> {
+        let scale = self.step_scale.unwrap_or_else(T::one);
+        let (mut total_dist, end) = match self.span(pos, rot, T::zero()) {
+            Some(span) => span,
+            None => return Ok(None),
+        };
+        // without relaxation, every estimate is a step
+        let max_steps = self
+            .max_evaluations
+            .map_or(self.max_steps, |max| max.min(self.max_steps));
+        let mut side: Option<T> = None;
+        for _ in 0..max_steps {
+            let measure_pos = pos + rot * total_dist;
+            let epsilon = self.epsilon_at(total_dist);
+            let dist = self.de.estimate(measure_pos) * scale;
+            if !dist.is_finite() {
+                return Err(NonFiniteEstimate {
+                    pos: measure_pos,
+                    dist,
+                });
+            }
+            let dist = dist * *side.get_or_insert_with(|| Self::side(dist, epsilon));
+            total_dist = total_dist + dist;
+
+            if dist <= epsilon {
+                return Ok(Some(measure_pos));
+            } else if total_dist >= end {
+                return Ok(None);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Like `estimate`, but also counts the steps taken and the closest approach to the surface,
+    /// for diagnosing slow or missed regions of an image.
+    pub fn estimate_with_stats(
+        &self,
+        pos: Vec3<T>,
+        rot: Vec3<T>,
+    ) -> (Option<Vec3<T>>, MarchStats<T>) {
+        let mut stats = MarchStats {
+            steps: 0,
+            closest: T::infinity(),
+            floored: 0,
+            out_of_evaluations: false,
+        };
+        let end = self.march(pos, rot, T::zero(), |step| {
+            stats.steps += 1;
+            stats.closest = stats.closest.min(step.dist);
+            stats.floored += step.floored as usize;
+        });
+        stats.out_of_evaluations = matches!(end, MarchEnd::OutOfEvaluations);
+        (end.hit(), stats)
+    }
+
+    /// Every step `estimate` takes marching from `pos` along `rot`, for debugging estimators;
+    /// the ray hit the surface if the last step's estimate is within ε.
+    pub fn march_trace(&self, pos: Vec3<T>, rot: Vec3<T>) -> Vec<MarchStep<T>> {
+        let mut steps = Vec::new();
+        self.march(pos, rot, T::zero(), |step| steps.push(step));
+        steps
+    }
+
+    /// distance along `rot` from `pos` to the geometry's surface, if the ray hits it
+    pub fn hit_distance(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<T> {
+        self.hit_distance_from(pos, rot, T::zero())
+    }
+
+    /// like `hit_distance`, but starts marching `start` units along the ray
+    pub fn hit_distance_from(&self, pos: Vec3<T>, rot: Vec3<T>, start: T) -> Option<T> {
+        self.estimate_from(pos, rot, start)
+            .map(|hit| (hit - pos).magnitude())
+    }
+
+    /// ε `total` units along a ray, grown by `epsilon_scale`
+    pub fn epsilon_at(&self, total: T) -> T {
+        self.epsilon_scale
+            .map_or(self.epsilon, |scale| self.epsilon + scale * total.abs())
+    }
+
+    /// The reduced iteration count to estimate with `total` units along a ray according to
+    /// `lod`: the full count out to `lod.near`, then `iterations_per_octave` fewer for each
+    /// doubling of the distance, down to `min_iterations`. `None` where the full count applies,
+    /// or if the estimator doesn't iterate.
+    pub fn lod_iterations(&self, total: T) -> Option<usize> {
+        let lod = self.lod?;
+        let full = self.de.iterations()?;
+        if total <= lod.near {
+            return None;
+        }
+        let fewer = (lod.iterations_per_octave * (total / lod.near).log2())
+            .floor()
+            .to_usize()
+            .unwrap_or(0);
+        let iterations = full.saturating_sub(fewer).max(lod.min_iterations);
+        if iterations < full {
+            Some(iterations)
+        } else {
+            None
+        }
+    }
+
+    /// This geometry with its quality parameters adjusted for a frame zoomed in by a factor of
+    /// `zoom` according to `zoom_quality`. A zoom that isn't positive and finite leaves them as
+    /// they are.
+    pub fn at_zoom(&self, zoom: T) -> Self {
+        let mut geom = self.clone();
+        if zoom <= T::zero() || !zoom.is_finite() {
+            return geom;
+        }
+        if let Some(rule) = self.zoom_quality {
+            let octaves = zoom.log2();
+            let eps_scale = rule.epsilon_per_octave.powf(octaves);
+            geom.epsilon = self.epsilon * eps_scale;
+            // the pixels shrink along with ε
+            geom.epsilon_scale = self.epsilon_scale.map(|scale| scale * eps_scale);
+            geom.sample_size = self.sample_size * eps_scale;
+            if let Some(iterations) = geom.de.iterations_mut() {
+                let extra = (rule.iterations_per_octave * octaves).round();
+                *iterations = (T::from(*iterations).unwrap() + extra)
+                    .max(T::one())
+                    .to_usize()
+                    .unwrap_or(*iterations);
+            }
+        }
+        geom
+    }
+
+    /// The estimated gradient of the distance field at `pos`; its direction is the surface
+    /// normal, and its length is about 1 for a well-behaved estimator.
+    pub fn gradient(&self, pos: Vec3<T>) -> Vec3<T> {
+        match self.normal_method {
+            NormalMethod::Central => {
+                let zero = T::zero();
+                let x = Vec3::new(self.sample_size, zero, zero);
+                let y = Vec3::new(zero, self.sample_size, zero);
+                let z = Vec3::new(zero, zero, self.sample_size);
+                Vec3::new(
+                    self.de.estimate(pos + x) - self.de.estimate(pos - x),
+                    self.de.estimate(pos + y) - self.de.estimate(pos - y),
+                    self.de.estimate(pos + z) - self.de.estimate(pos - z),
+                ) / (T::from(2).unwrap() * self.sample_size)
+            }
+            NormalMethod::Tetrahedral => {
+                // the corners (±1, ±1, ±1) with an odd number of minus signs; weighting each
+                // corner by its estimate and summing cancels the estimate at `pos` itself
+                let (one, neg) = (T::one(), -T::one());
+                [
+                    Vec3::new(one, neg, neg),
+                    Vec3::new(neg, neg, one),
+                    Vec3::new(neg, one, neg),
+                    Vec3::new(one, one, one),
+                ]
+                .iter()
+                .map(|&k| k * self.de.estimate(pos + k * self.sample_size))
+                .sum::<Vec3<T>>()
+                    / (T::from(4).unwrap() * self.sample_size)
+            }
+        }
+    }
+
+    pub fn normal(&self, pos: Vec3<T>) -> Vec3<T>
+    where
+        T: Float + Sum,
+    {
+        self.gradient(pos).normalized()
+    }
+}
+
+/// One of the four components of a quaternion
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuaternionComponent {
+    Real,
+    I,
+    J,
+    K,
+}
+
+/// A 3D cross-section of a 4D fractal: `fixed` is held at `value`, and the x, y, and z of a
+/// point fill the other three components in order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct QuaternionSlice<T> {
+    pub fixed: QuaternionComponent,
+    pub value: T,
+}
+
+impl<T> QuaternionSlice<T>
+where
+    T: Float + Sum,
+{
+    /// the slice where the real component is 0, so x, y, and z are i, j, and k
+    pub fn real_zero() -> Self {
+        QuaternionSlice {
+            fixed: QuaternionComponent::Real,
+            value: T::zero(),
+        }
+    }
+
+    /// the point of the 4D space at `pos` in the slice
+    pub fn quaternion(&self, pos: Vec3<T>) -> Quaternion<T> {
+        let (x, y, z, v) = (pos.x, pos.y, pos.z, self.value);
+        // (real, i, j, k)
+        let (w, i, j, k) = match self.fixed {
+            QuaternionComponent::Real => (v, x, y, z),
+            QuaternionComponent::I => (x, v, y, z),
+            QuaternionComponent::J => (x, y, v, z),
+            QuaternionComponent::K => (x, y, z, v),
+        };
+        Quaternion::from_xyzw(i, j, k, w)
+    }
+}
+
+#[derive(Clone)]
+pub struct Julia<T: Float + Sum> {
+    c: Quaternion<T>,
+    iterations: usize,
+    /// p in the iteration q ← qᵖ + c; need not be an integer (see `quaternion::powf`)
+    power: T,
+    slice: QuaternionSlice<T>,
+    variant: JuliaVariant,
+    /// whether `power` is exactly 2, decided once here rather than on every iteration, so the
+    /// orbit can square directly and skip the general power's logarithms
+    quadratic: bool,
+}
+
+/// Which iteration a `Julia` set uses
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JuliaVariant {
+    /// q ← qᵖ + c
+    Standard,
+    /// q ← |q|ᵖ + c, taking the absolute value of each component of q first; a quaternion
+    /// analogue of the burning ship fractal, with sharp creases along the folds
+    BurningShip,
+}
+
+impl<T> Julia<T>
+where
+    T: Float + Sum,
+{
+    /// powers of 1 or less aren't fractals, and are treated as 2
+    pub fn new(c: Quaternion<T>, iterations: usize, power: T, slice: QuaternionSlice<T>) -> Self {
+        let power = if power > T::one() {
+            power
+        } else {
+            T::from(2).unwrap()
+        };
+        Self {
+            c,
+            iterations,
+            power,
+            slice,
+            variant: JuliaVariant::Standard,
+            quadratic: power == T::from(2).unwrap(),
+        }
+    }
+
+    /// this set iterated with `variant` instead
+    pub fn with_variant(mut self, variant: JuliaVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// the distance estimate at `pos`, and how close its orbit came to `trap` if given
+    fn orbit(&self, pos: Vec3<T>, trap: Option<&OrbitTrap<T>>) -> (T, Option<T>) {
+        // keep one component fixed to view a 3d "slice" of the 4d fractal
+        let mut q = self.slice.quaternion(pos);
+        let mut closest = None;
+        // |q′|, running derivative of q; quaternion magnitudes are multiplicative, so the
+        // magnitude of q′ ← p qᵖ⁻¹ q′ is p |q|ᵖ⁻¹ |q′| even for non-integer powers, where the
+        // derivative itself has no closed form
+        let mut dr = T::one();
+
+        let t2 = T::from(2).unwrap();
+        let t16 = T::from(16).unwrap();
+        let p = self.power;
+
+        for _ in 0..self.iterations {
+            if self.variant == JuliaVariant::BurningShip {
+                // reflections, which leave |q|, and so the derivative's magnitude, alone
+                q = Quaternion::from_xyzw(q.x.abs(), q.y.abs(), q.z.abs(), q.w.abs());
+            }
+            if self.quadratic {
+                dr = t2 * q.magnitude() * dr;
+                q = quaternion::square(q) + self.c;
+            } else {
+                dr = p * q.magnitude().powf(p - T::one()) * dr;
+                q = quaternion::powf(q, p) + self.c;
+            }
+            closest = update_trap(closest, trap, Vec3::new(q.x, q.y, q.z));
+            if q.magnitude_squared() > t16 {
+                break;
+            }
+        }
+
+        //            |q| log |q|
+        // distance = ───────────
+        //               2 |q′|
+        let mag_q: T = q.magnitude();
+        (mag_q * mag_q.ln() / (t2 * dr), closest)
+    }
+}
+
+impl<T> Estimator<T> for Julia<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.orbit(pos, None).0
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.orbit(pos, Some(trap))
+    }
+}
+
+/// The quaternion Mandelbrot set: like `Julia`, but `c` is the sample point and every point
+/// starts iterating from the same `start`
+#[derive(Clone)]
+pub struct QuaternionMandelbrot<T: Float + Sum> {
+    start: Quaternion<T>,
+    iterations: usize,
+}
+
+impl<T> QuaternionMandelbrot<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(start: Quaternion<T>, iterations: usize) -> Self {
+        Self { start, iterations }
+    }
+
+    /// the distance estimate at `pos`, and how close its orbit came to `trap` if given
+    fn orbit(&self, pos: Vec3<T>, trap: Option<&OrbitTrap<T>>) -> (T, Option<T>) {
+        // as with the Julia sets, view the 3d slice where the real component is 0
+        let c = Quaternion::from(Vec4::from(pos));
+        let mut q = self.start;
+        let mut closest = None;
+        // q', running derivative of q with respect to c
+        let mut qp: Quaternion<T> = Quaternion::zero();
+
+        let t2 = T::from(2).unwrap();
+        let t16 = T::from(16).unwrap();
+
+        for _ in 0..self.iterations {
+            qp = (q * qp) * t2 + Quaternion::identity();
+            q = quaternion::square(q) + c;
+            closest = update_trap(closest, trap, Vec3::new(q.x, q.y, q.z));
+            if q.magnitude_squared() > t16 {
+                break;
+            }
+        }
+
+        let mag_q: T = q.magnitude();
+        (mag_q * mag_q.ln() / (t2 * qp.magnitude()), closest)
+    }
+}
+
+impl<T> Estimator<T> for QuaternionMandelbrot<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.orbit(pos, None).0
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.orbit(pos, Some(trap))
+    }
+}
+
+#[derive(Clone)]
+pub struct Mandelbox<T: Float + Sum> {
+    /// scale applied after each fold; typically around -1.5 or 2
+    scale: T,
+    /// radius inside which the sphere fold scales points up by a constant factor
+    min_radius: T,
+    /// half-width of the box fold
+    fold_limit: T,
+    iterations: usize,
+}
+
+impl<T> Mandelbox<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(scale: T, min_radius: T, fold_limit: T, iterations: usize) -> Self {
+        Self {
+            scale,
+            min_radius,
+            fold_limit,
+            iterations,
+        }
+    }
+
+    /// the distance estimate at `pos`, and how close its orbit came to `trap` if given
+    fn orbit(&self, pos: Vec3<T>, trap: Option<&OrbitTrap<T>>) -> (T, Option<T>) {
+        let mut z = pos;
+        // running derivative of z
+        let mut dr = T::one();
+        let mut closest = None;
+
+        for _ in 0..self.iterations {
+            let (next_z, next_dr) =
+                mandelbox_step(z, dr, pos, self.scale, self.min_radius, self.fold_limit);
+            z = next_z;
+            dr = next_dr;
+            closest = update_trap(closest, trap, z);
+        }
+
+        (z.magnitude() / dr.abs(), closest)
+    }
+}
+
+impl<T> Estimator<T> for Mandelbox<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.orbit(pos, None).0
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.orbit(pos, Some(trap))
+    }
+}
+
+/// One Mandelbox iteration of `z` and its running derivative `dr`: a box fold, a sphere fold,
+/// then scaling and adding the original point `pos`
+fn mandelbox_step<T>(
+    z: Vec3<T>,
+    dr: T,
+    pos: Vec3<T>,
+    scale: T,
+    min_radius: T,
+    fold_limit: T,
+) -> (Vec3<T>, T)
+where
+    T: Float + Sum,
+{
+    let t2 = T::from(2).unwrap();
+    let fixed_radius2 = T::one();
+    let min_radius2 = min_radius * min_radius;
+
+    // box fold: reflect components beyond ±fold_limit back inside
+    let z = z.map(|c| c.max(-fold_limit).min(fold_limit) * t2 - c);
+
+    // sphere fold: invert points inside the fixed radius
+    let r2 = z.magnitude_squared();
+    let fold = if r2 < min_radius2 {
+        fixed_radius2 / min_radius2
+    } else if r2 < fixed_radius2 {
+        fixed_radius2 / r2
+    } else {
+        T::one()
+    };
+
+    (z * fold * scale + pos, dr * fold * scale.abs() + T::one())
+}
+
+/// One step of a `Hybrid`'s iteration
+#[derive(Clone)]
+pub enum HybridFormula<T: Float + Sum> {
+    /// a `Mandelbox` fold and scale
+    Mandelbox {
+        scale: T,
+        min_radius: T,
+        fold_limit: T,
+    },
+    /// a Mandelbulb step: the triplex power `power` (see `quaternion::triplex_pow`)
+    Bulb { power: T },
+}
+
+/// Applies each of `formulas` in turn, every iteration, to the same point; e.g. two Mandelbox
+/// folds followed by a Mandelbulb power
+#[derive(Clone)]
+pub struct Hybrid<T: Float + Sum> {
+    formulas: Vec<HybridFormula<T>>,
+    iterations: usize,
+}
+
+impl<T> Hybrid<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(formulas: Vec<HybridFormula<T>>, iterations: usize) -> Self {
+        Self {
+            formulas,
+            iterations,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Hybrid<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let bailout = T::from(1e6).unwrap();
+
+        let mut z = pos;
+        // running derivative of z
+        let mut dr = T::one();
+        // whether any step was a bulb power, which changes how the distance is estimated
+        let mut bulb = false;
+
+        'iterate: for _ in 0..self.iterations {
+            for formula in &self.formulas {
+                match *formula {
+                    HybridFormula::Mandelbox {
+                        scale,
+                        min_radius,
+                        fold_limit,
+                    } => {
+                        let (next_z, next_dr) =
+                            mandelbox_step(z, dr, pos, scale, min_radius, fold_limit);
+                        z = next_z;
+                        dr = next_dr;
+                    }
+                    HybridFormula::Bulb { power } => {
+                        bulb = true;
+                        let r = z.magnitude();
+                        dr = power * r.powf(power - T::one()) * dr + T::one();
+                        z = quaternion::triplex_pow(z, power) + pos;
+                    }
+                }
+                if z.magnitude_squared() > bailout {
+                    break 'iterate;
+                }
+            }
+        }
+
+        let r = z.magnitude();
+        if bulb {
+            // the bulb's escape grows exponentially, so use the same estimate as the Julia sets
+            T::from(0.5).unwrap() * r.ln() * r / dr
+        } else {
+            r / dr.abs()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Sphere<T: Float + Sum> {
+    pub(crate) center: Vec3<T>,
+    pub(crate) radius: T,
+}
+
+impl<T> Sphere<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(center: Vec3<T>, radius: T) -> Self {
+        Self { center, radius }
+    }
+
+    /// see `GeometryEstimator::intersect`
+    fn intersect(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<T> {
+        let bounds = Bounds::Sphere {
+            center: self.center,
+            radius: self.radius,
+        };
+        first_crossing(bounds.clip(pos, rot))
+    }
+}
+
+impl<T> Estimator<T> for Sphere<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        (pos - self.center).magnitude() - self.radius
+    }
+}
+
+/// An axis-aligned box, optionally with its edges rounded off
+#[derive(Clone)]
+pub struct Cuboid<T: Float + Sum> {
+    pub(crate) center: Vec3<T>,
+    /// distance from the center to each face, before rounding
+    pub(crate) half_size: Vec3<T>,
+    /// radius of the rounded edges and corners; 0 for a sharp box
+    rounding: T,
+}
+
+impl<T> Cuboid<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(center: Vec3<T>, half_size: Vec3<T>, rounding: T) -> Self {
+        Self {
+            center,
+            half_size,
+            rounding,
+        }
+    }
+
+    /// see `GeometryEstimator::intersect`; only exact for boxes without rounding
+    fn intersect(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<T> {
+        let bounds = Bounds::Box {
+            center: self.center,
+            size: self.half_size * T::from(2).unwrap(),
+        };
+        first_crossing(bounds.clip(pos, rot))
+    }
+}
+
+impl<T> Estimator<T> for Cuboid<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        // distance past each pair of faces of the box shrunk by the rounding radius
+        let q = (pos - self.center).map(T::abs) - (self.half_size - Vec3::broadcast(self.rounding));
+        let outside = q.map(|c| c.max(T::zero())).magnitude();
+        let inside = q.reduce(T::max).min(T::zero());
+        outside + inside - self.rounding
+    }
+}
+
+/// A torus lying in the xz plane, around the y axis
+#[derive(Clone)]
+pub struct Torus<T: Float + Sum> {
+    center: Vec3<T>,
+    /// distance from the center to the middle of the tube
+    major_radius: T,
+    /// radius of the tube
+    minor_radius: T,
+}
+
+impl<T> Torus<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(center: Vec3<T>, major_radius: T, minor_radius: T) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Torus<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let p = pos - self.center;
+        // distance from the circle running through the middle of the tube
+        let ring = Vec2::new(Vec2::new(p.x, p.z).magnitude() - self.major_radius, p.y);
+        ring.magnitude() - self.minor_radius
+    }
+}
+
+/// An infinite plane; points on the side `normal` faces are outside it
+#[derive(Clone)]
+pub struct Plane<T: Float + Sum> {
+    normal: Vec3<T>,
+    /// distance from the origin to the plane along `normal`
+    offset: T,
+}
+
+impl<T> Plane<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(normal: Vec3<T>, offset: T) -> Self {
+        Self {
+            normal: normal.normalized(),
+            offset,
+        }
+    }
+
+    /// see `GeometryEstimator::intersect`
+    fn intersect(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<T> {
+        let dist = self.estimate(pos);
+        if dist == T::zero() {
+            return Some(T::zero());
+        }
+        // whichever side the ray starts on, it crosses if it heads toward the plane
+        let t = -dist / rot.dot(self.normal);
+        if t.is_finite() && t >= T::zero() {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Estimator<T> for Plane<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        pos.dot(self.normal) - self.offset
+    }
+}
+
+/// Which triply periodic minimal surface a `Tpms` is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TpmsKind {
+    /// Schoen's gyroid, sin x cos y + sin y cos z + sin z cos x = 0
+    Gyroid,
+    /// Schwarz's P surface, cos x + cos y + cos z = 0
+    SchwarzP,
+}
+
+/// A triply periodic minimal surface thickened into a sheet, filling all of space; cheap to
+/// evaluate and full of curvature and crevices, which makes it good for testing lighting and
+/// ambient occlusion
+#[derive(Clone)]
+pub struct Tpms<T: Float + Sum> {
+    kind: TpmsKind,
+    /// the length of the repeating cell
+    period: T,
+    /// the sheet's total thickness
+    thickness: T,
+}
+
+impl<T> Tpms<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(kind: TpmsKind, period: T, thickness: T) -> Self {
+        Self {
+            kind,
+            period,
+            thickness,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Tpms<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let frequency = T::from(2.0 * std::f64::consts::PI).unwrap() / self.period;
+        let p = pos * frequency;
+        let (s, c) = (p.map(T::sin), p.map(T::cos));
+        // the implicit function and a bound on its gradient's length, so that dividing by it
+        // never overestimates the distance to the surface
+        let (f, lipschitz) = match self.kind {
+            // each partial derivative, like cos x cos y - sin x sin z, is at most √2
+            TpmsKind::Gyroid => (
+                s.x * c.y + s.y * c.z + s.z * c.x,
+                T::from(6).unwrap().sqrt(),
+            ),
+            TpmsKind::SchwarzP => (c.sum(), T::from(3).unwrap().sqrt()),
+        };
+        f.abs() / (lipschitz * frequency) - self.thickness / T::from(2).unwrap()
+    }
+}
+
+/// A kaleidoscopic Sierpinski tetrahedron with vertices at (1, 1, 1), (-1, -1, 1), (1, -1, -1), and
+/// (-1, 1, -1)
+#[derive(Clone)]
+pub struct Sierpinski<T: Float + Sum> {
+    /// 2 for the classic tetrahedron; larger values leave bigger gaps
+    scale: T,
+    iterations: usize,
+}
+
+impl<T> Sierpinski<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(scale: T, iterations: usize) -> Self {
+        Self { scale, iterations }
+    }
+}
+
+impl<T> Estimator<T> for Sierpinski<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let mut z = pos;
+        let vertex = Vec3::one();
+        for _ in 0..self.iterations {
+            // fold across the tetrahedron's planes of symmetry so z ends up near `vertex`
+            if z.x + z.y < T::zero() {
+                z = Vec3::new(-z.y, -z.x, z.z);
+            }
+            if z.x + z.z < T::zero() {
+                z = Vec3::new(-z.z, z.y, -z.x);
+            }
+            if z.y + z.z < T::zero() {
+                z = Vec3::new(z.x, -z.z, -z.y);
+            }
+            z = z * self.scale - vertex * (self.scale - T::one());
+        }
+        z.magnitude() * self.scale.powi(-(self.iterations as i32))
+    }
+}
+
+/// Knighty's pseudo-Kleinian fractal, which approximates the limit sets of Kleinian groups by
+/// alternating box folds and sphere inversions
+#[derive(Clone)]
+pub struct Kleinian<T: Float + Sum> {
+    /// half-extents of the box fold; around (0.92, 0.9, 0.92) for the classic look
+    fold: Vec3<T>,
+    /// radius of the inversion sphere; points inside it are inverted out
+    inversion_radius: T,
+    /// radius of the cylinder the final distance is measured against
+    core_radius: T,
+    iterations: usize,
+}
+
+impl<T> Kleinian<T>
+where
+    T: Float + Sum,
+{
+    /// the parameters Knighty's original pseudo-Kleinian renders used
+    pub const STANDARD_FOLD: [f64; 3] = [0.92436, 0.90756, 0.92436];
+    pub const STANDARD_INVERSION_RADIUS: f64 = 1.0;
+    pub const STANDARD_CORE_RADIUS: f64 = 0.92784;
+
+    pub fn new(fold: Vec3<T>, inversion_radius: T, core_radius: T, iterations: usize) -> Self {
+        Self {
+            fold,
+            inversion_radius,
+            core_radius,
+            iterations,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Kleinian<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let t2 = T::from(2).unwrap();
+        let inversion2 = self.inversion_radius * self.inversion_radius;
+        let mut p = pos;
+        // running derivative of p
+        let mut dr = T::one();
+
+        for _ in 0..self.iterations {
+            p = p.map2(self.fold, |c, f| c.max(-f).min(f) * t2 - c);
+            let k = (inversion2 / p.magnitude_squared()).max(T::one());
+            p = p * k;
+            dr = dr * k;
+        }
+
+        let rxy = Vec2::new(p.x, p.y).magnitude();
+        (rxy - self.core_radius).max((rxy * p.z).abs() / p.magnitude()) / dr
+    }
+}
+
+/// One step of an iterated function system: fold the point across some planes through the
+/// origin, then rotate, scale, and translate it
+#[derive(Clone)]
+pub struct AffineMap<T: Float + Sum> {
+    /// normals of the fold planes; points on the side a normal faces away from are reflected
+    pub folds: Vec<Vec3<T>>,
+    pub rotation: Quaternion<T>,
+    pub scale: T,
+    pub translation: Vec3<T>,
+}
+
+impl<T> AffineMap<T>
+where
+    T: Float + Sum,
+{
+    fn apply(&self, z: Vec3<T>) -> Vec3<T> {
+        let t2 = T::from(2).unwrap();
+        let folded = self.folds.iter().fold(z, |z, &normal| {
+            let n = normal.normalized();
+            z - n * (t2 * z.dot(n).min(T::zero()))
+        });
+        quaternion::rotate(self.rotation, folded) * self.scale + self.translation
+    }
+}
+
+/// A (kaleidoscopic) iterated function system: each iteration applies every map in turn
+#[derive(Clone)]
+pub struct Ifs<T: Float + Sum> {
+    maps: Vec<AffineMap<T>>,
+    iterations: usize,
+}
+
+impl<T> Ifs<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(maps: Vec<AffineMap<T>>, iterations: usize) -> Self {
+        Self { maps, iterations }
+    }
+}
+
+impl<T> Estimator<T> for Ifs<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let mut z = pos;
+        // folds and rotations are isometries, so only the scales stretch distances
+        let mut dr = T::one();
+        for _ in 0..self.iterations {
+            for map in &self.maps {
+                z = map.apply(z);
+                dr = dr * map.scale.abs();
+            }
+        }
+        z.magnitude() / dr
+    }
+}
+
+/// An Apollonian-packing style fractal: space is folded into a repeating cell, then inverted
+/// through each of a set of spheres, over and over; what's left is sliced by the plane y = 0
+#[derive(Clone)]
+pub struct Apollonian<T: Float + Sum> {
+    /// inversion spheres, as (center, radius); points inside a sphere are inverted through it
+    spheres: Vec<(Vec3<T>, T)>,
+    /// width of the repeating cell space is folded into
+    period: T,
+    iterations: usize,
+}
+
+impl<T> Apollonian<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(spheres: Vec<(Vec3<T>, T)>, period: T, iterations: usize) -> Self {
+        Self {
+            spheres,
+            period,
+            iterations,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Apollonian<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let half = T::from(0.5).unwrap();
+        let mut p = pos;
+        // running derivative of p
+        let mut dr = T::one();
+
+        for _ in 0..self.iterations {
+            // fold into the cell [-period / 2, period / 2)
+            p = p.map(|c| {
+                let t = c / self.period + half;
+                (t - t.floor() - half) * self.period
+            });
+            for &(center, radius) in &self.spheres {
+                let d = p - center;
+                let r2 = d.magnitude_squared();
+                if r2 < radius * radius {
+                    let k = radius * radius / r2;
+                    p = center + d * k;
+                    dr = dr * k;
+                }
+            }
+        }
+
+        // the limit set is dense, so measure against a slice through it
+        T::from(0.25).unwrap() * p.y.abs() / dr
+    }
+}
+
+/// All points within `radius` of the line segment from `start` to `end`
+#[derive(Clone)]
+pub struct Capsule<T: Float + Sum> {
+    start: Vec3<T>,
+    end: Vec3<T>,
+    radius: T,
+}
+
+impl<T> Capsule<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(start: Vec3<T>, end: Vec3<T>, radius: T) -> Self {
+        Self { start, end, radius }
+    }
+}
+
+impl<T> Estimator<T> for Capsule<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let p = pos - self.start;
+        let axis = self.end - self.start;
+        // how far along the segment the closest point to `pos` is, from 0 to 1
+        let t = (p.dot(axis) / axis.magnitude_squared())
+            .max(T::zero())
+            .min(T::one());
+        (p - axis * t).magnitude() - self.radius
+    }
+}
+
+/// A cylinder with flat caps, standing upright along the y axis
+#[derive(Clone)]
+pub struct Cylinder<T: Float + Sum> {
+    center: Vec3<T>,
+    radius: T,
+    height: T,
+}
+
+impl<T> Cylinder<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(center: Vec3<T>, radius: T, height: T) -> Self {
+        Self {
+            center,
+            radius,
+            height,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Cylinder<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let p = pos - self.center;
+        // distance past the curved side and past the caps
+        let d = Vec2::new(
+            Vec2::new(p.x, p.z).magnitude() - self.radius,
+            p.y.abs() - self.height / T::from(2).unwrap(),
+        );
+        d.reduce(T::max).min(T::zero()) + d.map(|c| c.max(T::zero())).magnitude()
+    }
+}
+
+/// Where a heightfield's heights come from
+#[derive(Clone)]
+pub enum HeightSource<T> {
+    /// `noise::fbm` with features about `1 / frequency` across
+    Noise {
+        frequency: T,
+        octaves: usize,
+        seed: u32,
+    },
+    /// a grid of heights from -1 to 1, like a grayscale heightmap image, in row-major order from
+    /// -x, -z; it covers `size` units of the xz plane centered on the origin, and is
+    /// interpolated between samples and extended from its edges
+    Grid {
+        heights: Vec<T>,
+        resolution: Extent2<usize>,
+        size: Extent2<T>,
+    },
+}
+
+/// Terrain: the region below `y = amplitude * height(x, z)`, so fractals can sit in a landscape
+#[derive(Clone)]
+pub struct Heightfield<T> {
+    source: HeightSource<T>,
+    amplitude: T,
+    /// how much steeper than a plane the terrain can be; the vertical distance to the surface
+    /// is divided by this so it never overestimates the true distance
+    steepness: T,
+}
+
+impl<T> Heightfield<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(source: HeightSource<T>, amplitude: T) -> Self {
+        // an upper bound on the height's slope
+        let slope = match &source {
+            HeightSource::Noise {
+                frequency, octaves, ..
+            } => *frequency * T::from(noise::fbm_lipschitz(*octaves)).unwrap(),
+            HeightSource::Grid {
+                heights,
+                resolution,
+                size,
+            } => {
+                let cell = Vec2::new(
+                    size.w / T::from(resolution.w.max(2) - 1).unwrap(),
+                    size.h / T::from(resolution.h.max(2) - 1).unwrap(),
+                );
+                let at = |x: usize, z: usize| heights[z * resolution.w + x];
+                let mut steepest: Vec2<T> = Vec2::zero();
+                for z in 0..resolution.h {
+                    for x in 0..resolution.w {
+                        if x + 1 < resolution.w {
+                            let dx = (at(x + 1, z) - at(x, z)).abs() / cell.x;
+                            steepest.x = steepest.x.max(dx);
+                        }
+                        if z + 1 < resolution.h {
+                            let dz = (at(x, z + 1) - at(x, z)).abs() / cell.y;
+                            steepest.y = steepest.y.max(dz);
+                        }
+                    }
+                }
+                steepest.magnitude()
+            }
+        };
+        Self {
+            source,
+            amplitude,
+            steepness: (T::one() + (amplitude.abs() * slope).powi(2)).sqrt(),
+        }
+    }
+
+    /// the terrain's height at (x, z)
+    pub fn height(&self, x: T, z: T) -> T {
+        let height = match &self.source {
+            HeightSource::Noise {
+                frequency,
+                octaves,
+                seed,
+            } => noise::fbm(Vec3::new(x, T::zero(), z) * *frequency, *octaves, *seed),
+            HeightSource::Grid {
+                heights,
+                resolution,
+                size,
+            } => {
+                if heights.is_empty() {
+                    return T::zero();
+                }
+                let half = T::from(0.5).unwrap();
+                // position in grid samples, clamped to the grid
+                let sample = |c: T, size: T, samples: usize| {
+                    let last = T::from(samples - 1).unwrap();
+                    ((c / size + half) * last).max(T::zero()).min(last)
+                };
+                let (gx, gz) = (
+                    sample(x, size.w, resolution.w),
+                    sample(z, size.h, resolution.h),
+                );
+                let (x0, z0) = (gx.to_usize().unwrap(), gz.to_usize().unwrap());
+                let (x1, z1) = (
+                    (x0 + 1).min(resolution.w - 1),
+                    (z0 + 1).min(resolution.h - 1),
+                );
+                let (tx, tz) = (gx - T::from(x0).unwrap(), gz - T::from(z0).unwrap());
+                let at = |x: usize, z: usize| heights[z * resolution.w + x];
+                let lerp = |a: T, b: T, t: T| a + (b - a) * t;
+                lerp(
+                    lerp(at(x0, z0), at(x1, z0), tx),
+                    lerp(at(x0, z1), at(x1, z1), tx),
+                    tz,
+                )
+            }
+        };
+        height * self.amplitude
+    }
+}
+
+impl<T> Estimator<T> for Heightfield<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        (pos.y - self.height(pos.x, pos.z)) / self.steepness
+    }
+}
+
+/// A shape from signed distances sampled on a grid, like a mesh baked by `mesh::Mesh::bake` or
+/// a volume loaded by `volume::Volume::load`, trilinearly interpolated between samples. The
+/// grid's corner is at `origin`, with `samples` samples `spacing` apart along each axis, in
+/// x-major order; it must contain the whole shape.
+#[derive(Clone)]
+pub struct SdfGrid<T> {
+    origin: Vec3<T>,
+    spacing: Vec3<T>,
+    samples: Vec3<usize>,
+    /// shared, since estimators are cloned for each render
+    distances: Arc<Vec<T>>,
+}
+
+impl<T> SdfGrid<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(
+        origin: Vec3<T>,
+        spacing: Vec3<T>,
+        samples: Vec3<usize>,
+        distances: Arc<Vec<T>>,
+    ) -> Self {
+        Self {
+            origin,
+            spacing,
+            samples,
+            distances,
+        }
+    }
+
+    fn at(&self, x: usize, y: usize, z: usize) -> T {
+        self.distances[(z * self.samples.y + y) * self.samples.x + x]
+    }
+}
+
+impl<T> Estimator<T> for SdfGrid<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        if self.distances.is_empty() {
+            // no samples, so no shape
+            return T::infinity();
+        }
+        let last = self.samples.map(|s| T::from(s.max(1) - 1).unwrap());
+        let grid = (pos - self.origin) / self.spacing;
+        let clamped: Vec3<T> = Vec3::partial_max(Vec3::partial_min(grid, last), Vec3::zero());
+        let lower: Vec3<usize> = clamped.map(|c| c.to_usize().unwrap());
+        let upper: Vec3<usize> = lower
+            .zip(self.samples)
+            .map(|(l, s): (usize, usize)| (l + 1).min(s.max(1) - 1));
+        let t = clamped - lower.map(|l| T::from(l).unwrap());
+
+        let lerp = |a: T, b: T, t: T| a + (b - a) * t;
+        let edge = |y, z| lerp(self.at(lower.x, y, z), self.at(upper.x, y, z), t.x);
+        let face = |z| lerp(edge(lower.y, z), edge(upper.y, z), t.y);
+        let inside = lerp(face(lower.z), face(upper.z), t.z);
+
+        // outside the grid, both the distance to the grid and the distance from its edge less
+        // that distance are lower bounds on the distance to the shape within it
+        let outside = ((grid - clamped) * self.spacing).magnitude();
+        if outside > T::zero() {
+            outside.max(inside - outside)
+        } else {
+            inside
+        }
+    }
+}
+
+/// A fractal whose iteration step is an expression (see `expr`): each iteration sets `q` to
+/// `step`, starting from the sample point. With `c` given, the fractal is a Julia set with that
+/// constant; otherwise `c` is the sample point, like the Mandelbrot set.
+///
+/// Arbitrary formulas have no analytic derivative, so the distance is estimated from a finite
+/// difference gradient, which takes four times as many iterations as the built-in fractals.
+#[derive(Clone)]
+pub struct Formula<T: Float + Sum> {
+    step: Expr,
+    c: Option<Quaternion<T>>,
+    iterations: usize,
+}
+
+impl<T> Formula<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(step: Expr, c: Option<Quaternion<T>>, iterations: usize) -> Self {
+        Self {
+            step,
+            c,
+            iterations,
+        }
+    }
+
+    /// |q| after iterating from `pos`, and how many iterations it took to escape (or all of them)
+    fn orbit(&self, pos: Vec3<T>, iterations: usize) -> (T, usize) {
+        let pos = Quaternion::from(Vec4::from(pos));
+        let mut vars = expr::Vars {
+            q: pos,
+            c: self.c.unwrap_or(pos),
+            pos,
+        };
+        let bailout = T::from(1e6).unwrap();
+        for i in 0..iterations {
+            vars.q = self.step.eval(&vars);
+            if vars.q.magnitude_squared() > bailout {
+                return (vars.q.magnitude(), i + 1);
+            }
+        }
+        (vars.q.magnitude(), iterations)
+    }
+}
+
+impl<T> Estimator<T> for Formula<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let (r, iterations) = self.orbit(pos, self.iterations);
+        if r <= T::one() {
+            // never escaped; the estimate below is only meaningful outside the set
+            return T::zero();
+        }
+        // a forward difference is most accurate with a step around the square root of the
+        // float's precision, relative to the coordinates' size
+        let h = T::epsilon().sqrt() * pos.map(|c| c.abs()).reduce_partial_max().max(T::one());
+        // the gradient of |q|, iterating each neighbor as many times as the sample point so the
+        // differences are comparable
+        let gradient = Vec3::new(Vec3::unit_x(), Vec3::unit_y(), Vec3::unit_z())
+            .map(|axis| (self.orbit(pos + axis * h, iterations).0 - r) / h)
+            .magnitude();
+        if gradient == T::zero() || !gradient.is_finite() {
+            // a flat or overflowing orbit says nothing about where the surface is
+            return T::zero();
+        }
+        T::from(0.5).unwrap() * r * r.ln() / gradient
+    }
+}
+
+/// Every point inside either of two shapes
+#[derive(Clone)]
+pub struct Union<T: Float + Sum> {
+    a: Box<GeometryEstimator<T>>,
+    b: Box<GeometryEstimator<T>>,
+}
+
+impl<T> Union<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(a: GeometryEstimator<T>, b: GeometryEstimator<T>) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+        }
+    }
+}
+
+impl<T> Estimator<T> for Union<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.a.estimate(pos).min(self.b.estimate(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        // the orbit of the nearer shape
+        let (a, b) = (
+            self.a.estimate_trapped(pos, trap),
+            self.b.estimate_trapped(pos, trap),
+        );
+        if a.0 <= b.0 {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+/// Like `Union`, but blends the two shapes together where they're within `k` of each other,
+/// using the polynomial smooth minimum
+#[derive(Clone)]
+pub struct SmoothUnion<T: Float + Sum> {
+    a: Box<GeometryEstimator<T>>,
+    b: Box<GeometryEstimator<T>>,
+    k: T,
+}
+
+impl<T> SmoothUnion<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(a: GeometryEstimator<T>, b: GeometryEstimator<T>, k: T) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            k,
+        }
+    }
+
+    /// the smooth minimum of the distances `a` and `b`, and how much of `a` it takes: 1 where `a`
+    /// is much closer, and 0 where `b` is
+    fn blend(&self, a: T, b: T) -> (T, T) {
+        let half = T::from(0.5).unwrap();
+        let h = (half + half * (b - a) / self.k)
+            .max(T::zero())
+            .min(T::one());
+        (b + (a - b) * h - self.k * h * (T::one() - h), h)
+    }
+}
+
+impl<T> Estimator<T> for SmoothUnion<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.blend(self.a.estimate(pos), self.b.estimate(pos)).0
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (a, a_closest) = self.a.estimate_trapped(pos, trap);
+        let (b, b_closest) = self.b.estimate_trapped(pos, trap);
+        // the orbit of the shape more of the blend is taken from
+        let (dist, h) = self.blend(a, b);
+        if h >= T::from(0.5).unwrap() {
+            (dist, a_closest)
+        } else {
+            (dist, b_closest)
+        }
+    }
+}
+
+/// A shape `t` of the way from `a` to `b`, interpolating their distance fields linearly; 0 is
+/// just `a` and 1 just `b`, and values outside that range exaggerate the difference between them
+#[derive(Clone)]
+pub struct Morph<T: Float + Sum> {
+    a: Box<GeometryEstimator<T>>,
+    b: Box<GeometryEstimator<T>>,
+    t: T,
+}
+
+impl<T> Morph<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(a: GeometryEstimator<T>, b: GeometryEstimator<T>, t: T) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            t,
+        }
+    }
+
+    /// the morph's distance, from the distances `a` and `b` to the two shapes
+    fn mix(&self, a: T, b: T) -> T {
+        let s = T::one() - self.t;
+        // a weighted sum of fields changes at most as fast as the sum of the weights' sizes, which
+        // is 1 between the shapes but more when extrapolating past them; scale back down so we
+        // never step through the surface
+        let lipschitz = s.abs() + self.t.abs();
+        (a * s + b * self.t) / lipschitz
+    }
+}
+
+impl<T> Estimator<T> for Morph<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.mix(self.a.estimate(pos), self.b.estimate(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (a, a_closest) = self.a.estimate_trapped(pos, trap);
+        let (b, b_closest) = self.b.estimate_trapped(pos, trap);
+        // the orbit of the shape the morph is more like
+        let closest = if self.t.abs() > (T::one() - self.t).abs() {
+            b_closest
+        } else {
+            a_closest
+        };
+        (self.mix(a, b), closest)
+    }
+}
+
+/// Every point inside both of two shapes; outside, the estimate is a lower bound rather than the
+/// exact distance
+#[derive(Clone)]
+pub struct Intersection<T: Float + Sum> {
+    a: Box<GeometryEstimator<T>>,
+    b: Box<GeometryEstimator<T>>,
+}
+
+impl<T> Intersection<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(a: GeometryEstimator<T>, b: GeometryEstimator<T>) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+        }
+    }
+}
+
+impl<T> Estimator<T> for Intersection<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.a.estimate(pos).max(self.b.estimate(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        // the orbit of the shape whose surface bounds the intersection here
+        let (a, b) = (
+            self.a.estimate_trapped(pos, trap),
+            self.b.estimate_trapped(pos, trap),
+        );
+        if a.0 >= b.0 {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+/// Every point inside `a` but not `b`; like `Intersection`, a lower bound outside the shape
+#[derive(Clone)]
+pub struct Difference<T: Float + Sum> {
+    a: Box<GeometryEstimator<T>>,
+    b: Box<GeometryEstimator<T>>,
+}
+
+impl<T> Difference<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(a: GeometryEstimator<T>, b: GeometryEstimator<T>) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+        }
+    }
+}
+
+impl<T> Estimator<T> for Difference<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.a.estimate(pos).max(-self.b.estimate(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        // as with `Intersection`, where the cut is `b`'s surface
+        let (a, a_closest) = self.a.estimate_trapped(pos, trap);
+        let (b, b_closest) = self.b.estimate_trapped(pos, trap);
+        if a >= -b {
+            (a, a_closest)
+        } else {
+            (-b, b_closest)
+        }
+    }
+}
+
+/// Twists a shape around the line through the origin along `axis`, by `amount` radians per unit
+/// along the axis
+#[derive(Clone)]
+pub struct Twist<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    amount: T,
+    axis: Vec3<T>,
+}
+
+impl<T> Twist<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, amount: T, axis: Vec3<T>) -> Self {
+        Self {
+            de: Box::new(de),
+            amount,
+            axis: axis.normalized(),
+        }
+    }
+
+    /// `pos` untwisted into the shape's frame, and how much the twist stretches space there
+    fn local(&self, pos: Vec3<T>) -> (Vec3<T>, T) {
+        let height = pos.dot(self.axis);
+        let rotation = Quaternion::rotation_3d(-self.amount * height, self.axis);
+        // the twist stretches space by up to sqrt(1 + (amount * r)²) at radius r from the axis
+        let radius = (pos - self.axis * height).magnitude();
+        let stretch = (T::one() + (self.amount * radius).powi(2)).sqrt();
+        (quaternion::rotate(rotation, pos), stretch)
+    }
+}
+
+impl<T> Estimator<T> for Twist<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let (local, stretch) = self.local(pos);
+        self.de.estimate(local) / stretch
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (local, stretch) = self.local(pos);
+        let (dist, closest) = self.de.estimate_trapped(local, trap);
+        (dist / stretch, closest)
+    }
+}
+
+/// Bends a shape in the xy plane, rotating each point around the z axis by `amount` radians per
+/// unit along x
+#[derive(Clone)]
+pub struct Bend<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    amount: T,
+}
+
+impl<T> Bend<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, amount: T) -> Self {
+        Self {
+            de: Box::new(de),
+            amount,
+        }
+    }
+
+    /// `pos` bent into the shape's frame, and how much the bend stretches space there
+    fn local(&self, pos: Vec3<T>) -> (Vec3<T>, T) {
+        let angle = self.amount * pos.x;
+        let (sin, cos) = angle.sin_cos();
+        let bent = Vec3::new(cos * pos.x - sin * pos.y, sin * pos.x + cos * pos.y, pos.z);
+        // as with `Twist`, space is stretched more the further a point is from the bend's axis
+        let radius = Vec2::new(pos.x, pos.y).magnitude();
+        let stretch = (T::one() + (self.amount * radius).powi(2)).sqrt();
+        (bent, stretch)
+    }
+}
+
+impl<T> Estimator<T> for Bend<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let (local, stretch) = self.local(pos);
+        self.de.estimate(local) / stretch
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (local, stretch) = self.local(pos);
+        let (dist, closest) = self.de.estimate_trapped(local, trap);
+        (dist / stretch, closest)
+    }
+}
+
+/// Tapers a shape along `axis` (through the origin), scaling it across the axis by 1 + `rate` h at
+/// height h along it, so it narrows toward one end and widens toward the other; where the scale
+/// would reach 0, the shape pinches to a point.
+#[derive(Clone)]
+pub struct Taper<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    rate: T,
+    axis: Vec3<T>,
+}
+
+impl<T> Taper<T>
+where
+    T: Float + Sum,
+{
+    /// the smallest scale a tapered cross-section shrinks to, so it never collapses or flips
+    const MIN_SCALE: f64 = 1e-3;
+
+    pub fn new(de: GeometryEstimator<T>, rate: T, axis: Vec3<T>) -> Self {
+        Self {
+            de: Box::new(de),
+            rate,
+            axis: axis.normalized(),
+        }
+    }
+
+    /// `pos` untapered into the shape's frame, and how much the taper stretches space there
+    fn local(&self, pos: Vec3<T>) -> (Vec3<T>, T) {
+        let height = pos.dot(self.axis);
+        let across = pos - self.axis * height;
+        let scale = (T::one() + self.rate * height).max(T::from(Self::MIN_SCALE).unwrap());
+        // space is stretched across the axis by 1 / scale where the shape shrinks, and slanted by
+        // how fast the scaled radius changes with height
+        let stretch =
+            scale.recip().max(T::one()) + self.rate.abs() * across.magnitude() / (scale * scale);
+        (self.axis * height + across / scale, stretch)
+    }
+}
+
+impl<T> Estimator<T> for Taper<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let (local, stretch) = self.local(pos);
+        self.de.estimate(local) / stretch
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (local, stretch) = self.local(pos);
+        let (dist, closest) = self.de.estimate_trapped(local, trap);
+        (dist / stretch, closest)
+    }
+}
+
+/// Stretches a shape by `amount` along each axis, pulling its two halves apart from the origin
+/// and filling the gap with the shape's cross-section there
+#[derive(Clone)]
+pub struct Elongate<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    amount: Vec3<T>,
+}
+
+impl<T> Elongate<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, amount: Vec3<T>) -> Self {
+        Self {
+            de: Box::new(de),
+            amount,
+        }
+    }
+
+    /// the point the shape is sampled at for `pos`; clamping only ever brings points closer
+    /// together, so the distance bound is kept
+    fn fold(&self, pos: Vec3<T>) -> Vec3<T> {
+        let half = self.amount.map(|a| a.abs() / T::from(2).unwrap());
+        pos - Vec3::partial_max(-half, Vec3::partial_min(pos, half))
+    }
+}
+
+impl<T> Estimator<T> for Elongate<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(self.fold(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.de.estimate_trapped(self.fold(pos), trap)
+    }
+}
+
+/// Mirrors a shape across a plane: points behind the plane (with `normal` and `offset` as in
+/// `Plane`) are reflected in front of it, so only the half of the shape in front of the plane is
+/// seen, on both sides
+#[derive(Clone)]
+pub struct Mirror<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    normal: Vec3<T>,
+    offset: T,
+}
+
+impl<T> Mirror<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, normal: Vec3<T>, offset: T) -> Self {
+        Self {
+            de: Box::new(de),
+            normal: normal.normalized(),
+            offset,
+        }
+    }
+
+    /// `pos` reflected in front of the plane, if it's behind it
+    fn fold(&self, pos: Vec3<T>) -> Vec3<T> {
+        let side = pos.dot(self.normal) - self.offset;
+        if side < T::zero() {
+            pos - self.normal * (side * T::from(2).unwrap())
+        } else {
+            pos
+        }
+    }
+}
+
+impl<T> Estimator<T> for Mirror<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(self.fold(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.de.estimate_trapped(self.fold(pos), trap)
+    }
+}
+
+/// Repeats a shape `count` times around `axis` (through the origin): every point is rotated into
+/// the wedge of angle 2π / `count` centered on the direction perpendicular to the axis nearest x
+/// (or y, for axes near x), so only the part of the shape in that wedge is seen, in every wedge.
+/// Like `Mirror`, this costs one evaluation of the shape however many copies there are; parts of
+/// the shape outside the wedge can make the estimate overshoot at the wedge's edges.
+#[derive(Clone)]
+pub struct Symmetry<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    count: usize,
+    axis: Vec3<T>,
+    /// the middle of the wedge, perpendicular to `axis`
+    reference: Vec3<T>,
+}
+
+impl<T> Symmetry<T>
+where
+    T: Float + Sum,
+{
+    /// counts of 0 are treated as 1, which leaves the shape as it is
+    pub fn new(de: GeometryEstimator<T>, count: usize, axis: Vec3<T>) -> Self {
+        let axis = axis.normalized();
+        let near = if axis.x.abs() < T::from(0.9).unwrap() {
+            Vec3::unit_x()
+        } else {
+            Vec3::unit_y()
+        };
+        Self {
+            de: Box::new(de),
+            count: count.max(1),
+            axis,
+            reference: (near - axis * near.dot(axis)).normalized(),
+        }
+    }
+
+    /// `pos` rotated into the wedge
+    fn local(&self, pos: Vec3<T>) -> Vec3<T> {
+        let across = self.axis.cross(self.reference);
+        let angle = pos.dot(across).atan2(pos.dot(self.reference));
+        let wedge = T::from(2.0 * std::f64::consts::PI).unwrap() / T::from(self.count).unwrap();
+        // the nearest copy of the wedge is this many radians away
+        let offset = wedge * (angle / wedge).round();
+        let rotation = Quaternion::rotation_3d(-offset, self.axis);
+        quaternion::rotate(rotation, pos)
+    }
+}
+
+impl<T> Estimator<T> for Symmetry<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(self.local(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.de.estimate_trapped(self.local(pos), trap)
+    }
+}
+
+/// Repeats a shape in a grid of `count` copies along each axis, `spacing` apart and centered on
+/// the origin. Each point is measured against the copy whose cell it's in, so the estimate is
+/// only a bound if the shape fits inside one cell.
+#[derive(Clone)]
+pub struct Repeat<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    spacing: Vec3<T>,
+    count: Vec3<usize>,
+}
+
+/// One copy of a repeated shape: its cell in the grid, counted from the negative corner, and
+/// the size of the grid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instance {
+    pub cell: Vec3<usize>,
+    pub count: Vec3<usize>,
+}
+
+impl Instance {
+    /// where the copy is in the grid from 0, the first, to 1, the last, counting along x, then
+    /// y, then z; 0 if there's only one
+    pub fn fraction<T: Float>(&self) -> T {
+        let total = self.count.x * self.count.y * self.count.z;
+        if total <= 1 {
+            return T::zero();
+        }
+        let index = self.cell.x + self.count.x * (self.cell.y + self.count.y * self.cell.z);
+        T::from(index).unwrap() / T::from(total - 1).unwrap()
+    }
+}
+
+impl<T> Repeat<T>
+where
+    T: Float + Sum,
+{
+    /// counts of 0 are treated as 1
+    pub fn new(de: GeometryEstimator<T>, spacing: Vec3<T>, count: Vec3<usize>) -> Self {
+        Self {
+            de: Box::new(de),
+            spacing,
+            count: count.map(|n| n.max(1)),
+        }
+    }
+
+    /// the copy whose cell `pos` is in; points beyond the grid belong to the copy at its edge
+    pub fn instance(&self, pos: Vec3<T>) -> Instance {
+        let cell = Vec3::new(0, 1, 2).map(|axis| {
+            let last = T::from(self.count[axis] - 1).unwrap();
+            if self.spacing[axis] == T::zero() {
+                return 0;
+            }
+            (pos[axis] / self.spacing[axis] + last / T::from(2).unwrap())
+                .round()
+                .max(T::zero())
+                .min(last)
+                .to_usize()
+                .unwrap_or(0)
+        });
+        Instance {
+            cell,
+            count: self.count,
+        }
+    }
+
+    /// `pos` relative to the center of the copy whose cell it's in
+    fn local(&self, pos: Vec3<T>) -> Vec3<T> {
+        let cell = self.instance(pos).cell;
+        let offset = Vec3::new(0, 1, 2).map(|axis| {
+            T::from(cell[axis]).unwrap()
+                - T::from(self.count[axis] - 1).unwrap() / T::from(2).unwrap()
+        });
+        pos - offset * self.spacing
+    }
+}
+
+impl<T> Estimator<T> for Repeat<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(self.local(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        self.de.estimate_trapped(self.local(pos), trap)
+    }
+}
+
+/// Hollows a shape into a shell `thickness` thick, centered on its surface
+#[derive(Clone)]
+pub struct Onion<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    thickness: T,
+}
+
+impl<T> Onion<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, thickness: T) -> Self {
+        Self {
+            de: Box::new(de),
+            thickness,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Onion<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(pos).abs() - self.thickness / T::from(2).unwrap()
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (dist, trapped) = self.de.estimate_trapped(pos, trap);
+        (dist.abs() - self.thickness / T::from(2).unwrap(), trapped)
+    }
+}
+
+/// Rounds a shape's edges by `radius`, growing it outward by the same amount; shrink the shape by
+/// `radius` first to keep its size
+#[derive(Clone)]
+pub struct Round<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    radius: T,
+}
+
+impl<T> Round<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, radius: T) -> Self {
+        Self {
+            de: Box::new(de),
+            radius,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Round<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(pos) - self.radius
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (dist, trapped) = self.de.estimate_trapped(pos, trap);
+        (dist - self.radius, trapped)
+    }
+}
+
+/// Moves a shape out of its own frame: scales it by `scale` about the origin, rotates it by
+/// `rotation`, then translates it by `translation`
+#[derive(Clone)]
+pub struct Transformed<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    translation: Vec3<T>,
+    rotation: Quaternion<T>,
+    scale: T,
+}
+
+impl<T> Transformed<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(
+        de: GeometryEstimator<T>,
+        translation: Vec3<T>,
+        rotation: Quaternion<T>,
+        scale: T,
+    ) -> Self {
+        Self {
+            de: Box::new(de),
+            translation,
+            rotation: rotation.normalized(),
+            scale,
+        }
+    }
+
+    /// `pos` in the shape's own frame, undoing the transform
+    fn local(&self, pos: Vec3<T>) -> Vec3<T> {
+        quaternion::rotate(self.rotation.conjugate(), pos - self.translation) / self.scale
+    }
+
+    /// see `GeometryEstimator::intersect`; the ray is carried into the shape's frame with its
+    /// direction scaled down along with it, so distances along it come back unchanged
+    fn intersect(&self, pos: Vec3<T>, rot: Vec3<T>) -> Option<Option<T>> {
+        let local_rot = quaternion::rotate(self.rotation.conjugate(), rot) / self.scale;
+        self.de.intersect(self.local(pos), local_rot)
+    }
+}
+
+impl<T> Estimator<T> for Transformed<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        // distances in the shape's frame are scaled along with it
+        self.de.estimate(self.local(pos)) * self.scale
+    }
+
+    /// the trap is measured in the shape's own frame, so it moves along with the shape
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (dist, closest) = self.de.estimate_trapped(self.local(pos), trap);
+        (dist * self.scale, closest)
+    }
+}
+
+/// Roughens a shape's surface by raising or lowering it by up to `amplitude` with value noise
+/// (see `noise::value_noise`) whose features are about `1 / frequency` across
+#[derive(Clone)]
+pub struct Displace<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    amplitude: T,
+    frequency: T,
+    seed: u32,
+}
+
+impl<T> Displace<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, amplitude: T, frequency: T, seed: u32) -> Self {
+        Self {
+            de: Box::new(de),
+            amplitude,
+            frequency,
+            seed,
+        }
+    }
+
+    /// the shape's distance `dist` at `pos`, raised or lowered by the noise there
+    fn displace(&self, pos: Vec3<T>, dist: T) -> T {
+        let noise = noise::value_noise(pos * self.frequency, self.seed);
+        // the noise steepens the field; scale back down so we never step through the surface
+        let lipschitz = T::one()
+            + self.amplitude * self.frequency * T::from(noise::VALUE_NOISE_LIPSCHITZ).unwrap();
+        (dist + self.amplitude * noise) / lipschitz
+    }
+}
+
+impl<T> Estimator<T> for Displace<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.displace(pos, self.de.estimate(pos))
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (dist, closest) = self.de.estimate_trapped(pos, trap);
+        (self.displace(pos, dist), closest)
+    }
+}
+
+/// Melts a shape by moving every sample point by up to `amplitude` along each axis before
+/// evaluating it, with a separate `noise::fbm` field of `octaves` octaves per axis whose largest
+/// features are about `1 / frequency` across. Unlike `Displace`, which only raises and lowers the
+/// surface, this can drag whole parts of the shape sideways.
+#[derive(Clone)]
+pub struct Warp<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    amplitude: T,
+    frequency: T,
+    octaves: usize,
+    seed: u32,
+}
+
+impl<T> Warp<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(
+        de: GeometryEstimator<T>,
+        amplitude: T,
+        frequency: T,
+        octaves: usize,
+        seed: u32,
+    ) -> Self {
+        Self {
+            de: Box::new(de),
+            amplitude,
+            frequency,
+            octaves,
+            seed,
+        }
+    }
+
+    /// how far the warp moves `pos`
+    fn offset(&self, pos: Vec3<T>) -> Vec3<T> {
+        let p = pos * self.frequency;
+        // unrelated seeds for each axis, far enough apart that their octaves don't overlap
+        let axis = |k: u32| {
+            noise::fbm(
+                p,
+                self.octaves,
+                self.seed.wrapping_add(k.wrapping_mul(0x9e37_79b9)),
+            )
+        };
+        Vec3::new(axis(0), axis(1), axis(2)) * self.amplitude
+    }
+
+    /// how much the warp can stretch distances: moving nearby points apart stretches them by at
+    /// most its Lipschitz constant, and each of the three components changes at most as fast as
+    /// its fbm
+    fn lipschitz(&self) -> T {
+        T::one()
+            + self.amplitude
+                * self.frequency
+                * T::from(noise::fbm_lipschitz(self.octaves) * 3f64.sqrt()).unwrap()
+    }
+}
+
+impl<T> Estimator<T> for Warp<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        self.de.estimate(pos + self.offset(pos)) / self.lipschitz()
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let (dist, closest) = self.de.estimate_trapped(pos + self.offset(pos), trap);
+        (dist / self.lipschitz(), closest)
+    }
+}
+
+/// Skips an expensive estimator far away from its shape. Outside `bound`, which must contain the
+/// whole shape, the distance to the bound never overestimates the distance to the shape, so it's
+/// used instead until the point is within `margin` of the bound; `margin` must be larger than the
+/// geometry's ε, or rays will stop at the bound.
+#[derive(Clone)]
+pub struct Bounded<T: Float + Sum> {
+    de: Box<GeometryEstimator<T>>,
+    pub(crate) bound: Box<GeometryEstimator<T>>,
+    margin: T,
+}
+
+impl<T> Bounded<T>
+where
+    T: Float + Sum,
+{
+    pub fn new(de: GeometryEstimator<T>, bound: GeometryEstimator<T>, margin: T) -> Self {
+        Self {
+            de: Box::new(de),
+            bound: Box::new(bound),
+            margin,
+        }
+    }
+}
+
+impl<T> Estimator<T> for Bounded<T>
+where
+    T: Float + Sum,
+{
+    fn estimate(&self, pos: Vec3<T>) -> T {
+        let bound = self.bound.estimate(pos);
+        if bound > self.margin {
+            bound
+        } else {
+            self.de.estimate(pos)
+        }
+    }
+
+    fn estimate_trapped(&self, pos: Vec3<T>, trap: &OrbitTrap<T>) -> (T, Option<T>) {
+        let bound = self.bound.estimate(pos);
+        if bound > self.margin {
+            (bound, None)
+        } else {
+            self.de.estimate_trapped(pos, trap)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use pretty_assertions::{assert_eq, assert_ne};
+    use vek::{Extent2, Quaternion, Vec3};
+
+    use super::{
+        AffineMap, Bounded, Bounds, Capsule, Cuboid, Cylinder, DistanceLod, Elongate, Estimator,
+        Formula, Geometry, GeometryEstimator, HeightSource, Heightfield, Hybrid, HybridFormula,
+        Ifs, Instance, Intersection, Julia, JuliaVariant, Kleinian, Mandelbox, MarchStats,
+        MarchStep, Mirror, Morph, NormalMethod, Onion, OrbitTrap, Plane, QuaternionComponent,
+        QuaternionMandelbrot, QuaternionSlice, Repeat, Round, SdfGrid, Sierpinski, SmoothUnion,
+        Sphere, Symmetry, Taper, Torus, Tpms, TpmsKind, Transformed, Twist, Union, Warp,
+        ZoomQuality,
+    };
+
+    #[test]
+    fn sphere_test() {
+        let sphere = Sphere::new(Vec3::new(1.0, 0.0, 0.0), 0.5);
+        assert_eq!(sphere.estimate(Vec3::new(3.0, 0.0, 0.0)), 1.5);
+        assert_eq!(sphere.estimate(Vec3::new(1.0, 0.0, 0.0)), -0.5);
+    }
+
+    #[test]
+    fn cuboid_test() {
+        let cuboid = Cuboid::new(Vec3::zero(), Vec3::new(1.0, 2.0, 3.0), 0.0);
+        assert_eq!(cuboid.estimate(Vec3::new(2.0, 0.0, 0.0)), 1.0);
+        assert_eq!(cuboid.estimate(Vec3::new(0.0, 0.0, 0.0)), -1.0);
+        assert_eq!(cuboid.estimate(Vec3::new(4.0, 6.0, 0.0)), 5.0);
+
+        let rounded = Cuboid::new(Vec3::zero(), Vec3::new(1.0, 2.0, 3.0), 0.5);
+        assert_eq!(rounded.estimate(Vec3::new(2.0, 0.0, 0.0)), 1.0);
+        assert_eq!(rounded.estimate(Vec3::new(3.5, 5.5, 0.0)), 4.5);
+    }
+
+    #[test]
+    fn torus_test() {
+        let torus = Torus::new(Vec3::zero(), 2.0, 0.5);
+        assert_eq!(torus.estimate(Vec3::new(2.0, 0.0, 0.0)), -0.5);
+        assert_eq!(torus.estimate(Vec3::new(0.0, 0.0, -4.0)), 1.5);
+        assert_eq!(torus.estimate(Vec3::new(0.0, 0.0, 0.0)), 1.5);
+        assert_eq!(torus.estimate(Vec3::new(2.0, 1.0, 0.0)), 0.5);
+    }
+
+    #[test]
+    fn tpms_test() {
+        let gyroid = Tpms::new(TpmsKind::Gyroid, 2.0f64, 0.1);
+        // the origin is on the surface, in the middle of the sheet
+        assert_eq!(gyroid.estimate(Vec3::zero()), -0.05);
+        // and so is everywhere a whole number of periods away
+        assert!((gyroid.estimate(Vec3::new(2.0, -4.0, 6.0)) + 0.05).abs() < 1e-9);
+
+        let schwarz = Tpms::new(TpmsKind::SchwarzP, 4.0f64, 0.0);
+        // cos x + cos y + cos z = 0 a quarter period along each axis
+        assert!(schwarz.estimate(Vec3::broadcast(1.0)).abs() < 1e-9);
+        // the center of a cell is no further from the surface than that point
+        let center = schwarz.estimate(Vec3::zero());
+        assert!(center > 0.0 && center <= 3f64.sqrt());
+    }
+
+    #[test]
+    fn plane_test() {
+        let plane = Plane::new(Vec3::new(0.0, 2.0, 0.0), -1.0);
+        assert_eq!(plane.estimate(Vec3::new(5.0, 0.0, 3.0)), 1.0);
+        assert_eq!(plane.estimate(Vec3::new(5.0, -3.0, 3.0)), -2.0);
+    }
+
+    #[test]
+    fn capsule_test() {
+        let capsule = Capsule::new(Vec3::zero(), Vec3::new(0.0, 4.0, 0.0), 1.0);
+        assert_eq!(capsule.estimate(Vec3::new(3.0, 2.0, 0.0)), 2.0);
+        assert_eq!(capsule.estimate(Vec3::new(0.0, 7.0, 0.0)), 2.0);
+        assert_eq!(capsule.estimate(Vec3::new(0.0, -2.0, 0.0)), 1.0);
+    }
+
+    #[test]
+    fn cylinder_test() {
+        let cylinder = Cylinder::new(Vec3::zero(), 1.0, 4.0);
+        assert_eq!(cylinder.estimate(Vec3::new(3.0, 1.0, 0.0)), 2.0);
+        assert_eq!(cylinder.estimate(Vec3::new(0.0, 5.0, 0.0)), 3.0);
+        assert_eq!(cylinder.estimate(Vec3::new(4.0, 6.0, 0.0)), 5.0);
+        assert_eq!(cylinder.estimate(Vec3::zero()), -1.0);
+    }
+
+    #[test]
+    fn kleinian_test() {
+        // with a fold wider than the points and an inversion sphere inside them, no iteration
+        // moves them, so the estimate is the distance to the core cylinder's rim in z = 0
+        let kleinian = Kleinian::new(Vec3::<f64>::one(), 0.5, 0.6, 8);
+        assert_eq!(kleinian.estimate(Vec3::new(0.6, 0.0, 0.0)), 0.0);
+        assert!((kleinian.estimate(Vec3::new(0.9, 0.0, 0.0)) - 0.3).abs() < 1e-12);
+        let above = kleinian.estimate(Vec3::new(0.6, 0.0, 0.3));
+        assert!((above - 0.18 / 0.45f64.sqrt()).abs() < 1e-12);
+
+        let standard = Kleinian::new(
+            Vec3::from(Kleinian::<f64>::STANDARD_FOLD),
+            Kleinian::<f64>::STANDARD_INVERSION_RADIUS,
+            Kleinian::<f64>::STANDARD_CORE_RADIUS,
+            12,
+        );
+        for &pos in &[(3.0, 2.0, 1.0), (0.5, 0.5, 5.0), (-4.0, 1.0, -2.0)] {
+            let dist = standard.estimate(Vec3::from(pos));
+            assert!(dist.is_finite() && dist > 0.0);
+        }
+        // the folds and inversions keep the z axis on itself, and it's inside the core
+        // cylinder, so it's part of the surface
+        assert!(standard.estimate(Vec3::new(0.0, 0.0, 5.0)) <= 1e-6);
+    }
+
+    #[test]
+    fn sierpinski_test() {
+        let sierpinski = Sierpinski::new(2.0, 12);
+        // the tetrahedron's vertices are part of the fractal
+        for &v in &[
+            (1.0, 1.0, 1.0),
+            (-1.0, -1.0, 1.0),
+            (1.0, -1.0, -1.0),
+            (-1.0, 1.0, -1.0),
+        ] {
+            assert!(sierpinski.estimate(Vec3::from(v)) < 1e-3);
+        }
+        assert!(sierpinski.estimate(Vec3::new(5.0, 5.0, 5.0)) > 1.0);
+    }
+
+    #[test]
+    fn ifs_sierpinski_test() {
+        // the Sierpinski tetrahedron as a single IFS map matches the dedicated estimator
+        let map = AffineMap {
+            folds: vec![
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(1.0, 0.0, 1.0),
+                Vec3::new(0.0, 1.0, 1.0),
+            ],
+            rotation: Quaternion::identity(),
+            scale: 2.0,
+            translation: Vec3::broadcast(-1.0),
+        };
+        let ifs = Ifs::new(vec![map], 10);
+        let sierpinski = Sierpinski::new(2.0, 10);
+        for &v in &[(0.3, -0.2, 0.9), (2.0, 1.0, -1.0), (-0.5, 0.5, 0.1)] {
+            let v: Vec3<f64> = Vec3::from(v);
+            assert!((ifs.estimate(v) - sierpinski.estimate(v)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn mandelbox_test() {
+        // one iteration from (10, 0, 0): the box fold takes it to (-8, 0, 0), outside the
+        // sphere fold, then scaling and adding the point gives (-6, 0, 0) with a derivative of 3
+        let once = Mandelbox::new(2.0, 0.5, 1.0, 1);
+        assert_eq!(once.estimate(Vec3::new(10.0, 0.0, 0.0)), 2.0);
+        let mandelbox = Mandelbox::new(2.0, 0.5, 1.0, 12);
+        assert!(mandelbox.estimate(Vec3::new(10.0, 0.0, 0.0)) > 1.0);
+        // the origin is a fixed point, so it's in the set
+        assert_eq!(mandelbox.estimate(Vec3::zero()), 0.0);
+    }
+
+    #[test]
+    fn hybrid_mandelbox_test() {
+        // a hybrid of only Mandelbox steps is just a Mandelbox
+        let formula = HybridFormula::Mandelbox {
+            scale: -1.5,
+            min_radius: 0.5,
+            fold_limit: 1.0,
+        };
+        let hybrid = Hybrid::new(vec![formula.clone(), formula], 4);
+        let mandelbox = Mandelbox::new(-1.5, 0.5, 1.0, 8);
+        for &v in &[(0.3, -0.2, 0.9), (2.0, 1.0, -1.0), (-0.5, 0.5, 0.1)] {
+            let v: Vec3<f64> = Vec3::from(v);
+            assert!((hybrid.estimate(v) - mandelbox.estimate(v)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn julia_slice_test() {
+        let c = Quaternion::from_xyzw(-0.0410, -0.563, -0.560, -0.213);
+        let pos = Vec3::new(0.3, -0.2, 0.5);
+        // the default slice is the one the Julia estimator has always used
+        let default = Julia::new(c, 16, 2.0, QuaternionSlice::real_zero());
+        let real = Julia::new(
+            c,
+            16,
+            2.0,
+            QuaternionSlice {
+                fixed: QuaternionComponent::Real,
+                value: 0.0,
+            },
+        );
+        assert_eq!(default.estimate(pos), real.estimate(pos));
+
+        let slice = QuaternionSlice {
+            fixed: QuaternionComponent::J,
+            value: 0.25,
+        };
+        assert_eq!(
+            slice.quaternion(pos),
+            Quaternion::from_xyzw(-0.2, 0.25, 0.5, 0.3)
+        );
+        let sliced = Julia::new(c, 16, 2.0, slice);
+        assert_ne!(sliced.estimate(pos), default.estimate(pos));
+    }
+
+    #[test]
+    fn julia_power_test() {
+        let c = Quaternion::from_xyzw(-0.0410, -0.563, -0.560, -0.213);
+        let far = Vec3::new(2.0, 1.0, 0.5);
+        for &p in &[2.0, 2.5, 3.0, 7.25] {
+            let julia = Julia::new(c, 16, p, QuaternionSlice::real_zero());
+            // never more than the distance to the origin, which is inside the set's bounds
+            let dist = julia.estimate(far);
+            assert!(dist > 0.0 && dist < far.magnitude(), "power {}", p);
+        }
+        // with c = 0, the set is the unit ball, and the running derivative of every integer degree
+        // gives the same estimate, r ln r / 2, at radius r
+        for &p in &[2.0, 3.0, 4.0] {
+            let ball = Julia::new(Quaternion::zero(), 16, p, QuaternionSlice::real_zero());
+            let r: f64 = 1.5;
+            let dist = ball.estimate(Vec3::new(r, 0.0, 0.0));
+            assert!((dist - r * r.ln() / 2.0).abs() < 1e-9, "degree {}", p);
+        }
+        // a power of 1 would be a linear map, so it's treated as the usual 2
+        assert_eq!(
+            Julia::new(c, 16, 1.0, QuaternionSlice::real_zero()).estimate(far),
+            Julia::new(c, 16, 2.0, QuaternionSlice::real_zero()).estimate(far)
+        );
+    }
+
+    #[test]
+    fn julia_quadratic_test() {
+        let c = Quaternion::from_xyzw(-0.0410, -0.563, -0.560, -0.213);
+        let quadratic = Julia::new(c, 16, 2.0, QuaternionSlice::real_zero());
+        assert!(quadratic.quadratic);
+        // the general path, for comparison
+        let general = Julia {
+            quadratic: false,
+            ..quadratic.clone()
+        };
+        for &v in &[[0.3, -0.2, 0.5], [1.5, 0.0, 0.0], [0.1, 0.9, -0.4]] {
+            let v: Vec3<f64> = Vec3::from(v);
+            let (fast, slow) = (quadratic.estimate(v), general.estimate(v));
+            assert!((fast - slow).abs() <= 1e-12 * slow.abs().max(1.0));
+        }
+        assert!(!Julia::new(c, 16, 3.0, QuaternionSlice::real_zero()).quadratic);
+    }
+
+    #[test]
+    fn julia_variant_test() {
+        let c = Quaternion::from_xyzw(-0.0410, -0.563, -0.560, -0.213);
+        let standard = Julia::new(c, 16, 2.0, QuaternionSlice::real_zero());
+        let ship = standard.clone().with_variant(JuliaVariant::BurningShip);
+        // the fold only changes orbits which leave the positive orthant
+        let pos = Vec3::new(-0.3, 0.2, 0.5);
+        assert_ne!(ship.estimate(pos), standard.estimate(pos));
+        // mirroring the point mirrors q, which the fold then undoes
+        let mirrored = Vec3::new(0.3, -0.2, -0.5);
+        assert_eq!(ship.estimate(pos), ship.estimate(mirrored));
+        // c = 0 keeps every orbit in the positive orthant after the first fold, so it's still the
+        // unit ball
+        let ball = Julia::new(Quaternion::zero(), 16, 2.0, QuaternionSlice::real_zero())
+            .with_variant(JuliaVariant::BurningShip);
+        let r: f64 = 1.5;
+        assert!((ball.estimate(Vec3::new(-r, 0.0, 0.0)) - r * r.ln() / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quaternion_mandelbrot_test() {
+        let mandelbrot = QuaternionMandelbrot::new(Quaternion::zero(), 16);
+        // the set lies within 2 of the origin
+        let far = Vec3::new(2.0, 1.0, 0.5);
+        let dist = mandelbrot.estimate(far);
+        assert!(dist > 0.0 && dist < far.magnitude());
+        // c = 0.1i settles on a fixed point inside the unit ball, so the estimate is negative
+        assert!(mandelbrot.estimate(Vec3::new(0.1, 0.0, 0.0)) < 0.0);
+    }
+
+    #[test]
+    fn smooth_union_test() {
+        let a = Sphere::new(Vec3::new(-1.0, 0.0, 0.0), 0.75);
+        let b = Sphere::new(Vec3::new(1.0, 0.0, 0.0), 0.75);
+        let union = SmoothUnion::new(a.clone().into(), b.clone().into(), 0.5);
+        // far from the seam, it's just the nearer shape
+        let far: Vec3<f64> = Vec3::new(-3.0, 0.0, 0.0);
+        assert!((union.estimate(far) - a.estimate(far)).abs() < 1e-9);
+        // between the two, the blend bridges the gap
+        assert!(union.estimate(Vec3::zero()) < a.estimate(Vec3::zero()));
+    }
+
+    #[test]
+    fn morph_test() {
+        let a = Sphere::new(Vec3::zero(), 1.0);
+        let b = Cuboid::new(Vec3::zero(), Vec3::new(4.0, 4.0, 4.0), 0.0);
+        let p: Vec3<f64> = Vec3::new(3.0, 0.5, 0.0);
+        let morph = |t| Morph::new(a.clone().into(), b.clone().into(), t);
+        assert!((morph(0.0).estimate(p) - a.estimate(p)).abs() < 1e-9);
+        assert!((morph(1.0).estimate(p) - b.estimate(p)).abs() < 1e-9);
+        assert!(
+            (morph(0.25).estimate(p) - (0.75 * a.estimate(p) + 0.25 * b.estimate(p))).abs() < 1e-9
+        );
+        // extrapolating past b: 1.5b - 0.5a, slowed down by the steeper field
+        let past = (1.5 * b.estimate(p) - 0.5 * a.estimate(p)) / 2.0;
+        assert!((morph(1.5).estimate(p) - past).abs() < 1e-9);
+    }
+
+    #[test]
+    fn elongate_test() {
+        let sphere = Sphere::new(Vec3::zero(), 1.0);
+        let capsule = Elongate::new(sphere.clone().into(), Vec3::new(0.0, 4.0, 0.0));
+        // the sphere's ends move 2 up and down, and the side runs straight between them
+        let side: Vec3<f64> = Vec3::new(3.0, 1.5, 0.0);
+        assert!((capsule.estimate(side) - 2.0).abs() < 1e-9);
+        let top: Vec3<f64> = Vec3::new(0.0, 5.0, 0.0);
+        assert!((capsule.estimate(top) - 2.0).abs() < 1e-9);
+        // unstretched axes are untouched
+        let p: Vec3<f64> = Vec3::new(0.5, 0.0, 2.0);
+        assert!((capsule.estimate(p) - sphere.estimate(p)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn repeat_test() {
+        let sphere = Sphere::new(Vec3::zero(), 0.5);
+        let grid = Repeat::new(
+            sphere.clone().into(),
+            Vec3::new(2.0, 2.0, 2.0),
+            Vec3::new(3, 2, 1),
+        );
+        // three copies along x at -2, 0, and 2, and two along y at -1 and 1
+        let p: Vec3<f64> = Vec3::new(2.0, 1.0, 0.0);
+        assert!((grid.estimate(p) + 0.5).abs() < 1e-9);
+        assert_eq!(
+            grid.instance(p),
+            Instance {
+                cell: Vec3::new(2, 1, 0),
+                count: Vec3::new(3, 2, 1),
+            }
+        );
+        assert_eq!(grid.instance(p).fraction::<f64>(), 1.0);
+        assert_eq!(
+            grid.instance(Vec3::new(0.0, -1.0, 0.0)).fraction::<f64>(),
+            0.2
+        );
+        // beyond the grid, points are measured against the copy at its edge
+        let beyond: Vec3<f64> = Vec3::new(6.0, 1.0, 0.0);
+        assert!((grid.estimate(beyond) - 3.5).abs() < 1e-9);
+        // and transforms are looked through to find the copy
+        let moved: GeometryEstimator<f64> = Transformed::new(
+            grid.into(),
+            Vec3::new(0.0, 0.0, 10.0),
+            Quaternion::identity(),
+            1.0,
+        )
+        .into();
+        assert_eq!(
+            moved.instance(Vec3::new(-2.0, 1.0, 10.0)).map(|i| i.cell),
+            Some(Vec3::new(0, 1, 0))
+        );
+        assert_eq!(GeometryEstimator::from(sphere).instance(p), None);
+    }
+
+    #[test]
+    fn twist_test() {
+        let cuboid = Cuboid::new(Vec3::zero(), Vec3::new(1.0, 2.0, 0.25), 0.0);
+        let twist = Twist::new(cuboid.clone().into(), 0.5, Vec3::unit_y());
+        // no rotation at the base, only the stretch compensation
+        let v: Vec3<f64> = Vec3::new(0.5, 0.0, 0.1);
+        let stretch = (1.0 + 0.25 * v.magnitude_squared()).sqrt();
+        assert!((twist.estimate(v) - cuboid.estimate(v) / stretch).abs() < 1e-9);
+        // the twisted box is still a lower bound for the distance to its surface
+        let far: Vec3<f64> = Vec3::new(3.0, 1.0, 0.0);
+        assert!(twist.estimate(far) > 0.0);
+        assert!(twist.estimate(far) <= far.magnitude());
+    }
+
+    #[test]
+    fn taper_test() {
+        let cylinder = Cylinder::new(Vec3::zero(), 1.0, 4.0);
+        let taper = Taper::new(cylinder.clone().into(), 0.25, Vec3::unit_y());
+        // unscaled at the base, only the slant compensation
+        let v: Vec3<f64> = Vec3::new(0.5, 0.0, 0.0);
+        assert!((taper.estimate(v) - cylinder.estimate(v) / 1.125).abs() < 1e-9);
+        // the radius is 1.25 at y = 1 and 0.75 at y = -1, and the estimate never overshoots the
+        // horizontal distance to the side
+        for &(x, y) in &[(2.0, 1.0), (2.0, -1.0), (2.0, 1.5), (3.0, 0.5)] {
+            let radius = 1.0 + 0.25 * y;
+            let dist = taper.estimate(Vec3::new(x, y, 0.0));
+            assert!(dist > 0.0 && dist <= x - radius, "({}, {})", x, y);
+        }
+        assert!(taper.estimate(Vec3::new(1.1, 1.0, 0.0)) < 0.0);
+        assert!(taper.estimate(Vec3::new(0.9, -1.0, 0.0)) > 0.0);
+    }
+
+    #[test]
+    fn mirror_test() {
+        let sphere = Sphere::new(Vec3::new(1.0, 0.0, 0.0), 0.5);
+        let mirror = Mirror::new(sphere.into(), Vec3::unit_x(), 0.0);
+        // the sphere's reflection appears at x = -1
+        assert_eq!(mirror.estimate(Vec3::new(-1.0, 0.0, 0.0)), -0.5);
+        assert_eq!(mirror.estimate(Vec3::new(1.0, 0.0, 0.0)), -0.5);
+        assert_eq!(mirror.estimate(Vec3::new(-3.0, 0.0, 0.0)), 1.5);
+    }
+
+    #[test]
+    fn warp_test() {
+        let floor: GeometryEstimator<f64> = Plane::new(Vec3::unit_y(), 0.0).into();
+        let still = Warp::new(floor.clone(), 0.0, 1.0, 3, 7);
+        let p = Vec3::new(0.3, 2.0, -1.2);
+        assert_eq!(still.estimate(p), floor.estimate(p));
+
+        let warp = Warp::new(floor, 0.5, 0.8, 3, 7);
+        // the floor now crosses each vertical line somewhere within 0.5 of y = 0
+        for i in 0..20 {
+            let i = f64::from(i);
+            let (x, z) = (i * 0.31, -i * 0.17);
+            let crossing = (0..=1000)
+                .map(|j| -0.6 + f64::from(j) * 0.0012)
+                .find(|&y| warp.estimate(Vec3::new(x, y, z)) > 0.0)
+                .unwrap();
+            assert!(crossing.abs() <= 0.5 + 0.0012, "crossing at {}", crossing);
+            // never further than the crossing straight below
+            for &y in &[1.0, 2.5] {
+                let dist = warp.estimate(Vec3::new(x, y, z));
+                assert!(
+                    dist > 0.0 && dist <= y - crossing,
+                    "{} at height {}",
+                    dist,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn symmetry_test() {
+        let sphere = Sphere::new(Vec3::new(2.0f64, 0.0, 0.0), 0.5);
+        let symmetry = Symmetry::new(sphere.into(), 4, Vec3::unit_y());
+        // copies a quarter turn apart around y
+        for &(x, z) in &[(2.0, 0.0), (0.0, 2.0), (-2.0, 0.0), (0.0, -2.0)] {
+            let dist = symmetry.estimate(Vec3::new(x, 0.0, z));
+            assert!((dist + 0.5).abs() < 1e-9, "({}, {})", x, z);
+        }
+        // but not an eighth
+        let diagonal = 2f64.sqrt();
+        assert!(symmetry.estimate(Vec3::new(diagonal, 0.0, diagonal)) > 1.0);
+        // the axis's height is left alone
+        assert!((symmetry.estimate(Vec3::new(0.0, 1.0, -2.0)) - 0.5).abs() < 1e-9);
+
+        let single = Symmetry::new(Sphere::new(Vec3::zero(), 1.0f64).into(), 0, Vec3::unit_x());
+        assert!((single.estimate(Vec3::new(0.0, 0.0, 3.0)) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn heightfield_test() {
+        // a ramp from -1 at -x to 1 at +x, 4 units across
+        let ramp = Heightfield::new(
+            HeightSource::Grid {
+                heights: vec![-1.0, 1.0, -1.0, 1.0],
+                resolution: Extent2::new(2, 2),
+                size: Extent2::new(4.0, 4.0),
+            },
+            1.0,
+        );
+        assert_eq!(ramp.height(0.0, 1.0), 0.0);
+        assert_eq!(ramp.height(1.0, 0.0), 0.5);
+        // extended past its edges
+        assert_eq!(ramp.height(10.0, 0.0), 1.0);
+        // a slope of 1/2 shortens the vertical distance to the perpendicular one
+        let d: f64 = ramp.estimate(Vec3::new(0.0, 1.0, 0.0));
+        assert!((d - 1.0 / 1.25f64.sqrt()).abs() < 1e-9);
+
+        let terrain = Heightfield::new(
+            HeightSource::Noise {
+                frequency: 0.5,
+                octaves: 4,
+                seed: 1,
+            },
+            2.0,
+        );
+        for i in 0..50 {
+            let p = Vec3::new(f64::from(i) * 0.7, 3.0, f64::from(i) * -0.3);
+            let d = terrain.estimate(p);
+            // never past the surface straight below
+            assert!(d <= p.y - terrain.height(p.x, p.z) + 1e-12);
+            assert!(d > 0.0);
+        }
+    }
+
+    #[test]
+    fn onion_round_test() {
+        let sphere: Sphere<f64> = Sphere::new(Vec3::zero(), 1.0);
+        let onion = Onion::new(sphere.clone().into(), 0.2);
+        // a shell from radius 0.9 to 1.1, hollow in the middle
+        assert!((onion.estimate(Vec3::new(1.0, 0.0, 0.0)) + 0.1).abs() < 1e-9);
+        assert!((onion.estimate(Vec3::zero()) - 0.9).abs() < 1e-9);
+        assert!((onion.estimate(Vec3::new(2.0, 0.0, 0.0)) - 0.9).abs() < 1e-9);
+
+        let cuboid: Cuboid<f64> = Cuboid::new(Vec3::zero(), Vec3::broadcast(1.0), 0.0);
+        let round = Round::new(cuboid.into(), 0.25);
+        assert!((round.estimate(Vec3::new(2.0, 0.0, 0.0)) - 0.75).abs() < 1e-9);
+        // along the diagonal, the surface is `radius` out from the box's corner
+        let corner = Vec3::broadcast(1.0 + 0.25 / 3f64.sqrt());
+        assert!(round.estimate(corner).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transformed_test() {
+        let cuboid = Cuboid::new(Vec3::zero(), Vec3::new(1.0, 0.5, 0.5), 0.0);
+        let transformed = Transformed::new(
+            cuboid.into(),
+            Vec3::new(0.0, 5.0, 0.0),
+            Quaternion::rotation_z(std::f64::consts::PI / 2.0),
+            2.0,
+        );
+        // the box is now 2 wide in x and 4 tall in y, centered at y = 5
+        assert!((transformed.estimate(Vec3::new(0.0, 8.0, 0.0)) - 1.0).abs() < 1e-9);
+        assert!((transformed.estimate(Vec3::new(3.0, 5.0, 0.0)) - 2.0).abs() < 1e-9);
+        assert!((transformed.estimate(Vec3::new(0.0, 5.0, 0.0)) + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn intersect_test() {
+        let left = Vec3::new(-5.0, 0.0, 0.0);
+        let sphere: GeometryEstimator<f64> = Sphere::new(Vec3::zero(), 2.0).into();
+        assert_eq!(sphere.intersect(left, Vec3::unit_x()), Some(Some(3.0)));
+        assert_eq!(sphere.intersect(left, -Vec3::unit_x()), Some(None));
+        // from inside, the ray meets the surface where it leaves
+        assert_eq!(
+            sphere.intersect(Vec3::zero(), Vec3::unit_x()),
+            Some(Some(2.0))
+        );
+        let plane: GeometryEstimator<f64> = Plane::new(Vec3::unit_z(), 1.0).into();
+        let down = -Vec3::unit_z();
+        assert_eq!(
+            plane.intersect(Vec3::new(0.0, 0.0, 4.0), down),
+            Some(Some(3.0))
+        );
+        assert_eq!(plane.intersect(Vec3::zero(), down), Some(None));
+        assert_eq!(
+            plane.intersect(Vec3::zero(), Vec3::unit_z()),
+            Some(Some(1.0))
+        );
+        assert_eq!(plane.intersect(Vec3::zero(), Vec3::unit_x()), Some(None));
+        // the transformed box from `transformed_test`
+        let cuboid = Cuboid::new(Vec3::zero(), Vec3::new(1.0, 0.5, 0.5), 0.0);
+        let transformed: GeometryEstimator<f64> = Transformed::new(
+            cuboid.into(),
+            Vec3::new(0.0, 5.0, 0.0),
+            Quaternion::rotation_z(std::f64::consts::PI / 2.0),
+            2.0,
+        )
+        .into();
+        let t = transformed.intersect(Vec3::zero(), Vec3::unit_y()).unwrap();
+        assert!((t.unwrap() - 3.0).abs() < 1e-9);
+        // rounded boxes and everything else are marched
+        let rounded: GeometryEstimator<f64> = Cuboid::new(Vec3::zero(), Vec3::one(), 0.1).into();
+        assert_eq!(rounded.intersect(left, Vec3::unit_x()), None);
+        let torus: GeometryEstimator<f64> = Torus::new(Vec3::zero(), 2.0, 0.5).into();
+        assert_eq!(torus.intersect(left, Vec3::unit_x()), None);
+
+        let mut geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: transformed,
+        };
+        let hit = geom.estimate(Vec3::zero(), Vec3::unit_y()).unwrap();
+        let marched = geom.march(Vec3::zero(), Vec3::unit_y(), 0.0, |_| ()).hit();
+        assert!((hit - marched.unwrap()).magnitude() < 1e-5);
+        // hits past the cutoff are still misses
+        geom.cutoff = 2.0;
+        assert_eq!(geom.estimate(Vec3::zero(), Vec3::unit_y()), None);
+        // and starting on the surface is a hit there
+        geom.cutoff = 100.0;
+        let surface = Vec3::new(0.0, 3.0, 0.0);
+        assert_eq!(geom.hit_distance(surface, Vec3::unit_y()), Some(0.0));
+    }
+
+    #[test]
+    fn orbit_trap_test() {
+        let plane = OrbitTrap::Plane {
+            normal: Vec3::new(0.0, 2.0, 0.0),
+            offset: 1.0,
+        };
+        assert_eq!(plane.distance(Vec3::new(5.0, -1.0, 0.0)), 2.0);
+        let axis = OrbitTrap::Axis {
+            direction: Vec3::unit_z(),
+        };
+        assert_eq!(axis.distance(Vec3::new(3.0, 4.0, 9.0)), 5.0);
+
+        let trap = OrbitTrap::Point {
+            point: Vec3::zero(),
+        };
+        // two iterations from (0.3, 0.2, 0.1): the sphere fold scales it by 4 to (2.7, 1.8, 0.9),
+        // then the box fold takes that to (-0.7, 0.2, 0.9), and (-1.1, 0.6, 1.9) is nearer
+        let mandelbox = Mandelbox::new(2.0, 0.5, 1.0, 2);
+        let pos = Vec3::new(0.3, 0.2, 0.1);
+        let (dist, closest) = mandelbox.estimate_trapped(pos, &trap);
+        assert_eq!(dist, mandelbox.estimate(pos));
+        assert!((closest.unwrap() - 5.18f64.sqrt()).abs() < 1e-12);
+        // shapes which don't iterate have no orbit
+        let sphere = Sphere::new(Vec3::zero(), 1.0);
+        assert_eq!(sphere.estimate_trapped(pos, &trap).1, None);
+
+        // combinations take the orbit of the shape whose surface is nearest
+        let far = GeometryEstimator::from(Sphere::new(Vec3::new(10.0, 0.0, 0.0), 1.0));
+        let union = Union::new(mandelbox.clone().into(), far.clone());
+        assert_eq!(union.estimate_trapped(pos, &trap), (dist, closest));
+        let intersection = Intersection::new(mandelbox.clone().into(), far);
+        assert_eq!(intersection.estimate_trapped(pos, &trap).1, None);
+        let mirror = Mirror::new(mandelbox.into(), Vec3::unit_x(), 0.0);
+        assert_eq!(
+            mirror.estimate_trapped(Vec3::new(-0.3, 0.2, 0.1), &trap).1,
+            closest
+        );
+    }
+
+    #[test]
+    fn bounded_test() {
+        let mandelbox = Mandelbox::new(2.0, 0.5, 1.0, 8);
+        // the scale 2 mandelbox fits in a cube 12 wide, so within a radius of 6√3
+        let bounded = Bounded::new(
+            mandelbox.clone().into(),
+            Sphere::new(Vec3::zero(), 11.0).into(),
+            0.1,
+        );
+        assert_eq!(bounded.estimate(Vec3::new(20.0, 0.0, 0.0)), 9.0);
+        let inside = Vec3::new(3.0, 1.0, 0.5);
+        assert_eq!(bounded.estimate(inside), mandelbox.estimate(inside));
+    }
+
+    #[test]
+    fn formula_test() {
+        // with c = 0, q ← q² + c keeps the unit ball and sends everything else off to infinity
+        let formula = Formula::new("q * q + c".parse().unwrap(), Some(Quaternion::zero()), 16);
+        let outside = formula.estimate(Vec3::new(2.0, 0.0, 0.0));
+        assert!(outside > 0.0 && outside <= 1.0);
+        assert!(formula.estimate(Vec3::new(0.5, 0.0, 0.0)) <= 0.0);
+        // an orbit which doesn't depend on the point has no gradient
+        let constant = Formula::new(
+            "c".parse().unwrap(),
+            Some(Quaternion::from_xyzw(2.0, 0.0, 0.0, 0.0)),
+            16,
+        );
+        assert_eq!(constant.estimate(Vec3::new(2.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn empty_sdf_grid_test() {
+        let grid = SdfGrid::<f64>::new(
+            Vec3::zero(),
+            Vec3::one(),
+            Vec3::zero(),
+            Arc::new(Vec::new()),
+        );
+        assert_eq!(grid.estimate(Vec3::zero()), f64::INFINITY);
+    }
+
+    #[test]
+    fn estimate_checked_test() {
+        let geom = |de| Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de,
+        };
+        let sphere = geom(Sphere::new(Vec3::zero(), 1.0).into());
+        let pos = Vec3::new(0.0, 0.0, -5.0);
+        assert_eq!(
+            sphere.estimate_checked(pos, Vec3::unit_z()),
+            Ok(sphere.estimate(pos, Vec3::unit_z()))
+        );
+        // a degenerate shape whose estimate is NaN everywhere
+        let nan = geom(Sphere::new(Vec3::zero(), f64::NAN).into());
+        let err = nan.estimate_checked(pos, Vec3::unit_z()).unwrap_err();
+        assert_eq!(err.pos, pos);
+        assert!(err.dist.is_nan());
+    }
+
+    #[test]
+    fn estimate_with_stats_test() {
+        let geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Sphere::new(Vec3::zero(), 1.0).into(),
+        };
+        // straight at the sphere: one step to the surface, one to confirm the hit
+        let (hit, stats) = geom.estimate_with_stats(Vec3::new(0.0, 0.0, -5.0), Vec3::unit_z());
+        assert_eq!(hit, Some(Vec3::new(0.0, 0.0, -1.0)));
+        assert_eq!(
+            stats,
+            MarchStats {
+                steps: 2,
+                closest: 0.0,
+                floored: 0,
+                out_of_evaluations: false,
+            }
+        );
+        // passing 1 unit from the sphere's surface
+        let (hit, stats) = geom.estimate_with_stats(Vec3::new(0.0, 2.0, -5.0), Vec3::unit_z());
+        assert_eq!(hit, None);
+        // only as close as the samples the march happened to take
+        assert!(stats.closest >= 1.0 && stats.closest < 1.1);
+        assert_eq!(
+            stats.steps,
+            geom.march_trace(Vec3::new(0.0, 2.0, -5.0), Vec3::unit_z())
+                .len()
+        );
+    }
+
+    #[test]
+    fn min_step_test() {
+        let mut geom = Geometry {
+            max_steps: 1000,
+            max_evaluations: None,
+            epsilon: 0.01,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 0.01,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Plane::new(Vec3::unit_z(), 0.0).into(),
+        };
+        // grazing a plane, each step only gets a tenth closer
+        let pos = Vec3::new(0.0, 0.0, 1.0);
+        let rot = Vec3::new(1.0, 0.0, -0.1).normalized();
+        let (creep, creep_stats) = geom.estimate_with_stats(pos, rot);
+        assert!(creep.is_some());
+        assert_eq!(creep_stats.floored, 0);
+
+        geom.min_step = Some(20.0);
+        let (hit, stats) = geom.estimate_with_stats(pos, rot);
+        assert!(hit.is_some());
+        assert!(stats.floored > 0);
+        assert!(stats.steps < creep_stats.steps);
+        let trace = geom.march_trace(pos, rot);
+        assert!(trace
+            .iter()
+            .filter(|s| s.floored)
+            .all(|s| s.step == 0.2 && s.dist > 0.01));
+
+        // a budget of estimates gives up early, and says so
+        geom.min_step = None;
+        geom.max_evaluations = Some(5);
+        let (hit, stats) = geom.estimate_with_stats(pos, rot);
+        assert_eq!(hit, None);
+        assert_eq!(stats.steps, 5);
+        assert!(stats.out_of_evaluations);
+        // running out of steps isn't running out of the budget
+        geom.max_evaluations = None;
+        geom.max_steps = 5;
+        let (hit, stats) = geom.estimate_with_stats(pos, rot);
+        assert_eq!(hit, None);
+        assert!(!stats.out_of_evaluations);
+    }
+
+    #[test]
+    fn epsilon_scale_test() {
+        let mut geom = Geometry {
+            max_steps: 1000,
+            max_evaluations: None,
+            epsilon: 1e-4,
+            epsilon_scale: None,
+            cutoff: 1000.0,
+            sample_size: 1e-4,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Plane::new(Vec3::unit_z(), 0.0).into(),
+        };
+        // a ray grazing a distant plane
+        let pos = Vec3::new(0.0, 0.0, 10.0);
+        let rot = Vec3::new(1.0, 0.0, -0.05).normalized();
+        let (fixed, fixed_stats) = geom.estimate_with_stats(pos, rot);
+        assert!(fixed.is_some());
+
+        geom.epsilon_scale = Some(1e-3);
+        assert_eq!(geom.epsilon_at(0.0), 1e-4);
+        assert!((geom.epsilon_at(100.0) - 0.1001_f64).abs() < 1e-9);
+        let (scaled, stats) = geom.estimate_with_stats(pos, rot);
+        assert!(stats.steps * 2 < fixed_stats.steps);
+        // the hit is only as far off the surface as ε is out there
+        let (fixed, scaled) = (fixed.unwrap(), scaled.unwrap());
+        assert!(scaled.z <= geom.epsilon_at((scaled - pos).magnitude()));
+        assert!((scaled - fixed).magnitude() < 5.0);
+
+        // zooming shrinks the growth along with ε
+        geom.zoom_quality = Some(ZoomQuality {
+            epsilon_per_octave: 0.5,
+            iterations_per_octave: 0.0,
+        });
+        assert_eq!(geom.at_zoom(4.0).epsilon_scale, Some(2.5e-4));
+    }
+
+    #[test]
+    fn at_zoom_test() {
+        let geom: Geometry<f64> = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-3,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-4,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: Some(ZoomQuality {
+                epsilon_per_octave: 0.5,
+                iterations_per_octave: 2.0,
+            }),
+            lod: None,
+            bounds: None,
+            de: Mandelbox::new(2.0, 0.5, 1.0, 8).into(),
+        };
+        // three octaves in: ε and the sample size halve three times, and six iterations are added
+        let zoomed = geom.at_zoom(8.0);
+        assert_eq!(zoomed.epsilon, 1.25e-4);
+        assert_eq!(zoomed.sample_size, 1.25e-5);
+        assert_eq!(zoomed.de.iterations(), Some(14));
+        // zooming out takes them away, down to a single iteration
+        let out = geom.at_zoom(0.5);
+        assert_eq!(out.epsilon, 2e-3);
+        assert_eq!(out.de.iterations(), Some(6));
+        assert_eq!(geom.at_zoom(2f64.powi(-10)).de.iterations(), Some(1));
+        // no zoom at all is left alone
+        for &zoom in &[0.0, -2.0, f64::NAN, f64::INFINITY] {
+            let same = geom.at_zoom(zoom);
+            assert_eq!(same.epsilon, geom.epsilon);
+            assert_eq!(same.sample_size, geom.sample_size);
+            assert_eq!(same.de.iterations(), Some(8));
+        }
+    }
+
+    #[test]
+    fn refine_test() {
+        let mut geom: Geometry<f64> = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 0.05,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Torus::new(Vec3::zero(), 2.0, 0.5).into(),
+        };
+        // a ray skimming the top of the tube, so the march stops well short of the surface
+        let pos = Vec3::new(-5.0, 0.45, 0.0);
+        let rot = Vec3::unit_x();
+        let coarse = geom.estimate(pos, rot).unwrap();
+        assert!(geom.de.estimate(coarse) > 1e-3);
+        assert!(geom.de.estimate(coarse) <= 0.05);
+
+        geom.refine_steps = Some(4);
+        let refined = geom.estimate(pos, rot).unwrap();
+        assert!(geom.de.estimate(refined).abs() < 1e-6);
+        // refinement only moves the hit along the ray
+        let along = (refined - pos).normalized();
+        assert!((along - rot).magnitude() < 1e-9);
+        assert!((refined - coarse).magnitude() < 0.1);
+    }
+
+    #[test]
+    fn interior_test() {
+        let geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Sphere::new(Vec3::zero(), 2.0).into(),
+        };
+        // from the center, the ray marches out to the far side of the surface
+        let center = Vec3::zero();
+        assert_eq!(geom.hit_distance(center, Vec3::unit_x()), Some(2.0));
+        assert_eq!(
+            geom.estimate_checked(center, Vec3::unit_x()),
+            Ok(Some(Vec3::new(2.0, 0.0, 0.0)))
+        );
+        let trace = geom.march_trace(Vec3::new(-1.0, 0.0, 0.0), Vec3::unit_x());
+        assert_eq!(trace[0].dist, 1.0);
+        assert_eq!(trace.last().unwrap().total, 3.0);
+        // from outside, it still stops at the near side
+        let outside = Vec3::new(-5.0, 0.0, 0.0);
+        assert_eq!(geom.hit_distance(outside, Vec3::unit_x()), Some(3.0));
+    }
+
+    #[test]
+    fn march_trace_test() {
+        let geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Plane::new(Vec3::unit_z(), 0.0).into(),
+        };
+        let pos = Vec3::new(0.0, 0.0, 3.0);
+        let trace = geom.march_trace(pos, -Vec3::unit_z());
+        // straight at a plane, the first step lands exactly on it
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].pos, pos);
+        assert_eq!(trace[0].dist, 3.0);
+        assert_eq!(trace[1].total, 3.0);
+        assert_eq!(Some(trace[1].pos), geom.estimate(pos, -Vec3::unit_z()));
+    }
+
+    #[test]
+    fn bounds_test() {
+        let sphere = Bounds::Sphere {
+            center: Vec3::zero(),
+            radius: 2.0,
+        };
+        let down = -Vec3::unit_z();
+        assert_eq!(
+            sphere.clip(Vec3::new(0.0, 0.0, 5.0), down),
+            Some((3.0, 7.0))
+        );
+        assert_eq!(sphere.clip(Vec3::new(3.0, 0.0, 5.0), down), None);
+        let cuboid = Bounds::Box {
+            center: Vec3::new(0.0, 0.0, 1.0),
+            size: Vec3::new(2.0, 2.0, 2.0),
+        };
+        let diagonal = Vec3::new(1.0, 0.0, -1.0);
+        assert_eq!(
+            cuboid.clip(Vec3::new(-1.5, 0.0, 2.5), diagonal),
+            Some((0.5, 2.5))
+        );
+        assert_eq!(cuboid.clip(Vec3::new(2.0, 0.0, 1.0), down), None);
+
+        let mut geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: Some(sphere),
+            de: Plane::new(Vec3::unit_z(), 0.0).into(),
+        };
+        // marching starts where the ray enters the bounds
+        let trace = geom.march_trace(Vec3::new(0.0, 0.0, 5.0), down);
+        assert_eq!(trace[0].total, 3.0);
+        assert_eq!(trace.len(), 2);
+        // the plane goes on past the bounds, but rays missing them aren't marched at all
+        let pos = Vec3::new(3.0, 0.0, 5.0);
+        assert!(geom.march_trace(pos, down).is_empty());
+        assert_eq!(geom.estimate_checked(pos, down), Ok(None));
+        geom.bounds = None;
+        assert_eq!(geom.hit_distance(pos, down), Some(5.0));
+    }
+
+    #[test]
+    fn normal_method_test() {
+        let mut geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Torus::new(Vec3::zero(), 2.0, 0.5).into(),
+        };
+        let pos = Vec3::new(2.4, 0.3, 0.0);
+        let central = geom.normal(pos);
+        geom.normal_method = NormalMethod::Tetrahedral;
+        let tetrahedral = geom.normal(pos);
+        assert!((central - Vec3::new(0.8, 0.6, 0.0)).magnitude() < 1e-6);
+        assert!((tetrahedral - central).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn relaxation_test() {
+        let mut geom = Geometry {
+            max_steps: 1024,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 1000.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Plane::new(Vec3::unit_y(), 0.0).into(),
+        };
+        // a ray grazing a plane shrinks its steps only slowly
+        let pos = Vec3::new(0.0, 1.0, 0.0);
+        let rot = Vec3::new(1.0, -0.05, 0.0).normalized();
+        let plain = geom.march_trace(pos, rot);
+        geom.relaxation = Some(1.6);
+        let relaxed = geom.march_trace(pos, rot);
+        assert!(relaxed.len() * 3 < plain.len() * 2);
+        let hit = |steps: &[MarchStep<f64>]| steps.last().unwrap().pos;
+        assert!((hit(&relaxed) - hit(&plain)).magnitude() < 1e-4);
+        // planes are hit exactly rather than marched, at most ε from where the march stops
+        assert!((geom.estimate(pos, rot).unwrap() - hit(&relaxed)).magnitude() < 1e-4);
+
+        // straight at the plane, the first relaxed step overshoots and falls back
+        let head_on = geom.march_trace(pos, -Vec3::unit_y());
+        assert_eq!(head_on.last().unwrap().pos, Vec3::zero());
+    }
+
+    #[test]
+    fn step_scale_test() {
+        let mut geom = Geometry {
+            max_steps: 1024,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Sphere::new(Vec3::zero(), 1.0).into(),
+        };
+        let pos = Vec3::new(0.0, 0.0, -5.0);
+        let full = geom.march_trace(pos, Vec3::unit_z());
+        geom.step_scale = Some(0.5);
+        let halved: Vec<MarchStep<f64>> = geom.march_trace(pos, Vec3::unit_z());
+        // shorter steps take longer to reach the same surface
+        assert!(halved.len() > full.len());
+        assert_eq!(halved[0].step, 2.0);
+        assert!((halved.last().unwrap().pos.z + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn lod_iterations_test() {
+        let mut geom = Geometry {
+            max_steps: 256,
+            max_evaluations: None,
+            epsilon: 1e-3,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-3,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: Some(DistanceLod {
+                near: 2.0,
+                iterations_per_octave: 3.0,
+                min_iterations: 6,
+            }),
+            bounds: None,
+            de: Mandelbox::new(2.0, 0.5, 1.0, 12).into(),
+        };
+        assert_eq!(geom.lod_iterations(1.0), None);
+        assert_eq!(geom.lod_iterations(4.0), Some(9));
+        assert_eq!(geom.lod_iterations(8.0), Some(6));
+        assert_eq!(geom.lod_iterations(1000.0), Some(6));
+        // the box is about 12 units across, so a ray from 20 units away hits it beyond `near`
+        let pos = Vec3::new(0.0, 0.0, -20.0);
+        assert!(geom.estimate(pos, Vec3::unit_z()).is_some());
+
+        // shapes which don't iterate have nothing to reduce
+        geom.de = Sphere::new(Vec3::zero(), 1.0).into();
+        assert_eq!(geom.lod_iterations(1000.0), None);
+    }
+
+    #[test]
+    fn sphere_hit_distance_test() {
+        let geom = Geometry {
+            max_steps: 64,
+            max_evaluations: None,
+            epsilon: 1e-6,
+            epsilon_scale: None,
+            cutoff: 100.0,
+            sample_size: 1e-6,
+            normal_method: NormalMethod::Central,
+            relaxation: None,
+            step_scale: None,
+            min_step: None,
+            refine_steps: None,
+            zoom_quality: None,
+            lod: None,
+            bounds: None,
+            de: Sphere::new(Vec3::new(1.0, 0.0, 0.0), 0.5).into(),
+        };
+        let dist: f64 = geom
+            .hit_distance(Vec3::new(-5.0, 0.0, 0.0), Vec3::unit_x())
+            .unwrap();
+        assert!((dist - 5.5).abs() < 1e-5);
+        assert_eq!(
+            geom.hit_distance(Vec3::new(-5.0, 0.0, 0.0), -Vec3::unit_x()),
+            None
+        );
     }
 }
@@ -0,0 +1,153 @@
+//! Process exit statuses and error reports, so scripts and render farms can tell failures apart.
+use std::str::FromStr;
+
+use crate::filename::FrameError;
+use crate::img::{SizeError, UnsupportedOutput};
+use crate::matrix::RenderError;
+use crate::messages::{Locale, Message};
+use crate::serialize::SceneDeserializeErr;
+
+/// Why a run failed; each kind exits with its own status
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Failure {
+    /// the scene file isn't valid YAML or doesn't match the schema
+    SceneParse,
+    /// the scene parsed but refers to things that don't exist, or can't be set up
+    Validation,
+    /// reading the scene or writing an image failed
+    Io,
+    /// the render was stopped before it finished
+    Cancelled,
+    /// some renders of a batch succeeded and some failed
+    PartialSuccess,
+}
+
+impl Failure {
+    /// the process exit status; 1 is left to clap's argument errors
+    pub fn code(self) -> i32 {
+        match self {
+            Failure::SceneParse => 2,
+            Failure::Validation => 3,
+            Failure::Io => 4,
+            Failure::Cancelled => 5,
+            Failure::PartialSuccess => 6,
+        }
+    }
+
+    /// a stable name for the failure, for machine-readable reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Failure::SceneParse => "scene_parse",
+            Failure::Validation => "validation",
+            Failure::Io => "io",
+            Failure::Cancelled => "cancelled",
+            Failure::PartialSuccess => "partial_success",
+        }
+    }
+
+    /// the report to print to stderr for this failure
+    pub fn report(self, message: &str, format: ErrorFormat) -> String {
+        match format {
+            ErrorFormat::Text => format!("error: {}", message),
+            ErrorFormat::Json => format!(
+                r#"{{"error":"{}","code":{},"message":"{}"}}"#,
+                self.name(),
+                self.code(),
+                json_escape(message)
+            ),
+        }
+    }
+}
+
+impl From<&SceneDeserializeErr> for Failure {
+    fn from(_: &SceneDeserializeErr) -> Self {
+        Failure::Validation
+    }
+}
+
+impl From<&UnsupportedOutput> for Failure {
+    fn from(_: &UnsupportedOutput) -> Self {
+        Failure::Validation
+    }
+}
+
+impl From<&SizeError> for Failure {
+    fn from(_: &SizeError) -> Self {
+        Failure::Validation
+    }
+}
+
+impl From<&RenderError> for Failure {
+    fn from(_: &RenderError) -> Self {
+        Failure::Validation
+    }
+}
+
+impl From<&FrameError> for Failure {
+    fn from(_: &FrameError) -> Self {
+        Failure::Validation
+    }
+}
+
+impl From<&serde_yaml::Error> for Failure {
+    fn from(_: &serde_yaml::Error) -> Self {
+        Failure::SceneParse
+    }
+}
+
+impl From<&std::io::Error> for Failure {
+    fn from(_: &std::io::Error) -> Self {
+        Failure::Io
+    }
+}
+
+/// How errors are reported on stderr
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorFormat {
+    Text,
+    /// one JSON object per error, with the failure's name, exit status, and message
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ErrorFormat::Text),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(Message::UnknownErrorFormat(s).text(Locale::from_env())),
+        }
+    }
+}
+
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorFormat, Failure};
+
+    #[test]
+    fn report_test() {
+        assert_eq!(
+            Failure::Io.report("no such file", ErrorFormat::Text),
+            "error: no such file"
+        );
+        assert_eq!(
+            Failure::SceneParse.report("bad \"key\"\n\tat line 3", ErrorFormat::Json),
+            r#"{"error":"scene_parse","code":2,"message":"bad \"key\"\n\u0009at line 3"}"#
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! A small expression language for custom fractal formulas.
+//!
+//! Expressions are quaternion arithmetic (`+`, `-`, `*`, `/`, and `^` for powers) on numbers and
+//! the variables `q` (the current iterate), `c` (the formula's constant), and `pos` (the point
+//! being estimated, with a real part of 0), along with the functions:
+//!
+//! - `sin(x)`, `cos(x)`, `abs(x)`: applied to each component
+//! - `pow(x, n)`: `x` to the real power `n`, like `x ^ n`
+//! - `sqr(x)`: `x * x`
+//! - `length(x)`: the magnitude of `x`, as a real number
+//!
+//! ```
+//! use ray_marcher::expr::Expr;
+//! let step: Expr = "abs(q) ^ 2 + c".parse().unwrap();
+//! ```
+use std::fmt;
+use std::iter::Sum;
+use std::str::FromStr;
+
+use num::Float;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use vek::Quaternion;
+
+use crate::quaternion;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Var {
+    Q,
+    C,
+    Pos,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Abs,
+    Pow,
+    Sqr,
+    Length,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Func::Sin),
+            "cos" => Some(Func::Cos),
+            "abs" => Some(Func::Abs),
+            "pow" => Some(Func::Pow),
+            "sqr" => Some(Func::Sqr),
+            "length" => Some(Func::Length),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Func::Sin => "sin",
+            Func::Cos => "cos",
+            Func::Abs => "abs",
+            Func::Pow => "pow",
+            Func::Sqr => "sqr",
+            Func::Length => "length",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Func::Pow => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Var(Var),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+/// The values of an expression's variables
+pub struct Vars<T> {
+    pub q: Quaternion<T>,
+    pub c: Quaternion<T>,
+    pub pos: Quaternion<T>,
+}
+
+impl Expr {
+    pub fn eval<T>(&self, vars: &Vars<T>) -> Quaternion<T>
+    where
+        T: Float + Sum,
+    {
+        let real = |x: T| Quaternion::from_scalar_and_vec3((x, vek::Vec3::zero()));
+        match self {
+            Expr::Num(n) => real(T::from(*n).unwrap()),
+            Expr::Var(Var::Q) => vars.q,
+            Expr::Var(Var::C) => vars.c,
+            Expr::Var(Var::Pos) => vars.pos,
+            Expr::Neg(a) => -a.eval(vars),
+            Expr::Add(a, b) => a.eval(vars) + b.eval(vars),
+            Expr::Sub(a, b) => a.eval(vars) - b.eval(vars),
+            Expr::Mul(a, b) => a.eval(vars) * b.eval(vars),
+            Expr::Div(a, b) => {
+                let b = b.eval(vars);
+                a.eval(vars) * b.conjugate() / b.magnitude_squared()
+            }
+            Expr::Pow(a, b) => quaternion::powf(a.eval(vars), b.eval(vars).w),
+            Expr::Call(func, args) => {
+                let x = args[0].eval(vars);
+                let each = |f: fn(T) -> T| Quaternion::from_xyzw(f(x.x), f(x.y), f(x.z), f(x.w));
+                match func {
+                    Func::Sin => each(T::sin),
+                    Func::Cos => each(T::cos),
+                    Func::Abs => each(T::abs),
+                    Func::Pow => quaternion::powf(x, args[1].eval(vars).w),
+                    Func::Sqr => quaternion::square(x),
+                    Func::Length => real(x.magnitude()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    /// prints the expression fully parenthesized, so it parses back to the same tree
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Var(Var::Q) => write!(f, "q"),
+            Expr::Var(Var::C) => write!(f, "c"),
+            Expr::Var(Var::Pos) => write!(f, "pos"),
+            Expr::Neg(a) => write!(f, "(-{})", a),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Sub(a, b) => write!(f, "({} - {})", a, b),
+            Expr::Mul(a, b) => write!(f, "({} * {})", a, b),
+            Expr::Div(a, b) => write!(f, "({} / {})", a, b),
+            Expr::Pow(a, b) => write!(f, "({} ^ {})", a, b),
+            Expr::Call(func, args) => {
+                let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+                write!(f, "{}({})", func.name(), args.join(", "))
+            }
+        }
+    }
+}
+
+/// Why an expression couldn't be parsed, and where
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// character offset of the problem
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at character {}", self.message, self.position)
+    }
+}
+
+/// A recursive-descent parser; from loosest to tightest binding:
+///
+/// ```text
+/// sum     = product (("+" | "-") product)*
+/// product = unary (("*" | "/") unary)*
+/// unary   = "-" unary | power
+/// power   = atom ("^" unary)?
+/// atom    = number | name | name "(" sum ("," sum)* ")" | "(" sum ")"
+/// ```
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<R>(&self, message: &str) -> Result<R, ParseError> {
+        Err(ParseError {
+            position: self.src[..self.pos].chars().count(),
+            message: message.to_string(),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.src[self.pos..].chars().next()
+    }
+
+    /// consumes `c` if it's next
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// consumes the longest run of characters matching `pred`
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        let len = self.src[start..]
+            .find(|c| !pred(c))
+            .unwrap_or(self.src.len() - start);
+        self.pos += len;
+        &self.src[start..start + len]
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.product()?;
+        loop {
+            if self.eat('+') {
+                expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
+            } else if self.eat('-') {
+                expr = Expr::Sub(Box::new(expr), Box::new(self.product()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        loop {
+            if self.eat('*') {
+                expr = Expr::Mul(Box::new(expr), Box::new(self.unary()?));
+            } else if self.eat('/') {
+                expr = Expr::Div(Box::new(expr), Box::new(self.unary()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat('-') {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            // right-associative: a ^ b ^ c is a ^ (b ^ c)
+            Ok(Expr::Pow(Box::new(base), Box::new(self.unary()?)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.sum()?;
+                if !self.eat(')') {
+                    return self.error("expected `)`");
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                let num = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                num.parse().map(Expr::Num).or_else(|_| {
+                    self.pos = start;
+                    self.error("invalid number")
+                })
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                match name {
+                    "q" => return Ok(Expr::Var(Var::Q)),
+                    "c" => return Ok(Expr::Var(Var::C)),
+                    "pos" => return Ok(Expr::Var(Var::Pos)),
+                    _ => {}
+                }
+                let func = match Func::from_name(name) {
+                    Some(func) => func,
+                    None => {
+                        self.pos = start;
+                        return self.error(&format!("unknown variable or function `{}`", name));
+                    }
+                };
+                if !self.eat('(') {
+                    return self.error(&format!("expected `(` after `{}`", name));
+                }
+                let mut args = vec![self.sum()?];
+                while self.eat(',') {
+                    args.push(self.sum()?);
+                }
+                if !self.eat(')') {
+                    return self.error("expected `)`");
+                }
+                if args.len() != func.arity() {
+                    self.pos = start;
+                    return self.error(&format!(
+                        "`{}` takes {} argument(s) but was given {}",
+                        name,
+                        func.arity(),
+                        args.len()
+                    ));
+                }
+                Ok(Expr::Call(func, args))
+            }
+            Some(_) => self.error("expected a number, variable, or `(`"),
+            None => self.error("unexpected end of expression"),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { src: s, pos: 0 };
+        let expr = parser.sum()?;
+        if parser.peek().is_some() {
+            return parser.error("unexpected trailing input");
+        }
+        Ok(expr)
+    }
+}
+
+impl Serialize for Expr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vek::Quaternion;
+
+    use super::{Expr, Vars};
+    use crate::quaternion;
+
+    fn vars() -> Vars<f64> {
+        Vars {
+            q: Quaternion::from_xyzw(0.3, -1.2, 0.5, 2.0),
+            c: Quaternion::from_xyzw(0.1, 0.2, 0.3, -0.4),
+            pos: Quaternion::from_xyzw(1.0, 2.0, 3.0, 0.0),
+        }
+    }
+
+    fn eval(src: &str) -> Quaternion<f64> {
+        src.parse::<Expr>().unwrap().eval(&vars())
+    }
+
+    fn assert_close(a: Quaternion<f64>, b: Quaternion<f64>) {
+        assert!((a - b).magnitude() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn eval_test() {
+        let Vars { q, c, pos } = vars();
+        assert_close(eval("q * q + c"), quaternion::square(q) + c);
+        assert_close(eval("q ^ 3 - pos"), q * q * q - pos);
+        assert_close(eval("pow(q, 2) + c"), quaternion::square(q) + c);
+        assert_close(
+            eval("sqr(abs(q))"),
+            quaternion::square(Quaternion::from_xyzw(0.3, 1.2, 0.5, 2.0)),
+        );
+        assert_close(eval("q / q"), Quaternion::identity());
+        assert_close(
+            eval("-length(pos)"),
+            Quaternion::identity() * -(14f64).sqrt(),
+        );
+        // precedence: 1 + (2 * (3 ^ 2)), and - binds looser than ^
+        assert_close(eval("1 + 2 * 3 ^ 2"), Quaternion::identity() * 19.0);
+        assert_close(eval("-2 ^ 2"), Quaternion::identity() * -4.0);
+    }
+
+    #[test]
+    fn display_round_trip_test() {
+        let expr: Expr = "abs(q) ^ 2 - -c / 2".parse().unwrap();
+        assert_eq!(expr.to_string().parse::<Expr>().unwrap(), expr);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let err = "q * z + c".parse::<Expr>().unwrap_err();
+        assert_eq!(err.position, 4);
+        // counting characters, not bytes, past the non-breaking space
+        let err = "q *\u{a0}z + c".parse::<Expr>().unwrap_err();
+        assert_eq!(err.position, 4);
+        assert!("sin(q".parse::<Expr>().is_err());
+        assert!("pow(q)".parse::<Expr>().is_err());
+        assert!("q q".parse::<Expr>().is_err());
+    }
+}
@@ -0,0 +1,441 @@
+//! Output filename templates. `{name}` placeholders stand for the render's parameters, like
+//! `{scene}-{render}-{frame:04}.png`, and the rest of the template is a chrono strftime format
+//! string for the time of the render.
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::format::{strftime::StrftimeItems, Item};
+use chrono::{DateTime, TimeZone};
+use vek::Extent2;
+
+use crate::messages::Message;
+
+/// the most digits a number can be padded to, as many as the largest `u64` has
+const MAX_PADDING: usize = 20;
+
+/// A value a template can refer to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placeholder {
+    Scene,
+    Render,
+    Camera,
+    Width,
+    Height,
+    /// the width and height, as `WIDTHxHEIGHT`
+    Resolution,
+    Frame,
+    Seed,
+    /// the iteration count
+    Quality,
+}
+
+impl Placeholder {
+    pub const ALL: [Placeholder; 9] = [
+        Placeholder::Scene,
+        Placeholder::Render,
+        Placeholder::Camera,
+        Placeholder::Width,
+        Placeholder::Height,
+        Placeholder::Resolution,
+        Placeholder::Frame,
+        Placeholder::Seed,
+        Placeholder::Quality,
+    ];
+
+    /// the placeholder's name in templates
+    pub fn name(self) -> &'static str {
+        match self {
+            Placeholder::Scene => "scene",
+            Placeholder::Render => "render",
+            Placeholder::Camera => "camera",
+            Placeholder::Width => "width",
+            Placeholder::Height => "height",
+            Placeholder::Resolution => "resolution",
+            Placeholder::Frame => "frame",
+            Placeholder::Seed => "seed",
+            Placeholder::Quality => "quality",
+        }
+    }
+
+    /// whether the placeholder's value is a number, and so can be zero-padded
+    fn numeric(self) -> bool {
+        !matches!(
+            self,
+            Placeholder::Scene | Placeholder::Camera | Placeholder::Resolution
+        )
+    }
+}
+
+impl FromStr for Placeholder {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Placeholder::ALL
+            .iter()
+            .cloned()
+            .find(|p| p.name() == s)
+            .ok_or(())
+    }
+}
+
+/// The values a template's placeholders are filled with; placeholders without a value are left
+/// empty
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Values {
+    /// the scene file's name, without its extension
+    pub scene: Option<String>,
+    /// the render's index in the scene
+    pub render: Option<usize>,
+    pub camera: Option<String>,
+    pub size: Option<Extent2<usize>>,
+    /// 0 for still images
+    pub frame: usize,
+    pub seed: Option<u64>,
+    pub quality: Option<usize>,
+}
+
+impl Values {
+    fn get(&self, placeholder: Placeholder) -> Option<String> {
+        match placeholder {
+            Placeholder::Scene => self.scene.clone(),
+            Placeholder::Render => self.render.map(|r| r.to_string()),
+            Placeholder::Camera => self.camera.clone(),
+            Placeholder::Width => self.size.map(|s| s.w.to_string()),
+            Placeholder::Height => self.size.map(|s| s.h.to_string()),
+            Placeholder::Resolution => self.size.map(|s| format!("{}x{}", s.w, s.h)),
+            Placeholder::Frame => Some(self.frame.to_string()),
+            Placeholder::Seed => self.seed.map(|s| s.to_string()),
+            Placeholder::Quality => self.quality.map(|q| q.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateError {
+    UnknownPlaceholder(String),
+    /// a `{` without a matching `}`, or a `}` without a `{`
+    Unbalanced,
+    /// a `:` suffix other than a zero and a width, or on a placeholder that isn't a number
+    InvalidPadding(String),
+    InvalidStrftime,
+}
+
+impl TemplateError {
+    /// a description of the error for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            TemplateError::UnknownPlaceholder(p) => Message::UnknownPlaceholder(p),
+            TemplateError::Unbalanced => Message::UnbalancedBraces,
+            TemplateError::InvalidPadding(p) => Message::InvalidPadding(p),
+            TemplateError::InvalidStrftime => Message::InvalidStrftime,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    /// strftime format text
+    Text(String),
+    /// a placeholder, zero-padded to `width` digits
+    Field {
+        placeholder: Placeholder,
+        width: usize,
+    },
+}
+
+/// A parsed filename template; `{{` and `}}` are literal braces
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parses `s`, checking every placeholder and the strftime formatting, so a bad template can
+    /// be reported before rendering rather than after.
+    pub fn parse(s: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(TemplateError::Unbalanced),
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(TemplateError::Unbalanced),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Self::parse_field(&field)?);
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        let strftime_error = parts.iter().any(|part| match part {
+            Part::Text(t) => StrftimeItems::new(t).any(|item| item == Item::Error),
+            _ => false,
+        });
+        if strftime_error {
+            return Err(TemplateError::InvalidStrftime);
+        }
+        Ok(Template { parts })
+    }
+
+    /// a placeholder like `frame` or `frame:04`, padded to at most `MAX_PADDING` digits
+    fn parse_field(field: &str) -> Result<Part, TemplateError> {
+        let mut split = field.splitn(2, ':');
+        let name = split.next().unwrap_or("");
+        let placeholder: Placeholder = name
+            .parse()
+            .map_err(|_| TemplateError::UnknownPlaceholder(name.to_string()))?;
+        let width = match split.next() {
+            None => 0,
+            Some(pad) if pad.starts_with('0') && placeholder.numeric() => pad[1..]
+                .parse()
+                .ok()
+                .filter(|&width| width <= MAX_PADDING)
+                .ok_or_else(|| TemplateError::InvalidPadding(field.to_string()))?,
+            Some(_) => return Err(TemplateError::InvalidPadding(field.to_string())),
+        };
+        Ok(Part::Field { placeholder, width })
+    }
+
+    /// the filename for a render with `values` at `time`
+    pub fn format<Tz>(&self, values: &Values, time: &DateTime<Tz>) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(t) => time.format(t).to_string(),
+                Part::Field { placeholder, width } => values
+                    .get(*placeholder)
+                    .map(|v| format!("{:0>width$}", v, width = width))
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// whether the template refers to `placeholder`
+    pub fn contains(&self, placeholder: Placeholder) -> bool {
+        self.parts.iter().any(|part| match part {
+            Part::Field { placeholder: p, .. } => *p == placeholder,
+            _ => false,
+        })
+    }
+
+    /// This template with `{frame}` placeholders which don't give their own padding zero-padded
+    /// to `digits` digits
+    pub fn with_frame_padding(mut self, digits: usize) -> Self {
+        for part in self.parts.iter_mut() {
+            if let Part::Field {
+                placeholder: Placeholder::Frame,
+                width,
+            } = part
+            {
+                if *width == 0 {
+                    *width = digits;
+                }
+            }
+        }
+        self
+    }
+}
+
+/// A problem with the frames requested for an animation
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameError {
+    /// the range ends before it starts
+    Empty { start: usize, end: usize },
+    /// several frames would be written to the same file, since the template has no `{frame}`
+    Unnamed,
+}
+
+impl FrameError {
+    /// a description of the error for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            FrameError::Empty { start, end } => Message::EmptyFrameRange {
+                start: *start,
+                end: *end,
+            },
+            FrameError::Unnamed => Message::UnnamedFrames,
+        }
+    }
+}
+
+/// The frames of an animation to render: `start` to `end` inclusive, every `step` frames
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameRange {
+    pub start: usize,
+    pub end: usize,
+    pub step: usize,
+}
+
+impl FrameRange {
+    /// just `frame`, for spot checks of an animation
+    pub fn single(frame: usize) -> Self {
+        FrameRange {
+            start: frame,
+            end: frame,
+            step: 1,
+        }
+    }
+
+    /// the frame numbers in the range, in order
+    pub fn frames(&self) -> impl Iterator<Item = usize> {
+        (self.start..=self.end).step_by(self.step.max(1))
+    }
+
+    /// Checks that the range has at least one frame, and that each of its frames would be
+    /// written to its own file by `template`.
+    pub fn check(&self, template: &Template) -> Result<(), FrameError> {
+        if self.end < self.start {
+            Err(FrameError::Empty {
+                start: self.start,
+                end: self.end,
+            })
+        } else if self.frames().nth(1).is_some() && !template.contains(Placeholder::Frame) {
+            Err(FrameError::Unnamed)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use vek::Extent2;
+
+    use super::{FrameError, FrameRange, Template, TemplateError, Values};
+
+    #[test]
+    fn template_test() {
+        let time = Utc.ymd(2019, 10, 5).and_hms(13, 4, 5);
+        let values = Values {
+            scene: Some("julia".to_string()),
+            render: Some(1),
+            camera: Some("main".to_string()),
+            size: Some(Extent2::new(640, 480)),
+            frame: 12,
+            seed: None,
+            quality: Some(64),
+        };
+        let format = |s: &str| Template::parse(s).unwrap().format(&values, &time);
+        assert_eq!(
+            format("{scene}-{render}-{frame:04}.png"),
+            "julia-1-0012.png"
+        );
+        assert_eq!(
+            format("{camera}_{resolution}_q{quality}_%F.exr"),
+            "main_640x480_q64_2019-10-05.exr"
+        );
+        // missing values are left empty, and doubled braces are literal
+        assert_eq!(format("{{{seed}}}-%H_%M.png"), "{}-13_04.png");
+        // a `%` in a value isn't taken as a strftime format
+        let values = Values {
+            scene: Some("100%".to_string()),
+            ..Values::default()
+        };
+        let template = Template::parse("{scene}.png").unwrap();
+        assert_eq!(template.format(&values, &time), "100%.png");
+
+        assert_eq!(
+            Template::parse("{scene}-{shot}.png"),
+            Err(TemplateError::UnknownPlaceholder("shot".to_string()))
+        );
+        assert_eq!(
+            Template::parse("{scene:04}.png"),
+            Err(TemplateError::InvalidPadding("scene:04".to_string()))
+        );
+        assert_eq!(
+            Template::parse("{frame:4}.png"),
+            Err(TemplateError::InvalidPadding("frame:4".to_string()))
+        );
+        // wider than any number needs
+        assert!(Template::parse("{frame:020}.png").is_ok());
+        assert_eq!(
+            Template::parse("{frame:021}.png"),
+            Err(TemplateError::InvalidPadding("frame:021".to_string()))
+        );
+        assert_eq!(
+            Template::parse("{frame:099999999999}.png"),
+            Err(TemplateError::InvalidPadding(
+                "frame:099999999999".to_string()
+            ))
+        );
+        assert_eq!(
+            Template::parse("{frame.png"),
+            Err(TemplateError::Unbalanced)
+        );
+        assert_eq!(
+            Template::parse("frame}.png"),
+            Err(TemplateError::Unbalanced)
+        );
+        assert_eq!(
+            Template::parse("{frame}%Q.png"),
+            Err(TemplateError::InvalidStrftime)
+        );
+    }
+
+    #[test]
+    fn frame_range_test() {
+        let range = FrameRange {
+            start: 10,
+            end: 20,
+            step: 4,
+        };
+        assert_eq!(range.frames().collect::<Vec<_>>(), vec![10, 14, 18]);
+        assert_eq!(
+            FrameRange::single(113).frames().collect::<Vec<_>>(),
+            vec![113]
+        );
+
+        let named = Template::parse("shot-{frame}.png").unwrap();
+        let unnamed = Template::parse("shot.png").unwrap();
+        assert_eq!(range.check(&named), Ok(()));
+        assert_eq!(range.check(&unnamed), Err(FrameError::Unnamed));
+        // one frame can't collide with itself
+        assert_eq!(FrameRange::single(113).check(&unnamed), Ok(()));
+        let backwards = FrameRange {
+            start: 20,
+            end: 10,
+            step: 1,
+        };
+        assert_eq!(
+            backwards.check(&named),
+            Err(FrameError::Empty { start: 20, end: 10 })
+        );
+
+        let time = Utc.ymd(2019, 10, 5).and_hms(13, 4, 5);
+        let values = Values {
+            frame: 7,
+            ..Values::default()
+        };
+        let padded = Template::parse("{frame}-{frame:02}.png")
+            .unwrap()
+            .with_frame_padding(5);
+        // explicit padding wins
+        assert_eq!(padded.format(&values, &time), "00007-07.png");
+    }
+}
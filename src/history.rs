@@ -0,0 +1,126 @@
+//! An opt-in local log of past renders, one JSON object per line, so the parameters behind an
+//! image can be found again. Nothing is recorded unless the user asks for it, and nothing ever
+//! leaves their machine.
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exit::json_escape;
+
+/// One render
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// when the render finished, in RFC 3339 format
+    pub time: String,
+    /// `scene_hash` of the scene file's contents, in hex
+    pub scene_hash: String,
+    /// the command-line arguments the render was run with
+    pub parameters: Vec<String>,
+    pub duration_secs: f64,
+    pub output: String,
+}
+
+impl Entry {
+    /// the entry as a single line of JSON
+    pub fn to_json(&self) -> String {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|p| format!("\"{}\"", json_escape(p)))
+            .collect();
+        format!(
+            r#"{{"time":"{}","scene_hash":"{}","parameters":[{}],"duration_secs":{},"output":"{}"}}"#,
+            json_escape(&self.time),
+            json_escape(&self.scene_hash),
+            parameters.join(","),
+            self.duration_secs,
+            json_escape(&self.output)
+        )
+    }
+
+    /// whether `query` appears in the entry's scene hash, parameters, or output path
+    pub fn matches(&self, query: &str) -> bool {
+        self.scene_hash.contains(query)
+            || self.output.contains(query)
+            || self.parameters.iter().any(|p| p.contains(query))
+    }
+}
+
+/// A stable 64-bit FNV-1a hash of a scene file's contents, so renders of the same scene can be
+/// found even after the file is moved or renamed
+pub fn scene_hash(scene: &str) -> String {
+    let hash = scene.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// `$XDG_DATA_HOME/ray-marcher/history.jsonl`, defaulting to `~/.local/share`; `None` if
+/// neither variable is set
+pub fn default_path() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .map(|data| data.join("ray-marcher").join("history.jsonl"))
+}
+
+/// appends `entry` to the history file at `path`, creating it if needed
+pub fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry.to_json())
+}
+
+/// the entries of the history file at `path` matching `query` (see `Entry::matches`), oldest
+/// first; lines which can't be parsed are skipped
+pub fn search(path: &Path, query: &str) -> io::Result<Vec<Entry>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        // no renders have been recorded yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        // JSON is a subset of YAML
+        if let Ok(entry) = serde_yaml::from_str::<Entry>(&line?) {
+            if entry.matches(query) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::{append, scene_hash, search, Entry};
+
+    #[test]
+    fn history_test() {
+        let path = env::temp_dir()
+            .join(format!("ray-marcher-history-test-{}", std::process::id()))
+            .join("history.jsonl");
+        let entry = |output: &str| Entry {
+            time: "2019-10-01T12:00:00+00:00".to_string(),
+            scene_hash: scene_hash("geometry: []"),
+            parameters: vec!["-i".to_string(), "32".to_string(), "say \"hi\"".to_string()],
+            duration_secs: 12.5,
+            output: output.to_string(),
+        };
+        assert_eq!(search(&path, "").unwrap(), vec![]);
+        append(&path, &entry("julia.png")).unwrap();
+        append(&path, &entry("mandelbox.png")).unwrap();
+        assert_eq!(search(&path, "").unwrap().len(), 2);
+        assert_eq!(search(&path, "julia").unwrap(), vec![entry("julia.png")]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,5 +1,18 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::iter::Sum;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use deflate::write::ZlibEncoder;
+use deflate::Compression;
+use num::Float;
 use palette::{Component, Pixel, Srgba};
-use vek::Extent2;
+use vek::{Extent2, Rect, Vec2, Vec3, Vec4};
+
+use crate::messages::Message;
 
 // 8-bit rgba image data
 struct ImageData {
@@ -11,13 +24,16 @@ impl ImageData {
     /// number of channels per pixel; rgba
     const CHANNELS: usize = 4;
 
-    fn new(width: usize, height: usize) -> Self {
-        ImageData {
-            size: Extent2::new(width, height),
-            data: Vec::with_capacity(width * height * Self::CHANNELS),
-        }
+    fn new(width: usize, height: usize) -> Result<Self, SizeError> {
+        let size = Extent2::new(width, height);
+        Ok(ImageData {
+            size,
+            data: Vec::with_capacity(buffer_len(size, Self::CHANNELS)?),
+        })
     }
 
+    /// can't overflow for coordinates within the image, since `new` checked the whole buffer's
+    /// length fits in a `usize`
     fn coords_to_inx(&self, x: usize, y: usize) -> usize {
         y * self.size.w + x
     }
@@ -67,3 +83,1743 @@ impl ImageData {
         }
     }
 }
+
+/// Running mean and variance of a pixel's samples, by Welford's algorithm
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PixelStats<T: Default> {
+    count: usize,
+    mean: Vec4<T>,
+    /// sum of squared differences from the mean
+    m2: Vec4<T>,
+}
+
+impl<T> PixelStats<T>
+where
+    T: Float + Default,
+{
+    /// z-score of a 95% confidence interval
+    const Z_95: f64 = 1.96;
+
+    pub fn add(&mut self, sample: Vec4<T>) {
+        self.count += 1;
+        let delta = sample - self.mean;
+        self.mean = self.mean + delta / T::from(self.count).unwrap();
+        self.m2 = self.m2 + delta * (sample - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Vec4<T> {
+        self.mean
+    }
+
+    /// half-width of the 95% confidence interval of the mean, for the worst channel
+    pub fn confidence(&self) -> T {
+        if self.count < 2 {
+            return T::infinity();
+        }
+        let n = T::from(self.count).unwrap();
+        let variance = self.m2 / (n - T::one());
+        let worst = variance.reduce(T::max);
+        T::from(Self::Z_95).unwrap() * (worst / n).sqrt()
+    }
+
+    /// whether the mean has stabilized enough that further samples aren't worth taking
+    pub fn converged(&self, threshold: T, min_samples: usize) -> bool {
+        self.count >= min_samples && self.confidence() <= threshold
+    }
+}
+
+/// Per-pixel sample statistics for multi-sample rendering, so that sampling can stop early on
+/// pixels whose mean has converged (e.g. flat backgrounds) while continuing elsewhere.
+pub struct SampleBuffer<T: Default> {
+    size: Extent2<usize>,
+    stats: Vec<PixelStats<T>>,
+    /// confidence interval half-width below which a pixel is considered converged
+    pub threshold: T,
+    /// samples every pixel takes before it's allowed to converge
+    pub min_samples: usize,
+}
+
+impl<T> SampleBuffer<T>
+where
+    T: Float + Default,
+{
+    pub fn new(width: usize, height: usize, threshold: T, min_samples: usize) -> Self {
+        SampleBuffer {
+            size: Extent2::new(width, height),
+            stats: vec![PixelStats::default(); width * height],
+            threshold,
+            min_samples,
+        }
+    }
+
+    fn coords_to_inx(&self, x: usize, y: usize) -> usize {
+        y * self.size.w + x
+    }
+
+    pub fn add(&mut self, x: usize, y: usize, sample: Vec4<T>) {
+        let inx = self.coords_to_inx(x, y);
+        self.stats[inx].add(sample);
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &PixelStats<T> {
+        &self.stats[self.coords_to_inx(x, y)]
+    }
+
+    /// coordinates of the pixels which still need more samples
+    pub fn pending(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.size.w;
+        self.stats
+            .iter()
+            .enumerate()
+            .filter(move |(_, stats)| !stats.converged(self.threshold, self.min_samples))
+            .map(move |(inx, _)| (inx % width, inx / width))
+    }
+}
+
+/// whether pixel (x, y) is rendered in a checkerboard pass; alternating `phase` between 0 and 1
+/// covers the other half of the image
+pub fn checkerboard(x: usize, y: usize, phase: usize) -> bool {
+    (x + y + phase).is_multiple_of(2)
+}
+
+/// Per-pixel depth, normal, and albedo buffers used to guide reconstruction; these are the same
+/// auxiliary passes external denoisers expect alongside the beauty pass
+pub struct Guides<'a, T> {
+    pub depth: &'a [T],
+    pub normal: &'a [Vec3<T>],
+    pub albedo: &'a [T],
+}
+
+impl<'a, T> Guides<'a, T>
+where
+    T: Float + Sum,
+{
+    /// how different the surfaces at two pixels are; 0 for identical depth, normal, and albedo
+    fn discontinuity(&self, a: usize, b: usize) -> T {
+        (self.depth[a] - self.depth[b]).abs() + T::one() - self.normal[a].dot(self.normal[b])
+            + (self.albedo[a] - self.albedo[b]).abs()
+    }
+}
+
+/// Fills in the pixels skipped by a checkerboard pass (see `checkerboard`) from their rendered
+/// neighbors.
+///
+/// Each skipped pixel is averaged from whichever of its horizontal or vertical neighbor pairs lies
+/// on the more continuous surface according to `guides`, so edges stay sharp instead of being
+/// blurred across.
+pub fn reconstruct_checkerboard<T>(
+    color: &mut [Vec4<T>],
+    guides: &Guides<T>,
+    width: usize,
+    phase: usize,
+) where
+    T: Float + Sum,
+{
+    let height = color.len() / width;
+    let t2 = T::from(2).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            if checkerboard(x, y, phase) {
+                continue;
+            }
+            let inx = y * width + x;
+            let horiz = match (x.checked_sub(1), Some(x + 1).filter(|&r| r < width)) {
+                (Some(l), Some(r)) => Some((y * width + l, y * width + r)),
+                _ => None,
+            };
+            let vert = match (y.checked_sub(1), Some(y + 1).filter(|&d| d < height)) {
+                (Some(u), Some(d)) => Some((u * width + x, d * width + x)),
+                _ => None,
+            };
+            let pair = match (horiz, vert) {
+                (Some(h), Some(v)) => {
+                    if guides.discontinuity(h.0, h.1) <= guides.discontinuity(v.0, v.1) {
+                        h
+                    } else {
+                        v
+                    }
+                }
+                (Some(p), None) | (None, Some(p)) => p,
+                // corners (and 1-pixel-wide images) only have one neighbor per axis
+                (None, None) => {
+                    let n = if x + 1 < width {
+                        inx + 1
+                    } else if x > 0 {
+                        inx - 1
+                    } else if y > 0 {
+                        inx - width
+                    } else if y + 1 < height {
+                        inx + width
+                    } else {
+                        continue;
+                    };
+                    (n, n)
+                }
+            };
+            color[inx] = (color[pair.0] + color[pair.1]) / t2;
+        }
+    }
+}
+
+/// Smooths a normal pass (`None` for misses) with a bilateral filter guided by `depth`, for
+/// estimators whose finite-difference normals sparkle at practical sample sizes.
+///
+/// Each hit pixel's normal becomes the weighted average of the hit normals within `radius`
+/// pixels of it, weighted by a Gaussian of their distance in pixels and another of their
+/// difference in depth as a fraction of the pixel's own, with `depth_tolerance` as its standard
+/// deviation. Neighbors on other surfaces, well in front of or behind the pixel, barely count, so
+/// silhouettes stay sharp; non-finite normals don't count at all. With no depth to measure that
+/// fraction against (at depth 0) or no tolerance for it, a pixel keeps its own normal.
+pub fn smooth_normals<T>(
+    normal: &[Option<Vec3<T>>],
+    depth: &[Option<T>],
+    width: usize,
+    radius: usize,
+    depth_tolerance: T,
+) -> Vec<Option<Vec3<T>>>
+where
+    T: Float + Sum,
+{
+    if radius == 0 || width == 0 {
+        return normal.to_vec();
+    }
+    let height = normal.len() / width;
+    let half = T::from(0.5).unwrap();
+    // the edge of the window is 2 standard deviations out
+    let spatial = T::from(radius).unwrap() * half;
+    let hit = |inx: usize| match (normal[inx], depth[inx]) {
+        (Some(n), Some(d)) if n.iter().all(|c| c.is_finite()) => Some((n, d)),
+        _ => None,
+    };
+    (0..normal.len())
+        .map(|inx| {
+            let d = match (normal[inx], depth[inx]) {
+                (Some(_), Some(d)) => d,
+                _ => return normal[inx],
+            };
+            let tolerance = d.abs() * depth_tolerance;
+            if tolerance <= T::zero() || !tolerance.is_finite() {
+                return normal[inx];
+            }
+            let (x, y) = (inx % width, inx / width);
+            let mut sum = Vec3::zero();
+            for ny in y.saturating_sub(radius)..(y + radius + 1).min(height) {
+                for nx in x.saturating_sub(radius)..(x + radius + 1).min(width) {
+                    let (n, nd) = match hit(ny * width + nx) {
+                        Some(hit) => hit,
+                        None => continue,
+                    };
+                    let dx = T::from(nx).unwrap() - T::from(x).unwrap();
+                    let dy = T::from(ny).unwrap() - T::from(y).unwrap();
+                    let pixels = (dx * dx + dy * dy) / (spatial * spatial);
+                    let relative = (nd - d) / tolerance;
+                    sum = sum + n * (-half * (pixels + relative * relative)).exp();
+                }
+            }
+            if sum.magnitude_squared() > T::zero() {
+                Some(sum.normalized())
+            } else {
+                normal[inx]
+            }
+        })
+        .collect()
+}
+
+/// Resolutions for progressive refinement, from `1 / 2^(levels - 1)` of `size` up to `size`
+/// itself, halving each time (e.g. 1/8, 1/4, 1/2 and full resolution for 4 levels)
+pub fn refinement_levels(size: Extent2<usize>, levels: u32) -> Vec<Extent2<usize>> {
+    (0..levels)
+        .rev()
+        .map(|level| size.map(|d| (d >> level).max(1)))
+        .collect()
+}
+
+/// Conservative starting distances for the rays of an image of size `size`, from the hit
+/// distances (`None` for rays which missed) of a coarser rendering of the same view.
+///
+/// Each pixel takes the smallest hit distance of the coarse pixels around it, scaled by `safety`
+/// (less than 1) so the march starts short of the surface; pixels near a miss start at 0, because
+/// thin features between coarse samples may have been skipped.
+pub fn seed_distances<T>(
+    coarse: &[Option<T>],
+    coarse_size: Extent2<usize>,
+    size: Extent2<usize>,
+    safety: T,
+) -> Vec<T>
+where
+    T: Float,
+{
+    let mut seeds = Vec::with_capacity(size.w * size.h);
+    for y in 0..size.h {
+        let cy = y * coarse_size.h / size.h;
+        for x in 0..size.w {
+            let cx = x * coarse_size.w / size.w;
+            let mut seed = Some(T::infinity());
+            for ny in cy.saturating_sub(1)..(cy + 2).min(coarse_size.h) {
+                for nx in cx.saturating_sub(1)..(cx + 2).min(coarse_size.w) {
+                    seed = match (seed, coarse[ny * coarse_size.w + nx]) {
+                        (Some(s), Some(d)) => Some(s.min(d)),
+                        _ => None,
+                    };
+                }
+            }
+            seeds.push(seed.map_or(T::zero(), |s| s * safety));
+        }
+    }
+    seeds
+}
+
+/// Draws a line from `from` to `to`, in pixel coordinates which may lie off the image, over
+/// `color`, an image `width` pixels wide, blending by the line color's alpha
+pub fn draw_line<T>(color: &mut [Vec4<T>], width: usize, from: Vec2<T>, to: Vec2<T>, line: Vec4<T>)
+where
+    T: Float,
+{
+    let height = color.len() / width;
+    let delta = to - from;
+    // clip the line to the image (Liang-Barsky), so lines running far off screen stay cheap
+    let (mut t0, mut t1) = (T::zero(), T::one());
+    let half = T::from(0.5).unwrap();
+    let edges = [
+        (-delta.x, from.x + half),
+        (delta.x, T::from(width).unwrap() - half - from.x),
+        (-delta.y, from.y + half),
+        (delta.y, T::from(height).unwrap() - half - from.y),
+    ];
+    for &(p, q) in &edges {
+        if p == T::zero() {
+            if q < T::zero() {
+                return;
+            }
+        } else if p < T::zero() {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 > t1 {
+        return;
+    }
+
+    let (start, end) = (from + delta * t0, from + delta * t1);
+    let steps = (end - start)
+        .map(|c| c.abs())
+        .reduce(T::max)
+        .ceil()
+        .to_usize()
+        .unwrap_or(0)
+        .max(1);
+    let alpha = line.w;
+    for i in 0..=steps {
+        let p = start + (end - start) * (T::from(i).unwrap() / T::from(steps).unwrap());
+        let (x, y) = (p.x.round().to_usize(), p.y.round().to_usize());
+        if let (Some(x), Some(y)) = (x, y) {
+            if x < width && y < height {
+                let px = &mut color[y * width + x];
+                let blended = *px + (line - *px) * alpha;
+                *px = Vec4::new(
+                    blended.x,
+                    blended.y,
+                    blended.z,
+                    alpha + px.w * (T::one() - alpha),
+                );
+            }
+        }
+    }
+}
+
+/// How an image is split into units of work for the render threads
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tiling {
+    /// full-width strips of this many rows
+    Scanline(usize),
+    /// squares this many pixels on a side (smaller at the right and bottom edges)
+    Square(usize),
+}
+
+impl Tiling {
+    /// about how many tiles each thread should get, so threads which finish early can pick up
+    /// the slack
+    const TILES_PER_THREAD: usize = 16;
+
+    /// A granularity for rendering an image of size `size` with `threads` threads: square tiles
+    /// of a power-of-two size between 16 and 256 pixels, or, for previews too small to fill every
+    /// thread with 16-pixel squares, scanlines.
+    pub fn auto(size: Extent2<usize>, threads: usize) -> Self {
+        let tiles = threads.max(1) * Self::TILES_PER_THREAD;
+        // in u64, since the whole image's area can overflow a 32-bit usize even when each tile's
+        // doesn't
+        let area = (size.w as u64 * size.h as u64 / tiles as u64).max(1) as usize;
+        if area < 16 * 16 {
+            Tiling::Scanline((size.h / tiles).max(1))
+        } else {
+            let side = (area as f64).sqrt() as usize;
+            // the largest power of two no greater than side
+            let side = (side + 1).next_power_of_two() / 2;
+            Tiling::Square(side.min(256))
+        }
+    }
+
+    /// the tiles covering an image of size `size`, row by row
+    pub fn tiles(self, size: Extent2<usize>) -> Vec<Rect<usize, usize>> {
+        let (tile_w, tile_h) = match self {
+            Tiling::Scanline(rows) => (size.w.max(1), rows.max(1)),
+            Tiling::Square(side) => (side.max(1), side.max(1)),
+        };
+        (0..size.h)
+            .step_by(tile_h)
+            .flat_map(|y| {
+                (0..size.w)
+                    .step_by(tile_w)
+                    .map(move |x| Rect::new(x, y, tile_w.min(size.w - x), tile_h.min(size.h - y)))
+            })
+            .collect()
+    }
+}
+
+/// An image file format, picked by the output filename's extension
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// 8-bit RGBA, one image per file
+    Png,
+    /// OpenEXR: floating-point channels, with any number of layers in one file
+    Exr,
+    /// 16-bit RGBA, for print workflows
+    Tiff,
+    /// lossless 8-bit RGBA, up to `WEBP_MAX_SIZE` pixels on a side
+    WebP,
+}
+
+impl OutputFormat {
+    /// the format for `path`'s extension, ignoring case
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "png" => Some(OutputFormat::Png),
+            "exr" => Some(OutputFormat::Exr),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "webp" => Some(OutputFormat::WebP),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Exr => "exr",
+            OutputFormat::Tiff => "tif",
+            OutputFormat::WebP => "webp",
+        }
+    }
+
+    /// whether the format stores floating-point color rather than quantizing it
+    pub fn supports_float(self) -> bool {
+        self == OutputFormat::Exr
+    }
+
+    /// whether the format can hold AOVs as extra layers alongside the beauty pass
+    pub fn supports_layers(self) -> bool {
+        self == OutputFormat::Exr
+    }
+
+    /// Checks the format can store an image of `size`: PNG and EXR sides are signed 32-bit,
+    /// TIFF offsets are unsigned 32-bit (so its whole 16-bit image must be under 4 GiB), and
+    /// WebP sides are 14-bit.
+    pub fn check_size(self, size: Extent2<usize>) -> Result<(), SizeError> {
+        if size.w == 0 || size.h == 0 {
+            return Err(SizeError::Empty);
+        }
+        let (w, h) = (size.w as u64, size.h as u64);
+        let max_side = match self {
+            OutputFormat::Png | OutputFormat::Exr => i32::MAX as u64,
+            OutputFormat::Tiff => u64::from(u32::MAX),
+            OutputFormat::WebP => WEBP_MAX_SIZE as u64,
+        };
+        if w > max_side || h > max_side {
+            return Err(SizeError::Side {
+                format: self,
+                max: max_side,
+            });
+        }
+        if self == OutputFormat::Tiff {
+            // the pixels and the 16 bytes of header and bits per sample before the directory
+            let max = (u64::from(u32::MAX) - 16) / 8;
+            if w * h > max {
+                return Err(SizeError::Area { format: self, max });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why an image can't be rendered or written at the size requested
+#[derive(Clone, Debug, PartialEq)]
+pub enum SizeError {
+    /// a side of zero pixels
+    Empty,
+    /// its buffer's length in bytes overflows this platform's address space
+    TooLarge(Extent2<usize>),
+    /// a side is longer than `format` can store
+    Side { format: OutputFormat, max: u64 },
+    /// it has more pixels than `format` can store
+    Area { format: OutputFormat, max: u64 },
+}
+
+impl SizeError {
+    /// a description of the error for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            SizeError::Empty => Message::EmptyImage,
+            SizeError::TooLarge(size) => Message::ImageTooLarge {
+                width: size.w,
+                height: size.h,
+            },
+            SizeError::Side { format, max } => Message::SideLimit {
+                format: format.extension(),
+                max: *max,
+            },
+            SizeError::Area { format, max } => Message::AreaLimit {
+                format: format.extension(),
+                max: *max,
+            },
+        }
+    }
+}
+
+/// The length in bytes of a buffer holding `bytes_per_pixel` for each pixel of an image of
+/// `size`, if it's addressable on this platform: under 2 GiB on 32-bit targets
+pub fn buffer_len(size: Extent2<usize>, bytes_per_pixel: usize) -> Result<usize, SizeError> {
+    size.w
+        .checked_mul(size.h)
+        .and_then(|px| px.checked_mul(bytes_per_pixel))
+        // allocations are limited to `isize::MAX` bytes
+        .filter(|&len| len <= isize::MAX as usize)
+        .ok_or(SizeError::TooLarge(size))
+}
+
+/// Checks an image of `size` can be both rendered here, holding a `Vec4<f64>` per pixel, and
+/// written as `format`
+pub fn check_render_size(size: Extent2<usize>, format: OutputFormat) -> Result<(), SizeError> {
+    format.check_size(size)?;
+    buffer_len(size, mem::size_of::<Vec4<f64>>()).map(|_| ())
+}
+
+/// An arbitrary output variable: an auxiliary pass rendered alongside the beauty pass
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aov {
+    Depth,
+    Normal,
+    Albedo,
+    ObjectId,
+    /// distance estimates the primary ray took, over every geometry (see `render::step_pass`)
+    Steps,
+}
+
+impl Aov {
+    /// the pass's name on the command line and in split filenames
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "id",
+            Aov::Steps => "steps",
+        }
+    }
+
+    /// the names of the pass's channels within its EXR layer, in the conventions Nuke and
+    /// Blender read (`depth.Z`, `normal.X`, and so on)
+    pub fn channels(self) -> &'static [&'static str] {
+        match self {
+            Aov::Depth => &["Z"],
+            Aov::Normal => &["X", "Y", "Z"],
+            Aov::Albedo => &["R", "G", "B"],
+            Aov::ObjectId => &["R"],
+            Aov::Steps => &["Y"],
+        }
+    }
+
+    /// The pass's values `data`, as its layer holds them (see `Layer::aov`), as colors from 0 to
+    /// 1 to look at, for formats which can't store them as they are: depth in gray from white for
+    /// the nearest hit to black for the farthest, normals with each component mapped from -1..1
+    /// to 0..1, object IDs in gray from dark to white, and steps as a heat map from black through red
+    /// and yellow to white for the most; pixels whose rays missed are transparent black, except in
+    /// the heat map, as misses take steps too. The albedo is already a color, and stays opaque, as a black material and a
+    /// miss look the same.
+    pub fn preview(self, data: &[f32]) -> Vec<Vec4<f32>> {
+        let gray = |v: f32, hit: bool| {
+            if hit {
+                Vec4::new(v, v, v, 1.0)
+            } else {
+                Vec4::zero()
+            }
+        };
+        // the range of `values` to stretch over 0..1
+        let range = |values: &mut dyn Iterator<Item = f32>| {
+            values.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            })
+        };
+        match self {
+            Aov::Depth => {
+                let (near, far) = range(&mut data.iter().cloned().filter(|d| d.is_finite()));
+                data.iter()
+                    .map(|&d| {
+                        let v = if far > near {
+                            (far - d) / (far - near)
+                        } else {
+                            1.0
+                        };
+                        gray(v, d.is_finite())
+                    })
+                    .collect()
+            }
+            Aov::Normal => data
+                .chunks(3)
+                .map(|n| {
+                    if n.iter().all(|&c| c == 0.0) {
+                        return Vec4::zero();
+                    }
+                    let c = Vec3::new(n[0], n[1], n[2]) * 0.5 + 0.5;
+                    Vec4::new(c.x, c.y, c.z, 1.0)
+                })
+                .collect(),
+            Aov::Albedo => data
+                .chunks(3)
+                .map(|c| Vec4::new(c[0], c[1], c[2], 1.0))
+                .collect(),
+            Aov::ObjectId => {
+                let (_, last) = range(&mut data.iter().cloned());
+                // the first ID isn't black, so it stands out from the background
+                data.iter()
+                    .map(|&id| gray((id + 1.0) / (last + 1.0), id > 0.0))
+                    .collect()
+            }
+            Aov::Steps => {
+                let (_, most) = range(&mut data.iter().cloned());
+                data.iter()
+                    .map(|&steps| {
+                        let heat = if most > 0.0 { 3.0 * steps / most } else { 0.0 };
+                        let ramp = |from: f32| (heat - from).clamp(0.0, 1.0);
+                        Vec4::new(ramp(0.0), ramp(1.0), ramp(2.0), 1.0)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromStr for Aov {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depth" => Ok(Aov::Depth),
+            "normal" => Ok(Aov::Normal),
+            "albedo" => Ok(Aov::Albedo),
+            "id" => Ok(Aov::ObjectId),
+            "steps" => Ok(Aov::Steps),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What the user asked to be written
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputRequest {
+    /// floating-point color instead of 8 bits per channel
+    pub float: bool,
+    pub aovs: Vec<Aov>,
+}
+
+/// What to do when the output format can't hold everything requested
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Degradation {
+    /// refuse to render, rather than lose data the user asked for
+    Error,
+    /// write each AOV to its own file next to the output (`render.depth.png` for `render.png`)
+    Split,
+}
+
+/// One pass of a render
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pass {
+    Beauty,
+    Aov(Aov),
+}
+
+/// A file to write and the passes that go in it, as layers if there's more than one
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub format: OutputFormat,
+    pub passes: Vec<Pass>,
+}
+
+/// A request the output format can't satisfy
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnsupportedOutput {
+    /// the output's extension isn't a format we can write
+    UnknownFormat(String),
+    /// float output was requested for a format that only stores 8-bit color
+    Float(OutputFormat),
+    /// AOVs were requested for a format without layers, and splitting wasn't allowed
+    Layers(OutputFormat),
+}
+
+impl UnsupportedOutput {
+    /// a description of the error for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            UnsupportedOutput::UnknownFormat(path) => Message::UnknownOutputFormat(path),
+            UnsupportedOutput::Float(format) => Message::FloatUnsupported(format.extension()),
+            UnsupportedOutput::Layers(format) => Message::LayersUnsupported(format.extension()),
+        }
+    }
+}
+
+/// Works out which files to write for `request` with the output filename `path`.
+///
+/// Formats with layers get every pass in one file. Otherwise, with `Degradation::Split`, each AOV
+/// goes to its own file, and with `Degradation::Error` AOVs are an error; nothing requested is
+/// ever silently dropped. Float output can't be split off, so it's an error for formats which
+/// don't store floats.
+pub fn plan_outputs(
+    path: &Path,
+    request: &OutputRequest,
+    degradation: Degradation,
+) -> Result<Vec<OutputFile>, UnsupportedOutput> {
+    let format = OutputFormat::from_path(path)
+        .ok_or_else(|| UnsupportedOutput::UnknownFormat(path.display().to_string()))?;
+    if request.float && !format.supports_float() {
+        return Err(UnsupportedOutput::Float(format));
+    }
+    let aovs = request.aovs.iter().map(|&aov| Pass::Aov(aov));
+    if format.supports_layers() || request.aovs.is_empty() {
+        return Ok(vec![OutputFile {
+            path: path.to_owned(),
+            format,
+            passes: Some(Pass::Beauty).into_iter().chain(aovs).collect(),
+        }]);
+    }
+    match degradation {
+        Degradation::Error => Err(UnsupportedOutput::Layers(format)),
+        Degradation::Split => Ok(Some(OutputFile {
+            path: path.to_owned(),
+            format,
+            passes: vec![Pass::Beauty],
+        })
+        .into_iter()
+        .chain(request.aovs.iter().map(|&aov| OutputFile {
+            path: path.with_extension(format!("{}.{}", aov.name(), format.extension())),
+            format,
+            passes: vec![Pass::Aov(aov)],
+        }))
+        .collect()),
+    }
+}
+
+/// A named set of channels in a multi-layer EXR
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    /// the prefix of the layer's channel names; empty for the beauty pass, whose channels are
+    /// plain `R`, `G`, `B`, and `A`
+    pub name: String,
+    pub channels: &'static [&'static str],
+    /// one value per channel per pixel, interleaved, in row-major order
+    pub data: Vec<f32>,
+}
+
+impl Layer {
+    pub fn beauty(color: &[Vec4<f32>]) -> Self {
+        Layer {
+            name: String::new(),
+            channels: &["R", "G", "B", "A"],
+            data: color.iter().flat_map(|c| c.into_array().to_vec()).collect(),
+        }
+    }
+
+    /// `data` has `aov.channels().len()` values per pixel
+    pub fn aov(aov: Aov, data: Vec<f32>) -> Self {
+        Layer {
+            name: aov.name().to_string(),
+            channels: aov.channels(),
+            data,
+        }
+    }
+
+    /// the light reaching the camera from one group of lights; the groups sum to the beauty pass
+    pub fn light_group(group: &str, color: &[Vec3<f32>]) -> Self {
+        Layer {
+            name: format!("light_{}", group),
+            channels: &["R", "G", "B"],
+            data: color.iter().flat_map(|c| c.into_array().to_vec()).collect(),
+        }
+    }
+
+    /// the layer's full channel names and their offsets into each pixel's values
+    fn full_channels(&self) -> impl Iterator<Item = (String, usize)> + '_ {
+        self.channels.iter().enumerate().map(move |(i, channel)| {
+            if self.name.is_empty() {
+                (channel.to_string(), i)
+            } else {
+                (format!("{}.{}", self.name, channel), i)
+            }
+        })
+    }
+}
+
+/// Appends an EXR header attribute: its name, type name, size, and value
+fn exr_attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+/// Encodes `layers`, each `size` pixels, as one uncompressed scanline OpenEXR image with 32-bit
+/// float channels, so every pass lands in the same file
+pub fn encode_exr(size: Extent2<usize>, layers: &[Layer]) -> Result<Vec<u8>, SizeError> {
+    OutputFormat::Exr.check_size(size)?;
+    // EXR readers expect the channel list (and each scanline's channel data) sorted by name
+    let mut channels: Vec<(String, &Layer, usize)> = layers
+        .iter()
+        .flat_map(|layer| {
+            layer
+                .full_channels()
+                .map(move |(name, offset)| (name, layer, offset))
+        })
+        .collect();
+    channels.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // magic number, then version 2 with no flags: single-part scanline
+    let mut out = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0];
+
+    let mut chlist = Vec::new();
+    for (name, _, _) in &channels {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        // FLOAT pixels, not perceptually linear, three reserved bytes, and no subsampling
+        chlist.extend_from_slice(&2i32.to_le_bytes());
+        chlist.extend_from_slice(&[0, 0, 0, 0]);
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+    exr_attribute(&mut out, "channels", "chlist", &chlist);
+    exr_attribute(&mut out, "compression", "compression", &[0]);
+    let window: Vec<u8> = [0, 0, size.w as i32 - 1, size.h as i32 - 1]
+        .iter()
+        .flat_map(|i| i.to_le_bytes().to_vec())
+        .collect();
+    exr_attribute(&mut out, "dataWindow", "box2i", &window);
+    exr_attribute(&mut out, "displayWindow", "box2i", &window);
+    // increasing y
+    exr_attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+    exr_attribute(&mut out, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    exr_attribute(&mut out, "screenWindowCenter", "v2f", &[0; 8]);
+    exr_attribute(&mut out, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    out.push(0);
+
+    // uncompressed files have one scanline per block; the offset table points at each block,
+    // which is its y coordinate, its data's length, then each channel's values for the line
+    // each scanline's length is also signed 32-bit
+    let line_len = channels.len() * size.w * 4;
+    if line_len > i32::MAX as usize {
+        return Err(SizeError::Side {
+            format: OutputFormat::Exr,
+            max: (i32::MAX as usize / (channels.len() * 4)) as u64,
+        });
+    }
+    let table_end = out.len() + size.h * 8;
+    for y in 0..size.h {
+        out.extend_from_slice(&((table_end + y * (8 + line_len)) as u64).to_le_bytes());
+    }
+    for y in 0..size.h {
+        out.extend_from_slice(&(y as i32).to_le_bytes());
+        out.extend_from_slice(&(line_len as i32).to_le_bytes());
+        for (_, layer, offset) in &channels {
+            let stride = layer.channels.len();
+            for x in 0..size.w {
+                let value = layer.data[(y * size.w + x) * stride + offset];
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Encodes `color`, `size` pixels in row-major order with components from 0 to 1, as an
+/// uncompressed 16-bit RGBA TIFF. Like PNG output, the color should already be encoded for
+/// display (sRGB); it's only quantized more finely.
+pub fn encode_tiff(size: Extent2<usize>, color: &[Vec4<f32>]) -> Result<Vec<u8>, SizeError> {
+    OutputFormat::Tiff.check_size(size)?;
+    let samples: Vec<u8> = color
+        .iter()
+        .flat_map(|c| c.into_array().to_vec())
+        .flat_map(|c| {
+            let sample = (c.clamp(0.0, 1.0) * 65535.0).round() as u16;
+            sample.to_le_bytes().to_vec()
+        })
+        .collect();
+
+    // the header, the pixels, four bits-per-sample values, then the image file directory
+    let bits_offset = 8 + samples.len();
+    let ifd_offset = bits_offset + 8;
+    let mut out = b"II".to_vec();
+    out.extend_from_slice(&42u16.to_le_bytes());
+    out.extend_from_slice(&(ifd_offset as u32).to_le_bytes());
+    out.extend_from_slice(&samples);
+    for _ in 0..4 {
+        out.extend_from_slice(&16u16.to_le_bytes());
+    }
+
+    const SHORT: u16 = 3;
+    const LONG: u16 = 4;
+    // tag, type, count, and value (or offset to the values), in increasing tag order
+    let entries: [(u16, u16, u32, u32); 11] = [
+        (256, LONG, 1, size.w as u32),
+        (257, LONG, 1, size.h as u32),
+        (258, SHORT, 4, bits_offset as u32),
+        // no compression
+        (259, SHORT, 1, 1),
+        // RGB
+        (262, SHORT, 1, 2),
+        // the whole image is one strip, right after the header
+        (273, LONG, 1, 8),
+        (277, SHORT, 1, 4),
+        (278, LONG, 1, size.h as u32),
+        (279, LONG, 1, samples.len() as u32),
+        // interleaved channels
+        (284, SHORT, 1, 1),
+        // the fourth sample is unassociated alpha
+        (338, SHORT, 1, 2),
+    ];
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for &(tag, kind, count, value) in &entries {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    // no further images
+    out.extend_from_slice(&0u32.to_le_bytes());
+    Ok(out)
+}
+
+/// The largest width or height a WebP image can have
+pub const WEBP_MAX_SIZE: usize = 1 << 14;
+
+/// Bits packed least significant first, as WebP's lossless format reads them
+struct BitWriter {
+    bytes: Vec<u8>,
+    used: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            used: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, bits: u32) {
+        for i in 0..bits {
+            let offset = self.used % 8;
+            if offset == 0 {
+                self.bytes.push(0);
+            }
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= (((value >> i) & 1) as u8) << offset;
+            self.used += 1;
+        }
+    }
+}
+
+/// Encodes `rgba`, 8-bit pixels `size` in row-major order, as a lossless WebP; an error if it's
+/// larger than `WEBP_MAX_SIZE` on either side.
+///
+/// Every channel is written with a fixed 8-bit prefix code and no transforms or backward
+/// references, so files are about the size of the raw pixels, but decode exactly.
+pub fn encode_webp(size: Extent2<usize>, rgba: &[u8]) -> Result<Vec<u8>, SizeError> {
+    OutputFormat::WebP.check_size(size)?;
+    let mut bits = BitWriter::new();
+    // signature, dimensions, alpha used, and version 0
+    bits.write(0x2f, 8);
+    bits.write(size.w as u32 - 1, 14);
+    bits.write(size.h as u32 - 1, 14);
+    bits.write(1, 1);
+    bits.write(0, 3);
+    // no transforms, no color cache, and one set of prefix codes for the whole image
+    bits.write(0, 1);
+    bits.write(0, 1);
+    bits.write(0, 1);
+
+    // green (with the 24 length prefixes after its 256 literals), red, blue, and alpha: the
+    // code lengths are themselves coded with 1-bit codes, 0 for length 0 and 1 for length 8
+    for &symbols in &[256 + 24, 256, 256, 256] {
+        // a normal code, with the code length code's lengths for symbols 17, 18, 0, 1, 2, 3,
+        // 4, 5, 16, 6, 7, and 8
+        bits.write(0, 1);
+        bits.write(12 - 4, 4);
+        for &length in &[0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1] {
+            bits.write(length, 3);
+        }
+        // lengths for every symbol follow
+        bits.write(0, 1);
+        for symbol in 0..symbols {
+            bits.write(if symbol < 256 { 1 } else { 0 }, 1);
+        }
+    }
+    // distances are never used: a simple code with the single 1-bit symbol 0
+    bits.write(1, 1);
+    bits.write(0, 1);
+    bits.write(0, 1);
+    bits.write(0, 1);
+
+    // prefix codes are read a bit at a time from their most significant bit
+    for pixel in rgba.chunks(4) {
+        for &channel in &[1, 0, 2, 3] {
+            bits.write(u32::from(pixel[channel].reverse_bits()), 8);
+        }
+    }
+
+    let mut data = bits.bytes;
+    let len = data.len();
+    if len % 2 == 1 {
+        data.push(0);
+    }
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&((4 + 8 + data.len()) as u32).to_le_bytes());
+    out.extend_from_slice(b"WEBPVP8L");
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+    out.extend_from_slice(&data);
+    Ok(out)
+}
+
+/// The PNG text chunk keyword a render's scene file is embedded under
+pub const SCENE_KEYWORD: &str = "ray-marcher scene";
+
+/// CRC-32 as used by PNG chunks (the ISO 3309 polynomial, reflected)
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ u32::from(b), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// A PNG chunk: its data's length, `kind`, `data`, and a CRC of the kind and data
+fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    let crc = crc32(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+    chunk
+}
+
+/// An uncompressed `iTXt` (UTF-8 text) chunk with `keyword` and `text`
+fn text_chunk(keyword: &str, text: &str) -> Vec<u8> {
+    // keyword, then no compression, no language tag, and no translated keyword
+    let mut data = keyword.as_bytes().to_vec();
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    data.extend_from_slice(text.as_bytes());
+    png_chunk(b"iTXt", &data)
+}
+
+/// Inserts an uncompressed `iTXt` (UTF-8 text) chunk with `keyword` and `text` into the encoded
+/// PNG `png`, right after its header
+pub fn embed_text(png: &[u8], keyword: &str, text: &str) -> Vec<u8> {
+    let chunk = text_chunk(keyword, text);
+    // the 8-byte signature and the IHDR chunk (4 bytes each of length, type, and CRC around 13
+    // bytes of data)
+    let header_len = 8 + 12 + 13;
+    let mut out = Vec::with_capacity(png.len() + chunk.len());
+    out.extend_from_slice(&png[..header_len]);
+    out.extend_from_slice(&chunk);
+    out.extend_from_slice(&png[header_len..]);
+    out
+}
+
+/// Splits compressed image data into `IDAT` chunks as it's written
+struct IdatWriter<W: Write> {
+    out: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> IdatWriter<W> {
+    const CHUNK_SIZE: usize = 1 << 16;
+}
+
+impl<W: Write> Write for IdatWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while self.buffer.len() >= Self::CHUNK_SIZE {
+            let rest = self.buffer.split_off(Self::CHUNK_SIZE);
+            self.out.write_all(&png_chunk(b"IDAT", &self.buffer))?;
+            self.buffer = rest;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.out.write_all(&png_chunk(b"IDAT", &self.buffer))?;
+            self.buffer.clear();
+        }
+        self.out.flush()
+    }
+}
+
+/// Encodes an 8-bit RGBA PNG a band of full-width rows at a time (like the tiles of
+/// `Tiling::Scanline`), so a large render is never buffered whole before encoding. Bands may
+/// arrive in any order; each is held only until the rows above it have been written.
+pub struct PngStream<W: Write> {
+    zlib: ZlibEncoder<IdatWriter<W>>,
+    size: Extent2<usize>,
+    /// the first row not written yet
+    next_row: usize,
+    /// bands which arrived before the rows above them, by their first row
+    pending: BTreeMap<usize, Vec<u8>>,
+    /// the last row written, unfiltered, for the next row's filter
+    prev: Vec<u8>,
+    texts: Vec<Vec<u8>>,
+}
+
+/// the Paeth predictor: whichever of left, up, and up-left is closest to left + up - up-left
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+impl<W: Write> PngStream<W> {
+    /// Writes the PNG signature and header for an image of `size` pixels to `out`
+    pub fn new(mut out: W, size: Extent2<usize>) -> io::Result<Self> {
+        OutputFormat::Png.check_size(size).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PNG sides must be from 1 to 2^31 - 1 pixels",
+            )
+        })?;
+        out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+        let mut header = (size.w as u32).to_be_bytes().to_vec();
+        header.extend_from_slice(&(size.h as u32).to_be_bytes());
+        // 8 bits per channel, RGBA, deflate, adaptive filtering, and no interlacing
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+        out.write_all(&png_chunk(b"IHDR", &header))?;
+        Ok(PngStream {
+            zlib: ZlibEncoder::new(
+                IdatWriter {
+                    out,
+                    buffer: Vec::new(),
+                },
+                Compression::Default,
+            ),
+            size,
+            next_row: 0,
+            pending: BTreeMap::new(),
+            prev: vec![0; size.w * 4],
+            texts: Vec::new(),
+        })
+    }
+
+    /// Adds the rows `rows` covers, whose pixels are `rgba`; `rows` must span the image's width,
+    /// lie within its height, and not overlap any band already added
+    pub fn write_band(&mut self, rows: Rect<usize, usize>, rgba: Vec<u8>) -> io::Result<()> {
+        if rows.x != 0 || rows.w != self.size.w || rgba.len() != rows.w * rows.h * 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PNG bands must be whole rows",
+            ));
+        }
+        let end = rows.y.saturating_add(rows.h);
+        if end > self.size.h {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PNG bands must lie within the image",
+            ));
+        }
+        if rows.h == 0 {
+            return Ok(());
+        }
+        let row_len = self.size.w * 4;
+        let overlaps = rows.y < self.next_row
+            || self
+                .pending
+                .iter()
+                .any(|(&y, band)| y < end && rows.y < y + band.len() / row_len);
+        if overlaps {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PNG bands must not overlap",
+            ));
+        }
+        self.pending.insert(rows.y, rgba);
+        while let Some(band) = self.pending.remove(&self.next_row) {
+            let mut filtered = vec![0; row_len];
+            for row in band.chunks(row_len.max(1)) {
+                for i in 0..row_len {
+                    let left = if i >= 4 { row[i - 4] } else { 0 };
+                    let up_left = if i >= 4 { self.prev[i - 4] } else { 0 };
+                    filtered[i] = row[i].wrapping_sub(paeth(left, self.prev[i], up_left));
+                }
+                // the Paeth filter type precedes each row
+                self.zlib.write_all(&[4])?;
+                self.zlib.write_all(&filtered)?;
+                self.prev.copy_from_slice(row);
+                self.next_row += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds an `iTXt` chunk with `keyword` and `text` after the image data, as `embed_text` does
+    pub fn embed_text(&mut self, keyword: &str, text: &str) {
+        self.texts.push(text_chunk(keyword, text));
+    }
+
+    /// Finishes the image, which must have had every row written, and returns the writer
+    pub fn finish(self) -> io::Result<W> {
+        if self.next_row < self.size.h {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PNG is missing rows",
+            ));
+        }
+        let mut idat = self.zlib.finish()?;
+        idat.flush()?;
+        let mut out = idat.out;
+        for text in &self.texts {
+            out.write_all(text)?;
+        }
+        out.write_all(&png_chunk(b"IEND", &[]))?;
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+/// The keywords and text of the `tEXt` and uncompressed `iTXt` chunks of the encoded PNG `png`
+pub fn extract_text(png: &[u8]) -> Vec<(String, String)> {
+    let mut texts = Vec::new();
+    let mut pos = 8;
+    while pos + 8 <= png.len() {
+        let mut len = [0; 4];
+        len.copy_from_slice(&png[pos..pos + 4]);
+        let len = u32::from_be_bytes(len) as usize;
+        let kind = &png[pos + 4..pos + 8];
+        let data = match png.get(pos + 8..pos + 8 + len) {
+            Some(data) => data,
+            None => break,
+        };
+        let mut fields = data.splitn(2, |&b| b == 0);
+        let keyword = String::from_utf8_lossy(fields.next().unwrap_or(&[])).into_owned();
+        let rest = fields.next().unwrap_or(&[]);
+        match kind {
+            b"tEXt" => texts.push((keyword, String::from_utf8_lossy(rest).into_owned())),
+            // compression flag, method, then null-terminated language tag and translated keyword
+            b"iTXt" if rest.first() == Some(&0) && rest.len() >= 2 => {
+                let mut fields = rest[2..].splitn(3, |&b| b == 0);
+                if let Some(text) = fields.nth(2) {
+                    texts.push((keyword, String::from_utf8_lossy(text).into_owned()));
+                }
+            }
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    texts
+}
+
+/// The scene file a rendered image came from: embedded in the image under `SCENE_KEYWORD`, or
+/// in a sidecar file next to it with the extension `.yml`; `None` if there's neither
+pub fn scene_for_image(image: &Path) -> io::Result<Option<String>> {
+    let embedded = extract_text(&fs::read(image)?)
+        .into_iter()
+        .find(|(keyword, _)| keyword == SCENE_KEYWORD)
+        .map(|(_, text)| text);
+    match embedded {
+        Some(scene) => Ok(Some(scene)),
+        None => match fs::read_to_string(image.with_extension("yml")) {
+            Ok(scene) => Ok(Some(scene)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use pretty_assertions::assert_eq;
+    use vek::{Extent2, Rect, Vec3, Vec4};
+
+    use super::{
+        buffer_len, check_render_size, checkerboard, crc32, embed_text, encode_exr, encode_tiff,
+        encode_webp, extract_text, plan_outputs, reconstruct_checkerboard, refinement_levels,
+        seed_distances, smooth_normals, Aov, Degradation, Guides, Layer, OutputFormat,
+        OutputRequest, Pass, PngStream, SampleBuffer, SizeError, Tiling, UnsupportedOutput,
+        SCENE_KEYWORD, WEBP_MAX_SIZE,
+    };
+
+    #[test]
+    fn sample_buffer_converges_test() {
+        let mut buf = SampleBuffer::new(2, 1, 0.01, 4);
+        for i in 0..8 {
+            // a flat pixel and a noisy one
+            buf.add(0, 0, Vec4::broadcast(0.5));
+            buf.add(1, 0, Vec4::broadcast(if i % 2 == 0 { 0.0 } else { 1.0 }));
+        }
+        assert_eq!(buf.get(0, 0).mean(), Vec4::broadcast(0.5));
+        assert_eq!(buf.pending().collect::<Vec<_>>(), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn reconstruct_checkerboard_keeps_edges_test() {
+        // the left two columns are a near surface and the right two a far one; the skipped pixel
+        // at (2, 1) should be filled from its vertical (same-surface) neighbors, not across the
+        // edge
+        let (near, far) = (Vec4::broadcast(1.0), Vec4::broadcast(0.0));
+        let width = 4;
+        let mut color: Vec<Vec4<f64>> = (0..12)
+            .map(
+                |inx| match (checkerboard(inx % width, inx / width, 0), inx % width) {
+                    (false, _) => Vec4::broadcast(-1.0),
+                    (true, 0) | (true, 1) => near,
+                    (true, _) => far,
+                },
+            )
+            .collect();
+        let depth: Vec<f64> = (0..12)
+            .map(|inx| if inx % width < 2 { 1.0 } else { 5.0 })
+            .collect();
+        let normal = vec![Vec3::unit_z(); 12];
+        let albedo = vec![1.0; 12];
+        let guides = Guides {
+            depth: &depth,
+            normal: &normal,
+            albedo: &albedo,
+        };
+        reconstruct_checkerboard(&mut color, &guides, width, 0);
+        assert_eq!(color[width + 2], far);
+        assert!(color.iter().all(|c| c.x >= 0.0));
+    }
+
+    #[test]
+    fn smooth_normals_test() {
+        // a noisy floor on the left half and a wall much further away on the right, with one
+        // pixel missing and one whose normal came out NaN
+        let width = 6;
+        let normal: Vec<Option<Vec3<f64>>> = (0..36)
+            .map(|inx| match (inx % width, inx / width) {
+                (0, 0) => None,
+                (1, 1) => Some(Vec3::broadcast(f64::NAN)),
+                (x, y) if x < 3 => {
+                    let wobble = if (x + y) % 2 == 0 { 0.2 } else { -0.2 };
+                    Some(Vec3::new(wobble, 1.0, 0.0).normalized())
+                }
+                _ => Some(Vec3::unit_z()),
+            })
+            .collect();
+        let depth: Vec<Option<f64>> = (0..36)
+            .map(|inx| match inx % width {
+                _ if inx == 0 => None,
+                x if x < 3 => Some(1.0),
+                _ => Some(10.0),
+            })
+            .collect();
+        let smooth = smooth_normals(&normal, &depth, width, 2, 0.01);
+        assert_eq!(smooth[0], None);
+        // the floor is much flatter, and none of the wall bleeds into it or vice versa
+        let noise = |n: Vec3<f64>| n.x.abs();
+        assert!(noise(smooth[2 * width + 1].unwrap()) < 0.05);
+        assert!(smooth[2 * width + 1].unwrap().z.abs() < 1e-9);
+        assert!((smooth[2 * width + 3].unwrap() - Vec3::unit_z()).magnitude() < 1e-9);
+        // the NaN is replaced by its neighbors'
+        assert!(smooth[width + 1].unwrap().y > 0.99);
+        assert_eq!(
+            smooth_normals(&normal, &depth, width, 0, 0.01)[2],
+            normal[2]
+        );
+        // nothing to compare depths against, so nothing is smoothed, and nothing is NaN
+        assert_eq!(smooth_normals(&normal, &depth, width, 2, 0.0)[2], normal[2]);
+        let mut at_camera = depth.clone();
+        at_camera[2] = Some(0.0);
+        assert_eq!(
+            smooth_normals(&normal, &at_camera, width, 2, 0.01)[2],
+            normal[2]
+        );
+        assert_eq!(smooth_normals::<f64>(&[], &[], 0, 2, 0.01), vec![]);
+    }
+
+    #[test]
+    fn refinement_levels_test() {
+        assert_eq!(
+            refinement_levels(Extent2::new(800, 600), 4),
+            vec![
+                Extent2::new(100, 75),
+                Extent2::new(200, 150),
+                Extent2::new(400, 300),
+                Extent2::new(800, 600),
+            ]
+        );
+    }
+
+    #[test]
+    fn seed_distances_test() {
+        let coarse = [Some(4.0), Some(2.0), Some(3.0), None];
+        let seeds = seed_distances(&coarse, Extent2::new(2, 2), Extent2::new(4, 4), 0.5);
+        // every fine pixel is next to the miss in the bottom right
+        assert!(seeds.iter().all(|&s| s == 0.0));
+
+        let coarse = [Some(4.0), Some(2.0), Some(3.0), Some(8.0)];
+        let seeds = seed_distances(&coarse, Extent2::new(2, 2), Extent2::new(4, 4), 0.5);
+        assert!(seeds.iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn tiling_test() {
+        let size = Extent2::new(100, 30);
+        let tiles = Tiling::Square(32).tiles(size);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(tiles[3], Rect::new(96, 0, 4, 30));
+        assert_eq!(
+            tiles.iter().map(|t| t.w * t.h).sum::<usize>(),
+            size.w * size.h
+        );
+
+        let rows = Tiling::Scanline(4).tiles(size);
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[7], Rect::new(0, 28, 100, 2));
+
+        // previews get scanlines, big renders get square tiles
+        assert_eq!(Tiling::auto(Extent2::new(160, 120), 8), Tiling::Scanline(1));
+        assert_eq!(
+            Tiling::auto(Extent2::new(3840, 2160), 8),
+            Tiling::Square(128)
+        );
+        assert_eq!(
+            Tiling::auto(Extent2::new(16384, 16384), 4),
+            Tiling::Square(256)
+        );
+    }
+
+    #[test]
+    fn plan_outputs_test() {
+        let request = OutputRequest {
+            float: false,
+            aovs: vec![Aov::Depth, Aov::Normal],
+        };
+        let exr = plan_outputs(Path::new("out.exr"), &request, Degradation::Error).unwrap();
+        assert_eq!(exr.len(), 1);
+        assert_eq!(exr[0].passes.len(), 3);
+
+        let png = Path::new("renders/out.png");
+        assert_eq!(
+            plan_outputs(png, &request, Degradation::Error),
+            Err(UnsupportedOutput::Layers(OutputFormat::Png))
+        );
+        let split = plan_outputs(png, &request, Degradation::Split).unwrap();
+        assert_eq!(
+            split.iter().map(|f| f.path.clone()).collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("renders/out.png"),
+                PathBuf::from("renders/out.depth.png"),
+                PathBuf::from("renders/out.normal.png"),
+            ]
+        );
+        assert_eq!(split[2].passes, vec![Pass::Aov(Aov::Normal)]);
+
+        // there's no splitting float color off into another file
+        let float = OutputRequest {
+            float: true,
+            aovs: vec![],
+        };
+        assert_eq!(
+            plan_outputs(png, &float, Degradation::Split),
+            Err(UnsupportedOutput::Float(OutputFormat::Png))
+        );
+        assert_eq!(
+            plan_outputs(Path::new("out.jpg"), &float, Degradation::Split),
+            Err(UnsupportedOutput::UnknownFormat("out.jpg".to_string()))
+        );
+    }
+
+    #[test]
+    fn aov_preview_test() {
+        let depth = Aov::Depth.preview(&[2.0, 4.0, f32::INFINITY, 3.0]);
+        assert_eq!(
+            depth,
+            vec![
+                Vec4::broadcast(1.0),
+                Vec4::new(0.0, 0.0, 0.0, 1.0),
+                Vec4::zero(),
+                Vec4::new(0.5, 0.5, 0.5, 1.0),
+            ]
+        );
+        let normal = Aov::Normal.preview(&[0.0, 0.0, -1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(normal, vec![Vec4::new(0.5, 0.5, 0.0, 1.0), Vec4::zero()]);
+        let id = Aov::ObjectId.preview(&[0.0, 1.0, 3.0]);
+        assert_eq!(id[0], Vec4::zero());
+        assert_eq!(id[1], Vec4::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(id[2], Vec4::broadcast(1.0));
+        let steps = Aov::Steps.preview(&[0.0, 2.0, 6.0]);
+        assert_eq!(
+            steps,
+            vec![
+                Vec4::new(0.0, 0.0, 0.0, 1.0),
+                Vec4::new(1.0, 0.0, 0.0, 1.0),
+                Vec4::broadcast(1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn encode_exr_test() {
+        let size = Extent2::new(2, 1);
+        let layers = vec![
+            Layer::beauty(&[Vec4::new(1.0, 0.5, 0.25, 1.0), Vec4::broadcast(0.0)]),
+            Layer::aov(Aov::Depth, vec![3.0, 4.0]),
+            Layer::light_group("key", &[Vec3::broadcast(0.5), Vec3::broadcast(0.0)]),
+        ];
+        let exr = encode_exr(size, &layers).unwrap();
+        assert_eq!(&exr[..4], &[0x76, 0x2f, 0x31, 0x01]);
+
+        // the channel list, sorted by name
+        let header = String::from_utf8_lossy(&exr);
+        let names = [
+            "A\0",
+            "B\0",
+            "G\0",
+            "R\0",
+            "depth.Z\0",
+            "light_key.B\0",
+            "light_key.G\0",
+            "light_key.R\0",
+        ];
+        let positions: Vec<usize> = names.iter().map(|n| header.find(n).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        // one scanline: 8 channels of 2 floats, after its y and length
+        let mut offset = [0; 8];
+        let table = exr.len() - 8 - 8 * 2 * 4 - 8;
+        offset.copy_from_slice(&exr[table..table + 8]);
+        let line = u64::from_le_bytes(offset) as usize;
+        assert_eq!(line, table + 8);
+        let float = |i: usize| {
+            let mut bytes = [0; 4];
+            let start = line + 8 + i * 4;
+            bytes.copy_from_slice(&exr[start..start + 4]);
+            f32::from_le_bytes(bytes)
+        };
+        // R is the fourth channel, and depth.Z the fifth
+        assert_eq!((float(6), float(7)), (1.0, 0.0));
+        assert_eq!((float(8), float(9)), (3.0, 4.0));
+    }
+
+    #[test]
+    fn encode_tiff_test() {
+        let tiff = encode_tiff(
+            Extent2::new(2, 1),
+            &[
+                Vec4::new(1.0, 0.5, 0.0, 1.0),
+                Vec4::new(2.0, -1.0, 0.0, 0.0),
+            ],
+        )
+        .unwrap();
+        assert_eq!(&tiff[..4], b"II*\0");
+        let u16_at = |i: usize| u16::from_le_bytes([tiff[i], tiff[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([tiff[i], tiff[i + 1], tiff[i + 2], tiff[i + 3]]);
+        // the pixels, clamped and scaled to 16 bits
+        let samples: Vec<u16> = (0..8).map(|i| u16_at(8 + 2 * i)).collect();
+        assert_eq!(samples, vec![65535, 32768, 0, 65535, 65535, 0, 0, 0]);
+        // the directory's entries, with the width and height first
+        let ifd = u32_at(4) as usize;
+        assert_eq!(u16_at(ifd), 11);
+        assert_eq!((u16_at(ifd + 2), u32_at(ifd + 2 + 8)), (256, 2));
+        assert_eq!((u16_at(ifd + 14), u32_at(ifd + 14 + 8)), (257, 1));
+        assert_eq!(tiff.len(), ifd + 2 + 11 * 12 + 4);
+    }
+
+    #[test]
+    fn encode_webp_test() {
+        assert_eq!(
+            encode_webp(Extent2::new(WEBP_MAX_SIZE + 1, 1), &[]),
+            Err(SizeError::Side {
+                format: OutputFormat::WebP,
+                max: WEBP_MAX_SIZE as u64
+            })
+        );
+
+        let rgba = [255, 128, 0, 255, 1, 2, 3, 4];
+        let webp = encode_webp(Extent2::new(2, 1), &rgba).unwrap();
+        assert_eq!(&webp[..4], b"RIFF");
+        assert_eq!(&webp[8..16], b"WEBPVP8L");
+        assert_eq!(webp.len() % 2, 0);
+        let riff_len = u32::from_le_bytes([webp[4], webp[5], webp[6], webp[7]]) as usize;
+        assert_eq!(riff_len + 8, webp.len());
+        // signature, then a width and height of 1 less than the image's
+        assert_eq!(webp[20], 0x2f);
+        let header = u32::from_le_bytes([webp[21], webp[22], webp[23], webp[24]]);
+        assert_eq!((header & 0x3fff, (header >> 14) & 0x3fff), (1, 0));
+
+        // the pixels follow the header, the four normal codes (1 + 4 + 12 * 3 + 1 bits, then one
+        // per symbol), and the simple distance code; each channel is read from its most
+        // significant bit, in green, red, blue, alpha order
+        let stream = &webp[20..];
+        let bit = |i: usize| (stream[i / 8] >> (i % 8)) & 1;
+        let start = 43 + (42 + 280) + 3 * (42 + 256) + 4;
+        let pixel_bits: Vec<u8> = (start..start + 64)
+            .collect::<Vec<_>>()
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &i| (acc << 1) | bit(i)))
+            .collect();
+        assert_eq!(pixel_bits, vec![128, 255, 0, 255, 2, 1, 3, 4]);
+    }
+
+    #[test]
+    fn size_limits_test() {
+        let huge = Extent2::new(usize::MAX, 2);
+        assert_eq!(buffer_len(huge, 1), Err(SizeError::TooLarge(huge)));
+        assert_eq!(buffer_len(Extent2::new(3, 2), 4), Ok(24));
+
+        // a 65536-pixel square render holds 128 GiB of samples: addressable on 64-bit targets,
+        // but not 32-bit ones, where 8192 by 8191 is about the largest
+        let giga = Extent2::new(1 << 16, 1 << 16);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(check_render_size(giga, OutputFormat::Exr), Ok(()));
+        #[cfg(target_pointer_width = "32")]
+        {
+            assert_eq!(
+                check_render_size(giga, OutputFormat::Exr),
+                Err(SizeError::TooLarge(giga))
+            );
+            assert_eq!(
+                check_render_size(Extent2::new(8192, 8191), OutputFormat::Exr),
+                Ok(())
+            );
+        }
+
+        // the formats' own limits, which are the same on every platform
+        assert_eq!(
+            OutputFormat::Png.check_size(Extent2::new(1 << 31, 1)),
+            Err(SizeError::Side {
+                format: OutputFormat::Png,
+                max: (1 << 31) - 1
+            })
+        );
+        assert_eq!(
+            OutputFormat::Png.check_size(Extent2::new(0, 1)),
+            Err(SizeError::Empty)
+        );
+        assert_eq!(
+            OutputFormat::Tiff.check_size(Extent2::new(1 << 16, 1 << 13)),
+            Err(SizeError::Area {
+                format: OutputFormat::Tiff,
+                max: ((1 << 32) - 17) / 8
+            })
+        );
+        assert_eq!(
+            OutputFormat::Tiff.check_size(Extent2::new(1 << 16, (1 << 13) - 1)),
+            Ok(())
+        );
+        assert!(PngStream::new(Vec::new(), Extent2::new(1 << 31, 1)).is_err());
+    }
+
+    #[test]
+    fn crc32_test() {
+        // the CRC of an empty IEND chunk, as found at the end of every PNG
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn png_stream_test() {
+        let size = Extent2::new(3, 4);
+        let rgba: Vec<u8> = (0..size.w * size.h * 4)
+            .map(|i| (i * 37 % 256) as u8)
+            .collect();
+        let mut stream = PngStream::new(Vec::new(), size).unwrap();
+        // the bands of a scanline tiling, finishing bottom first
+        let tiles = Tiling::Scanline(2).tiles(size);
+        let band = |t: Rect<usize, usize>| rgba[t.y * 12..(t.y + t.h) * 12].to_vec();
+        stream.write_band(tiles[1], band(tiles[1])).unwrap();
+        assert!(stream
+            .write_band(Rect::new(1, 0, 2, 2), vec![0; 16])
+            .is_err());
+        // past the bottom, or over a band still waiting for the rows above it
+        assert!(stream
+            .write_band(Rect::new(0, 3, 3, 2), vec![0; 24])
+            .is_err());
+        assert!(stream
+            .write_band(Rect::new(0, 1, 3, 2), vec![0; 24])
+            .is_err());
+        stream.write_band(tiles[0], band(tiles[0])).unwrap();
+        // over rows already written
+        assert!(stream
+            .write_band(Rect::new(0, 1, 3, 1), vec![0; 12])
+            .is_err());
+        stream.embed_text(SCENE_KEYWORD, "geometry: []");
+        let png = stream.finish().unwrap();
+
+        assert_eq!(
+            extract_text(&png),
+            vec![(SCENE_KEYWORD.to_string(), "geometry: []".to_string())]
+        );
+        let decoder = png::Decoder::new(&png[..]);
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!((info.width, info.height), (3, 4));
+        let mut pixels = vec![0; info.buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, rgba);
+
+        let incomplete = PngStream::new(Vec::new(), size).unwrap();
+        assert!(incomplete.finish().is_err());
+    }
+
+    #[test]
+    fn embed_text_test() {
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, 1, 1);
+            encoder.set_color(png::ColorType::RGBA);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[255, 0, 0, 255]).unwrap();
+        }
+        let scene = "geometry: []\n# ünïcödé\n";
+        let embedded = embed_text(&png, SCENE_KEYWORD, scene);
+        assert_eq!(
+            extract_text(&embedded),
+            vec![(SCENE_KEYWORD.to_string(), scene.to_string())]
+        );
+        // still a valid image
+        let decoder = png::Decoder::new(&embedded[..]);
+        let (info, mut reader) = decoder.read_info().unwrap();
+        let mut pixel = vec![0; info.buffer_size()];
+        reader.next_frame(&mut pixel).unwrap();
+        assert_eq!(pixel, vec![255, 0, 0, 255]);
+    }
+}
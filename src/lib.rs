@@ -1,7 +1,20 @@
 pub mod camera;
 pub mod distance;
+pub mod exit;
+pub mod expr;
+pub mod filename;
+pub mod history;
 pub mod img;
 pub mod light;
+pub mod matrix;
+pub mod mesh;
+pub mod messages;
+pub mod noise;
+pub mod overlay;
+pub mod quaternion;
+pub mod randomize;
 pub mod render;
 pub mod serialize;
-pub mod assert;
+pub mod threads;
+pub mod trace;
+pub mod volume;
@@ -1,20 +1,18 @@
 use std::iter::Sum;
 use std::ops::Mul;
+use std::sync::Arc;
 
 use num::Float;
 use palette::{Alpha, Blend, Component, ComponentWise};
 use serde::{Deserialize, Serialize};
 use vek::Vec3;
 
-use crate::camera::Viewport;
-
 pub struct BlinnPhong<T, C>
 where
     T: Default + Clone,
     C: Default + Clone,
 {
-    viewport: Viewport<T>,
-    lights: Vec<Light<T, C>>,
+    lights: Arc<[Light<T, C>]>,
 }
 
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq)]
@@ -47,12 +45,23 @@ where
     // k_s, k_d, k_a in a material
 }
 
+impl<T, C> BlinnPhong<T, C>
+where
+    T: Default + Clone,
+    C: Default + Clone,
+{
+    /// shading with the given lights, which can be shared with the scene and other threads
+    pub fn new(lights: Arc<[Light<T, C>]>) -> Self {
+        BlinnPhong { lights }
+    }
+}
+
 impl<T, C> BlinnPhong<T, Alpha<C, T>>
 where
     T: Float + Sum + Component + Default,
     C: Default + Copy + Blend<Color = C> + ComponentWise<Scalar = T> + Mul<T, Output = C>,
 {
-    /// lighting for a given normal and material
+    /// lighting for a given view direction, normal, and material
     /// Possible optimization: a cache
     ///
     /// Blinn-Phong is calculated with:
@@ -73,10 +82,10 @@ where
     ///     I_p = ∑_lights (k_a i_a
     ///                   + k_d i_d (L ⋅ N)
     ///                   + k_s i_s (N ⋅ H)^α)
-    pub fn lighting(&self, normal: Vec3<T>, mat: Material<T>) -> Alpha<C, T> {
+    pub fn lighting(&self, view: Vec3<T>, normal: Vec3<T>, mat: Material<T>) -> Alpha<C, T> {
         let mut color: Alpha<C, T> = Alpha::default();
-        for light in &self.lights {
-            let halfway = (self.viewport.cam.direction + light.rot).normalized();
+        for light in self.lights.iter() {
+            let halfway = (view + light.rot).normalized();
             // add the new light to the total light so far
             // note: light.ambient, light.diffuse, and light.specular
             // can be completely different colors
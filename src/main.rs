@@ -1,11 +1,36 @@
+use std::convert::TryInto;
+use std::fs;
+use std::hint::black_box;
+use std::io;
+use std::iter::Sum;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::str::FromStr;
+use std::time::Instant;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use num::Float;
+use palette::{LinSrgba, Srgba};
+use vek::{Extent2, Rect, Vec3, Vec4};
 
-use chrono::format::{strftime::StrftimeItems, Item};
 use chrono::prelude::*;
 
+use ray_marcher::camera::{self, Viewport};
+use ray_marcher::distance::{self, Estimator};
+use ray_marcher::exit::{ErrorFormat, Failure};
+use ray_marcher::filename::{FrameRange, Template, Values};
+use ray_marcher::history;
+use ray_marcher::img::{self, OutputFormat};
+use ray_marcher::matrix::{self, QualitySetting};
+use ray_marcher::messages::{Locale, Message};
+use ray_marcher::overlay;
+use ray_marcher::randomize::{self, Aspect};
+use ray_marcher::render::{self, Acceleration};
+use ray_marcher::serialize::{Render, Scene, SceneDeserializeErr};
+use ray_marcher::threads;
+use ray_marcher::trace;
+
 type ClapResult = Result<(), String>;
 
 fn to_clap<T>(r: Result<T, String>) -> ClapResult {
@@ -19,13 +44,18 @@ where
     s.parse::<T>().map(|_| ()).map_err(|_| msg.to_string())
 }
 
+/// `msg` in the user's locale
+fn localized(msg: Message) -> String {
+    msg.text(Locale::from_env())
+}
+
 fn validate_int(s: String) -> ClapResult {
-    validate::<i32>(s, &"Must be valid integer")
+    validate::<i32>(s, &localized(Message::InvalidInt))
 }
 
 /// int must be > 0
 fn validate_int_positive(s: String) -> ClapResult {
-    let msg = &"Must be valid integer > 0";
+    let msg = &localized(Message::InvalidPositiveInt);
     s.parse::<i32>()
         .ok()
         .filter(|&j| j > 0)
@@ -34,7 +64,10 @@ fn validate_int_positive(s: String) -> ClapResult {
 }
 
 fn validate_int_range(r: Range<i32>) -> impl Fn(String) -> ClapResult {
-    let msg = format!("Must be a valid integer between {} and {}", r.start, r.end);
+    let msg = localized(Message::InvalidIntRange {
+        start: r.start,
+        end: r.end,
+    });
     move |s| {
         s.parse::<i32>()
             .map_err(|_| msg.to_string())
@@ -45,23 +78,249 @@ fn validate_int_range(r: Range<i32>) -> impl Fn(String) -> ClapResult {
     }
 }
 
+/// int must be >= 0
+fn validate_frame(s: String) -> ClapResult {
+    validate::<usize>(s, &localized(Message::InvalidNonNegativeInt))
+}
+
 fn validate_float(s: String) -> ClapResult {
-    validate::<f64>(s, &"Must be valid floating point number")
+    validate::<f64>(s, &localized(Message::InvalidFloat))
 }
 
-fn validate_strftime(s: String) -> ClapResult {
-    if StrftimeItems::new(&s).any(|item| match item {
-        Item::Error => true,
-        _ => false,
-    }) {
-        Err("Must be a valid format string; see chrono::format::strftime docs".to_string())
-    } else {
-        Ok(())
+/// float must be > 0
+fn validate_float_positive(s: String) -> ClapResult {
+    let msg = &localized(Message::InvalidPositiveFloat);
+    s.parse::<f64>()
+        .ok()
+        .filter(|&f| f > 0.0)
+        .map(|_| ())
+        .ok_or(msg.to_string())
+}
+
+fn validate_template(s: String) -> ClapResult {
+    Template::parse(&s)
+        .map(|_| ())
+        .map_err(|e| localized(e.message()))
+}
+
+/// the options every rendering subcommand takes; see `render_options`
+fn render_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::from_usage("--proxy 'Render a fast, coarse preview of the scene, as the proxy subcommand derives it: half the iterations and width, and twice the epsilon'"),
+        Arg::from_usage("--tiling [KIND] 'Split the image into units of work: full-width strips of --tile-size rows (scanline), or squares --tile-size pixels on a side (square); picked from the resolution and thread count if omitted'")
+            .possible_values(&["scanline", "square"])
+            .requires("tile-size"),
+        Arg::from_usage("--tile-size [N] 'Rows per scanline tile, or pixels per side of a square tile'")
+            .validator(validate_int_positive)
+            .requires("tiling"),
+        Arg::from_usage("--threads [N] 'Render threads; as many as there are cores if omitted'")
+            .validator(validate_int_positive),
+        Arg::from_usage("--thread-groups [N] 'Split the threads into this many groups, each rendering its own contiguous band of the image, for machines with several NUMA nodes'")
+            .validator(validate_int_positive)
+            .default_value("1"),
+        Arg::from_usage("--pin-threads 'Pin each render thread to its own core; needs the affinity feature, on Linux'"),
+        Arg::from_usage("--ambient-occlusion [STEP] 'Darken the lighting where nearby geometry occludes it, sampling the distance to the scene this far apart along each surface normal'")
+            .validator(validate_float_positive),
+        Arg::from_usage("--ao-samples [N] 'Samples along the normal for --ambient-occlusion; 5 if omitted'")
+            .validator(validate_int_positive)
+            .requires("ambient-occlusion"),
+        Arg::from_usage("--adaptive [THRESHOLD] 'Stop antialiasing each pixel once its color is known to within this, from 0 to 1, with 95% confidence, after at least 4 samples'")
+            .validator(validate_float_positive),
+        Arg::from_usage("--checkerboard 'Render only half the pixels, in a checkerboard, filling in the rest from whichever of their neighbors are on the same surface; nearly twice as fast with --antialiasing'"),
+        Arg::from_usage("--safe-mode 'Render a slow reference image to check the fast paths against: on one thread, without foveation, over-relaxation, distance LOD, or exact intersections, and failing at the first distance estimate which isn't a finite number'"),
+    ]
+}
+
+/// the options the subcommands writing whole views to image files (rerender, turntable, and
+/// scan) take on top of `render_args`; see `render_options`
+fn view_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::from_usage("--de-cache [CELL] 'Reuse --ambient-occlusion's distance estimates within cells this wide, for expensive fractals, and print how often they were reused after each image'")
+            .validator(validate_float_positive)
+            .requires("ambient-occlusion"),
+        Arg::from_usage("--aov [PASS]... 'Also write this pass of every image as a layer of its EXR: the distance to the surface (depth), its normal (normal), its material's albedo (albedo), the index of its geometry in the scene, from 1 (id), or how many distance estimates its ray took, a heat map of where rendering time goes (steps); repeat for more passes'")
+            .possible_values(&["depth", "normal", "albedo", "id", "steps"])
+            .number_of_values(1),
+        Arg::from_usage("--split-layers 'Write each --aov pass to its own image next to the output, like out.depth.png for out.png, if the output format has no layers'")
+            .requires("aov"),
+        Arg::from_usage("--float 'Insist on 32-bit float color, failing before rendering unless the output format stores it (EXR)'"),
+        Arg::from_usage("--overlay 'Draw the lights' directions, the frustums of the scene's other renders, and geometry bounding volumes over each image'"),
+        Arg::from_usage("--grid [SPACING] 'Draw a faint grid on the ground (y = 0) plane with lines SPACING units apart, and the x, y, and z axes in red, green, and blue, over each image'")
+            .validator(validate_float_positive),
+    ]
+}
+
+/// The files to write each image to, from any `view_args`
+struct Outputs {
+    request: img::OutputRequest,
+    degradation: img::Degradation,
+}
+
+impl Outputs {
+    fn new(matches: &ArgMatches) -> Self {
+        Outputs {
+            request: img::OutputRequest {
+                aovs: matches
+                    .values_of("aov")
+                    .map(|aovs| aovs.map(|aov| aov.parse().unwrap()).collect())
+                    .unwrap_or_default(),
+                float: matches.is_present("float"),
+            },
+            degradation: if matches.is_present("split-layers") {
+                img::Degradation::Split
+            } else {
+                img::Degradation::Error
+            },
+        }
+    }
+
+    /// the files to write an image of `size` to with the output filename `out` (see
+    /// `img::plan_outputs`), checking they can all be written before spending any time rendering
+    fn plan(
+        &self,
+        size: Extent2<usize>,
+        out: &Path,
+    ) -> Result<Vec<img::OutputFile>, (Failure, String)> {
+        let files = img::plan_outputs(out, &self.request, self.degradation)
+            .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+        for file in &files {
+            img::check_render_size(size, file.format)
+                .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+        }
+        Ok(files)
+    }
+
+    /// the requested AOVs of `render` at size `size`, if any were requested (see
+    /// `matrix::render_aovs`)
+    fn render(
+        &self,
+        scene: &render::Scene<f64, LinSrgba<f64>>,
+        render: &camera::Render<f64>,
+        size: Extent2<usize>,
+        options: &matrix::Options,
+    ) -> Vec<img::Layer> {
+        if self.request.aovs.is_empty() {
+            Vec::new()
+        } else {
+            matrix::render_aovs(scene, render, size, &self.request.aovs, options)
+        }
+    }
+}
+
+/// The debug wireframes to draw over each image, from any `view_args`
+struct Overlays {
+    /// the scene's setup (see `overlay::draw_setup`)
+    setup: bool,
+    /// a ground grid with lines this far apart, and the axes (see `overlay::draw_grid`)
+    grid: Option<f64>,
+}
+
+impl Overlays {
+    fn new(matches: &ArgMatches) -> Self {
+        Overlays {
+            setup: matches.is_present("overlay"),
+            grid: matches
+                .value_of("grid")
+                .map(|spacing| spacing.parse().unwrap()),
+        }
+    }
+
+    /// draws the overlays over `color`, an image of `scene` of size `size` seen through `view`,
+    /// with the `others` views of the scene's other renders
+    fn draw(
+        &self,
+        scene: &render::Scene<f64, LinSrgba<f64>>,
+        view: &Viewport<f64>,
+        others: &[Viewport<f64>],
+        size: Extent2<usize>,
+        color: &mut [Vec4<f64>],
+    ) {
+        if self.setup {
+            overlay::draw_setup(color, size, view, &scene.geometry, &scene.lights, others);
+        }
+        if let Some(spacing) = self.grid {
+            overlay::draw_grid(color, size, view, spacing);
+        }
     }
 }
 
-fn fmt_filename(raw: &str) -> String {
-    Utc::now().format(raw).to_string()
+/// Writes `files`, as `Outputs::plan` gives them, for an image of size `size` with the color
+/// `color` and the AOV layers `aovs` (see `write_image`); a file with just one AOV gets its
+/// `img::Aov::preview`
+fn write_outputs(
+    files: &[img::OutputFile],
+    size: Extent2<usize>,
+    color: &[Vec4<f64>],
+    aovs: &[img::Layer],
+    scene: &str,
+) -> Result<(), (Failure, String)> {
+    for file in files {
+        let layers: Vec<img::Layer> = aovs
+            .iter()
+            .filter(|layer| {
+                file.passes
+                    .iter()
+                    .any(|pass| matches!(pass, img::Pass::Aov(aov) if aov.name() == layer.name))
+            })
+            .cloned()
+            .collect();
+        match file.passes[..] {
+            [img::Pass::Aov(aov)] => {
+                let preview: Vec<Vec4<f64>> = aov
+                    .preview(&layers[0].data)
+                    .into_iter()
+                    .map(|c| c.map(f64::from))
+                    .collect();
+                write_image(&file.path, size, &preview, &[], scene)?;
+            }
+            _ => write_image(&file.path, size, color, &layers, scene)?,
+        }
+    }
+    Ok(())
+}
+
+/// the `matrix::Options` given with `render_args` and any `view_args`, for a render that's
+/// `nice` if the main `--nice` was given
+fn render_options(matches: &ArgMatches, nice: bool) -> matrix::Options {
+    let de_cache = matches
+        .value_of("de-cache")
+        .map(|cell| cell.parse().unwrap());
+    let acceleration = if matches.is_present("safe-mode") {
+        Acceleration::safe()
+    } else {
+        Acceleration {
+            de_cache: de_cache.is_some(),
+            ..Acceleration::new(matches.value_of("threads").map_or_else(
+                || std::thread::available_parallelism().map_or(1, |n| n.get()),
+                |n| n.parse().unwrap(),
+            ))
+        }
+    };
+    let tile_size = matches.value_of("tile-size").map(|n| n.parse().unwrap());
+    let tiling = match (matches.value_of("tiling"), tile_size) {
+        (Some("scanline"), Some(rows)) => Some(img::Tiling::Scanline(rows)),
+        (Some("square"), Some(side)) => Some(img::Tiling::Square(side)),
+        _ => None,
+    };
+    matrix::Options {
+        proxy: matches.is_present("proxy"),
+        tiling,
+        thread_groups: matches.value_of("thread-groups").unwrap().parse().unwrap(),
+        pin_threads: matches.is_present("pin-threads"),
+        nice,
+        ambient_occlusion: matches.value_of("ambient-occlusion").map(|step| {
+            matrix::AmbientOcclusion {
+                samples: matches
+                    .value_of("ao-samples")
+                    .map_or(5, |n| n.parse().unwrap()),
+                step: step.parse().unwrap(),
+                de_cache,
+            }
+        }),
+        adaptive_sampling: matches.value_of("adaptive").map(|t| t.parse().unwrap()),
+        checkerboard: matches.is_present("checkerboard"),
+        ..matrix::Options::new(acceleration)
+    }
 }
 
 fn app<'a, 'b>() -> App<'a, 'b> {
@@ -72,19 +331,1159 @@ fn app<'a, 'b>() -> App<'a, 'b> {
         .arg(Arg::from_usage("-a --antialiasing [N] 'Subpixel antialiasing; note that 2 would render 4 samples per pixel'")
              .validator(validate_int_positive)
              .default_value("1"))
-        .arg(Arg::from_usage("-o --output [FILENAME] 'PNG output filename; accepts standard date/time formatters'")
-             .validator(validate_strftime)
+        .arg(Arg::from_usage("-o --output [FILENAME] 'Output filename, ending in .png, .exr, .tif (16-bit), or .webp (lossless); accepts standard date/time formatters and the placeholders {scene}, {render}, {camera}, {width}, {height}, {resolution}, {frame}, {seed}, and {quality} (the iteration count), with numbers zero-padded like {frame:04}'")
+             .validator(validate_template)
              .default_value("ray-marcher-%FT%H_%M_%S.png"))
+        .arg(Arg::from_usage("--frame [N] 'Render only this frame of the animation, for spot checks'")
+             .validator(validate_frame)
+             .conflicts_with_all(&["frame-start", "frame-end"]))
+        .arg(Arg::from_usage("--frame-start [N] 'First frame of the animation to render'")
+             .validator(validate_frame))
+        .arg(Arg::from_usage("--frame-end [N] 'Last frame of the animation to render, inclusive; only --frame-start is rendered if omitted'")
+             .validator(validate_frame))
+        .arg(Arg::from_usage("--frame-step [N] 'Render every Nth frame between --frame-start and --frame-end'")
+             .validator(validate_int_positive)
+             .default_value("1"))
+        .arg(Arg::from_usage("--frame-padding [DIGITS] 'Zero-pad {frame} in the output filename to this many digits, unless it gives its own padding like {frame:04}'")
+             .validator(validate_frame))
         .arg(Arg::from_usage("-i --iterations [N] 'Number of iterations to render with'")
              .validator(validate_int_positive)
              .default_value("64"))
         .arg(Arg::from_usage("-q --quaternion [F] [F] [F] [F] 'Quaternion to render, with the real component first, then i, j, and k components'")
              .validator(validate_float))
+        .arg(Arg::from_usage("--error-format [FORMAT] 'How to print errors; json prints one object per error with its kind and exit status'")
+             .possible_values(&["text", "json"])
+             .default_value("text"))
+        .arg(Arg::from_usage("--nice 'Render at the lowest scheduling priority, with the rendering subcommands also giving up the CPU every few rows, to keep the rest of the system responsive'"))
+        .arg(Arg::from_usage("[SCENE] 'Scene file to summarize with --summary or debug with --debug-pixel'"))
+        .arg(Arg::from_usage("--debug-pixel [X] [Y] 'Trace only this pixel of the SCENE's first render, printing every march step and the shading inputs as JSON'")
+             .validator(validate_frame)
+             .requires("SCENE"))
+        .arg(Arg::from_usage("--debug-obj [FILENAME] 'With --debug-pixel, also write the ray's path as an OBJ polyline'")
+             .requires("debug-pixel"))
+        .arg(Arg::from_usage("--summary 'Print what the SCENE contains (geometry by type, materials, lights, render resolutions, and buffer memory) and any warnings after loading it'")
+             .requires("SCENE"))
+        .arg(Arg::from_usage("--proxy 'Name the output for a proxy render, halving --resolution and --iterations in its placeholders; the rendering subcommands take their own --proxy'"))
+        .arg(Arg::from_usage("--history 'Record this render in the local history file; nothing is recorded otherwise'"))
+        .subcommand(SubCommand::with_name("history")
+             .about("Searches the local history of recorded renders")
+             .arg(Arg::from_usage("[QUERY] 'Text to find in the scene hash, parameters, or output filename'")))
+        .subcommand(SubCommand::with_name("rerender")
+             .about("Renders the scene an image was rendered from again, recovering it from the image's embedded metadata or a .yml file alongside it")
+             .arg(Arg::from_usage("<IMAGE> 'A PNG rendered by ray-marcher'"))
+             .arg(Arg::from_usage("--width [N] 'Override the width of every render in the scene'")
+                  .validator(validate_int_positive))
+             .arg(Arg::from_usage("-a --antialiasing [N] 'Subpixel samples per side'")
+                  .validator(validate_int_positive)
+                  .default_value("1"))
+             .arg(Arg::from_usage("-o --output [FILENAME] 'Filename for each of the scene's renders, ending in .png, .exr, .tif, or .webp, with the same placeholders as the main --output; {scene} is the image's name'")
+                  .validator(validate_template)
+                  .default_value("{scene}-rerender-{render}.png"))
+             .arg(Arg::from_usage("--refine [LEVELS] 'Preview each render at this many resolutions, from 1/2^(LEVELS - 1) of its size up to the whole, each overwriting the last and seeding the next level's march'")
+                  .validator(validate_int_range(0..8))
+                  .default_value("1"))
+             .arg(Arg::from_usage("--check-normals [TOLERANCE] 'Paint bad normals at primary hits in warning colors (magenta for NaN, yellow for gradients whose length is off from 1 by more than TOLERANCE, red for normals facing away from the camera) and print how many there were, to catch a sample_size or epsilon that doesn't suit the scene'")
+                  .validator(validate_float_positive))
+             .args(&render_args())
+             .args(&view_args()))
+        .subcommand(SubCommand::with_name("proxy")
+             .about("Derives a cheap preview version of a scene, with half the iterations and render widths and twice the epsilon, and prints it")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to derive the proxy from'"))
+             .arg(Arg::from_usage("--write 'Write the proxy next to the scene, as NAME.proxy.yml, instead of printing it'")))
+        .subcommand(SubCommand::with_name("benchmark")
+             .about("Times one distance estimate of each of a scene's geometries, in 32- and 64-bit floats, averaged over a lattice of points around the origin")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to time'"))
+             .arg(Arg::from_usage("--samples [N] 'Points per side of the lattice'")
+                  .validator(validate_int_positive)
+                  .default_value("32")))
+        .subcommand(SubCommand::with_name("quality-matrix")
+             .about("Renders a crop of a scene at every combination of the given iterations, epsilons, and antialiasing, side by side with labels, to compare quality settings")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to render'"))
+             .arg(Arg::from_usage("--render [N] 'Index of the scene's render to crop from'")
+                  .validator(validate_frame)
+                  .default_value("0"))
+             .arg(Arg::from_usage("--crop [X] [Y] [WIDTH] [HEIGHT] 'Region of the render to compare, in pixels; a 96-pixel square at its center if omitted'")
+                  .validator(validate_frame))
+             .arg(Arg::from_usage("-i --iterations [N]... 'Iteration counts to compare'")
+                  .validator(validate_int_positive)
+                  .use_delimiter(true)
+                  .default_value("16,32,64"))
+             .arg(Arg::from_usage("-e --epsilon [E]... 'Epsilons to compare'")
+                  .validator(validate_float)
+                  .use_delimiter(true)
+                  .default_value("0.001"))
+             .arg(Arg::from_usage("-a --antialiasing [N]... 'Antialiasing levels to compare'")
+                  .validator(validate_int_positive)
+                  .use_delimiter(true)
+                  .default_value("1,2"))
+             .arg(Arg::from_usage("-o --output [FILENAME] 'Image file to write the matrix to, ending in .png, .exr, .tif, or .webp'")
+                  .default_value("quality-matrix.png"))
+             .args(&render_args()))
+        .subcommand(SubCommand::with_name("rig")
+             .about("Renders every camera of one of a scene's rigs side by side, labelled with the cameras' names, into a contact sheet")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to render'"))
+             .arg(Arg::from_usage("<RIG> 'Name of the rig to render'"))
+             .arg(Arg::from_usage("-a --antialiasing [N] 'Subpixel samples per side'")
+                  .validator(validate_int_positive)
+                  .default_value("1"))
+             .arg(Arg::from_usage("-o --output [FILENAME] 'Image file to write the contact sheet to, ending in .png, .exr, .tif, or .webp; RIG.png if omitted'"))
+             .args(&render_args()))
+        .subcommand(SubCommand::with_name("turntable")
+             .about("Renders a full turn of a camera around the scene's geometry, at the camera's height, as numbered frames; the scene's animated values play over the frames")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to render'"))
+             .arg(Arg::from_usage("<CAMERA> 'Name of the camera to turn'"))
+             .arg(Arg::from_usage("--frames [N] 'Frames in the full turn'")
+                  .validator(validate_int_positive)
+                  .default_value("120"))
+             .arg(Arg::from_usage("--width [N] 'Width of each frame in pixels'")
+                  .validator(validate_int_positive)
+                  .default_value("640"))
+             .arg(Arg::from_usage("-a --antialiasing [N] 'Subpixel samples per side'")
+                  .validator(validate_int_positive)
+                  .default_value("1"))
+             .arg(Arg::from_usage("-o --output [FILENAME] 'Filename for each frame, ending in .png, .exr, .tif, or .webp, with the same placeholders as the main --output; must include {frame}'")
+                  .validator(validate_template)
+                  .default_value("{scene}-turntable-{frame:04}.png"))
+             .arg(Arg::from_usage("--clearance [UNITS] 'Pushes the camera path out of the geometry so that every frame is at least this far from its surfaces'")
+                  .validator(validate_float_positive))
+             .arg(Arg::from_usage("--clearance-smoothing [N] 'Frames on either side to smooth the pushes of --clearance over'")
+                  .validator(|s| validate::<usize>(s, &localized(Message::InvalidInt)))
+                  .default_value("2"))
+             .args(&render_args())
+             .args(&view_args()))
+        .subcommand(SubCommand::with_name("scan")
+             .about("Renders a clipping plane sweeping through the scene's geometry along an axis, capping the cut, as numbered frames; the scene's animated values play over the frames")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to render'"))
+             .arg(Arg::from_usage("<CAMERA> 'Name of the camera to render through'"))
+             .arg(Arg::from_usage("--axis [AXIS] 'Axis to sweep along; the part of the geometry toward its negative end is cut away'")
+                  .possible_values(&["x", "y", "z"])
+                  .default_value("z"))
+             .arg(Arg::from_usage("--frames [N] 'Frames in the sweep'")
+                  .validator(validate_int_positive)
+                  .default_value("120"))
+             .arg(Arg::from_usage("--width [N] 'Width of each frame in pixels'")
+                  .validator(validate_int_positive)
+                  .default_value("640"))
+             .arg(Arg::from_usage("-a --antialiasing [N] 'Subpixel samples per side'")
+                  .validator(validate_int_positive)
+                  .default_value("1"))
+             .arg(Arg::from_usage("-o --output [FILENAME] 'Filename for each frame, ending in .png, .exr, .tif, or .webp, with the same placeholders as the main --output; must include {frame}'")
+                  .validator(validate_template)
+                  .default_value("{scene}-scan-{frame:04}.png"))
+             .arg(Arg::from_usage("--clearance [UNITS] 'Pushes the camera out of the geometry so that it is at least this far from its surfaces'")
+                  .validator(validate_float_positive))
+             .args(&render_args())
+             .args(&view_args()))
+        .subcommand(SubCommand::with_name("randomize")
+             .about("Randomizes a scene's materials and light colors, keeping its geometry and cameras, and prints the result")
+             .arg(Arg::from_usage("<SCENE> 'Scene file to randomize'"))
+             .arg(Arg::from_usage("--seed [N] 'Seed to randomize with; a random seed is chosen and reported if omitted'")
+                  .validator(|s| validate::<u64>(s, &localized(Message::InvalidInt))))
+             .arg(Arg::from_usage("--lock [ASPECT]... 'Aspects of the scene to leave as they are'")
+                  .possible_values(&["materials", "lights"])))
+}
+
+/// Renders every render of the scene `image` was rendered from, with `width` overriding their
+/// widths and with `options`, writing each to the filename `template` gives it as `outputs` asks
+/// (see `write_outputs`) and giving the filenames; each is refined over `levels` resolutions (see `matrix::render_refined`), every
+/// level overwriting the last, and with `check_normals` has its bad normals marked (see
+/// `mark_normal_problems`) before `overlays` are drawn over it
+#[allow(clippy::too_many_arguments)]
+fn rerender(
+    image: &Path,
+    width: Option<usize>,
+    antialiasing: usize,
+    levels: u32,
+    check_normals: Option<f64>,
+    template: &Template,
+    outputs: &Outputs,
+    overlays: &Overlays,
+    options: &matrix::Options,
+) -> Result<Vec<String>, (Failure, String)> {
+    let text = img::scene_for_image(image)
+        .map_err(|e| (Failure::from(&e), e.to_string()))?
+        .ok_or_else(|| {
+            let name = image.display().to_string();
+            (
+                Failure::Validation,
+                localized(Message::NoEmbeddedScene(&name)),
+            )
+        })?;
+    let mut scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    if let Some(width) = width {
+        for render in scene.renders.iter_mut() {
+            render.width = width;
+        }
+    }
+    if options.proxy {
+        scene.proxy();
+    }
+    // embedded with the overrides, so the new images can be rerendered in turn
+    let text = serde_yaml::to_string(&scene).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let scene: render::Scene<f64, LinSrgba<f64>> = (&scene)
+        .try_into()
+        .map_err(|e: SceneDeserializeErr| (Failure::from(&e), localized(e.message())))?;
+
+    let now = Local::now();
+    let mut filenames = Vec::new();
+    for (i, r) in scene.renders.iter().enumerate() {
+        let others: Vec<Viewport<f64>> = scene
+            .renders
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, other)| other.view)
+            .collect();
+        let size = Extent2::new(r.width, (r.width as f64 / r.aspect()).round() as usize);
+        let filename = template.format(
+            &Values {
+                scene: image.file_stem().map(|s| s.to_string_lossy().into_owned()),
+                render: Some(i),
+                size: Some(size),
+                ..Values::default()
+            },
+            &now,
+        );
+        let files = outputs.plan(size, Path::new(&filename))?;
+        for level in matrix::render_refined(&scene, r, size, levels, antialiasing, options) {
+            let (mut frame, size) =
+                level.map_err(|e| (Failure::from(&e), localized(e.message())))?;
+            report_de_cache(&frame);
+            if let Some(tolerance) = check_normals {
+                mark_normal_problems(&scene, r, size, tolerance, &mut frame.color);
+            }
+            overlays.draw(&scene, &r.view, &others, size, &mut frame.color);
+            let aovs = outputs.render(&scene, r, size, options);
+            write_outputs(&files, size, &frame.color, &aovs, &text)?;
+        }
+        filenames.extend(files.iter().map(|file| file.path.display().to_string()));
+    }
+    Ok(filenames)
+}
+
+/// prints how often `frame`'s `DeCache`s had an estimate ready, if it used any
+fn report_de_cache(frame: &matrix::Frame<f64>) {
+    if let Some(stats) = frame.de_cache {
+        // on stderr, like the randomize seed, so it doesn't mix with the filenames
+        eprintln!(
+            "de cache: {:.1}% of {} estimates reused",
+            stats.hit_rate() * 100.0,
+            stats.hits + stats.misses
+        );
+    }
+}
+
+/// Paints the pixels of `color`, an image of `render` at size `size`, whose primary hits have bad
+/// normals in their warning colors (see `render::normal_check_pass`), and prints how many there
+/// were
+fn mark_normal_problems(
+    scene: &render::Scene<f64, LinSrgba<f64>>,
+    render: &camera::Render<f64>,
+    size: Extent2<usize>,
+    tolerance: f64,
+    color: &mut [Vec4<f64>],
+) {
+    let (problems, stats) =
+        render::normal_check_pass(&scene.geometry, &render.view, size, tolerance);
+    for (color, problem) in color.iter_mut().zip(problems) {
+        if let Some(problem) = problem {
+            *color = Vec4::<u8>::from(problem.color()).map(|c| f64::from(c) / 255.0);
+        }
+    }
+    // on stderr, like the de cache's hit rate
+    eprintln!(
+        "normals: {} of {} hits not finite, {} denormalized, {} backfacing",
+        stats.not_finite, stats.hits, stats.denormalized, stats.backfacing
+    );
+}
+
+/// the proxy of the scene at `path` (see `Scene::proxy`), as YAML
+fn proxied(path: &Path) -> Result<String, (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    scene.proxy();
+    serde_yaml::to_string(&scene).map_err(|e| (Failure::from(&e), e.to_string()))
+}
+
+/// The mean time one distance estimate of each of the scene's geometries takes, in nanoseconds,
+/// in `T` precision: every geometry is estimated at a lattice of `samples`³ points filling the
+/// cube from -2 to 2, which holds most fractals
+fn estimate_times<T>(text: &str, samples: usize) -> Result<Vec<f64>, (Failure, String)>
+where
+    T: Float + Sum + Default + Clone + for<'de> serde::Deserialize<'de>,
+{
+    let scene: Scene<T> =
+        serde_yaml::from_str(text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let side = |i: usize| {
+        T::from(4 * i).unwrap() / T::from(samples.max(2) - 1).unwrap() - T::from(2).unwrap()
+    };
+    let points: Vec<Vec3<T>> = (0..samples)
+        .flat_map(|z| (0..samples).flat_map(move |y| (0..samples).map(move |x| (x, y, z))))
+        .map(|(x, y, z)| Vec3::new(side(x), side(y), side(z)))
+        .collect();
+    Ok(scene
+        .geometry
+        .iter()
+        .map(|g| {
+            let geom = distance::Geometry::from(g);
+            let start = Instant::now();
+            // summed so the estimates can't be optimized away
+            let total: T = points.iter().map(|&p| geom.de.estimate(p)).sum();
+            let elapsed = start.elapsed();
+            black_box(total);
+            elapsed.as_nanos() as f64 / points.len() as f64
+        })
+        .collect())
+}
+
+/// a report of how long the estimators of the scene at `path` take in 32- and 64-bit floats; see
+/// `estimate_times`
+fn benchmark(path: &Path, samples: usize) -> Result<String, (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let single = estimate_times::<f32>(&text, samples)?;
+    let double = estimate_times::<f64>(&text, samples)?;
+    Ok(scene
+        .geometry
+        .iter()
+        .zip(single.iter().zip(&double))
+        .enumerate()
+        .map(|(i, (g, (single, double)))| {
+            format!(
+                "{} {}: f32 {:.1} ns, f64 {:.1} ns",
+                i,
+                g.name(),
+                single,
+                double
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// the summary of the scene at `path`, with its warnings (see `Scene::summary`)
+fn summarize(path: &Path) -> Result<String, (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    Ok(scene.summary().to_string())
+}
+
+/// The trace of pixel (`x`, `y`) of the first render of the scene at `path` (see
+/// `trace::trace_pixel`) as JSON, also writing the ray's path to `obj` as an OBJ polyline if given
+fn debug_pixel(
+    path: &Path,
+    x: usize,
+    y: usize,
+    obj: Option<&Path>,
+) -> Result<String, (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let scene: render::Scene<f64, LinSrgba<f64>> = (&scene)
+        .try_into()
+        .map_err(|e: SceneDeserializeErr| (Failure::from(&e), localized(e.message())))?;
+    let r = scene.renders.first().ok_or_else(|| {
+        (
+            Failure::Validation,
+            localized(Message::UnknownRender { index: 0, count: 0 }),
+        )
+    })?;
+    let size = Extent2::new(r.width, (r.width as f64 / r.aspect()).round() as usize);
+    if x >= size.w || y >= size.h {
+        return Err((
+            Failure::Validation,
+            localized(Message::PixelOutside {
+                x,
+                y,
+                width: size.w,
+                height: size.h,
+            }),
+        ));
+    }
+    let trace = trace::trace_pixel(&scene.geometry, &r.view, size, x, y);
+    if let Some(obj) = obj {
+        fs::write(obj, trace.to_obj()).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    }
+    Ok(trace.to_json())
+}
+
+/// Records each image a run writes in the local history (see `history`), if the user asked for
+/// it with `--history`
+struct Recorder {
+    /// the history file; `None` when not recording
+    path: Option<PathBuf>,
+    scene_hash: String,
+    /// when the run started, or when the last image was recorded
+    since: Instant,
+}
+
+impl Recorder {
+    /// a recorder for renders of the scene file contents `scene`, which records only if `enabled`
+    fn new(enabled: bool, scene: &str) -> Result<Self, (Failure, String)> {
+        let path = if enabled {
+            let path = history::default_path()
+                .ok_or_else(|| (Failure::Io, localized(Message::NoHistoryPath)))?;
+            Some(path)
+        } else {
+            None
+        };
+        Ok(Recorder {
+            path,
+            scene_hash: history::scene_hash(scene),
+            since: Instant::now(),
+        })
+    }
+
+    /// records that `output` was written, taking as long as it's been since the last record
+    fn record(&mut self, output: &str) -> Result<(), (Failure, String)> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let entry = history::Entry {
+            time: Local::now().to_rfc3339(),
+            scene_hash: self.scene_hash.clone(),
+            parameters: std::env::args().skip(1).collect(),
+            duration_secs: self.since.elapsed().as_secs_f64(),
+            output: output.to_string(),
+        };
+        self.since = Instant::now();
+        history::append(path, &entry).map_err(|e| (Failure::from(&e), e.to_string()))
+    }
+}
+
+/// Renders the quality matrix for `crop` (or a square at the center) of render `render` of the
+/// scene at `path` with every setting in `settings` and with `options`, and writes it to `out` (see `write_image`),
+/// recording it in the history if `history` is set
+fn quality_matrix(
+    path: &Path,
+    render: usize,
+    crop: Option<Rect<usize, usize>>,
+    settings: &[QualitySetting<f64>],
+    out: &Path,
+    options: &matrix::Options,
+    history: bool,
+) -> Result<(), (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut recorder = Recorder::new(history, &text)?;
+    let mut scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    if options.proxy {
+        scene.proxy();
+    }
+    let scene: render::Scene<f64, LinSrgba<f64>> = (&scene)
+        .try_into()
+        .map_err(|e: SceneDeserializeErr| (Failure::from(&e), localized(e.message())))?;
+    let r = scene.renders.get(render).ok_or_else(|| {
+        (
+            Failure::Validation,
+            localized(Message::UnknownRender {
+                index: render,
+                count: scene.renders.len(),
+            }),
+        )
+    })?;
+    let size = Extent2::new(r.width, (r.width as f64 / r.aspect()).round() as usize);
+    let crop = crop
+        .unwrap_or_else(|| {
+            let side = size.w.min(size.h).min(96);
+            Rect::new((size.w - side) / 2, (size.h - side) / 2, side, side)
+        })
+        .intersection(Rect::new(0, 0, size.w, size.h));
+    check_size(size, out)?;
+    let cells = settings
+        .iter()
+        .map(|setting| {
+            let pixels = matrix::render_crop(&scene, r, size, crop, setting, options)
+                .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+            Ok((setting.label(), pixels))
+        })
+        .collect::<Result<Vec<(String, Vec<u8>)>, (Failure, String)>>()?;
+    let (rgba, size) = matrix::layout(&cells, crop.extent());
+    write_image(out, size, &unquantize(&rgba), &[], &text)?;
+    recorder.record(&out.display().to_string())
+}
+
+/// `rgba`, 8-bit RGBA, with components from 0 to 1, as `matrix::render_view` gives them
+fn unquantize(rgba: &[u8]) -> Vec<Vec4<f64>> {
+    rgba.chunks(4)
+        .map(|c| Vec4::new(c[0], c[1], c[2], c[3]).map(|c| f64::from(c) / 255.0))
+        .collect()
+}
+
+/// the format `out`'s extension names (see `OutputFormat`)
+fn output_format(out: &Path) -> Result<OutputFormat, (Failure, String)> {
+    OutputFormat::from_path(out).ok_or_else(|| {
+        let e = img::UnsupportedOutput::UnknownFormat(out.display().to_string());
+        (Failure::from(&e), localized(e.message()))
+    })
+}
+
+/// checks an image of `size` can be rendered here and written to `out` (see
+/// `img::check_render_size`), before spending any time rendering it
+fn check_size(size: Extent2<usize>, out: &Path) -> Result<(), (Failure, String)> {
+    img::check_render_size(size, output_format(out)?)
+        .map_err(|e| (Failure::from(&e), localized(e.message())))
+}
+
+/// Writes `color`, an image of size `size` with sRGB components from 0 to 1, to `out` in the
+/// format its extension names (see `OutputFormat`), with the extra `layers` after it if it's an
+/// EXR; PNGs also embed the scene file contents `scene` it was rendered from
+fn write_image(
+    out: &Path,
+    size: Extent2<usize>,
+    color: &[Vec4<f64>],
+    layers: &[img::Layer],
+    scene: &str,
+) -> Result<(), (Failure, String)> {
+    let encoded = match output_format(out)? {
+        OutputFormat::Png => return write_png(out, size, matrix::quantize(color), scene),
+        OutputFormat::Exr => {
+            // EXR holds linear light
+            let linear: Vec<Vec4<f32>> = color
+                .iter()
+                .map(|c| {
+                    let (r, g, b, a) = Srgba::new(c.x, c.y, c.z, c.w)
+                        .into_linear()
+                        .into_components();
+                    Vec4::new(r, g, b, a).map(|c| c as f32)
+                })
+                .collect();
+            let layers: Vec<img::Layer> = Some(img::Layer::beauty(&linear))
+                .into_iter()
+                .chain(layers.iter().cloned())
+                .collect();
+            img::encode_exr(size, &layers)
+        }
+        OutputFormat::Tiff => {
+            let color: Vec<Vec4<f32>> = color.iter().map(|c| c.map(|c| c as f32)).collect();
+            img::encode_tiff(size, &color)
+        }
+        OutputFormat::WebP => img::encode_webp(size, &matrix::quantize(color)),
+    }
+    .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+    fs::write(out, encoded).map_err(|e| (Failure::from(&e), e.to_string()))
+}
+
+/// writes an 8-bit RGBA image of size `size` to `out` as a PNG, embedding the scene file contents
+/// `scene` it was rendered from (see `img::scene_for_image`)
+fn write_png(
+    out: &Path,
+    size: Extent2<usize>,
+    rgba: Vec<u8>,
+    scene: &str,
+) -> Result<(), (Failure, String)> {
+    let file = fs::File::create(out).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut png = img::PngStream::new(io::BufWriter::new(file), size)
+        .map_err(|e| (Failure::from(&e), e.to_string()))?;
+    png.embed_text(img::SCENE_KEYWORD, scene);
+    png.write_band(Rect::new(0, 0, size.w, size.h), rgba)
+        .and_then(|_| png.finish())
+        .map(|_| ())
+        .map_err(|e| (Failure::from(&e), e.to_string()))
+}
+
+/// Renders the contact sheet of rig `rig` of the scene at `path` with `options` (see
+/// `matrix::contact_sheet`), and writes it to `out` (see `write_image`), recording it in the history if `history` is set
+fn rig_sheet(
+    path: &Path,
+    rig: &str,
+    antialiasing: usize,
+    out: &Path,
+    options: &matrix::Options,
+    history: bool,
+) -> Result<(), (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut recorder = Recorder::new(history, &text)?;
+    let mut scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    scene
+        .select_rig(rig)
+        .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+    if options.proxy {
+        scene.proxy();
+    }
+    let labels: Vec<String> = scene.renders.iter().map(|r| r.camera.clone()).collect();
+    let scene: render::Scene<f64, LinSrgba<f64>> = (&scene)
+        .try_into()
+        .map_err(|e: SceneDeserializeErr| (Failure::from(&e), localized(e.message())))?;
+    for r in &scene.renders {
+        check_size(
+            Extent2::new(r.width, (r.width as f64 / r.aspect()).round() as usize),
+            out,
+        )?;
+    }
+    let (rgba, size) = matrix::contact_sheet(&scene, &labels, antialiasing, options)
+        .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+    write_image(out, size, &unquantize(&rgba), &[], &text)?;
+    recorder.record(&out.display().to_string())
+}
+
+/// Renders `frames` frames `width` pixels wide of camera `camera` of the scene at `path` turning
+/// once around the geometry's bounding center (see `render::bounding_center`), with the scene
+/// animated over the frames (see `Scene::animate`), its detail following the camera's zoom (see
+/// `render::Scene::at_zoom`), and rendered with `options`, writing each with `overlays` over it
+/// to the filename `template` gives it as `outputs` asks (see `write_outputs`) and giving the
+/// filenames; with `clearance` as `(units, smoothing)`, the camera's path is first pushed out of
+/// the geometry (see `render::clear_path`); each frame is recorded in the history if `history`
+/// is set
+#[allow(clippy::too_many_arguments)]
+fn turntable(
+    path: &Path,
+    camera: &str,
+    frames: usize,
+    width: usize,
+    antialiasing: usize,
+    clearance: Option<(f64, usize)>,
+    template: &Template,
+    outputs: &Outputs,
+    overlays: &Overlays,
+    options: &matrix::Options,
+    history: bool,
+) -> Result<Vec<String>, (Failure, String)> {
+    FrameRange {
+        start: 0,
+        end: frames - 1,
+        step: 1,
+    }
+    .check(template)
+    .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut recorder = Recorder::new(history, &text)?;
+    // a fresh copy of the scene for each frame, rendering just through `camera`
+    let load = |t: f64| -> Result<render::Scene<f64, LinSrgba<f64>>, (Failure, String)> {
+        let mut scene: Scene<f64> =
+            serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+        scene.renders = vec![Render {
+            camera: camera.to_string(),
+            width,
+            foveation: None,
+            normal_smoothing: None,
+        }];
+        if options.proxy {
+            scene.proxy();
+        }
+        scene.animate(t);
+        (&scene)
+            .try_into()
+            .map_err(|e: SceneDeserializeErr| (Failure::from(&e), localized(e.message())))
+    };
+    let first = load(0.0)?;
+    let mut render = first.renders[0];
+    let view = render.view;
+    // the proxy's width, if it's a proxy
+    let size = Extent2::new(
+        render.width,
+        (render.width as f64 / view.aspect()).round() as usize,
+    );
+    // from as far out as the camera, so the rays start outside the geometry; the turn stays
+    // around the first frame's center, even if the geometry moves
+    let reach = view.cam.origin.magnitude().max(1.0);
+    let center = render::bounding_center(&first.geometry, reach);
+    let angle = |frame: usize| 2.0 * std::f64::consts::PI * frame as f64 / frames as f64;
+    // the camera's path around the turn, kept clear of the first frame's geometry
+    let mut origins: Vec<_> = (0..frames)
+        .map(|frame| view.orbit(center, angle(frame)).cam.origin)
+        .collect();
+    if let Some((units, smoothing)) = clearance {
+        render::clear_path(&first.geometry, &mut origins, units, smoothing);
+    }
+
+    let values = Values {
+        scene: path.file_stem().map(|s| s.to_string_lossy().into_owned()),
+        camera: Some(camera.to_string()),
+        size: Some(size),
+        ..Values::default()
+    };
+    let now = Local::now();
+    let mut filenames = Vec::new();
+    for (frame, &origin) in origins.iter().enumerate() {
+        let filename = template.format(
+            &Values {
+                frame,
+                ..values.clone()
+            },
+            &now,
+        );
+        let files = outputs.plan(size, Path::new(&filename))?;
+        let scene = load(render::frame_time(frame, 0, frames - 1))?;
+        // the camera's animated zoom, with the geometry's detail following it
+        let focal_len = scene.renders[0].view.focal_len;
+        let scene = scene.at_zoom(focal_len / view.focal_len);
+        render.view = Viewport { focal_len, ..view }.orbit(center, angle(frame));
+        render.view.cam.origin = origin;
+        let mut frame = matrix::render_view(&scene, &render, size, antialiasing, options)
+            .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+        report_de_cache(&frame);
+        overlays.draw(&scene, &render.view, &[], size, &mut frame.color);
+        let aovs = outputs.render(&scene, &render, size, options);
+        write_outputs(&files, size, &frame.color, &aovs, &text)?;
+        recorder.record(&filename)?;
+        filenames.extend(files.iter().map(|file| file.path.display().to_string()));
+    }
+    Ok(filenames)
+}
+
+/// Renders `frames` frames `width` pixels wide of camera `camera` of the scene at `path` with a
+/// clipping plane (see `Scene::clip`) sweeping along `axis` from one end of the geometry to the
+/// other, with the scene animated over the frames (see `Scene::animate`), its detail following
+/// the camera's zoom (see `render::Scene::at_zoom`), and rendered with `options`, writing each
+/// with `overlays` over it to the filename `template` gives it as `outputs` asks (see
+/// `write_outputs`) and giving the filenames; with `clearance`, the camera is first pushed that
+/// far out of the whole geometry (see `render::clear_path`); each frame is recorded in the
+/// history if `history` is set
+#[allow(clippy::too_many_arguments)]
+fn scan(
+    path: &Path,
+    camera: &str,
+    axis: Vec3<f64>,
+    frames: usize,
+    width: usize,
+    antialiasing: usize,
+    clearance: Option<f64>,
+    template: &Template,
+    outputs: &Outputs,
+    overlays: &Overlays,
+    options: &matrix::Options,
+    history: bool,
+) -> Result<Vec<String>, (Failure, String)> {
+    FrameRange {
+        start: 0,
+        end: frames - 1,
+        step: 1,
+    }
+    .check(template)
+    .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut recorder = Recorder::new(history, &text)?;
+    // a fresh copy of the scene for each frame, rendering just through `camera`
+    let load = |t: f64,
+                clip: Option<f64>|
+     -> Result<render::Scene<f64, LinSrgba<f64>>, (Failure, String)> {
+        let mut scene: Scene<f64> =
+            serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+        scene.renders = vec![Render {
+            camera: camera.to_string(),
+            width,
+            foveation: None,
+            normal_smoothing: None,
+        }];
+        if options.proxy {
+            scene.proxy();
+        }
+        scene.animate(t);
+        if let Some(offset) = clip {
+            // keeping the side of the plane toward the positive end of the axis
+            scene.clip(-axis, -offset);
+        }
+        (&scene)
+            .try_into()
+            .map_err(|e: SceneDeserializeErr| (Failure::from(&e), localized(e.message())))
+    };
+    let whole = load(0.0, None)?;
+    // the camera's placement and focus are found on the whole geometry, so they don't follow
+    // the cut
+    let mut view = whole.renders[0].view;
+    if let Some(units) = clearance {
+        render::clear_path(
+            &whole.geometry,
+            std::slice::from_mut(&mut view.cam.origin),
+            units,
+            0,
+        );
+    }
+    // the proxy's width, if it's a proxy
+    let width = whole.renders[0].width;
+    let size = Extent2::new(width, (width as f64 / view.aspect()).round() as usize);
+    let reach = view.cam.origin.magnitude().max(1.0);
+    let (start, end) =
+        render::extent_along(&whole.geometry, axis, reach).unwrap_or((-reach, reach));
+
+    let values = Values {
+        scene: path.file_stem().map(|s| s.to_string_lossy().into_owned()),
+        camera: Some(camera.to_string()),
+        size: Some(size),
+        ..Values::default()
+    };
+    let now = Local::now();
+    let mut filenames = Vec::new();
+    for frame in 0..frames {
+        let filename = template.format(
+            &Values {
+                frame,
+                ..values.clone()
+            },
+            &now,
+        );
+        let files = outputs.plan(size, Path::new(&filename))?;
+        let t: f64 = render::frame_time(frame, 0, frames - 1);
+        let scene = load(t, Some(start + (end - start) * t))?;
+        // the camera's animated zoom, with the geometry's detail following it
+        let focal_len = scene.renders[0].view.focal_len;
+        let scene = scene.at_zoom(focal_len / view.focal_len);
+        let render = camera::Render {
+            view: Viewport { focal_len, ..view },
+            ..whole.renders[0]
+        };
+        let mut frame = matrix::render_view(&scene, &render, size, antialiasing, options)
+            .map_err(|e| (Failure::from(&e), localized(e.message())))?;
+        report_de_cache(&frame);
+        overlays.draw(&scene, &render.view, &[], size, &mut frame.color);
+        let aovs = outputs.render(&scene, &render, size, options);
+        write_outputs(&files, size, &frame.color, &aovs, &text)?;
+        recorder.record(&filename)?;
+        filenames.extend(files.iter().map(|file| file.path.display().to_string()));
+    }
+    Ok(filenames)
+}
+
+/// the scene at `path` with every aspect not in `locked` randomized from `seed`, as YAML
+fn randomized(path: &Path, locked: &[Aspect], seed: u64) -> Result<String, (Failure, String)> {
+    let text = fs::read_to_string(path).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let mut scene: Scene<f64> =
+        serde_yaml::from_str(&text).map_err(|e| (Failure::from(&e), e.to_string()))?;
+    let aspects: Vec<Aspect> = Aspect::ALL
+        .iter()
+        .cloned()
+        .filter(|a| !locked.contains(a))
+        .collect();
+    randomize::randomize(&mut scene, &aspects, seed);
+    serde_yaml::to_string(&scene).map_err(|e| (Failure::from(&e), e.to_string()))
 }
 
 fn main() {
     let matches = app().get_matches();
+    // clap has already checked it's one of the formats
+    let format: ErrorFormat = matches.value_of("error-format").unwrap().parse().unwrap();
+    // render threads inherit the main thread's priority
+    let nice = matches.is_present("nice");
+    if nice && !threads::lower_priority() {
+        eprintln!("warning: {}", localized(Message::PriorityUnchanged));
+    }
+
+    if let Some(sub) = matches.subcommand_matches("history") {
+        let entries = history::default_path()
+            .map(|path| history::search(&path, sub.value_of("QUERY").unwrap_or("")))
+            .unwrap_or_else(|| Ok(Vec::new()));
+        match entries {
+            Ok(entries) => {
+                for entry in entries {
+                    println!("{}", entry.to_json());
+                }
+            }
+            Err(e) => {
+                let failure = Failure::from(&e);
+                eprintln!("{}", failure.report(&e.to_string(), format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("rerender") {
+        let result = rerender(
+            Path::new(sub.value_of("IMAGE").unwrap()),
+            sub.value_of("width").map(|w| w.parse().unwrap()),
+            sub.value_of("antialiasing").unwrap().parse().unwrap(),
+            sub.value_of("refine").unwrap().parse().unwrap(),
+            sub.value_of("check-normals").map(|t| t.parse().unwrap()),
+            &Template::parse(sub.value_of("output").unwrap()).unwrap(),
+            &Outputs::new(sub),
+            &Overlays::new(sub),
+            &render_options(sub, nice),
+        );
+        match result {
+            Ok(filenames) => println!("{}", filenames.join("\n")),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("proxy") {
+        let path = Path::new(sub.value_of("SCENE").unwrap());
+        let result = proxied(path).and_then(|scene| {
+            if sub.is_present("write") {
+                let out = path.with_extension("proxy.yml");
+                fs::write(&out, scene).map_err(|e| (Failure::from(&e), e.to_string()))?;
+                Ok(out.display().to_string())
+            } else {
+                Ok(scene)
+            }
+        });
+        match result {
+            Ok(out) => println!("{}", out),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("benchmark") {
+        let result = benchmark(
+            Path::new(sub.value_of("SCENE").unwrap()),
+            sub.value_of("samples").unwrap().parse().unwrap(),
+        );
+        match result {
+            Ok(report) => println!("{}", report),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
 
-    let filename = fmt_filename(matches.value_of("output").unwrap());
-    print!("{}", filename);
+    if let Some(sub) = matches.subcommand_matches("quality-matrix") {
+        let crop = sub.values_of("crop").map(|c| {
+            let c: Vec<usize> = c.map(|n| n.parse().unwrap()).collect();
+            Rect::new(c[0], c[1], c[2], c[3])
+        });
+        let parsed = |name| -> Vec<_> {
+            sub.values_of(name)
+                .unwrap()
+                .map(|n| n.parse().unwrap())
+                .collect()
+        };
+        let epsilons: Vec<f64> = sub
+            .values_of("epsilon")
+            .unwrap()
+            .map(|e| e.parse().unwrap())
+            .collect();
+        let settings =
+            QualitySetting::combinations(&parsed("iterations"), &epsilons, &parsed("antialiasing"));
+        let result = quality_matrix(
+            Path::new(sub.value_of("SCENE").unwrap()),
+            sub.value_of("render").unwrap().parse().unwrap(),
+            crop,
+            &settings,
+            Path::new(sub.value_of("output").unwrap()),
+            &render_options(sub, nice),
+            matches.is_present("history"),
+        );
+        if let Err((failure, msg)) = result {
+            eprintln!("{}", failure.report(&msg, format));
+            process::exit(failure.code());
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("rig") {
+        let rig = sub.value_of("RIG").unwrap();
+        let out = sub
+            .value_of("output")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(format!("{}.png", rig)));
+        let result = rig_sheet(
+            Path::new(sub.value_of("SCENE").unwrap()),
+            rig,
+            sub.value_of("antialiasing").unwrap().parse().unwrap(),
+            &out,
+            &render_options(sub, nice),
+            matches.is_present("history"),
+        );
+        if let Err((failure, msg)) = result {
+            eprintln!("{}", failure.report(&msg, format));
+            process::exit(failure.code());
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("turntable") {
+        let parsed = |name| sub.value_of(name).unwrap().parse().unwrap();
+        let result = turntable(
+            Path::new(sub.value_of("SCENE").unwrap()),
+            sub.value_of("CAMERA").unwrap(),
+            parsed("frames"),
+            parsed("width"),
+            parsed("antialiasing"),
+            sub.value_of("clearance")
+                .map(|s| (s.parse().unwrap(), parsed("clearance-smoothing"))),
+            &Template::parse(sub.value_of("output").unwrap()).unwrap(),
+            &Outputs::new(sub),
+            &Overlays::new(sub),
+            &render_options(sub, nice),
+            matches.is_present("history"),
+        );
+        match result {
+            Ok(filenames) => println!("{}", filenames.join("\n")),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("scan") {
+        let axis = match sub.value_of("axis").unwrap() {
+            "x" => Vec3::unit_x(),
+            "y" => Vec3::unit_y(),
+            _ => Vec3::unit_z(),
+        };
+        let parsed = |name| sub.value_of(name).unwrap().parse().unwrap();
+        let result = scan(
+            Path::new(sub.value_of("SCENE").unwrap()),
+            sub.value_of("CAMERA").unwrap(),
+            axis,
+            parsed("frames"),
+            parsed("width"),
+            parsed("antialiasing"),
+            sub.value_of("clearance").map(|s| s.parse().unwrap()),
+            &Template::parse(sub.value_of("output").unwrap()).unwrap(),
+            &Outputs::new(sub),
+            &Overlays::new(sub),
+            &render_options(sub, nice),
+            matches.is_present("history"),
+        );
+        match result {
+            Ok(filenames) => println!("{}", filenames.join("\n")),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("randomize") {
+        let seed = sub
+            .value_of("seed")
+            .map(|s| s.parse().unwrap())
+            .unwrap_or_else(|| Utc::now().timestamp_nanos() as u64);
+        let locked: Vec<Aspect> = sub
+            .values_of("lock")
+            .map(|v| v.map(|a| a.parse().unwrap()).collect())
+            .unwrap_or_default();
+        // report the seed so a look worth keeping can be reproduced
+        eprintln!("seed: {}", seed);
+        match randomized(Path::new(sub.value_of("SCENE").unwrap()), &locked, seed) {
+            Ok(scene) => println!("{}", scene),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    if matches.is_present("summary") {
+        // on stderr, like the randomize seed, so it doesn't mix with the output
+        match summarize(Path::new(matches.value_of("SCENE").unwrap())) {
+            Ok(summary) => eprint!("{}", summary),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+    }
+
+    if let Some(mut pixel) = matches.values_of("debug-pixel") {
+        let mut coordinate = || pixel.next().unwrap().parse().unwrap();
+        let (x, y) = (coordinate(), coordinate());
+        let result = debug_pixel(
+            Path::new(matches.value_of("SCENE").unwrap()),
+            x,
+            y,
+            matches.value_of("debug-obj").map(Path::new),
+        );
+        match result {
+            Ok(trace) => println!("{}", trace),
+            Err((failure, msg)) => {
+                eprintln!("{}", failure.report(&msg, format));
+                process::exit(failure.code());
+            }
+        }
+        return;
+    }
+
+    // a proxy render halves the resolution and iterations, rounding up
+    let proxy = |n: usize| {
+        if matches.is_present("proxy") {
+            n.div_ceil(2).max(1)
+        } else {
+            n
+        }
+    };
+    let size = matches.values_of("resolution").map(|mut res| {
+        let mut side = || proxy(res.next().and_then(|s| s.parse().ok()).unwrap_or(1));
+        Extent2::new(side(), side())
+    });
+    let values = Values {
+        size,
+        quality: matches
+            .value_of("iterations")
+            .and_then(|i| i.parse().ok())
+            .map(proxy),
+        ..Values::default()
+    };
+    let template = Template::parse(matches.value_of("output").unwrap())
+        .unwrap()
+        .with_frame_padding(
+            matches
+                .value_of("frame-padding")
+                .map_or(0, |d| d.parse().unwrap()),
+        );
+    let frames = match matches.value_of("frame") {
+        Some(frame) => FrameRange::single(frame.parse().unwrap()),
+        None => {
+            let start = matches
+                .value_of("frame-start")
+                .map_or(0, |f| f.parse().unwrap());
+            FrameRange {
+                start,
+                end: matches
+                    .value_of("frame-end")
+                    .map_or(start, |f| f.parse().unwrap()),
+                step: matches.value_of("frame-step").unwrap().parse().unwrap(),
+            }
+        }
+    };
+    // just the beauty pass, until there's a render to take AOVs from
+    let request = img::OutputRequest::default();
+    if let Err(e) = frames.check(&template) {
+        let failure = Failure::from(&e);
+        eprintln!("{}", failure.report(&localized(e.message()), format));
+        process::exit(failure.code());
+    }
+    // every frame of a sequence is named for the time the sequence started
+    let now = Utc::now();
+    let mut filenames = Vec::new();
+    for frame in frames.frames() {
+        let filename = template.format(
+            &Values {
+                frame,
+                ..values.clone()
+            },
+            &now,
+        );
+        let outputs =
+            match img::plan_outputs(Path::new(&filename), &request, img::Degradation::Error) {
+                Ok(outputs) => outputs,
+                Err(e) => {
+                    let failure = Failure::from(&e);
+                    eprintln!("{}", failure.report(&localized(e.message()), format));
+                    process::exit(failure.code());
+                }
+            };
+        if let Some(size) = size {
+            for output in &outputs {
+                if let Err(e) = img::check_render_size(size, output.format) {
+                    let failure = Failure::from(&e);
+                    eprintln!("{}", failure.report(&localized(e.message()), format));
+                    process::exit(failure.code());
+                }
+            }
+        }
+        filenames.push(filename);
+    }
+    print!("{}", filenames.join("\n"));
 }
@@ -0,0 +1,1420 @@
+//! Quality comparison matrices: the same small crop of a render at several combinations of
+//! iterations, ε, and antialiasing, laid out side by side with labels, to choose final-render
+//! settings by eye rather than by guesswork; and contact sheets of every view of a camera rig.
+use std::iter::Sum;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use num::Float;
+use palette::{Component, LinSrgba, Srgba};
+use vek::{Extent2, Rect, Vec3, Vec4};
+
+use crate::camera::Render;
+use crate::distance::{Geometry, NonFiniteEstimate};
+use crate::img;
+use crate::messages::Message;
+use crate::render::{self, Acceleration, AoCache, DeCache, DeCacheStats, RenderGeometry, Scene};
+use crate::threads;
+
+/// How to render, beyond what the scene and its renders set
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// the optimizations the render may use
+    pub acceleration: Acceleration,
+    /// render a proxy of the scene (see `serialize::Scene::proxy`) for a fast preview; applied
+    /// where the scene file is loaded, before it's built into a `Scene`
+    pub proxy: bool,
+    /// how to split the image into units of work; `None` picks with `img::Tiling::auto`
+    pub tiling: Option<img::Tiling>,
+    /// groups of render threads to give each a contiguous band of the image (see
+    /// `threads::partition_tiles`), typically one per NUMA node
+    pub thread_groups: usize,
+    /// pin each render thread to its own core (see `threads::pin_current_thread`)
+    pub pin_threads: bool,
+    /// give up the CPU every `NICE_ROWS` rows of each tile, as `--nice` renders do
+    pub nice: bool,
+    /// darken the lighting with ambient occlusion
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    /// stop antialiasing each pixel once its mean is within this of converging, as far as
+    /// `Acceleration::adaptive_sampling` allows (see `img::SampleBuffer`)
+    pub adaptive_sampling: Option<f64>,
+    /// render only half the pixels, in a checkerboard, and fill in the rest from their neighbors,
+    /// as far as `Acceleration::checkerboard` allows (see `img::reconstruct_checkerboard`)
+    pub checkerboard: bool,
+}
+
+impl Options {
+    pub fn new(acceleration: Acceleration) -> Self {
+        Options {
+            acceleration,
+            proxy: false,
+            tiling: None,
+            thread_groups: 1,
+            pin_threads: false,
+            nice: false,
+            ambient_occlusion: None,
+            adaptive_sampling: None,
+            checkerboard: false,
+        }
+    }
+}
+
+/// How to sample ambient occlusion (see `render::ambient_occlusion`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientOcclusion {
+    /// distance estimates along the normal
+    pub samples: usize,
+    /// how far apart the samples are
+    pub step: f64,
+    /// the width of the cells of a `DeCache` to reuse the samples' distance estimates from, if
+    /// `Acceleration::de_cache` allows; used in place of any `AoCache`
+    pub de_cache: Option<f64>,
+}
+
+/// A rendered image, with what it took to render it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame<T> {
+    /// sRGB components from 0 to 1
+    pub color: Vec<Vec4<T>>,
+    /// how often the render threads' `DeCache`s had an estimate ready, if they were used
+    pub de_cache: Option<DeCacheStats>,
+    /// each pixel's nearest hit distance over its samples, or `None` if any of them missed, as
+    /// `render::depth_pass` gives them, for seeding a finer render of the same view
+    pub depth: Vec<Option<T>>,
+}
+
+/// Why a render failed
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderError {
+    /// a checked render (see `Acceleration::checked`) met a distance estimate which wasn't a
+    /// finite number
+    NonFiniteEstimate { pos: Vec3<f64>, dist: f64 },
+    /// the image, or its antialiasing samples, are too large to render here
+    Size(img::SizeError),
+}
+
+impl RenderError {
+    /// a description of the error for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            RenderError::NonFiniteEstimate { pos, dist } => Message::NonFiniteEstimate {
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+                dist: *dist,
+            },
+            RenderError::Size(e) => e.message(),
+        }
+    }
+}
+
+impl<T: Float> From<NonFiniteEstimate<T>> for RenderError {
+    fn from(e: NonFiniteEstimate<T>) -> Self {
+        RenderError::NonFiniteEstimate {
+            pos: e.pos.map(|c| c.to_f64().unwrap_or(f64::NAN)),
+            dist: e.dist.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+}
+
+/// One combination of settings to render
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QualitySetting<T> {
+    /// the iteration count of every iterated fractal; other shapes are unaffected
+    pub iterations: usize,
+    pub epsilon: T,
+    /// subpixel samples per side, as with `--antialiasing`
+    pub antialiasing: usize,
+}
+
+impl<T: Float> QualitySetting<T> {
+    /// every combination of the given values, iterations varying slowest and antialiasing
+    /// fastest
+    pub fn combinations(iterations: &[usize], epsilons: &[T], antialiasing: &[usize]) -> Vec<Self> {
+        let mut settings = Vec::new();
+        for &iterations in iterations {
+            for &epsilon in epsilons {
+                for &antialiasing in antialiasing {
+                    settings.push(QualitySetting {
+                        iterations,
+                        epsilon,
+                        antialiasing,
+                    });
+                }
+            }
+        }
+        settings
+    }
+
+    /// a short label for the setting, like `I64 E0.001 A2`; only uses characters in `GLYPHS`
+    pub fn label(&self) -> String {
+        format!(
+            "I{} E{} A{}",
+            self.iterations,
+            self.epsilon.to_f64().unwrap(),
+            self.antialiasing
+        )
+    }
+
+    /// `geom` with this setting applied
+    fn apply(&self, geom: &Geometry<T>) -> Geometry<T>
+    where
+        T: Sum,
+    {
+        let mut geom = geom.clone();
+        geom.epsilon = self.epsilon;
+        geom.sample_size = self.epsilon;
+        if let Some(n) = geom.de.iterations_mut() {
+            *n = self.iterations;
+        }
+        geom
+    }
+}
+
+/// Renders the pixels in `crop` of `render` at size `size` with `setting` and `options`, as 8-bit
+/// sRGB RGBA; rays that miss everything are opaque black. The render's foveation and normal
+/// smoothing are applied, foveation taking antialiasing samples away from the periphery unless
+/// `options` turns it off.
+pub fn render_crop<T>(
+    scene: &Scene<T, LinSrgba<T>>,
+    render: &Render<T>,
+    size: Extent2<usize>,
+    crop: Rect<usize, usize>,
+    setting: &QualitySetting<T>,
+    options: &Options,
+) -> Result<Vec<u8>, RenderError>
+where
+    T: Float + Sum + Default + Component + Send + Sync,
+{
+    let geometry: Vec<RenderGeometry<T>> = scene
+        .geometry
+        .iter()
+        .map(|g| RenderGeometry {
+            geom: setting.apply(&g.geom),
+            ..g.clone()
+        })
+        .collect();
+    shade(
+        scene,
+        &geometry,
+        render,
+        size,
+        crop,
+        setting.antialiasing,
+        options,
+        None,
+    )
+    .map(|frame| quantize(&frame.color))
+}
+
+/// `geometry` without the optimizations `acceleration` leaves out
+fn accelerated<T>(
+    geometry: &[RenderGeometry<T>],
+    acceleration: &Acceleration,
+) -> Vec<RenderGeometry<T>>
+where
+    T: Float + Sum + Default,
+{
+    geometry
+        .iter()
+        .map(|g| {
+            let mut g = g.clone();
+            if !acceleration.relaxation {
+                g.geom.relaxation = None;
+            }
+            if !acceleration.distance_lod {
+                g.geom.lod = None;
+            }
+            g
+        })
+        .collect()
+}
+
+/// rows each thread of a `nice` render marches between giving up the CPU
+const NICE_ROWS: usize = 8;
+
+/// `AoCache` cells per ambient occlusion step; small enough that the cells don't show
+const AO_CELLS_PER_STEP: f64 = 4.0;
+
+/// pixels per side of the blocks of samples which share a cone in `render::cone_prepass`
+const CONE_BLOCK: usize = 8;
+
+/// how many times coarser than the samples the depth prepass warm-starting them is
+const WARM_START_SCALE: usize = 4;
+
+/// how far short of the depth prepass's hits warm-started samples start, as a fraction
+const WARM_START_SAFETY: f64 = 0.9;
+
+/// samples an adaptively sampled pixel takes before it may stop (see `img::SampleBuffer`)
+const ADAPTIVE_MIN_SAMPLES: usize = 4;
+
+/// cells each render thread's `DeCache` keeps
+const DE_CACHE_CAPACITY: usize = 1 << 16;
+
+/// a render thread's caches, kept from tile to tile
+struct Caches<T> {
+    ao: Option<AoCache<T>>,
+    de: Option<DeCache<T>>,
+}
+
+/// a shaded pixel or sample: its sRGB color and its depth, as `Frame` has them
+type Shaded<T> = (Vec4<T>, Option<T>);
+
+/// the depth, normal, and albedo at a pixel, to guide a checkerboard's reconstruction (see
+/// `img::Guides`)
+type Guide<T> = (T, Vec3<T>, T);
+
+/// a rendered tile, its pixels row by row, and their guides if it's rendered in a checkerboard
+type Tile<T> = (Rect<usize, usize>, Vec<Shaded<T>>, Vec<Guide<T>>);
+
+/// the pixels of `crop`, as in `render_view`, ray marching `geometry` in place of the scene's
+/// own; a warm-started render seeds its marches from the depths of a `coarse` render of the same
+/// view and their size if given, in place of its own depth prepass
+#[allow(clippy::too_many_arguments)]
+fn shade<T>(
+    scene: &Scene<T, LinSrgba<T>>,
+    geometry: &[RenderGeometry<T>],
+    render: &Render<T>,
+    size: Extent2<usize>,
+    crop: Rect<usize, usize>,
+    antialiasing: usize,
+    options: &Options,
+    coarse: Option<(&[Option<T>], Extent2<usize>)>,
+) -> Result<Frame<T>, RenderError>
+where
+    T: Float + Sum + Default + Component + Send + Sync,
+{
+    let acceleration = &options.acceleration;
+    let geometry = &accelerated(geometry, acceleration)[..];
+    let foveation = render.foveation.filter(|_| acceleration.foveation);
+    let shading = scene.shading();
+    let view = &render.view;
+    let n = antialiasing.max(1);
+    // every subpixel sample, as the pixels of an image `n` times the size
+    let samples = match (size.w.checked_mul(n), size.h.checked_mul(n)) {
+        (Some(w), Some(h)) => Extent2::new(w, h),
+        _ => return Err(RenderError::Size(img::SizeError::TooLarge(size))),
+    };
+    // of the whole image even for a crop, so the crop's edges see the same neighbors as a whole
+    // render does
+    let normals = render.normal_smoothing.map(|smoothing| {
+        img::smooth_normals(
+            &render::normal_pass(geometry, view, samples),
+            &render::depth_pass(geometry, view, samples),
+            samples.w,
+            smoothing.radius * n,
+            smoothing.depth_tolerance,
+        )
+    });
+
+    // where each sample's march can start: past the empty space the cone prepass finds, and
+    // short of the hits of a coarse depth prepass; also of the whole image, so the cones' blocks
+    // and the coarse pixels line up
+    let cones = if acceleration.cone_prepass {
+        Some(render::cone_prepass(geometry, view, samples, CONE_BLOCK))
+    } else {
+        None
+    };
+    let seeds = if acceleration.warm_start {
+        let safety = T::from(WARM_START_SAFETY).unwrap();
+        Some(match coarse {
+            Some((depth, coarse)) => img::seed_distances(depth, coarse, samples, safety),
+            None => {
+                let coarse = samples.map(|d| (d / WARM_START_SCALE).max(1));
+                let depth = render::depth_pass(geometry, view, coarse);
+                img::seed_distances(&depth, coarse, samples, safety)
+            }
+        })
+    } else {
+        None
+    };
+    let start = |i: usize| {
+        [&cones, &seeds]
+            .iter()
+            .filter_map(|starts| starts.as_ref().map(|starts| starts[i]))
+            .fold(T::zero(), T::max)
+    };
+
+    let ao = options
+        .ambient_occlusion
+        .map(|ao| (ao.samples, T::from(ao.step).unwrap()));
+    let de_cell = options
+        .ambient_occlusion
+        .and_then(|ao| ao.de_cache)
+        .filter(|_| acceleration.de_cache);
+    // a thread's caches, for the ambient occlusion to be cached
+    let new_caches = || Caches {
+        ao: ao
+            .filter(|_| acceleration.ao_cache && de_cell.is_none())
+            .map(|(_, step)| AoCache::new(step / T::from(AO_CELLS_PER_STEP).unwrap())),
+        de: de_cell.map(|cell| DeCache::new(T::from(cell).unwrap(), DE_CACHE_CAPACITY)),
+    };
+
+    // samples per side of pixel (x, y): fewer toward the periphery of a foveated render
+    let per_side = |x: usize, y: usize| {
+        foveation.map_or(n, |foveation| {
+            foveation
+                .scale(render::pixel_location(x, y, size), n)
+                .min(n)
+        })
+    };
+    // sample (i, j) of the `m`×`m` of pixel (x, y) of the whole image, as an sRGB color and the
+    // hit distance, with the calling thread's `caches`; the samples are spread over the same
+    // subpixels as a full pixel's so they line up with the normals
+    let sample = |x: usize,
+                  y: usize,
+                  m: usize,
+                  (i, j): (usize, usize),
+                  caches: &mut Caches<T>|
+     -> Result<Shaded<T>, RenderError> {
+        let spread = |i: usize| (2 * i + 1) * n / (2 * m);
+        let (sx, sy) = (x * n + spread(i), y * n + spread(j));
+        let (pos, rot) = view.ray(render::pixel_location(sx, sy, samples));
+        let hit = if acceleration.checked {
+            render::nearest_checked(geometry, pos, rot)?
+        } else {
+            render::nearest_from(geometry, pos, rot, start(sy * samples.w + sx))
+        };
+        let color = match hit {
+            Some((g, d)) => {
+                let hit = pos + rot * d;
+                let normal = normals
+                    .as_ref()
+                    .and_then(|normals| normals[sy * samples.w + sx])
+                    .unwrap_or_else(|| g.geom.normal(hit));
+                let mut lit = shading.lighting(-rot, normal, g.material_at(hit));
+                if let Some((samples, step)) = ao {
+                    let occlusion = match caches {
+                        Caches { de: Some(de), .. } => render::ambient_occlusion_cached(
+                            geometry, de, hit, normal, samples, step,
+                        ),
+                        Caches { ao: Some(ao), .. } => ao.get(geometry, hit, normal, samples, step),
+                        _ => render::ambient_occlusion(geometry, hit, normal, samples, step),
+                    };
+                    lit.color = lit.color * occlusion;
+                }
+                let (r, g, b, a) = Srgba::from_linear(lit).into_components();
+                Vec4::new(r, g, b, a)
+            }
+            None => Vec4::new(T::zero(), T::zero(), T::zero(), T::one()),
+        };
+        Ok((color, hit.map(|(_, d)| d)))
+    };
+    let clamp = |color: Vec4<T>| color.map(|c: T| c.max(T::zero()).min(T::one()));
+    // the nearer of two depths, as `Frame::depth` has them
+    let nearer = |a: Option<T>, b: Option<T>| match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        _ => None,
+    };
+
+    let checkerboard = options.checkerboard && acceleration.checkerboard;
+    // whether pixel (x, y) of the whole image is rendered, rather than reconstructed
+    let renders = |x: usize, y: usize| !checkerboard || img::checkerboard(x, y, 0);
+
+    // pixel (x, y) of the whole image and its depth from every one of its samples
+    let pixel = |x: usize, y: usize, caches: &mut Caches<T>| -> Result<Shaded<T>, RenderError> {
+        let m = per_side(x, y);
+        let mut sum = Vec4::zero();
+        let mut depth = Some(T::infinity());
+        for j in 0..m {
+            for i in 0..m {
+                let (color, d) = sample(x, y, m, (i, j), caches)?;
+                sum = sum + color;
+                depth = nearer(depth, d);
+            }
+        }
+        Ok((clamp(sum / T::from(m * m).unwrap()), depth))
+    };
+
+    // the pixels of `tile` and their depths, each taking samples until its mean converges (see
+    // `img::SampleBuffer`) to within `threshold` or it has all of its samples
+    let adaptive = |tile: Rect<usize, usize>,
+                    threshold: T,
+                    caches: &mut Caches<T>,
+                    yielder: &mut threads::Yielder|
+     -> Result<Vec<Shaded<T>>, RenderError> {
+        let mut buffer = img::SampleBuffer::new(tile.w, tile.h, threshold, ADAPTIVE_MIN_SAMPLES);
+        let mut depth = vec![Some(T::infinity()); tile.w * tile.h];
+        loop {
+            let pending: Vec<(usize, usize)> = buffer
+                .pending()
+                .filter(|&(x, y)| {
+                    let (px, py) = (crop.x + tile.x + x, crop.y + tile.y + y);
+                    let m = per_side(px, py);
+                    renders(px, py) && buffer.get(x, y).count() < m * m
+                })
+                .collect();
+            if pending.is_empty() {
+                break;
+            }
+            for (x, y) in pending {
+                let (px, py) = (crop.x + tile.x + x, crop.y + tile.y + y);
+                let m = per_side(px, py);
+                // stepping through the samples m + 1 at a time, which is coprime with m², visits
+                // every one, spread over the pixel rather than a row at a time
+                let k = buffer.get(x, y).count() * (m + 1) % (m * m);
+                let (color, d) = sample(px, py, m, (k % m, k / m), caches)?;
+                buffer.add(x, y, color);
+                depth[y * tile.w + x] = nearer(depth[y * tile.w + x], d);
+            }
+            if options.nice {
+                yielder.tick();
+            }
+        }
+        Ok((0..tile.h)
+            .flat_map(|y| (0..tile.w).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if renders(crop.x + tile.x + x, crop.y + tile.y + y) {
+                    (clamp(buffer.get(x, y).mean()), depth[y * tile.w + x])
+                } else {
+                    (Vec4::zero(), None)
+                }
+            })
+            .collect())
+    };
+    let threshold = options
+        .adaptive_sampling
+        .filter(|_| acceleration.adaptive_sampling)
+        .map(|threshold| T::from(threshold).unwrap());
+
+    // the guide at the middle sample of pixel (x, y) of the whole image; misses are far enough
+    // away that no surface is continuous with them
+    let guide = |x: usize, y: usize| -> Guide<T> {
+        let (sx, sy) = (x * n + n / 2, y * n + n / 2);
+        let (pos, rot) = view.ray(render::pixel_location(sx, sy, samples));
+        match render::nearest_from(geometry, pos, rot, start(sy * samples.w + sx)) {
+            Some((g, d)) => {
+                let hit = pos + rot * d;
+                (d, g.geom.normal(hit), g.material_at(hit).diffuse)
+            }
+            None => (T::max_value(), Vec3::zero(), T::zero()),
+        }
+    };
+    // the guides of the pixels of `tile` which are rendered, for a checkerboard
+    let tile_guides = |tile: Rect<usize, usize>| -> Vec<Guide<T>> {
+        if !checkerboard {
+            return Vec::new();
+        }
+        (tile.y..tile.y + tile.h)
+            .flat_map(|y| (tile.x..tile.x + tile.w).map(move |x| (crop.x + x, crop.y + y)))
+            .map(|(x, y)| {
+                if renders(x, y) {
+                    guide(x, y)
+                } else {
+                    (T::zero(), Vec3::zero(), T::zero())
+                }
+            })
+            .collect()
+    };
+
+    let tiling = options
+        .tiling
+        .unwrap_or_else(|| img::Tiling::auto(crop.extent(), acceleration.threads));
+    let workers = acceleration.threads.max(1);
+    let groups = threads::partition_tiles(
+        tiling.tiles(crop.extent()),
+        options.thread_groups.max(1).min(workers),
+    );
+    // the next tile of each group for a thread to take
+    let next: Vec<AtomicUsize> = groups.iter().map(|_| AtomicUsize::new(0)).collect();
+    // renders tiles of `group` until there are none left, giving them and the caches it used
+    let work = |group: usize| -> Result<(Vec<Tile<T>>, Caches<T>), RenderError> {
+        let mut done = Vec::new();
+        let mut yielder = threads::Yielder::new(NICE_ROWS);
+        let mut caches = new_caches();
+        while let Some(&tile) = groups[group].get(next[group].fetch_add(1, Ordering::Relaxed)) {
+            if let Some(threshold) = threshold {
+                let pixels = adaptive(tile, threshold, &mut caches, &mut yielder)?;
+                done.push((tile, pixels, tile_guides(tile)));
+                continue;
+            }
+            let mut pixels = Vec::with_capacity(tile.w * tile.h);
+            for y in tile.y..tile.y + tile.h {
+                for x in tile.x..tile.x + tile.w {
+                    let (px, py) = (crop.x + x, crop.y + y);
+                    pixels.push(if renders(px, py) {
+                        pixel(px, py, &mut caches)?
+                    } else {
+                        (Vec4::zero(), None)
+                    });
+                }
+                if options.nice {
+                    yielder.tick();
+                }
+            }
+            done.push((tile, pixels, tile_guides(tile)));
+        }
+        Ok((done, caches))
+    };
+    let rendered = if workers == 1 {
+        vec![work(0)?]
+    } else {
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..workers)
+                .map(|i| {
+                    let (work, group) = (&work, i % groups.len());
+                    s.spawn(move || {
+                        if options.pin_threads {
+                            threads::pin_current_thread(i);
+                        }
+                        work(group)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("render thread panicked"))
+                .collect::<Result<Vec<_>, RenderError>>()
+        })?
+    };
+
+    let mut color = vec![Vec4::zero(); crop.w * crop.h];
+    let mut depth = vec![None; crop.w * crop.h];
+    let mut guides =
+        vec![(T::zero(), Vec3::zero(), T::zero()); if checkerboard { crop.w * crop.h } else { 0 }];
+    let mut de_cache: Option<DeCacheStats> = None;
+    for (tiles, caches) in rendered {
+        for (tile, pixels, tile_guides) in tiles {
+            for (row, y) in (tile.y..tile.y + tile.h).enumerate() {
+                let pixels = &pixels[row * tile.w..(row + 1) * tile.w];
+                for (x, &(c, d)) in (tile.x..tile.x + tile.w).zip(pixels) {
+                    color[y * crop.w + x] = c;
+                    depth[y * crop.w + x] = d;
+                }
+                if checkerboard {
+                    let start = y * crop.w + tile.x;
+                    guides[start..start + tile.w]
+                        .copy_from_slice(&tile_guides[row * tile.w..(row + 1) * tile.w]);
+                }
+            }
+        }
+        if let Some(de) = caches.de {
+            let (sum, stats) = (de_cache.unwrap_or_default(), de.stats());
+            de_cache = Some(DeCacheStats {
+                hits: sum.hits + stats.hits,
+                misses: sum.misses + stats.misses,
+            });
+        }
+    }
+    if checkerboard {
+        let (depths, normals, albedos): (Vec<T>, Vec<Vec3<T>>, Vec<T>) = (
+            guides.iter().map(|g| g.0).collect(),
+            guides.iter().map(|g| g.1).collect(),
+            guides.iter().map(|g| g.2).collect(),
+        );
+        let guides = img::Guides {
+            depth: &depths,
+            normal: &normals,
+            albedo: &albedos,
+        };
+        // the crop's own checkerboard lines up with the whole image's
+        let phase = (crop.x + crop.y) % 2;
+        img::reconstruct_checkerboard(&mut color, &guides, crop.w, phase);
+        // a reconstructed pixel is as deep as the nearest of its neighbors, to stay conservative
+        for y in 0..crop.h {
+            for x in 0..crop.w {
+                if img::checkerboard(x, y, phase) {
+                    continue;
+                }
+                let neighbors = [
+                    x.checked_sub(1).map(|l| (l, y)),
+                    Some((x + 1, y)).filter(|&(r, _)| r < crop.w),
+                    y.checked_sub(1).map(|u| (x, u)),
+                    Some((x, y + 1)).filter(|&(_, d)| d < crop.h),
+                ];
+                depth[y * crop.w + x] = neighbors
+                    .iter()
+                    .flatten()
+                    .map(|&(x, y)| depth[y * crop.w + x])
+                    .fold(Some(T::infinity()), nearer);
+            }
+        }
+    }
+    Ok(Frame {
+        color,
+        de_cache,
+        depth,
+    })
+}
+
+/// `color`, with components from 0 to 1, as 8-bit RGBA
+pub fn quantize<T: Float>(color: &[Vec4<T>]) -> Vec<u8> {
+    color
+        .iter()
+        .flat_map(|c| c.into_array().to_vec())
+        .map(|c| (c * T::from(255).unwrap()).round().to_u8().unwrap_or(0))
+        .collect()
+}
+
+/// Renders the whole of `render` at size `size` with the scene's own settings, `antialiasing`,
+/// and `options`, as in `render_crop` but with sRGB components from 0 to 1, for writing in any
+/// `OutputFormat`, along with the render's cache statistics
+pub fn render_view<T>(
+    scene: &Scene<T, LinSrgba<T>>,
+    render: &Render<T>,
+    size: Extent2<usize>,
+    antialiasing: usize,
+    options: &Options,
+) -> Result<Frame<T>, RenderError>
+where
+    T: Float + Sum + Default + Component + Send + Sync,
+{
+    let crop = Rect::new(0, 0, size.w, size.h);
+    shade(
+        scene,
+        &scene.geometry,
+        render,
+        size,
+        crop,
+        antialiasing,
+        options,
+        None,
+    )
+}
+
+/// Renders `render` as in `render_view`, but first at each coarser resolution of
+/// `img::refinement_levels(size, levels)`, giving each level's frame and size as it's rendered, so
+/// a preview shows the image's structure long before it's finished; each level's marches are
+/// warm-started from the previous level's depths if `options` allows.
+pub fn render_refined<'a, T>(
+    scene: &'a Scene<T, LinSrgba<T>>,
+    render: &'a Render<T>,
+    size: Extent2<usize>,
+    levels: u32,
+    antialiasing: usize,
+    options: &'a Options,
+) -> impl Iterator<Item = Result<(Frame<T>, Extent2<usize>), RenderError>> + 'a
+where
+    T: Float + Sum + Default + Component + Send + Sync,
+{
+    let mut previous: Option<(Vec<Option<T>>, Extent2<usize>)> = None;
+    img::refinement_levels(size, levels)
+        .into_iter()
+        .map(move |size| {
+            let coarse = previous.as_ref().map(|(depth, size)| (&depth[..], *size));
+            let frame = shade(
+                scene,
+                &scene.geometry,
+                render,
+                size,
+                Rect::new(0, 0, size.w, size.h),
+                antialiasing,
+                options,
+                coarse,
+            )?;
+            previous = Some((frame.depth.clone(), size));
+            Ok((frame, size))
+        })
+}
+
+/// The passes `aovs` of `render` at size `size`, one sample per pixel, ray marched as `options`
+/// allows, as EXR layers in the same order (see `img::Layer::aov`). Rays which miss are infinitely
+/// deep, with a zero normal and albedo and an object ID of 0; the geometries' IDs count from 1 in
+/// the order the scene lists them.
+pub fn render_aovs<T>(
+    scene: &Scene<T, LinSrgba<T>>,
+    render: &Render<T>,
+    size: Extent2<usize>,
+    aovs: &[img::Aov],
+    options: &Options,
+) -> Vec<img::Layer>
+where
+    T: Float + Sum + Default + Component,
+{
+    let geometry = &accelerated(&scene.geometry, &options.acceleration)[..];
+    let view = &render.view;
+    let to_f32 = |t: T| t.to_f32().unwrap_or(0.0);
+    aovs.iter()
+        .map(|&aov| {
+            let data = match aov {
+                img::Aov::Depth => render::depth_pass(geometry, view, size)
+                    .into_iter()
+                    .map(|depth| depth.map_or(f32::INFINITY, to_f32))
+                    .collect(),
+                img::Aov::Normal => render::normal_pass(geometry, view, size)
+                    .into_iter()
+                    .flat_map(|normal| {
+                        normal
+                            .map_or(Vec3::zero(), |n| n.map(to_f32))
+                            .into_array()
+                            .to_vec()
+                    })
+                    .collect(),
+                // the albedo is gray, as materials only scale the lights' colors
+                img::Aov::Albedo => render::albedo_pass(geometry, view, size)
+                    .into_iter()
+                    .flat_map(|albedo| vec![albedo.map_or(0.0, to_f32); 3])
+                    .collect(),
+                img::Aov::ObjectId => (0..size.h)
+                    .flat_map(|y| (0..size.w).map(move |x| (x, y)))
+                    .map(|(x, y)| {
+                        let (pos, rot) = view.ray(render::pixel_location(x, y, size));
+                        render::nearest(geometry, pos, rot)
+                            .and_then(|(g, _)| geometry.iter().position(|c| std::ptr::eq(c, g)))
+                            .map_or(0.0, |i| (i + 1) as f32)
+                    })
+                    .collect(),
+                img::Aov::Steps => render::step_pass(geometry, view, size)
+                    .0
+                    .into_iter()
+                    .map(|pixel| pixel.steps as f32)
+                    .collect(),
+            };
+            img::Layer::aov(aov, data)
+        })
+        .collect()
+}
+
+/// Renders every render of `scene` at its own resolution with `antialiasing` and `options`, and
+/// lays them out labelled with `labels` (like the names of a rig's cameras) as in `layout`, each
+/// in the top left of a cell as large as the largest view, giving the image and its size.
+pub fn contact_sheet<T>(
+    scene: &Scene<T, LinSrgba<T>>,
+    labels: &[String],
+    antialiasing: usize,
+    options: &Options,
+) -> Result<(Vec<u8>, Extent2<usize>), RenderError>
+where
+    T: Float + Sum + Default + Component + Send + Sync,
+{
+    let sizes: Vec<Extent2<usize>> = scene
+        .renders
+        .iter()
+        .map(|r| {
+            let height = (T::from(r.width).unwrap() / r.aspect()).round();
+            Extent2::new(r.width, height.to_usize().unwrap_or(0))
+        })
+        .collect();
+    let cell = sizes
+        .iter()
+        .fold(Extent2::zero(), |cell, size| Extent2::max(cell, *size));
+    let cells = scene
+        .renders
+        .iter()
+        .zip(&sizes)
+        .zip(labels)
+        .map(|((r, &size), label)| {
+            let pixels = quantize(&render_view(scene, r, size, antialiasing, options)?.color);
+            Ok((label.clone(), pad(&pixels, size, cell)))
+        })
+        .collect::<Result<Vec<(String, Vec<u8>)>, RenderError>>()?;
+    Ok(layout(&cells, cell))
+}
+
+/// `rgba`, of size `size`, in the top left of an image of size `to` filled with the background
+fn pad(rgba: &[u8], size: Extent2<usize>, to: Extent2<usize>) -> Vec<u8> {
+    let mut padded: Vec<u8> = BACKGROUND
+        .iter()
+        .cycle()
+        .take(to.w * to.h * 4)
+        .cloned()
+        .collect();
+    for y in 0..size.h {
+        padded[y * to.w * 4..(y * to.w + size.w) * 4]
+            .copy_from_slice(&rgba[y * size.w * 4..(y + 1) * size.w * 4]);
+    }
+    padded
+}
+
+/// The characters labels can use, with 3×5 bitmaps, one row per byte and the leftmost pixel in
+/// the 4s bit; lowercase letters are drawn as uppercase
+const GLYPHS: [(char, [u8; 5]); 40] = [
+    ('0', [7, 5, 5, 5, 7]),
+    ('1', [2, 6, 2, 2, 7]),
+    ('2', [7, 1, 7, 4, 7]),
+    ('3', [7, 1, 3, 1, 7]),
+    ('4', [5, 5, 7, 1, 1]),
+    ('5', [7, 4, 7, 1, 7]),
+    ('6', [7, 4, 7, 5, 7]),
+    ('7', [7, 1, 1, 1, 1]),
+    ('8', [7, 5, 7, 5, 7]),
+    ('9', [7, 5, 7, 1, 7]),
+    ('.', [0, 0, 0, 0, 2]),
+    ('-', [0, 0, 7, 0, 0]),
+    ('_', [0, 0, 0, 0, 7]),
+    ('A', [2, 5, 7, 5, 5]),
+    ('B', [6, 5, 6, 5, 6]),
+    ('C', [3, 4, 4, 4, 3]),
+    ('D', [6, 5, 5, 5, 6]),
+    ('E', [7, 4, 6, 4, 7]),
+    ('F', [7, 4, 6, 4, 4]),
+    ('G', [3, 4, 5, 5, 3]),
+    ('H', [5, 5, 7, 5, 5]),
+    ('I', [7, 2, 2, 2, 7]),
+    ('J', [1, 1, 1, 5, 2]),
+    ('K', [5, 5, 6, 5, 5]),
+    ('L', [4, 4, 4, 4, 7]),
+    ('M', [5, 7, 7, 5, 5]),
+    ('N', [6, 5, 5, 5, 5]),
+    ('O', [2, 5, 5, 5, 2]),
+    ('P', [6, 5, 6, 4, 4]),
+    ('Q', [2, 5, 5, 6, 3]),
+    ('R', [6, 5, 6, 5, 5]),
+    ('S', [3, 4, 2, 1, 6]),
+    ('T', [7, 2, 2, 2, 2]),
+    ('U', [5, 5, 5, 5, 7]),
+    ('V', [5, 5, 5, 5, 2]),
+    ('W', [5, 5, 7, 7, 5]),
+    ('X', [5, 5, 2, 5, 5]),
+    ('Y', [5, 5, 2, 2, 2]),
+    ('Z', [7, 1, 2, 4, 7]),
+    (' ', [0, 0, 0, 0, 0]),
+];
+
+/// pixels from the top of a cell to the top of its crop, with room for a label
+const LABEL_HEIGHT: usize = 9;
+/// pixels between cells
+const GAP: usize = 4;
+/// the dark gray between and behind cells
+const BACKGROUND: [u8; 4] = [32, 32, 32, 255];
+
+/// Draws `text` in white with its top left corner at (`x`, `y`) in an RGBA image `width` pixels
+/// wide, scaled up by `scale`; characters without a glyph are left blank.
+fn draw_text(rgba: &mut [u8], width: usize, x: usize, y: usize, text: &str, scale: usize) {
+    for (i, c) in text.chars().enumerate() {
+        let c = c.to_ascii_uppercase();
+        let glyph = match GLYPHS.iter().find(|(g, _)| *g == c) {
+            Some((_, rows)) => rows,
+            None => continue,
+        };
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (4 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = x + (i * 4 + col) * scale + dx;
+                        let py = y + row * scale + dy;
+                        let inx = (py * width + px) * 4;
+                        if px < width && inx + 4 <= rgba.len() {
+                            rgba[inx..inx + 4].copy_from_slice(&[255, 255, 255, 255]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Lays out `cells` (each an RGBA crop of size `crop` with its label) in a grid as close to
+/// square as possible, on a dark gray background, giving the image and its size.
+pub fn layout(cells: &[(String, Vec<u8>)], crop: Extent2<usize>) -> (Vec<u8>, Extent2<usize>) {
+    let cols = (1..=cells.len())
+        .find(|c| c * c >= cells.len())
+        .unwrap_or(1);
+    let rows = cells.len().div_ceil(cols).max(1);
+    let cell = Extent2::new(crop.w + GAP, crop.h + LABEL_HEIGHT + GAP);
+    let size = Extent2::new(cols * cell.w + GAP, rows * cell.h + GAP);
+    let mut rgba: Vec<u8> = BACKGROUND
+        .iter()
+        .cycle()
+        .take(size.w * size.h * 4)
+        .cloned()
+        .collect();
+    for (i, (label, pixels)) in cells.iter().enumerate() {
+        let (cx, cy) = (GAP + (i % cols) * cell.w, GAP + (i / cols) * cell.h);
+        draw_text(&mut rgba, size.w, cx, cy, label, 1);
+        for y in 0..crop.h {
+            let from = y * crop.w * 4;
+            let to = ((cy + LABEL_HEIGHT + y) * size.w + cx) * 4;
+            rgba[to..to + crop.w * 4].copy_from_slice(&pixels[from..from + crop.w * 4]);
+        }
+    }
+    (rgba, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use vek::Extent2;
+
+    use std::convert::TryInto;
+
+    use indoc::indoc;
+    use palette::LinSrgba;
+    use vek::{Rect, Vec2};
+
+    use super::{
+        contact_sheet, layout, quantize, render_aovs, render_crop, render_refined, render_view,
+        AmbientOcclusion, Options, QualitySetting, RenderError, BACKGROUND, GAP, GLYPHS,
+        LABEL_HEIGHT,
+    };
+    use crate::camera::{Foveation, NormalSmoothing, Render};
+    use crate::img::{self, SizeError, Tiling};
+    use crate::render::{Acceleration, Scene};
+    use crate::serialize;
+
+    #[test]
+    fn combinations_test() {
+        let settings = QualitySetting::combinations(&[32, 64], &[1e-3, 1e-4], &[1, 2]);
+        assert_eq!(settings.len(), 8);
+        assert_eq!(
+            settings[1],
+            QualitySetting {
+                iterations: 32,
+                epsilon: 1e-3,
+                antialiasing: 2,
+            }
+        );
+        assert_eq!(settings[7].label(), "I64 E0.0001 A2");
+        // every label can be drawn
+        for setting in &settings {
+            for c in setting.label().chars() {
+                assert!(GLYPHS.iter().any(|(g, _)| *g == c), "no glyph for {:?}", c);
+            }
+        }
+    }
+
+    #[test]
+    fn render_crop_test() {
+        let scene: serialize::Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 1, ambient: 0}
+            lights:
+              - facing: [0, 0, -1]
+                specular: rgba(0, 0, 0, 1)
+                diffuse: rgba(255, 255, 255, 1)
+                ambient: rgba(0, 0, 0, 1)
+            cameras:
+                main:
+                    facing: [0, 0, 1]
+                    right: [1, 0, 0]
+                    pos: [0, 0, -5]
+                    focal_len: 1
+                    width: 0.5
+                    height: 0.5
+            renders:
+              - camera: main
+                width: 4
+            "
+        ))
+        .unwrap();
+        let scene: Scene<f64, LinSrgba<f64>> = (&scene).try_into().unwrap();
+        let render = &scene.renders[0];
+        let setting = QualitySetting {
+            iterations: 1,
+            epsilon: 1e-3,
+            antialiasing: 2,
+        };
+        let size = Extent2::new(4, 4);
+        let whole = Rect::new(0, 0, 4, 4);
+        let options = Options::new(Acceleration::new(1));
+        let rendered = |render: &Render<f64>, crop, options: &Options| {
+            render_crop(&scene, render, size, crop, &setting, options).unwrap()
+        };
+        let rgba = rendered(render, whole, &options);
+        assert_eq!(rgba.len(), 4 * 4 * 4);
+        // the sphere fills the middle, lit head on, and misses the corners
+        let pixel = |x: usize, y: usize| &rgba[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4];
+        assert!(pixel(1, 1)[0] > 200);
+        assert_eq!(pixel(0, 0), &[0, 0, 0, 255]);
+        // a crop is the same pixels as the whole image
+        let crop = rendered(render, Rect::new(1, 1, 2, 1), &options);
+        assert_eq!(&crop[..4], pixel(1, 1));
+        assert_eq!(&crop[4..], pixel(2, 1));
+        // and so is the image rendered in any order
+        for &tiling in &[Tiling::Scanline(3), Tiling::Square(3)] {
+            let tiled = Options {
+                tiling: Some(tiling),
+                ..options.clone()
+            };
+            assert_eq!(rendered(render, whole, &tiled), rgba);
+        }
+        // so does spreading the tiles over threads, in one group or several
+        for &thread_groups in &[1, 2] {
+            let threaded = Options {
+                tiling: Some(Tiling::Square(2)),
+                thread_groups,
+                ..Options::new(Acceleration::new(3))
+            };
+            assert_eq!(rendered(render, whole, &threaded), rgba);
+        }
+        // and starting the rays where either prepass or neither leaves them
+        for &(cone_prepass, warm_start) in &[(true, false), (false, true), (false, false)] {
+            let started = Options::new(Acceleration {
+                cone_prepass,
+                warm_start,
+                ..Acceleration::new(1)
+            });
+            assert_eq!(rendered(render, whole, &started), rgba);
+        }
+        // sampling adaptively takes every sample of pixels with too few to converge
+        let adaptive = Options {
+            adaptive_sampling: Some(0.0),
+            ..options.clone()
+        };
+        assert_eq!(rendered(render, whole, &adaptive), rgba);
+        // and with more, stops once they're within the threshold, as all of them are of 1
+        let dense = QualitySetting {
+            antialiasing: 4,
+            ..setting
+        };
+        let dense =
+            |options: &Options| render_crop(&scene, render, size, whole, &dense, options).unwrap();
+        let all = dense(&options);
+        let loose = dense(&Options {
+            adaptive_sampling: Some(1.0),
+            ..options.clone()
+        });
+        assert_eq!(&loose[..4], &all[..4]);
+        assert_ne!(loose, all);
+
+        // a checkerboard renders the same pixels half the time, in step with the whole image even
+        // in a crop, and fills in the other half
+        let half = Options {
+            checkerboard: true,
+            ..options.clone()
+        };
+        let checkered = rendered(render, whole, &half);
+        let rendered_pixels = |rgba: &[u8], crop: Rect<usize, usize>| -> Vec<u8> {
+            (0..crop.w * crop.h)
+                .filter(|i| img::checkerboard(crop.x + i % crop.w, crop.y + i / crop.w, 0))
+                .flat_map(|i| rgba[i * 4..i * 4 + 4].to_vec())
+                .collect()
+        };
+        assert_eq!(
+            rendered_pixels(&checkered, whole),
+            rendered_pixels(&rgba, whole)
+        );
+        assert_ne!(checkered, rgba);
+        let odd = Rect::new(1, 1, 3, 2);
+        let cropped = rendered(render, odd, &half);
+        assert_eq!(
+            rendered_pixels(&cropped, odd),
+            rendered_pixels(&rendered(render, odd, &options), odd)
+        );
+        // a skipped pixel in the middle is filled in between the lit middle and its dim edge
+        assert!(pixel(1, 1)[0] > checkered[(4 + 2) * 4] && checkered[(4 + 2) * 4] > pixel(3, 1)[0]);
+        // unless acceleration turns it off
+        let safe = Options::new(Acceleration::safe());
+        assert_eq!(
+            rendered(
+                render,
+                whole,
+                &Options {
+                    checkerboard: true,
+                    ..safe.clone()
+                }
+            ),
+            rendered(render, whole, &safe)
+        );
+
+        // refining renders every level, finishing with the whole image seeded from the last
+        let levels = render_refined(&scene, render, size, 3, 2, &options)
+            .collect::<Result<Vec<_>, RenderError>>()
+            .unwrap();
+        let sizes: Vec<_> = levels.iter().map(|(_, size)| size.w).collect();
+        assert_eq!(sizes, vec![1, 2, 4]);
+        let view = render_view(&scene, render, size, 2, &options).unwrap();
+        assert_eq!(quantize(&levels[2].0.color), quantize(&view.color));
+        // the sphere's depth is known in the middle, but not in the corners which miss it
+        assert!(view.depth[5].is_some());
+        assert_eq!(view.depth[0], None);
+
+        // smoothing the sphere's normals shades it differently, but only where it's hit
+        let smoothed = Render {
+            normal_smoothing: Some(NormalSmoothing {
+                radius: 2,
+                depth_tolerance: 1.0,
+            }),
+            ..*render
+        };
+        let smooth = rendered(&smoothed, whole, &options);
+        assert_ne!(smooth, rgba);
+        assert_eq!(&smooth[..4], &[0, 0, 0, 255]);
+
+        // looking away from the whole image drops it to one sample per pixel
+        let foveated = Render {
+            foveation: Some(Foveation {
+                center: Vec2::new(10.0, 10.0),
+                radius: 0.1,
+                falloff: 0.1,
+                min_quality: 0.0,
+            }),
+            ..*render
+        };
+        let sparse = rendered(&foveated, whole, &options);
+        assert_ne!(sparse, rgba);
+        let looking = Render {
+            foveation: Some(Foveation {
+                center: Vec2::new(0.5, 0.5),
+                radius: 10.0,
+                ..foveated.foveation.unwrap()
+            }),
+            ..*render
+        };
+        assert_eq!(rendered(&looking, whole, &options), rgba);
+
+        // safe mode renders every sample, foveated or not
+        let safe = Options::new(Acceleration::safe());
+        assert_eq!(
+            rendered(&foveated, whole, &safe),
+            rendered(render, whole, &safe)
+        );
+
+        // too many antialiasing samples to count is an error, not an overflow
+        let huge = Extent2::new(usize::MAX / 2 + 1, 4);
+        assert_eq!(
+            render_crop(&scene, render, huge, whole, &setting, &options),
+            Err(RenderError::Size(SizeError::TooLarge(huge)))
+        );
+    }
+
+    #[test]
+    fn ambient_occlusion_test() {
+        // a sphere just in front of a wall, which it shadows near its edge
+        let scene: serialize::Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: plane
+                normal: [0, 0, -1]
+                offset: -1.1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 1, ambient: 0}
+            lights:
+              - facing: [0, 0, -1]
+                specular: rgba(0, 0, 0, 1)
+                diffuse: rgba(128, 128, 128, 1)
+                ambient: rgba(0, 0, 0, 1)
+            cameras:
+                main:
+                    facing: [0, 0, 1]
+                    right: [1, 0, 0]
+                    pos: [0, 0, -5]
+                    focal_len: 1
+                    width: 0.5
+                    height: 0.5
+            renders:
+              - camera: main
+                width: 8
+            "
+        ))
+        .unwrap();
+        let scene: Scene<f64, LinSrgba<f64>> = (&scene).try_into().unwrap();
+        let render = &scene.renders[0];
+        let setting = QualitySetting {
+            iterations: 1,
+            epsilon: 1e-3,
+            antialiasing: 1,
+        };
+        let size = Extent2::new(8, 8);
+        let whole = Rect::new(0, 0, 8, 8);
+        let rendered = |acceleration, ambient_occlusion| {
+            let options = Options {
+                ambient_occlusion,
+                ..Options::new(acceleration)
+            };
+            render_crop(&scene, render, size, whole, &setting, &options).unwrap()
+        };
+        let plain = rendered(Acceleration::new(1), None);
+        let ao = AmbientOcclusion {
+            samples: 4,
+            step: 0.25,
+            de_cache: None,
+        };
+        // occlusion only ever darkens, and darkens the wall around the sphere, cached or not
+        for &ao_cache in &[true, false] {
+            let acceleration = Acceleration {
+                ao_cache,
+                ..Acceleration::new(1)
+            };
+            let occluded = rendered(acceleration, Some(ao));
+            assert!(occluded.iter().zip(&plain).all(|(o, p)| o <= p));
+            assert!(occluded.iter().zip(&plain).any(|(o, p)| o < p));
+        }
+        // neighboring pixels' samples share a `DeCache`'s estimates, given pixels close enough
+        let view = |ambient_occlusion, acceleration| {
+            let options = Options {
+                ambient_occlusion,
+                ..Options::new(acceleration)
+            };
+            render_view(&scene, render, Extent2::new(32, 32), 1, &options).unwrap()
+        };
+        let acceleration = Acceleration {
+            de_cache: true,
+            ..Acceleration::new(1)
+        };
+        assert_eq!(view(Some(ao), acceleration).de_cache, None);
+        let de_cached = Some(AmbientOcclusion {
+            de_cache: Some(0.2),
+            ..ao
+        });
+        let frame = view(de_cached, acceleration);
+        assert!(frame.de_cache.unwrap().hit_rate() > 0.0);
+        let plain = quantize(&view(None, acceleration).color);
+        assert!(quantize(&frame.color) < plain);
+        // unless acceleration turns it off
+        assert_eq!(view(de_cached, Acceleration::new(1)).de_cache, None);
+    }
+
+    #[test]
+    fn render_aovs_test() {
+        let scene: serialize::Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras:
+                main:
+                    facing: [0, 0, 1]
+                    right: [1, 0, 0]
+                    pos: [0, 0, -5]
+                    focal_len: 1
+                    width: 0.5
+                    height: 0.5
+            renders:
+              - camera: main
+                width: 4
+            "
+        ))
+        .unwrap();
+        let scene: Scene<f64, LinSrgba<f64>> = (&scene).try_into().unwrap();
+        let size = Extent2::new(4, 4);
+        let aovs = [
+            img::Aov::Depth,
+            img::Aov::Normal,
+            img::Aov::ObjectId,
+            img::Aov::Albedo,
+            img::Aov::Steps,
+        ];
+        let layers = render_aovs(
+            &scene,
+            &scene.renders[0],
+            size,
+            &aovs,
+            &Options::new(Acceleration::new(1)),
+        );
+        let names: Vec<&str> = layers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, ["depth", "normal", "id", "albedo", "steps"]);
+        for (layer, aov) in layers.iter().zip(&aovs) {
+            assert_eq!(layer.data.len(), 16 * aov.channels().len());
+        }
+        // the middle pixels see the front of the sphere, and the corners miss it
+        let (middle, corner) = (size.w + 1, 0);
+        assert!((4.0..4.5).contains(&layers[0].data[middle]));
+        assert_eq!(layers[0].data[corner], f32::INFINITY);
+        assert!(layers[1].data[middle * 3 + 2] < -0.8);
+        assert_eq!(&layers[1].data[..3], &[0.0; 3]);
+        assert_eq!(layers[2].data[middle], 1.0);
+        assert_eq!(layers[2].data[corner], 0.0);
+        // the material's diffuse reflectance, as gray
+        assert_eq!(&layers[3].data[middle * 3..middle * 3 + 3], &[0.5; 3]);
+        assert_eq!(&layers[3].data[..3], &[0.0; 3]);
+        // every ray takes steps, hit or miss
+        assert!(layers[4].data.iter().all(|&steps| steps >= 1.0));
+    }
+
+    #[test]
+    fn contact_sheet_test() {
+        let mut scene: serialize::Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 1, ambient: 0}
+            lights:
+              - facing: [0, 0, -1]
+                specular: rgba(0, 0, 0, 1)
+                diffuse: rgba(255, 255, 255, 1)
+                ambient: rgba(0, 0, 0, 1)
+            cameras:
+                front:
+                    facing: [0, 0, 1]
+                    right: [1, 0, 0]
+                    pos: [0, 0, -5]
+                    focal_len: 1
+                    width: 0.5
+                    height: 0.5
+                wide:
+                    facing: [0, 0, 1]
+                    right: [1, 0, 0]
+                    pos: [0, 0, -5]
+                    focal_len: 1
+                    width: 0.5
+                    height: 0.25
+            renders: []
+            rigs:
+                both:
+                    cameras: [wide, front]
+                    width: 4
+            "
+        ))
+        .unwrap();
+        scene.select_rig("both").unwrap();
+        let scene: Scene<f64, LinSrgba<f64>> = (&scene).try_into().unwrap();
+        let labels = vec!["wide".to_string(), "front".to_string()];
+        let (rgba, size) =
+            contact_sheet(&scene, &labels, 1, &Options::new(Acceleration::new(1))).unwrap();
+        // two 4×4 cells side by side, the wide view only 2 pixels tall
+        let cell = Extent2::new(4 + GAP, 4 + LABEL_HEIGHT + GAP);
+        assert_eq!(size, Extent2::new(2 * cell.w + GAP, cell.h + GAP));
+        let pixel = |x: usize, y: usize| &rgba[(y * size.w + x) * 4..(y * size.w + x) * 4 + 4];
+        let top = GAP + LABEL_HEIGHT;
+        // the wide view's sphere, and the padding under it
+        assert!(pixel(GAP + 1, top)[0] > 200);
+        assert_eq!(pixel(GAP + 1, top + 3), &BACKGROUND);
+        // the front view fills its cell
+        assert!(pixel(GAP + cell.w + 1, top + 1)[0] > 200);
+        assert_eq!(pixel(GAP + cell.w, top + 3), &[0, 0, 0, 255]);
+        // the top of the lowercase label's W
+        assert_eq!(pixel(GAP, GAP), &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn layout_test() {
+        let crop = Extent2::new(2, 2);
+        let red = [255, 0, 0, 255].repeat(4);
+        let cells: Vec<(String, Vec<u8>)> =
+            (0..3).map(|_| ("1".to_string(), red.clone())).collect();
+        let (rgba, size) = layout(&cells, crop);
+        // two columns, two rows
+        let cell = Extent2::new(crop.w + GAP, crop.h + LABEL_HEIGHT + GAP);
+        assert_eq!(size, Extent2::new(2 * cell.w + GAP, 2 * cell.h + GAP));
+        assert_eq!(rgba.len(), size.w * size.h * 4);
+        let pixel = |x: usize, y: usize| &rgba[(y * size.w + x) * 4..(y * size.w + x) * 4 + 4];
+        // the third cell's crop, in the second row
+        assert_eq!(pixel(GAP, GAP + cell.h + LABEL_HEIGHT), &[255, 0, 0, 255]);
+        // the empty fourth cell
+        assert_eq!(
+            pixel(GAP + cell.w, GAP + cell.h + LABEL_HEIGHT),
+            &[32, 32, 32, 255]
+        );
+        // the top of the first label's 1
+        assert_eq!(pixel(GAP + 1, GAP), &[255, 255, 255, 255]);
+    }
+}
@@ -0,0 +1,356 @@
+//! Triangle meshes loaded from OBJ or STL files, baked into signed distance grids so they can be
+//! ray marched alongside fractals.
+use std::fmt;
+use std::fs;
+use std::iter::Sum;
+use std::path::Path;
+use std::sync::Arc;
+
+use num::Float;
+use vek::Vec3;
+
+use crate::distance::SdfGrid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    /// the file couldn't be read
+    Io(String),
+    /// a line of an OBJ or ASCII STL file that couldn't be parsed, counting from 1
+    Syntax(usize),
+    /// the file parsed, but has no triangles
+    Empty,
+    /// a triangle of a binary STL file, counting from 1, with an infinite or NaN coordinate
+    NonFinite(usize),
+    /// the extension isn't .obj or .stl
+    UnknownFormat(String),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshError::Io(e) => write!(f, "couldn't read mesh: {}", e),
+            MeshError::Syntax(line) => write!(f, "couldn't parse mesh at line {}", line),
+            MeshError::Empty => write!(f, "mesh has no triangles"),
+            MeshError::NonFinite(triangle) => {
+                write!(f, "mesh triangle {} has a non-finite vertex", triangle)
+            }
+            MeshError::UnknownFormat(path) => {
+                write!(f, "{} isn't a mesh; expected .obj or .stl", path)
+            }
+        }
+    }
+}
+
+/// A triangle soup; for the inside to be well-defined, it should be closed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    pub triangles: Vec<[Vec3<f64>; 3]>,
+}
+
+/// the three coordinates in `fields`, or `None` if there aren't three finite numbers
+fn parse_vertex<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<Vec3<f64>> {
+    let mut coord = || fields.next()?.parse().ok().filter(|c: &f64| c.is_finite());
+    Some(Vec3::new(coord()?, coord()?, coord()?))
+}
+
+impl Mesh {
+    /// Reads a mesh from an `.obj` or `.stl` file
+    pub fn load(path: &Path) -> Result<Self, MeshError> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        let bytes = || fs::read(path).map_err(|e| MeshError::Io(e.to_string()));
+        match ext.as_deref() {
+            Some("obj") => Self::parse_obj(&String::from_utf8_lossy(&bytes()?)),
+            Some("stl") => Self::parse_stl(&bytes()?),
+            _ => Err(MeshError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    /// Parses the vertices and faces of a Wavefront OBJ file, splitting polygons into fans of
+    /// triangles; everything else (normals, texture coordinates, groups) is ignored
+    pub fn parse_obj(src: &str) -> Result<Self, MeshError> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("v") => {
+                    vertices.push(parse_vertex(fields).ok_or(MeshError::Syntax(i + 1))?);
+                }
+                Some("f") => {
+                    // `v`, `v/vt`, `v//vn`, or `v/vt/vn`, indexed from 1 or, if negative, back
+                    // from the last vertex
+                    let face = fields
+                        .map(|f| {
+                            let inx: isize = f.split('/').next()?.parse().ok()?;
+                            let inx = if inx < 0 {
+                                vertices.len() as isize + inx
+                            } else {
+                                inx - 1
+                            };
+                            vertices.get(inx as usize).cloned()
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|face| face.len() >= 3)
+                        .ok_or(MeshError::Syntax(i + 1))?;
+                    for pair in face[1..].windows(2) {
+                        triangles.push([face[0], pair[0], pair[1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self::nonempty(triangles)
+    }
+
+    /// Parses a binary or ASCII STL file
+    pub fn parse_stl(bytes: &[u8]) -> Result<Self, MeshError> {
+        // an 80-byte header, a triangle count, then 50 bytes per triangle: a normal, three
+        // vertices, and an attribute byte count
+        if bytes.len() >= 84 {
+            let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            if bytes.len() == 84 + 50 * count {
+                let float = |at: usize| {
+                    let mut b = [0; 4];
+                    b.copy_from_slice(&bytes[at..at + 4]);
+                    f64::from(f32::from_le_bytes(b))
+                };
+                let vertex = |at: usize| Vec3::new(float(at), float(at + 4), float(at + 8));
+                let triangles = (0..count)
+                    .map(|t| {
+                        let start = 84 + 50 * t + 12;
+                        let triangle = [vertex(start), vertex(start + 12), vertex(start + 24)];
+                        if triangle.iter().all(|v| v.iter().all(|c| c.is_finite())) {
+                            Ok(triangle)
+                        } else {
+                            Err(MeshError::NonFinite(t + 1))
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+                return Self::nonempty(triangles);
+            }
+        }
+
+        let src = String::from_utf8_lossy(bytes);
+        let mut vertices = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            if fields.next() == Some("vertex") {
+                vertices.push(parse_vertex(fields).ok_or(MeshError::Syntax(i + 1))?);
+            }
+        }
+        Self::nonempty(
+            vertices
+                .chunks_exact(3)
+                .map(|v| [v[0], v[1], v[2]])
+                .collect(),
+        )
+    }
+
+    fn nonempty(triangles: Vec<[Vec3<f64>; 3]>) -> Result<Self, MeshError> {
+        if triangles.is_empty() {
+            Err(MeshError::Empty)
+        } else {
+            Ok(Mesh { triangles })
+        }
+    }
+
+    /// the corners of the mesh's axis-aligned bounding box
+    pub fn bounds(&self) -> (Vec3<f64>, Vec3<f64>) {
+        self.triangles.iter().flat_map(|t| t.iter()).fold(
+            (
+                Vec3::broadcast(f64::INFINITY),
+                Vec3::broadcast(f64::NEG_INFINITY),
+            ),
+            |(min, max), &v| (Vec3::partial_min(min, v), Vec3::partial_max(max, v)),
+        )
+    }
+
+    /// The signed distance from `pos` to the mesh, negative inside. Inside and outside are told
+    /// apart by the generalized winding number, so small holes are tolerated.
+    pub fn distance(&self, pos: Vec3<f64>) -> f64 {
+        let (dist, winding) =
+            self.triangles
+                .iter()
+                .fold((f64::INFINITY, 0.0), |(dist, winding), &[a, b, c]| {
+                    let closest = closest_on_triangle(pos, a, b, c);
+                    (
+                        dist.min(pos.distance(closest)),
+                        winding + solid_angle(a - pos, b - pos, c - pos),
+                    )
+                });
+        if (winding / (4.0 * std::f64::consts::PI)).abs() > 0.5 {
+            -dist
+        } else {
+            dist
+        }
+    }
+
+    /// The most samples `bake` takes along a side; more would take gigabytes
+    pub const MAX_RESOLUTION: usize = 512;
+
+    /// Samples the mesh's signed distance on a grid with `resolution` samples along the longest
+    /// side of its bounding box, up to `MAX_RESOLUTION`, plus a margin of two samples on each
+    /// side.
+    ///
+    /// Baking finds the nearest of every triangle for every sample, so it's slow for large
+    /// meshes; keep `resolution` around 64 unless fine detail is needed.
+    pub fn bake<T>(&self, resolution: usize) -> SdfGrid<T>
+    where
+        T: Float + Sum,
+    {
+        let (min, max) = self.bounds();
+        let extent = max - min;
+        let resolution = resolution.clamp(2, Self::MAX_RESOLUTION);
+        let spacing = extent.reduce_partial_max().max(1e-9) / (resolution - 1) as f64;
+        let margin = 2;
+        let origin = min - Vec3::broadcast(spacing * margin as f64);
+        let samples = extent.map(|e| (e / spacing).ceil() as usize + 1 + 2 * margin);
+        let mut distances = Vec::with_capacity(samples.product());
+        for z in 0..samples.z {
+            for y in 0..samples.y {
+                for x in 0..samples.x {
+                    let pos = origin + Vec3::new(x as f64, y as f64, z as f64) * spacing;
+                    distances.push(T::from(self.distance(pos)).unwrap());
+                }
+            }
+        }
+        SdfGrid::new(
+            origin.map(|c| T::from(c).unwrap()),
+            Vec3::broadcast(T::from(spacing).unwrap()),
+            samples,
+            Arc::new(distances),
+        )
+    }
+}
+
+/// the point of the triangle `abc` closest to `p`
+fn closest_on_triangle(p: Vec3<f64>, a: Vec3<f64>, b: Vec3<f64>, c: Vec3<f64>) -> Vec3<f64> {
+    // the Voronoi regions of the vertices, then the edges, then the face
+    let (ab, ac, ap) = (b - a, c - a, p - a);
+    let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+/// the signed solid angle of the triangle with corners `a`, `b`, and `c` relative to the origin,
+/// by Van Oosterom and Strackee's formula
+fn solid_angle(a: Vec3<f64>, b: Vec3<f64>, c: Vec3<f64>) -> f64 {
+    let (la, lb, lc) = (a.magnitude(), b.magnitude(), c.magnitude());
+    let numerator = a.dot(b.cross(c));
+    let denominator = la * lb * lc + a.dot(b) * lc + a.dot(c) * lb + b.dot(c) * la;
+    2.0 * numerator.atan2(denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use vek::Vec3;
+
+    use super::{Mesh, MeshError};
+    use crate::distance::Estimator;
+
+    /// a cube from -1 to 1, as quads
+    const CUBE: &str = "
+        v -1 -1 -1
+        v 1 -1 -1
+        v 1 1 -1
+        v -1 1 -1
+        v -1 -1 1
+        v 1 -1 1
+        v 1 1 1
+        v -1 1 1
+        f 1 4 3 2
+        f 5 6 7 8
+        f 1 2 6 5
+        f 3 4 8 7
+        f 1 5 8 4
+        f 2/1 3/2 7/3 6/4
+    ";
+
+    #[test]
+    fn obj_test() {
+        let cube = Mesh::parse_obj(CUBE).unwrap();
+        assert_eq!(cube.triangles.len(), 12);
+        assert_eq!(cube.bounds(), (Vec3::broadcast(-1.0), Vec3::broadcast(1.0)));
+        assert_eq!(cube.distance(Vec3::new(3.0, 0.0, 0.0)), 2.0);
+        assert_eq!(cube.distance(Vec3::new(0.0, 0.5, 0.0)), -0.5);
+        assert_eq!(cube.distance(Vec3::new(2.0, 2.0, 1.0)), 2.0f64.sqrt());
+
+        assert_eq!(
+            Mesh::parse_obj("v 0 0 0\nf 1 2 3"),
+            Err(MeshError::Syntax(2))
+        );
+        assert_eq!(Mesh::parse_obj("v 0 0 0"), Err(MeshError::Empty));
+        assert_eq!(
+            Mesh::parse_obj("v 0 0 0\nv 1 NaN 0\nv 0 1 0\nf 1 2 3"),
+            Err(MeshError::Syntax(2))
+        );
+    }
+
+    #[test]
+    fn stl_test() {
+        let ascii = "solid t
+            facet normal 0 0 1
+              outer loop
+                vertex 0 0 0
+                vertex 1 0 0
+                vertex 0 1 0
+              endloop
+            endfacet
+            endsolid t";
+        let mesh = Mesh::parse_stl(ascii.as_bytes()).unwrap();
+
+        let mut binary = vec![0; 80];
+        binary.extend_from_slice(&1u32.to_le_bytes());
+        for &f in &[
+            0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ] {
+            binary.extend_from_slice(&f.to_le_bytes());
+        }
+        binary.extend_from_slice(&[0, 0]);
+        assert_eq!(Mesh::parse_stl(&binary), Ok(mesh));
+        binary[84 + 12..84 + 16].copy_from_slice(&f32::INFINITY.to_le_bytes());
+        assert_eq!(Mesh::parse_stl(&binary), Err(MeshError::NonFinite(1)));
+    }
+
+    #[test]
+    fn bake_test() {
+        let cube = Mesh::parse_obj(CUBE).unwrap();
+        let grid = cube.bake::<f64>(9);
+        // at the samples, the grid is exact
+        assert_eq!(grid.estimate(Vec3::zero()), -1.0);
+        assert!((grid.estimate(Vec3::new(1.5, 0.0, 0.0)) - 0.5).abs() < 1e-9);
+        // outside the grid, the estimate stays below the true distance
+        let far = Vec3::new(10.0, 3.0, -2.0);
+        assert!(grid.estimate(far) > 0.0);
+        assert!(grid.estimate(far) <= cube.distance(far));
+    }
+}
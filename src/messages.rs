@@ -0,0 +1,397 @@
+//! User-facing messages. Each is looked up in the catalog for a `Locale`, so the command line
+//! and error reports can be translated; English is the default.
+use std::env;
+use std::str::FromStr;
+
+use crate::filename::Placeholder;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Locale {
+    En,
+    Es,
+}
+
+impl Locale {
+    /// the locale named by `LC_ALL`, `LC_MESSAGES`, or `LANG` (checked in that order, as with
+    /// gettext), or English if none of them names a known locale
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|val| !val.is_empty())
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(Locale::En)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ();
+
+    /// parses the language of a POSIX locale name like `es_MX.UTF-8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(&['_', '.', '-'][..]).next() {
+            Some("en") | Some("C") | Some("POSIX") => Ok(Locale::En),
+            Some("es") => Ok(Locale::Es),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A message, with any values it mentions
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message<'a> {
+    InvalidInt,
+    InvalidPositiveInt,
+    InvalidNonNegativeInt,
+    InvalidIntRange {
+        start: i32,
+        end: i32,
+    },
+    InvalidFloat,
+    InvalidPositiveFloat,
+    InvalidStrftime,
+    UnknownPlaceholder(&'a str),
+    UnbalancedBraces,
+    InvalidPadding(&'a str),
+    EmptyFrameRange {
+        start: usize,
+        end: usize,
+    },
+    UnnamedFrames,
+    UnknownRender {
+        index: usize,
+        count: usize,
+    },
+    PixelOutside {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    UnknownErrorFormat(&'a str),
+    UnknownMaterial(&'a str),
+    UnknownCamera(&'a str),
+    UnknownRig(&'a str),
+    ColorParse(&'a str),
+    FocusPointMissed(&'a str),
+    SurfaceMissed(&'a str),
+    FoveationFalloff(&'a str),
+    NoEmbeddedScene(&'a str),
+    NoHistoryPath,
+    UnusedMaterial(&'a str),
+    UnusedCamera(&'a str),
+    DenormalizedRotation {
+        geometry: usize,
+        magnitude: f64,
+    },
+    UnknownOutputFormat(&'a str),
+    FloatUnsupported(&'a str),
+    LayersUnsupported(&'a str),
+    EmptyImage,
+    ImageTooLarge {
+        width: usize,
+        height: usize,
+    },
+    SideLimit {
+        format: &'a str,
+        max: u64,
+    },
+    AreaLimit {
+        format: &'a str,
+        max: u64,
+    },
+    NonFiniteEstimate {
+        x: f64,
+        y: f64,
+        z: f64,
+        dist: f64,
+    },
+    PriorityUnchanged,
+}
+
+impl<'a> Message<'a> {
+    /// the message's text in `locale`
+    pub fn text(&self, locale: Locale) -> String {
+        match locale {
+            Locale::En => self.en(),
+            Locale::Es => self.es(),
+        }
+    }
+
+    fn en(&self) -> String {
+        match self {
+            Message::InvalidInt => "Must be valid integer".to_string(),
+            Message::InvalidPositiveInt => "Must be valid integer > 0".to_string(),
+            Message::InvalidNonNegativeInt => "Must be valid integer >= 0".to_string(),
+            Message::InvalidIntRange { start, end } => {
+                format!("Must be a valid integer between {} and {}", start, end)
+            }
+            Message::InvalidFloat => "Must be valid floating point number".to_string(),
+            Message::InvalidPositiveFloat => "Must be valid floating point number > 0".to_string(),
+            Message::InvalidStrftime => {
+                "Must be a valid format string; see chrono::format::strftime docs".to_string()
+            }
+            Message::UnknownPlaceholder(p) => format!(
+                "Unknown placeholder {{{}}}; expected one of {}",
+                p,
+                placeholder_names()
+            ),
+            Message::UnbalancedBraces => {
+                "Every { must be closed by a }; write {{ or }} for a literal brace".to_string()
+            }
+            Message::InvalidPadding(p) => format!(
+                "Invalid padding in {{{}}}; numbers can be zero-padded to at most 20 digits like {{frame:04}}",
+                p
+            ),
+            Message::EmptyFrameRange { start, end } => format!(
+                "The frame range {} to {} is empty; --frame-end must be at least --frame-start",
+                start, end
+            ),
+            Message::UnnamedFrames => "Rendering more than one frame needs a {frame} placeholder \
+                                       in the output filename, or every frame would overwrite \
+                                       the last"
+                .to_string(),
+            Message::UnknownRender { index, count } => format!(
+                "There's no render {}; the scene has {} renders, numbered from 0",
+                index, count
+            ),
+            Message::PixelOutside {
+                x,
+                y,
+                width,
+                height,
+            } => format!(
+                "Pixel ({}, {}) is outside the {}x{} render",
+                x, y, width, height
+            ),
+            Message::UnknownErrorFormat(f) => {
+                format!("Unknown error format {}; expected text or json", f)
+            }
+            Message::UnknownMaterial(m) => format!("Unknown material {}", m),
+            Message::UnknownCamera(c) => format!("Unknown camera {}", c),
+            Message::UnknownRig(r) => format!("Unknown rig {}", r),
+            Message::ColorParse(c) => format!("Couldn't parse color {}", c),
+            Message::FocusPointMissed(c) => {
+                format!("Camera {}'s focus point doesn't hit any geometry", c)
+            }
+            Message::SurfaceMissed(c) => format!(
+                "Camera {} can't be placed above a surface; its ray doesn't hit any geometry",
+                c
+            ),
+            Message::FoveationFalloff(c) => format!(
+                "The foveation of the render through camera {} needs a falloff greater than 0",
+                c
+            ),
+            Message::NoEmbeddedScene(i) => {
+                format!("{} has no embedded scene and no .yml file alongside it", i)
+            }
+            Message::NoHistoryPath => {
+                "Can't find where to keep the render history; set XDG_DATA_HOME or HOME".to_string()
+            }
+            Message::UnusedMaterial(m) => format!("Material {} isn't used by any geometry", m),
+            Message::UnusedCamera(c) => format!("Camera {} isn't used by any render", c),
+            Message::DenormalizedRotation {
+                geometry,
+                magnitude,
+            } => format!(
+                "Geometry {} has a rotation quaternion of magnitude {}; it was normalized",
+                geometry, magnitude
+            ),
+            Message::UnknownOutputFormat(p) => {
+                format!(
+                    "Can't tell the image format of {}; expected .png, .exr, .tif, or .webp",
+                    p
+                )
+            }
+            Message::FloatUnsupported(f) => format!(
+                "Float output needs an EXR file; .{} only stores integer color",
+                f
+            ),
+            Message::LayersUnsupported(f) => format!(
+                ".{} files can't hold AOV layers; use an EXR file or write them to separate files",
+                f
+            ),
+            Message::EmptyImage => "Images must be at least 1 pixel on each side".to_string(),
+            Message::ImageTooLarge { width, height } => format!(
+                "A {}×{} image is too large to hold in memory on this platform",
+                width, height
+            ),
+            Message::SideLimit { format, max } => {
+                format!(".{} images can be at most {} pixels on a side", format, max)
+            }
+            Message::AreaLimit { format, max } => {
+                format!(".{} images can have at most {} pixels", format, max)
+            }
+            Message::NonFiniteEstimate { x, y, z, dist } => format!(
+                "The safe-mode render got a distance estimate of {} at ({}, {}, {})",
+                dist, x, y, z
+            ),
+            Message::PriorityUnchanged => "Couldn't lower the render's scheduling priority; \
+                                           it will run at normal priority"
+                .to_string(),
+        }
+    }
+
+    fn es(&self) -> String {
+        match self {
+            Message::InvalidInt => "Debe ser un número entero válido".to_string(),
+            Message::InvalidPositiveInt => "Debe ser un número entero válido > 0".to_string(),
+            Message::InvalidNonNegativeInt => "Debe ser un número entero válido >= 0".to_string(),
+            Message::InvalidIntRange { start, end } => {
+                format!("Debe ser un número entero válido entre {} y {}", start, end)
+            }
+            Message::InvalidFloat => "Debe ser un número de punto flotante válido".to_string(),
+            Message::InvalidPositiveFloat => {
+                "Debe ser un número de punto flotante válido > 0".to_string()
+            }
+            Message::InvalidStrftime => "Debe ser una cadena de formato válida; \
+                                         consulte la documentación de chrono::format::strftime"
+                .to_string(),
+            Message::UnknownPlaceholder(p) => format!(
+                "Marcador desconocido {{{}}}; se esperaba uno de {}",
+                p,
+                placeholder_names()
+            ),
+            Message::UnbalancedBraces => {
+                "Cada { debe cerrarse con una }; escriba {{ o }} para una llave literal".to_string()
+            }
+            Message::InvalidPadding(p) => format!(
+                "Relleno no válido en {{{}}}; los números se pueden rellenar con ceros hasta 20 dígitos como {{frame:04}}",
+                p
+            ),
+            Message::EmptyFrameRange { start, end } => format!(
+                "El rango de fotogramas de {} a {} está vacío; --frame-end debe ser al menos \
+                 --frame-start",
+                start, end
+            ),
+            Message::UnnamedFrames => "Renderizar más de un fotograma requiere un marcador \
+                                       {frame} en el nombre del archivo de salida, o cada \
+                                       fotograma sobrescribiría el anterior"
+                .to_string(),
+            Message::UnknownRender { index, count } => format!(
+                "No existe el render {}; la escena tiene {} renders, numerados desde 0",
+                index, count
+            ),
+            Message::PixelOutside {
+                x,
+                y,
+                width,
+                height,
+            } => format!(
+                "El píxel ({}, {}) está fuera del render de {}x{}",
+                x, y, width, height
+            ),
+            Message::UnknownErrorFormat(f) => {
+                format!(
+                    "Formato de error desconocido {}; se esperaba text o json",
+                    f
+                )
+            }
+            Message::UnknownMaterial(m) => format!("Material desconocido {}", m),
+            Message::UnknownCamera(c) => format!("Cámara desconocida {}", c),
+            Message::UnknownRig(r) => format!("Rig desconocido {}", r),
+            Message::ColorParse(c) => format!("No se pudo interpretar el color {}", c),
+            Message::FocusPointMissed(c) => format!(
+                "El punto de enfoque de la cámara {} no alcanza ninguna geometría",
+                c
+            ),
+            Message::SurfaceMissed(c) => format!(
+                "La cámara {} no se puede colocar sobre una superficie; \
+                 su rayo no alcanza ninguna geometría",
+                c
+            ),
+            Message::FoveationFalloff(c) => format!(
+                "La foveación del render a través de la cámara {} necesita una caída mayor que 0",
+                c
+            ),
+            Message::NoEmbeddedScene(i) => format!(
+                "{} no tiene una escena incrustada ni un archivo .yml junto a ella",
+                i
+            ),
+            Message::NoHistoryPath => {
+                "No se encuentra dónde guardar el historial de renders; defina XDG_DATA_HOME o HOME"
+                    .to_string()
+            }
+            Message::UnusedMaterial(m) => {
+                format!("Ninguna geometría usa el material {}", m)
+            }
+            Message::UnusedCamera(c) => format!("Ningún render usa la cámara {}", c),
+            Message::DenormalizedRotation {
+                geometry,
+                magnitude,
+            } => format!(
+                "La geometría {} tiene un cuaternión de rotación de magnitud {}; se normalizó",
+                geometry, magnitude
+            ),
+            Message::UnknownOutputFormat(p) => format!(
+                "No se puede determinar el formato de imagen de {}; se esperaba .png, .exr, .tif o .webp",
+                p
+            ),
+            Message::FloatUnsupported(f) => format!(
+                "La salida de punto flotante requiere un archivo EXR; .{} solo guarda color entero",
+                f
+            ),
+            Message::LayersUnsupported(f) => format!(
+                "Los archivos .{} no pueden contener capas AOV; use un archivo EXR o escríbalas en archivos separados",
+                f
+            ),
+            Message::EmptyImage => {
+                "Las imágenes deben tener al menos 1 píxel por lado".to_string()
+            }
+            Message::ImageTooLarge { width, height } => format!(
+                "Una imagen de {}×{} es demasiado grande para la memoria de esta plataforma",
+                width, height
+            ),
+            Message::SideLimit { format, max } => format!(
+                "Las imágenes .{} pueden tener como máximo {} píxeles por lado",
+                format, max
+            ),
+            Message::AreaLimit { format, max } => format!(
+                "Las imágenes .{} pueden tener como máximo {} píxeles",
+                format, max
+            ),
+            Message::NonFiniteEstimate { x, y, z, dist } => format!(
+                "El render en modo seguro obtuvo una estimación de distancia de {} en ({}, {}, {})",
+                dist, x, y, z
+            ),
+            Message::PriorityUnchanged => "No se pudo bajar la prioridad de planificación del \
+                                           render; se ejecutará con prioridad normal"
+                .to_string(),
+        }
+    }
+}
+
+/// the names of every filename placeholder, comma-separated
+fn placeholder_names() -> String {
+    Placeholder::ALL
+        .iter()
+        .map(|p| p.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locale, Message};
+
+    #[test]
+    fn locale_parse_test() {
+        assert_eq!("es_MX.UTF-8".parse(), Ok(Locale::Es));
+        assert_eq!("en_US".parse(), Ok(Locale::En));
+        assert_eq!("C".parse(), Ok(Locale::En));
+        assert_eq!("fr_FR".parse::<Locale>(), Err(()));
+    }
+
+    #[test]
+    fn message_text_test() {
+        let msg = Message::InvalidIntRange { start: 1, end: 8 };
+        assert_eq!(
+            msg.text(Locale::En),
+            "Must be a valid integer between 1 and 8"
+        );
+        assert_eq!(
+            msg.text(Locale::Es),
+            "Debe ser un número entero válido entre 1 y 8"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Procedural noise for adding surface detail.
+use num::Float;
+use vek::Vec3;
+
+/// An upper bound on how fast `value_noise` can change: each axis's smoothstep interpolation
+/// has a slope of at most 1.5 across a range of 2
+pub const VALUE_NOISE_LIPSCHITZ: f64 = 3.0 * 1.732_050_808;
+
+/// a pseudorandom value from -1 to 1 for the lattice point (x, y, z)
+fn lattice<T: Float>(x: i64, y: i64, z: i64, seed: u32) -> T {
+    // a splitmix64-style finalizer over the mixed coordinates
+    let mut h = u64::from(seed)
+        ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+        ^ (z as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+    h ^= h >> 30;
+    h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h ^= h >> 27;
+    h = h.wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^= h >> 31;
+    // the top 53 bits, as a float from 0 to 1
+    let unit = (h >> 11) as f64 / (1u64 << 53) as f64;
+    T::from(unit * 2.0 - 1.0).unwrap()
+}
+
+/// 3D value noise from -1 to 1 with features about 1 unit across; `seed` picks one of many
+/// unrelated noise fields
+pub fn value_noise<T: Float>(pos: Vec3<T>, seed: u32) -> T {
+    let cell = pos.map(|c| c.floor());
+    // position within the cell, eased so the noise is smooth across cell boundaries
+    let t = (pos - cell).map(|c| c * c * (T::from(3).unwrap() - T::from(2).unwrap() * c));
+    let cell = cell.map(|c| c.to_i64().unwrap_or(0));
+
+    let lerp = |a: T, b: T, t: T| a + (b - a) * t;
+    let corner = |dx, dy, dz| lattice::<T>(cell.x + dx, cell.y + dy, cell.z + dz, seed);
+    let face = |dz| {
+        lerp(
+            lerp(corner(0, 0, dz), corner(1, 0, dz), t.x),
+            lerp(corner(0, 1, dz), corner(1, 1, dz), t.x),
+            t.y,
+        )
+    };
+    lerp(face(0), face(1), t.z)
+}
+
+/// Fractal Brownian motion: `octaves` layers of `value_noise`, each twice the frequency and half
+/// the amplitude of the last, scaled back to -1 to 1
+pub fn fbm<T: Float>(pos: Vec3<T>, octaves: usize, seed: u32) -> T {
+    let two = T::from(2).unwrap();
+    let (sum, _, _, total) = (0..octaves).fold(
+        (T::zero(), pos, T::one(), T::zero()),
+        |(sum, pos, amplitude, total), octave| {
+            let noise = value_noise(pos, seed.wrapping_add(octave as u32));
+            (
+                sum + noise * amplitude,
+                pos * two,
+                amplitude / two,
+                total + amplitude,
+            )
+        },
+    );
+    if total > T::zero() {
+        sum / total
+    } else {
+        T::zero()
+    }
+}
+
+/// An upper bound on how fast `fbm` with `octaves` octaves can change: each octave's halved
+/// amplitude cancels its doubled frequency, so every octave adds the same slope
+pub fn fbm_lipschitz(octaves: usize) -> f64 {
+    let total: f64 = (0..octaves).map(|octave| 0.5f64.powi(octave as i32)).sum();
+    if total > 0.0 {
+        VALUE_NOISE_LIPSCHITZ * octaves as f64 / total
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vek::Vec3;
+
+    use super::{fbm, fbm_lipschitz, value_noise, VALUE_NOISE_LIPSCHITZ};
+
+    #[test]
+    fn value_noise_test() {
+        let points: Vec<Vec3<f64>> = (0..200)
+            .map(|i| {
+                let i = f64::from(i);
+                Vec3::new(i * 0.37, -i * 0.11, i * 0.23 - 7.0)
+            })
+            .collect();
+        for &p in &points {
+            let n = value_noise(p, 1);
+            assert!((-1.0..=1.0).contains(&n));
+            // the noise never changes faster than its Lipschitz bound
+            let step = Vec3::new(0.01, -0.02, 0.015);
+            let diff = (value_noise(p + step, 1) - n).abs();
+            assert!(diff <= VALUE_NOISE_LIPSCHITZ * step.magnitude());
+        }
+        // the same seed gives the same field; a different one doesn't
+        assert_eq!(value_noise(points[5], 1), value_noise(points[5], 1));
+        assert_ne!(value_noise(points[5], 1), value_noise(points[5], 2));
+    }
+
+    #[test]
+    fn fbm_test() {
+        let lipschitz = fbm_lipschitz(5);
+        for i in 0..200 {
+            let i = f64::from(i);
+            let p = Vec3::new(i * 0.29, 1.5, -i * 0.17);
+            let n = fbm(p, 5, 3);
+            assert!((-1.0..=1.0).contains(&n));
+            let step = Vec3::new(0.003, 0.0, -0.002);
+            assert!((fbm(p + step, 5, 3) - n).abs() <= lipschitz * step.magnitude());
+        }
+        // one octave is plain value noise
+        let p = Vec3::new(0.3, 0.6, 0.9);
+        assert_eq!(fbm(p, 1, 3), value_noise(p, 3));
+    }
+}
@@ -0,0 +1,309 @@
+//! Wireframes drawn over a render for debugging a scene's setup: which way the lights point,
+//! where other cameras are looking, and where geometry's bounding volumes are.
+use std::iter::Sum;
+
+use num::Float;
+use vek::{Extent2, Vec2, Vec3, Vec4};
+
+use crate::camera::Viewport;
+use crate::distance::GeometryEstimator;
+use crate::img;
+use crate::light::Light;
+use crate::render::{self, RenderGeometry};
+
+/// A line segment in world space
+pub type Segment<T> = (Vec3<T>, Vec3<T>);
+
+/// A line `length` long from `anchor` toward each light
+pub fn light_gizmos<T, C>(lights: &[Light<T, C>], anchor: Vec3<T>, length: T) -> Vec<Segment<T>>
+where
+    T: Float + Sum + Default,
+    C: Default + Clone,
+{
+    lights
+        .iter()
+        .map(|light| (anchor, anchor + light.rot.normalized() * length))
+        .collect()
+}
+
+/// The edges of the volume `view` renders, from the viewport out to `far` units along its rays
+pub fn frustum<T>(view: &Viewport<T>, far: T) -> Vec<Segment<T>>
+where
+    T: Float + Sum + Default,
+{
+    let (zero, one) = (T::zero(), T::one());
+    let corners: Vec<(Vec3<T>, Vec3<T>)> = [(zero, zero), (one, zero), (one, one), (zero, one)]
+        .iter()
+        .map(|&(x, y)| {
+            let (pos, rot) = view.ray(Vec2::new(x, y));
+            (pos, pos + rot * far)
+        })
+        .collect();
+    (0..4)
+        .flat_map(|i| {
+            let (near, far) = corners[i];
+            let (next_near, next_far) = corners[(i + 1) % 4];
+            vec![(near, next_near), (far, next_far), (near, far)]
+        })
+        .collect()
+}
+
+/// The 12 edges of an axis-aligned box
+pub fn box_wireframe<T>(center: Vec3<T>, half_size: Vec3<T>) -> Vec<Segment<T>>
+where
+    T: Float,
+{
+    let corner = |i: usize| {
+        let sign = |bit: usize| if i & bit == 0 { -T::one() } else { T::one() };
+        center + half_size * Vec3::new(sign(1), sign(2), sign(4))
+    };
+    // each edge joins two corners whose indices differ in one bit
+    (0..8)
+        .flat_map(|i| {
+            [1, 2, 4]
+                .iter()
+                .filter(move |&&bit| i & bit == 0)
+                .map(move |&bit| (corner(i), corner(i | bit)))
+        })
+        .collect()
+}
+
+/// Circles around a sphere in each of the three axis planes, each made of `segments` segments
+pub fn sphere_wireframe<T>(center: Vec3<T>, radius: T, segments: usize) -> Vec<Segment<T>>
+where
+    T: Float,
+{
+    let tau = T::from(2.0 * std::f64::consts::PI).unwrap();
+    let point = |axis: usize, i: usize| {
+        let angle = tau * T::from(i).unwrap() / T::from(segments).unwrap();
+        let (sin, cos) = (angle.sin() * radius, angle.cos() * radius);
+        center
+            + match axis {
+                0 => Vec3::new(T::zero(), cos, sin),
+                1 => Vec3::new(cos, T::zero(), sin),
+                _ => Vec3::new(cos, sin, T::zero()),
+            }
+    };
+    (0..3)
+        .flat_map(|axis| (0..segments).map(move |i| (point(axis, i), point(axis, i + 1))))
+        .collect()
+}
+
+/// Lines every `spacing` units on the ground (y = 0) plane, out to `extent` units from the
+/// origin in x and z
+pub fn ground_grid<T>(extent: T, spacing: T) -> Vec<Segment<T>>
+where
+    T: Float,
+{
+    let lines = (extent / spacing).floor().to_i64().unwrap_or(0);
+    (-lines..=lines)
+        .flat_map(|i| {
+            let offset = spacing * T::from(i).unwrap();
+            vec![
+                (
+                    Vec3::new(offset, T::zero(), -extent),
+                    Vec3::new(offset, T::zero(), extent),
+                ),
+                (
+                    Vec3::new(-extent, T::zero(), offset),
+                    Vec3::new(extent, T::zero(), offset),
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// The x, y, and z axes from the origin, `length` units long, with the conventional red, green,
+/// and blue colors to draw each in
+pub fn axes<T>(length: T) -> [(Segment<T>, Vec4<T>); 3]
+where
+    T: Float,
+{
+    let (zero, one) = (T::zero(), T::one());
+    let axis = |dir: Vec3<T>| (Vec3::zero(), dir * length);
+    [
+        (axis(Vec3::unit_x()), Vec4::new(one, zero, zero, one)),
+        (axis(Vec3::unit_y()), Vec4::new(zero, one, zero, one)),
+        (axis(Vec3::unit_z()), Vec4::new(zero, zero, one, one)),
+    ]
+}
+
+/// Wireframes of the bounding volumes of `de`, for bounds which are spheres or boxes
+pub fn bounds<T>(de: &GeometryEstimator<T>) -> Vec<Segment<T>>
+where
+    T: Float + Sum,
+{
+    match de {
+        GeometryEstimator::Bounded(bounded) => match &*bounded.bound {
+            GeometryEstimator::Sphere(sphere) => sphere_wireframe(sphere.center, sphere.radius, 32),
+            GeometryEstimator::Cuboid(cuboid) => box_wireframe(cuboid.center, cuboid.half_size),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Draws `segments` as seen from `view` over `color`, an image of size `size`, in `line`'s color
+/// (blended by its alpha); parts of segments behind the viewport are cut off.
+pub fn draw<T>(
+    color: &mut [Vec4<T>],
+    size: Extent2<usize>,
+    view: &Viewport<T>,
+    segments: &[Segment<T>],
+    line: Vec4<T>,
+) where
+    T: Float + Sum + Default,
+{
+    let eye = view.cam.origin - view.cam.direction * view.focal_len;
+    let depth = |p: Vec3<T>| (p - eye).dot(view.cam.direction) - view.focal_len;
+    // from the viewport's 0 to 1 locations to pixel coordinates; see `render::pixel_location`
+    let half = T::from(0.5).unwrap();
+    let to_pixel = |loc: Vec2<T>| {
+        Vec2::new(
+            loc.x * T::from(size.w).unwrap() - half,
+            (T::one() - loc.y) * T::from(size.h).unwrap() - half,
+        )
+    };
+
+    for &(a, b) in segments {
+        let (da, db) = (depth(a), depth(b));
+        if da < T::zero() && db < T::zero() {
+            continue;
+        }
+        // clip to the viewport plane, nudged forward so the clipped end still projects
+        let nudge = view.focal_len * T::from(1e-6).unwrap();
+        let clip = |inside: Vec3<T>, d_in: T, outside: Vec3<T>, d_out: T| {
+            inside + (outside - inside) * ((d_in - nudge) / (d_in - d_out))
+        };
+        let (a, b) = if da < T::zero() {
+            (clip(b, db, a, da), b)
+        } else if db < T::zero() {
+            (a, clip(a, da, b, db))
+        } else {
+            (a, b)
+        };
+        if let (Some(pa), Some(pb)) = (view.project(a), view.project(b)) {
+            img::draw_line(color, size.w, to_pixel(pa), to_pixel(pb), line);
+        }
+    }
+}
+
+/// grid lines `draw_grid` draws each way from the origin
+const GRID_LINES: usize = 10;
+
+/// Draws a faint grid on the ground (y = 0) plane over `color`, an image of size `size` seen
+/// through `view`, with lines `spacing` units apart out to `GRID_LINES` from the origin, and the
+/// x, y, and z axes as far out in red, green, and blue over it
+pub fn draw_grid<T>(color: &mut [Vec4<T>], size: Extent2<usize>, view: &Viewport<T>, spacing: T)
+where
+    T: Float + Sum + Default,
+{
+    let extent = spacing * T::from(GRID_LINES).unwrap();
+    let faint = T::from(0.25).unwrap();
+    let grid = ground_grid(extent, spacing);
+    draw(
+        color,
+        size,
+        view,
+        &grid,
+        Vec4::new(T::one(), T::one(), T::one(), faint),
+    );
+    for &(axis, line) in axes(extent).iter() {
+        draw(color, size, view, &[axis], line);
+    }
+}
+
+/// Draws the setup of a scene over `color`, an image of size `size` seen through `view`: the
+/// directions of `lights` from the center of `geometry` in yellow, the frustums of the `others`
+/// views in cyan, and the bounding volumes of `geometry` in magenta. The gizmos are scaled to the
+/// distance from the camera to the center, so they show at any zoom.
+pub fn draw_setup<T, C>(
+    color: &mut [Vec4<T>],
+    size: Extent2<usize>,
+    view: &Viewport<T>,
+    geometry: &[RenderGeometry<T>],
+    lights: &[Light<T, C>],
+    others: &[Viewport<T>],
+) where
+    T: Float + Sum + Default,
+    C: Default + Clone,
+{
+    let (zero, one) = (T::zero(), T::one());
+    // from as far out as the camera, as the turntable finds its center
+    let reach = view.cam.origin.magnitude().max(one);
+    let center = render::bounding_center(geometry, reach);
+    let scale = (center - view.cam.origin).magnitude().max(one);
+    let quarter = T::from(0.25).unwrap();
+    let lights = light_gizmos(lights, center, scale * quarter);
+    draw(color, size, view, &lights, Vec4::new(one, one, zero, one));
+    let frustums: Vec<Segment<T>> = others
+        .iter()
+        .flat_map(|other| frustum(other, scale))
+        .collect();
+    draw(color, size, view, &frustums, Vec4::new(zero, one, one, one));
+    let bounds: Vec<Segment<T>> = geometry.iter().flat_map(|g| bounds(&g.geom.de)).collect();
+    draw(color, size, view, &bounds, Vec4::new(one, zero, one, one));
+}
+
+#[cfg(test)]
+mod tests {
+    use vek::{Extent2, Ray, Vec2, Vec3, Vec4};
+
+    use super::{axes, box_wireframe, draw, draw_grid, frustum, ground_grid};
+    use crate::camera::Viewport;
+
+    fn view() -> Viewport<f64> {
+        Viewport {
+            cam: Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::unit_z()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 1.0,
+            focus_dist: None,
+        }
+    }
+
+    #[test]
+    fn project_test() {
+        let view = view();
+        let location = Vec2::new(0.2, 0.9);
+        let (pos, rot) = view.ray(location);
+        let projected = view.project(pos + rot * 3.0).unwrap();
+        assert!((projected - location).magnitude() < 1e-9);
+        assert_eq!(view.project(Vec3::new(0.0, 0.0, -10.0)), None);
+    }
+
+    #[test]
+    fn wireframe_test() {
+        let edges = box_wireframe(Vec3::<f64>::zero(), Vec3::one());
+        assert_eq!(edges.len(), 12);
+        assert!(edges.iter().all(|(a, b)| (*a - *b).magnitude() == 2.0));
+        assert_eq!(frustum(&view(), 10.0).len(), 12);
+        // 5 lines each way, from -2 to 2
+        let grid = ground_grid(2.5, 1.0);
+        assert_eq!(grid.len(), 10);
+        assert!(grid.iter().all(|(a, b)| a.y == 0.0 && b.y == 0.0));
+        assert_eq!(axes(2.0)[1].0, (Vec3::zero(), Vec3::new(0.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn draw_test() {
+        let size = Extent2::new(9, 9);
+        let mut color = vec![Vec4::zero(); size.w * size.h];
+        let red = Vec4::new(1.0, 0.0, 0.0, 1.0);
+        // a vertical line through the middle of the view, running from behind the camera
+        let segment = (Vec3::new(0.0, -1.0, -20.0), Vec3::new(0.0, 1.0, 20.0));
+        draw(&mut color, size, &view(), &[segment], red);
+        assert_eq!(color[4 * size.w + 4], red);
+        assert_eq!(color[4 * size.w], Vec4::zero());
+
+        // looking straight down the z axis, so the ground is edge on behind the x axis, and the z
+        // axis is a dot in the middle
+        let mut color = vec![Vec4::zero(); size.w * size.h];
+        draw_grid(&mut color, size, &view(), 0.5);
+        assert_eq!(color[4 * size.w + 6], red);
+        assert_eq!(color[6 * size.w + 4], Vec4::new(0.0, 1.0, 0.0, 1.0));
+        assert_eq!(color[4 * size.w + 4], Vec4::new(0.0, 0.0, 1.0, 1.0));
+        // with the grid's lines all on top of each other left of the origin
+        assert!(color[4 * size.w].x > 0.0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Quaternion and triplex math for fractal iteration formulas.
+//!
+//! vek's `Quaternion` is built for rotations; the fractal estimators mostly need arithmetic on
+//! arbitrary (non-unit) quaternions, so those formulas live here.
+use std::iter::Sum;
+
+use num::Float;
+use vek::{Quaternion, Vec3};
+
+/// q², computed directly rather than through the general product:
+/// (w + v)² = w² − |v|² + 2wv
+pub fn square<T>(q: Quaternion<T>) -> Quaternion<T>
+where
+    T: Float + Sum,
+{
+    let (w, v) = q.into_scalar_and_vec3();
+    let t2 = T::from(2).unwrap();
+    Quaternion::from_scalar_and_vec3((w * w - v.magnitude_squared(), v * (t2 * w)))
+}
+
+/// qⁿ for a non-negative integer n, by repeated squaring
+pub fn powi<T>(q: Quaternion<T>, n: u32) -> Quaternion<T>
+where
+    T: Float + Sum,
+{
+    let mut result = Quaternion::identity();
+    let mut base = q;
+    let mut n = n;
+    while n > 0 {
+        if n & 1 == 1 {
+            result = result * base;
+        }
+        base = square(base);
+        n >>= 1;
+    }
+    result
+}
+
+/// qᵖ for a real power p, in polar form: for q = |q| (cos θ + n sin θ) with n a unit pure
+/// quaternion, qᵖ = |q|ᵖ (cos pθ + n sin pθ). Integer powers are computed with `powi` instead,
+/// which is exact and faster.
+pub fn powf<T>(q: Quaternion<T>, p: T) -> Quaternion<T>
+where
+    T: Float + Sum,
+{
+    if p.fract() == T::zero() && p >= T::zero() {
+        if let Some(n) = p.to_u32() {
+            return powi(q, n);
+        }
+    }
+    let r = q.magnitude();
+    if r == T::zero() {
+        return q;
+    }
+    let (w, v) = q.into_scalar_and_vec3();
+    let v_len = v.magnitude();
+    let theta = v_len.atan2(w) * p;
+    // a real q has no axis, so its powers are kept real
+    let n = if v_len == T::zero() { v } else { v / v_len };
+    Quaternion::from_scalar_and_vec3((theta.cos(), n * theta.sin())) * r.powf(p)
+}
+
+/// `v` rotated by the unit quaternion `q` (q v q*)
+pub fn rotate<T>(q: Quaternion<T>, v: Vec3<T>) -> Vec3<T>
+where
+    T: Float + Sum,
+{
+    let p = Quaternion::from_scalar_and_vec3((T::zero(), v));
+    (q * p * q.conjugate()).into()
+}
+
+/// Spherical interpolation between the unit quaternions `from` and `to` by `factor` from 0 to 1,
+/// taking the shorter of the two arcs between them
+pub fn slerp<T>(from: Quaternion<T>, to: Quaternion<T>, factor: T) -> Quaternion<T>
+where
+    T: Float + Sum,
+{
+    let mut to = to;
+    let mut cos = from.dot(to);
+    if cos < T::zero() {
+        to = to * -T::one();
+        cos = -cos;
+    }
+    // nearly parallel; sin θ is too small to divide by, and a lerp is indistinguishable
+    if cos > T::from(0.9995).unwrap() {
+        return (from * (T::one() - factor) + to * factor).normalized();
+    }
+    let theta = cos.acos();
+    let sin = theta.sin();
+    from * (((T::one() - factor) * theta).sin() / sin) + to * ((factor * theta).sin() / sin)
+}
+
+/// The "triplex" power of a 3D point used by the Mandelbulb: the point's spherical coordinates
+/// with its radius raised to `n` and its angles multiplied by `n`
+pub fn triplex_pow<T>(v: Vec3<T>, n: T) -> Vec3<T>
+where
+    T: Float + Sum,
+{
+    let r = v.magnitude();
+    if r == T::zero() {
+        return v;
+    }
+    let theta = (v.z / r).acos() * n;
+    let phi = v.y.atan2(v.x) * n;
+    Vec3::new(
+        theta.sin() * phi.cos(),
+        theta.sin() * phi.sin(),
+        theta.cos(),
+    ) * r.powf(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use vek::{Quaternion, Vec3};
+
+    use super::{powf, powi, rotate, slerp, square, triplex_pow};
+
+    fn assert_close(a: Quaternion<f64>, b: Quaternion<f64>) {
+        assert!(
+            (a - b).magnitude() < 1e-9,
+            "{:?} is not close to {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn square_test() {
+        let q = Quaternion::from_xyzw(0.3, -1.2, 0.5, 2.0);
+        assert_close(square(q), q * q);
+    }
+
+    #[test]
+    fn powi_test() {
+        let q = Quaternion::from_xyzw(0.3, -1.2, 0.5, 2.0);
+        assert_close(powi(q, 0), Quaternion::identity());
+        assert_close(powi(q, 1), q);
+        assert_close(powi(q, 3), q * q * q);
+        assert_close(powi(q, 4), q * q * q * q);
+    }
+
+    #[test]
+    fn powf_test() {
+        let q = Quaternion::from_xyzw(0.3, -1.2, 0.5, 2.0);
+        assert_close(powf(q, 3.0), q * q * q);
+        // the polar form agrees with repeated multiplication
+        let half = powf(q, 0.5);
+        assert_close(half * half, q);
+        let q = Quaternion::from_xyzw(0.3, -1.2, 0.5, -2.0);
+        assert_close(powf(q, 2.5) * powf(q, 0.5), q * q * q);
+    }
+
+    #[test]
+    fn rotate_test() {
+        let q = Quaternion::rotation_z(PI / 2.0);
+        let v = rotate(q, Vec3::unit_x());
+        assert!((v - Vec3::unit_y()).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn slerp_test() {
+        let a = Quaternion::identity();
+        let b = Quaternion::rotation_z(PI / 2.0);
+        assert_close(slerp(a, b, 0.0), a);
+        assert_close(slerp(a, b, 1.0), b);
+        assert_close(slerp(a, b, 0.5), Quaternion::rotation_z(PI / 4.0));
+        // -b is the same rotation as b; the shorter arc still ends at the same rotation
+        assert_close(slerp(a, b * -1.0, 0.5), Quaternion::rotation_z(PI / 4.0));
+    }
+
+    #[test]
+    fn triplex_pow_test() {
+        // a power of 1 leaves the point where it is
+        let v: Vec3<f64> = Vec3::new(0.3, -0.4, 0.5);
+        assert!((triplex_pow(v, 1.0) - v).magnitude() < 1e-9);
+        // the radius is raised to the power
+        assert!((triplex_pow(v, 8.0).magnitude() - v.magnitude().powi(8)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,156 @@
+//! Randomizing a scene's look (materials and light colors) while keeping its geometry and
+//! cameras, for quickly exploring variations of a fixed composition.
+use std::iter::Sum;
+use std::str::FromStr;
+
+use num::Float;
+use palette::{Hsv, RgbHue, Srgb};
+
+use crate::serialize::Scene;
+
+/// A part of a scene's look which can be randomized or locked
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aspect {
+    /// every material's specular, diffuse, and ambient coefficients and shininess
+    Materials,
+    /// every light's specular, diffuse, and ambient colors
+    Lights,
+}
+
+impl Aspect {
+    pub const ALL: [Aspect; 2] = [Aspect::Materials, Aspect::Lights];
+}
+
+impl FromStr for Aspect {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "materials" => Ok(Aspect::Materials),
+            "lights" => Ok(Aspect::Lights),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A small seeded pseudorandom generator (splitmix64); the same seed always gives the same
+/// sequence, so a randomized look can be reproduced from its reported seed
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut h = self.state;
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^ (h >> 31)
+    }
+
+    /// a float from `min` up to `max`
+    pub fn range<T: Float>(&mut self, min: T, max: T) -> T {
+        // the top 53 bits, as a float from 0 to 1
+        let unit = T::from((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64).unwrap();
+        min + (max - min) * unit
+    }
+}
+
+/// a random color as an `rgba()` string, with saturation and value drawn from the given ranges
+fn color(rng: &mut Rng, saturation: (f64, f64), value: (f64, f64)) -> String {
+    let hsv = Hsv::new(
+        RgbHue::from_degrees(rng.range(0.0, 360.0)),
+        rng.range(saturation.0, saturation.1),
+        rng.range(value.0, value.1),
+    );
+    let rgb: Srgb<u8> = Srgb::from(hsv).into_format();
+    format!("rgba({}, {}, {}, 1)", rgb.red, rgb.green, rgb.blue)
+}
+
+/// Randomizes the `aspects` of `scene` from `seed`, leaving everything else (including
+/// unlisted aspects) untouched.
+pub fn randomize<T>(scene: &mut Scene<T>, aspects: &[Aspect], seed: u64)
+where
+    T: Float + Sum + Default + Clone,
+{
+    // each aspect draws from its own stream, so locking one doesn't change the others
+    if aspects.contains(&Aspect::Materials) {
+        let mut rng = Rng::new(seed);
+        // visit materials in a fixed order; a HashMap's order would make seeds irreproducible
+        let mut names: Vec<String> = scene.materials.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let mat = scene.materials.get_mut(&name).unwrap();
+            mat.specular = rng.range(T::zero(), T::one());
+            mat.diffuse = rng.range(T::from(0.2).unwrap(), T::one());
+            mat.ambient = rng.range(T::zero(), T::from(0.2).unwrap());
+            mat.shininess = rng.range(T::one(), T::from(64).unwrap());
+        }
+    }
+
+    if aspects.contains(&Aspect::Lights) {
+        let mut rng = Rng::new(seed ^ 0x5bd1_e995);
+        for light in &mut scene.lights {
+            light.col.specular = color(&mut rng, (0.0, 0.2), (0.8, 1.0));
+            light.col.diffuse = color(&mut rng, (0.1, 0.6), (0.6, 1.0));
+            light.col.ambient = color(&mut rng, (0.1, 0.8), (0.1, 0.4));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_yaml;
+
+    use super::{randomize, Aspect, Rng};
+    use crate::serialize::Scene;
+
+    const SCENE: &str = "
+geometry: []
+materials:
+    stone: {specular: 0.2, diffuse: 0.8, ambient: 0.05, shininess: 8.0}
+    metal: {specular: 0.9, diffuse: 0.3, ambient: 0.0, shininess: 32.0}
+lights:
+    - facing: [-1, 1, -1]
+      specular: white
+      diffuse: white
+      ambient: white
+cameras: {}
+renders: []
+";
+
+    fn scene() -> Scene<f64> {
+        serde_yaml::from_str(SCENE).unwrap()
+    }
+
+    #[test]
+    fn rng_range_test() {
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let x = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&x));
+        }
+    }
+
+    #[test]
+    fn randomize_test() {
+        let mut a = scene();
+        let mut b = scene();
+        randomize(&mut a, &Aspect::ALL, 42);
+        randomize(&mut b, &Aspect::ALL, 42);
+        assert_eq!(a.materials, b.materials);
+        assert_eq!(a.lights, b.lights);
+        assert_ne!(a.materials, scene().materials);
+        assert_ne!(a.lights, scene().lights);
+
+        // locking the lights leaves them alone, and doesn't change how materials are randomized
+        let mut locked = scene();
+        randomize(&mut locked, &[Aspect::Materials], 42);
+        assert_eq!(locked.materials, a.materials);
+        assert_eq!(locked.lights, scene().lights);
+    }
+}
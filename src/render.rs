@@ -1,17 +1,868 @@
+use std::collections::{BTreeMap, HashMap};
 use std::iter::Sum;
+use std::sync::Arc;
 
 use num::Float;
+use vek::{Extent2, Vec2, Vec3};
 
-use crate::camera::Render;
-use crate::distance::Geometry;
-use crate::light::{Light, Material};
+use crate::camera::{Render, Viewport};
+use crate::distance::{Estimator, Geometry, MarchStats, NonFiniteEstimate, OrbitTrap};
+use crate::light::{BlinnPhong, Light, Material};
 
+#[derive(Clone)]
 pub struct RenderGeometry<T>
 where
     T: Float + Sum + Default,
 {
     pub mat: Material<T>,
     pub geom: Geometry<T>,
+    pub checker: Option<Checker<T>>,
+    pub instance_gradient: Option<InstanceGradient<T>>,
+    pub orbit_trap: Option<TrapColoring<T>>,
+}
+
+/// Alternates a geometry's surface between its own material and `mat` in a 3D checkerboard of
+/// cubes `size` units wide
+#[derive(Clone)]
+pub struct Checker<T>
+where
+    T: Default,
+{
+    pub mat: Material<T>,
+    pub size: T,
+}
+
+/// Blends a fractal's surface from its own material toward `mat` where the point's orbit comes
+/// close to `trap`; the blend falls off exponentially, reaching about a third at `falloff` units
+#[derive(Clone)]
+pub struct TrapColoring<T>
+where
+    T: Default,
+{
+    pub trap: OrbitTrap<T>,
+    pub mat: Material<T>,
+    pub falloff: T,
+}
+
+impl<T> TrapColoring<T>
+where
+    T: Float + Sum + Default,
+{
+    /// `base` blended toward the trap's material for an orbit which came within `closest` of it
+    fn blend(&self, base: Material<T>, closest: T) -> Material<T> {
+        mix(base, self.mat, (-closest / self.falloff).exp())
+    }
+}
+
+/// Shades the copies of a repeated shape (see `distance::Repeat`) in a gradient across the grid,
+/// from the geometry's own material for the first copy to `mat` for the last
+#[derive(Clone)]
+pub struct InstanceGradient<T>
+where
+    T: Default,
+{
+    pub mat: Material<T>,
+}
+
+/// `w` of the way from `a` to `b`
+fn mix<T>(a: Material<T>, b: Material<T>, w: T) -> Material<T>
+where
+    T: Float + Default,
+{
+    let mix = |a: T, b: T| a + (b - a) * w;
+    Material {
+        specular: mix(a.specular, b.specular),
+        diffuse: mix(a.diffuse, b.diffuse),
+        ambient: mix(a.ambient, b.ambient),
+        shininess: mix(a.shininess, b.shininess),
+    }
+}
+
+impl<T> RenderGeometry<T>
+where
+    T: Float + Sum + Default,
+{
+    /// the material of the geometry's surface at `pos`
+    pub fn material_at(&self, pos: Vec3<T>) -> Material<T> {
+        let mut mat = self.base_material_at(pos);
+        if let Some(gradient) = &self.instance_gradient {
+            if let Some(instance) = self.geom.de.instance(pos) {
+                mat = mix(mat, gradient.mat, instance.fraction());
+            }
+        }
+        match &self.orbit_trap {
+            Some(coloring) => match self.geom.de.estimate_trapped(pos, &coloring.trap).1 {
+                Some(closest) => coloring.blend(mat, closest),
+                // not an iterated fractal, so there's no orbit to color by
+                None => mat,
+            },
+            None => mat,
+        }
+    }
+
+    /// the material at `pos` before orbit trap coloring
+    fn base_material_at(&self, pos: Vec3<T>) -> Material<T> {
+        match &self.checker {
+            Some(checker) => {
+                let cell = (pos / checker.size).map(|c| c.floor()).sum();
+                if (cell / T::from(2).unwrap()).fract() == T::zero() {
+                    self.mat
+                } else {
+                    checker.mat
+                }
+            }
+            None => self.mat,
+        }
+    }
+}
+
+/// the geometry closest to `pos` along `rot` and the distance to its surface, if the ray hits any
+/// of them
+pub fn nearest<T>(
+    geometry: &[RenderGeometry<T>],
+    pos: Vec3<T>,
+    rot: Vec3<T>,
+) -> Option<(&RenderGeometry<T>, T)>
+where
+    T: Float + Sum + Default,
+{
+    nearest_from(geometry, pos, rot, T::zero())
+}
+
+/// like `nearest`, but starts marching `start` units along the ray
+pub fn nearest_from<T>(
+    geometry: &[RenderGeometry<T>],
+    pos: Vec3<T>,
+    rot: Vec3<T>,
+    start: T,
+) -> Option<(&RenderGeometry<T>, T)>
+where
+    T: Float + Sum + Default,
+{
+    geometry
+        .iter()
+        .filter_map(|g| {
+            g.geom
+                .hit_distance_from(pos, rot, start)
+                .map(|dist| (g, dist))
+        })
+        .fold(None, |nearest, (g, dist)| match nearest {
+            Some((n, n_dist)) if n_dist <= dist => Some((n, n_dist)),
+            _ => Some((g, dist)),
+        })
+}
+
+/// like `nearest`, but marches with `Geometry::estimate_checked`, failing at the first estimate
+/// which isn't a finite number
+pub fn nearest_checked<T>(
+    geometry: &[RenderGeometry<T>],
+    pos: Vec3<T>,
+    rot: Vec3<T>,
+) -> Result<Option<(&RenderGeometry<T>, T)>, NonFiniteEstimate<T>>
+where
+    T: Float + Sum + Default,
+{
+    let mut nearest: Option<(&RenderGeometry<T>, T)> = None;
+    for g in geometry {
+        if let Some(hit) = g.geom.estimate_checked(pos, rot)? {
+            let dist = (hit - pos).magnitude();
+            match nearest {
+                Some((_, n_dist)) if n_dist <= dist => {}
+                _ => nearest = Some((g, dist)),
+            }
+        }
+    }
+    Ok(nearest)
+}
+
+/// distance along `rot` from `pos` to the closest surface of any of the geometries, if the ray
+/// hits one of them
+pub fn nearest_hit<T>(geometry: &[RenderGeometry<T>], pos: Vec3<T>, rot: Vec3<T>) -> Option<T>
+where
+    T: Float + Sum + Default,
+{
+    nearest(geometry, pos, rot).map(|(_, dist)| dist)
+}
+
+/// Where the line through the origin along the unit vector `axis` first and last meets the
+/// geometries, as distances along `axis`, found by marching toward the origin from `reach` units
+/// away on either side; `None` if either ray misses.
+pub fn extent_along<T>(geometry: &[RenderGeometry<T>], axis: Vec3<T>, reach: T) -> Option<(T, T)>
+where
+    T: Float + Sum + Default,
+{
+    let near = nearest_hit(geometry, -axis * reach, axis)?;
+    let far = nearest_hit(geometry, axis * reach, -axis)?;
+    Some((near - reach, reach - far))
+}
+
+/// The approximate center of the geometries' bounding box: along each axis, the midpoint of its
+/// `extent_along` that axis. Axes whose rays miss are centered at 0.
+pub fn bounding_center<T>(geometry: &[RenderGeometry<T>], reach: T) -> Vec3<T>
+where
+    T: Float + Sum + Default,
+{
+    let axis = |a: Vec3<T>| {
+        extent_along(geometry, a, reach)
+            .map_or_else(T::zero, |(start, end)| (start + end) / T::from(2).unwrap())
+    };
+    Vec3::new(
+        axis(Vec3::unit_x()),
+        axis(Vec3::unit_y()),
+        axis(Vec3::unit_z()),
+    )
+}
+
+/// the point `offset` units above (along the surface normal) the surface hit by marching from
+/// `pos` along `rot`, if the ray hits any of the geometries
+pub fn above_surface<T>(
+    geometry: &[RenderGeometry<T>],
+    pos: Vec3<T>,
+    rot: Vec3<T>,
+    offset: T,
+) -> Option<Vec3<T>>
+where
+    T: Float + Sum + Default,
+{
+    nearest(geometry, pos, rot).map(|(g, dist)| {
+        let hit = pos + rot * dist;
+        hit + g.geom.normal(hit) * offset
+    })
+}
+
+/// screen-space location (as in `Viewport::ray`) of the center of pixel (x, y) in an image of
+/// size `size`, with rows counted from the top
+pub fn pixel_location<T>(x: usize, y: usize, size: Extent2<usize>) -> Vec2<T>
+where
+    T: Float,
+{
+    let half = T::from(0.5).unwrap();
+    Vec2::new(
+        (T::from(x).unwrap() + half) / T::from(size.w).unwrap(),
+        T::one() - (T::from(y).unwrap() + half) / T::from(size.h).unwrap(),
+    )
+}
+
+/// How far through an animation from frame `start` to frame `end` `frame` is, from 0 at the first
+/// frame to 1 at the last, for `serialize::Scene::animate`; a single frame is at 0.
+pub fn frame_time<T: Float>(frame: usize, start: usize, end: usize) -> T {
+    if end <= start {
+        T::zero()
+    } else {
+        T::from(frame.saturating_sub(start)).unwrap() / T::from(end - start).unwrap()
+    }
+}
+
+/// Hit distances (`None` for misses) of the primary rays through each pixel of an image of size
+/// `size`; rendered at a low resolution, this is a depth prepass whose distances (reduced with
+/// `img::seed_distances`) can warm-start the full-resolution march with `nearest_from`.
+pub fn depth_pass<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+) -> Vec<Option<T>>
+where
+    T: Float + Sum + Default,
+{
+    (0..size.h)
+        .flat_map(|y| (0..size.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (pos, rot) = view.ray(pixel_location(x, y, size));
+            nearest_hit(geometry, pos, rot)
+        })
+        .collect()
+}
+
+/// Safe starting distances for the primary rays of an image of size `size`, by cone marching: one
+/// cone is marched per `block`×`block` square of pixels, wide enough to contain all of their
+/// rays, until it comes within its own radius of a surface. None of the block's rays can hit
+/// anything short of that distance, so they can start marching there with `nearest_from`,
+/// skipping the empty space in front of the scene once per block rather than once per pixel.
+pub fn cone_prepass<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+    block: usize,
+) -> Vec<T>
+where
+    T: Float + Sum + Default,
+{
+    let block = block.max(1);
+    let max_steps = geometry.iter().map(|g| g.geom.max_steps).max().unwrap_or(0);
+    let cutoff = geometry
+        .iter()
+        .map(|g| g.geom.cutoff)
+        .fold(T::zero(), T::max);
+    let half = T::from(0.5).unwrap();
+    let (w, h) = (T::from(size.w).unwrap(), T::from(size.h).unwrap());
+
+    let mut starts = vec![T::zero(); size.w * size.h];
+    for by in (0..size.h).step_by(block) {
+        for bx in (0..size.w).step_by(block) {
+            let (bw, bh) = (block.min(size.w - bx), block.min(size.h - by));
+            let (bw_t, bh_t) = (T::from(bw).unwrap(), T::from(bh).unwrap());
+            let center = Vec2::new(
+                (T::from(bx).unwrap() + bw_t * half) / w,
+                T::one() - (T::from(by).unwrap() + bh_t * half) / h,
+            );
+            let (pos, rot) = view.ray(center);
+            // the block's half-diagonal on the viewport, which grows with distance as the rays
+            // spread out from the eye `focal_len` behind it
+            let radius =
+                Vec2::new(bw_t * view.size.w / w, bh_t * view.size.h / h).magnitude() * half;
+            let cone = |t: T| radius * (T::one() + t / view.focal_len);
+
+            let mut t = T::zero();
+            for _ in 0..max_steps {
+                let dist = match closest(geometry, pos + rot * t) {
+                    Some((_, dist)) => dist,
+                    None => break,
+                };
+                if dist <= cone(t) || t >= cutoff {
+                    break;
+                }
+                t = t + dist - cone(t);
+            }
+
+            for y in by..by + bh {
+                for x in bx..bx + bw {
+                    starts[y * size.w + x] = t;
+                }
+            }
+        }
+    }
+    starts
+}
+
+/// Unlit surface albedo (the diffuse reflectance of the material hit, checkers included) of the
+/// primary ray through each pixel of an image of size `size`, or `None` for misses; an input for
+/// denoising alongside the depth and normal passes (see `img::Guides`).
+pub fn albedo_pass<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+) -> Vec<Option<T>>
+where
+    T: Float + Sum + Default,
+{
+    (0..size.h)
+        .flat_map(|y| (0..size.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (pos, rot) = view.ray(pixel_location(x, y, size));
+            nearest(geometry, pos, rot).map(|(g, dist)| g.material_at(pos + rot * dist).diffuse)
+        })
+        .collect()
+}
+
+/// Surface normals at the primary hit through each pixel of an image of size `size`, or `None`
+/// for misses; the normal pass for denoising (see `img::Guides`), and what
+/// `img::smooth_normals` smooths.
+pub fn normal_pass<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+) -> Vec<Option<Vec3<T>>>
+where
+    T: Float + Sum + Default,
+{
+    (0..size.h)
+        .flat_map(|y| (0..size.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (pos, rot) = view.ray(pixel_location(x, y, size));
+            nearest(geometry, pos, rot).map(|(g, dist)| g.geom.normal(pos + rot * dist))
+        })
+        .collect()
+}
+
+/// Which optimizations a render may use. `safe` turns them all off (and turns on extra checks)
+/// for a slow but canonical reference render, to compare the fast paths against and to debug
+/// platform-specific artifacts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Acceleration {
+    /// render threads; 1 renders on the calling thread
+    pub threads: usize,
+    /// render the periphery at lower quality, for renders with foveation
+    pub foveation: bool,
+    /// over-relax sphere tracing, for geometry with a `relaxation` factor
+    pub relaxation: bool,
+    /// estimate far geometry with fewer iterations, for geometry with a `lod` rule
+    pub distance_lod: bool,
+    /// start primary rays from a cone-marched depth (see `cone_prepass`)
+    pub cone_prepass: bool,
+    /// start primary rays short of the hits of a low-resolution depth prepass (see
+    /// `img::seed_distances`)
+    pub warm_start: bool,
+    /// reuse ambient occlusion between nearby points (see `AoCache`)
+    pub ao_cache: bool,
+    /// reuse distance estimates between nearby ambient occlusion samples (see `DeCache`); an
+    /// experiment for expensive hybrid fractals, so off unless asked for
+    pub de_cache: bool,
+    /// render half the pixels each pass and reconstruct the rest (see
+    /// `img::reconstruct_checkerboard`)
+    pub checkerboard: bool,
+    /// stop sampling pixels whose mean has converged (see `img::SampleBuffer`)
+    pub adaptive_sampling: bool,
+    /// march with `Geometry::estimate_checked`, failing on the first non-finite estimate
+    pub checked: bool,
+}
+
+impl Acceleration {
+    /// every optimization enabled, rendering on `threads` threads
+    pub fn new(threads: usize) -> Self {
+        Acceleration {
+            threads: threads.max(1),
+            foveation: true,
+            relaxation: true,
+            distance_lod: true,
+            cone_prepass: true,
+            warm_start: true,
+            ao_cache: true,
+            de_cache: false,
+            checkerboard: true,
+            adaptive_sampling: true,
+            checked: false,
+        }
+    }
+
+    /// a single thread, no approximations, and checked marching
+    pub fn safe() -> Self {
+        Acceleration {
+            threads: 1,
+            foveation: false,
+            relaxation: false,
+            distance_lod: false,
+            cone_prepass: false,
+            warm_start: false,
+            ao_cache: false,
+            de_cache: false,
+            checkerboard: false,
+            adaptive_sampling: false,
+            checked: true,
+        }
+    }
+}
+
+/// Something wrong with the normal estimated at a primary hit, usually a sign that a geometry's
+/// `sample_size` or ε doesn't suit its scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalProblem {
+    /// the gradient has an infinite or NaN component
+    NotFinite,
+    /// the gradient's length is far from 1, so the estimator over- or underestimates distances
+    Denormalized,
+    /// the normal points away from the camera, although the ray came from the camera's side
+    Backfacing,
+}
+
+impl NormalProblem {
+    /// the warning color to show pixels with this problem in, as 8-bit RGBA
+    pub fn color(self) -> [u8; 4] {
+        match self {
+            NormalProblem::NotFinite => [255, 0, 255, 255],
+            NormalProblem::Denormalized => [255, 255, 0, 255],
+            NormalProblem::Backfacing => [255, 0, 0, 255],
+        }
+    }
+}
+
+/// How many primary hits had each kind of normal problem
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NormalStats {
+    pub hits: usize,
+    pub not_finite: usize,
+    pub denormalized: usize,
+    pub backfacing: usize,
+}
+
+/// Checks the normal at the primary hit through each pixel of an image of size `size`, giving the
+/// problem with each pixel's normal (`None` for good normals and misses) and totals. Gradients
+/// whose length differs from 1 by more than `tolerance` count as denormalized.
+pub fn normal_check_pass<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+    tolerance: T,
+) -> (Vec<Option<NormalProblem>>, NormalStats)
+where
+    T: Float + Sum + Default,
+{
+    let mut stats = NormalStats::default();
+    let problems = (0..size.h)
+        .flat_map(|y| (0..size.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (pos, rot) = view.ray(pixel_location(x, y, size));
+            let (g, dist) = nearest(geometry, pos, rot)?;
+            stats.hits += 1;
+            let gradient = g.geom.gradient(pos + rot * dist);
+            let problem = if !gradient.iter().all(|c| c.is_finite()) {
+                stats.not_finite += 1;
+                NormalProblem::NotFinite
+            } else if (gradient.magnitude() - T::one()).abs() > tolerance {
+                stats.denormalized += 1;
+                NormalProblem::Denormalized
+            } else if gradient.dot(rot) > T::zero() {
+                stats.backfacing += 1;
+                NormalProblem::Backfacing
+            } else {
+                return None;
+            };
+            Some(problem)
+        })
+        .collect();
+    (problems, stats)
+}
+
+/// Totals of the march statistics of every pixel in an image
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StepStats {
+    pub rays: usize,
+    pub hits: usize,
+    pub total_steps: usize,
+    /// the most steps any one pixel took
+    pub max_steps: usize,
+    /// steps lengthened to their geometry's `min_step`
+    pub floored_steps: usize,
+    /// rays which gave up on a geometry for running out of its `max_evaluations`
+    pub out_of_evaluations: usize,
+}
+
+impl StepStats {
+    /// the average number of steps per pixel
+    pub fn mean_steps(&self) -> f64 {
+        if self.rays == 0 {
+            0.0
+        } else {
+            self.total_steps as f64 / self.rays as f64
+        }
+    }
+}
+
+/// Marches the primary ray through each pixel of an image of size `size` against every geometry,
+/// giving each pixel's steps (summed over the geometries) and closest approach to any surface,
+/// and totals for the image; a heat map of the steps shows where rendering time goes.
+pub fn step_pass<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+) -> (Vec<MarchStats<T>>, StepStats)
+where
+    T: Float + Sum + Default,
+{
+    let mut stats = StepStats::default();
+    let pixels = (0..size.h)
+        .flat_map(|y| (0..size.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (pos, rot) = view.ray(pixel_location(x, y, size));
+            let mut pixel = MarchStats {
+                steps: 0,
+                closest: T::infinity(),
+                floored: 0,
+                out_of_evaluations: false,
+            };
+            let mut hit = false;
+            for g in geometry {
+                let (g_hit, g_stats) = g.geom.estimate_with_stats(pos, rot);
+                hit |= g_hit.is_some();
+                pixel.steps += g_stats.steps;
+                pixel.closest = pixel.closest.min(g_stats.closest);
+                pixel.floored += g_stats.floored;
+                pixel.out_of_evaluations |= g_stats.out_of_evaluations;
+            }
+            stats.rays += 1;
+            stats.hits += hit as usize;
+            stats.total_steps += pixel.steps;
+            stats.max_steps = stats.max_steps.max(pixel.steps);
+            stats.floored_steps += pixel.floored;
+            stats.out_of_evaluations += pixel.out_of_evaluations as usize;
+            pixel
+        })
+        .collect();
+    (pixels, stats)
+}
+
+/// the geometry whose surface is closest to `pos` and its estimated distance
+fn closest<T>(geometry: &[RenderGeometry<T>], pos: Vec3<T>) -> Option<(&RenderGeometry<T>, T)>
+where
+    T: Float + Sum + Default,
+{
+    geometry
+        .iter()
+        .map(|g| (g, g.geom.de.estimate(pos)))
+        .fold(None, |closest, (g, dist)| match closest {
+            Some((c, c_dist)) if c_dist <= dist => Some((c, c_dist)),
+            _ => Some((g, dist)),
+        })
+}
+
+/// Ambient occlusion of the surface point `pos` with normal `normal`, from 0 (fully occluded) to 1
+/// (unoccluded): samples the distance field at `samples` points spaced `step` apart along the
+/// normal, where an unoccluded surface would have the full step distance
+pub fn ambient_occlusion<T>(
+    geometry: &[RenderGeometry<T>],
+    pos: Vec3<T>,
+    normal: Vec3<T>,
+    samples: usize,
+    step: T,
+) -> T
+where
+    T: Float + Sum + Default,
+{
+    occlusion_from(
+        |sample| closest(geometry, sample).map(|(_, dist)| dist),
+        pos,
+        normal,
+        samples,
+        step,
+    )
+}
+
+/// Like `ambient_occlusion`, but takes the distance estimates at the samples from `cache`
+pub fn ambient_occlusion_cached<T>(
+    geometry: &[RenderGeometry<T>],
+    cache: &mut DeCache<T>,
+    pos: Vec3<T>,
+    normal: Vec3<T>,
+    samples: usize,
+    step: T,
+) -> T
+where
+    T: Float + Sum + Default,
+{
+    occlusion_from(
+        |sample| cache.closest(geometry, sample),
+        pos,
+        normal,
+        samples,
+        step,
+    )
+}
+
+/// ambient occlusion as in `ambient_occlusion`, with the scene's distance at each sample given by
+/// `distance`
+fn occlusion_from<T, F>(
+    mut distance: F,
+    pos: Vec3<T>,
+    normal: Vec3<T>,
+    samples: usize,
+    step: T,
+) -> T
+where
+    T: Float,
+    F: FnMut(Vec3<T>) -> Option<T>,
+{
+    let t2 = T::from(2).unwrap();
+    let mut occlusion = T::zero();
+    // nearer samples count for more
+    let mut weight = T::one();
+    for i in 1..=samples {
+        let expected = step * T::from(i).unwrap();
+        let dist = distance(pos + normal * expected).unwrap_or(expected);
+        occlusion = occlusion + (expected - dist).max(T::zero()) / expected * weight;
+        weight = weight / t2;
+    }
+    (T::one() - occlusion).max(T::zero()).min(T::one())
+}
+
+/// Ambient occlusion values cached by position and normal, quantized to cells `cell` units wide;
+/// AO varies slowly across a surface, so a static scene can compute it once per cell and reuse it
+/// across samples and frames. It's only valid as long as the geometry doesn't change.
+pub struct AoCache<T> {
+    cell: T,
+    values: HashMap<[i64; 6], T>,
+}
+
+impl<T> AoCache<T>
+where
+    T: Float + Sum + Default,
+{
+    pub fn new(cell: T) -> Self {
+        Self {
+            cell,
+            values: HashMap::new(),
+        }
+    }
+
+    fn key(&self, pos: Vec3<T>, normal: Vec3<T>) -> [i64; 6] {
+        let pos = pos.map(|c| (c / self.cell).floor().to_i64().unwrap_or(0));
+        // normals are bucketed into quarter-unit steps per component
+        let normal = normal.map(|c| (c * T::from(4).unwrap()).round().to_i64().unwrap_or(0));
+        [pos.x, pos.y, pos.z, normal.x, normal.y, normal.z]
+    }
+
+    /// the cached occlusion for the cell containing `pos` and `normal`, computed with
+    /// `ambient_occlusion` if it's not cached yet
+    pub fn get(
+        &mut self,
+        geometry: &[RenderGeometry<T>],
+        pos: Vec3<T>,
+        normal: Vec3<T>,
+        samples: usize,
+        step: T,
+    ) -> T {
+        let key = self.key(pos, normal);
+        *self
+            .values
+            .entry(key)
+            .or_insert_with(|| ambient_occlusion(geometry, pos, normal, samples, step))
+    }
+
+    /// the number of cells with a cached value
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// forgets every cached value, e.g. after the geometry changes
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// How often a `DeCache` had an estimate ready
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DeCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl DeCacheStats {
+    /// the fraction of lookups answered from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Distance estimates to the closest geometry cached by position, quantized to cells `cell` units
+/// wide, so that neighboring rays' ambient occlusion samples, which often land in the same cells,
+/// only estimate expensive fractals once per cell. Each cell keeps the estimate at the first
+/// point sampled in it, which is off by up to the cell's diagonal elsewhere in the cell, so `cell`
+/// should be well under the AO step. At most `capacity` cells are kept; the least recently used
+/// is forgotten to make room for a new one.
+pub struct DeCache<T> {
+    cell: T,
+    capacity: usize,
+    /// each cell's estimate, and when it was last used
+    values: HashMap<[i64; 3], (Option<T>, u64)>,
+    /// cells by when they were last used
+    recent: BTreeMap<u64, [i64; 3]>,
+    clock: u64,
+    stats: DeCacheStats,
+}
+
+impl<T> DeCache<T>
+where
+    T: Float + Sum + Default,
+{
+    pub fn new(cell: T, capacity: usize) -> Self {
+        Self {
+            cell,
+            capacity: capacity.max(1),
+            values: HashMap::new(),
+            recent: BTreeMap::new(),
+            clock: 0,
+            stats: DeCacheStats::default(),
+        }
+    }
+
+    fn key(&self, pos: Vec3<T>) -> [i64; 3] {
+        let pos = pos.map(|c| (c / self.cell).floor().to_i64().unwrap_or(0));
+        [pos.x, pos.y, pos.z]
+    }
+
+    /// the cached distance from the cell containing `pos` to the closest geometry, estimated at
+    /// `pos` if it's not cached yet; `None` if there's no geometry
+    pub fn closest(&mut self, geometry: &[RenderGeometry<T>], pos: Vec3<T>) -> Option<T> {
+        let key = self.key(pos);
+        self.clock += 1;
+        if let Some((dist, used)) = self.values.get_mut(&key) {
+            self.recent.remove(used);
+            *used = self.clock;
+            self.recent.insert(self.clock, key);
+            self.stats.hits += 1;
+            return *dist;
+        }
+        self.stats.misses += 1;
+        if self.values.len() >= self.capacity {
+            if let Some((&oldest, _)) = self.recent.iter().next() {
+                let stale = self.recent.remove(&oldest).unwrap();
+                self.values.remove(&stale);
+            }
+        }
+        let dist = closest(geometry, pos).map(|(_, dist)| dist);
+        self.values.insert(key, (dist, self.clock));
+        self.recent.insert(self.clock, key);
+        dist
+    }
+
+    /// lookups so far, and how many were answered from the cache
+    pub fn stats(&self) -> DeCacheStats {
+        self.stats
+    }
+
+    /// the number of cells with a cached estimate
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// forgets every cached estimate, e.g. after the geometry changes; the stats are kept
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.recent.clear();
+    }
+}
+
+/// Pushes the points of a camera path out of the geometry so that each is at least `clearance`
+/// units from every surface.
+///
+/// Each point is moved along the surface normal of its closest geometry; the resulting
+/// displacements are then averaged over a window of `smoothing` points on either side so the
+/// adjusted path doesn't jerk where it grazes the surface, topped up wherever the average moves a
+/// point less far along its own push than the push itself. Where the pushes turn, the sideways
+/// part of the average can leave a point a little short of `clearance`, but never back inside.
+pub fn clear_path<T>(
+    geometry: &[RenderGeometry<T>],
+    path: &mut [Vec3<T>],
+    clearance: T,
+    smoothing: usize,
+) where
+    T: Float + Sum + Default,
+{
+    let pushes: Vec<Vec3<T>> = path
+        .iter()
+        .map(|&pos| match closest(geometry, pos) {
+            Some((g, dist)) if dist < clearance => g.geom.normal(pos) * (clearance - dist),
+            _ => Vec3::zero(),
+        })
+        .collect();
+
+    for (i, pos) in path.iter_mut().enumerate() {
+        let window = &pushes[i.saturating_sub(smoothing)..(i + smoothing + 1).min(pushes.len())];
+        // never push a point less far along its own push than it needs on its own, so smoothing
+        // can't put it back inside the geometry; a longer mean pointing elsewhere isn't enough
+        let mean =
+            window.iter().fold(Vec3::zero(), |acc, &p| acc + p) / T::from(window.len()).unwrap();
+        let own = pushes[i];
+        let needed = own.magnitude();
+        let push = if needed > T::zero() {
+            let dir = own / needed;
+            mean + dir * (needed - mean.dot(dir)).max(T::zero())
+        } else {
+            mean
+        };
+        *pos = *pos + push;
+    }
 }
 
 //impl RenderGeometry<'a, T, E>
@@ -24,12 +875,492 @@ where
 //}
 //}
 
+/// A loaded scene; it's immutable once built, so worker threads can share it through an `Arc`
 pub struct Scene<T, C>
 where
     T: Float + Sum + Default + Clone,
     C: Default + Clone,
 {
     pub geometry: Vec<RenderGeometry<T>>,
-    pub lights: Vec<Light<T, C>>,
+    pub lights: Arc<[Light<T, C>]>,
     pub renders: Vec<Render<T>>,
 }
+
+impl<T, C> Scene<T, C>
+where
+    T: Float + Sum + Default + Clone,
+    C: Default + Clone,
+{
+    /// Blinn-Phong shading with the scene's lights
+    pub fn shading(&self) -> BlinnPhong<T, C> {
+        BlinnPhong::new(Arc::clone(&self.lights))
+    }
+
+    /// The scene with its geometry's quality adjusted for a frame zoomed in by a factor of `zoom`
+    /// (see `Geometry::at_zoom`)
+    pub fn at_zoom(mut self, zoom: T) -> Self {
+        for g in self.geometry.iter_mut() {
+            g.geom = g.geom.at_zoom(zoom);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use palette::Srgba;
+    use vek::{Extent2, Ray, Vec3};
+
+    use super::{
+        above_surface, albedo_pass, ambient_occlusion, ambient_occlusion_cached, bounding_center,
+        clear_path, cone_prepass, depth_pass, extent_along, nearest, nearest_checked, nearest_from,
+        nearest_hit, normal_check_pass, normal_pass, pixel_location, step_pass, AoCache, Checker,
+        DeCache, DeCacheStats, NormalProblem, NormalStats, RenderGeometry, Scene, StepStats,
+        TrapColoring,
+    };
+    use crate::camera::Viewport;
+    use crate::distance::{
+        Estimator, Geometry, Mandelbox, NormalMethod, OrbitTrap, Plane, Sphere, Torus,
+    };
+    use crate::img;
+    use crate::light::Material;
+
+    fn assert_send_sync<S: Send + Sync>() {}
+
+    #[test]
+    fn scene_is_send_sync_test() {
+        assert_send_sync::<Scene<f32, Srgba>>();
+        assert_send_sync::<Scene<f64, Srgba<f64>>>();
+    }
+
+    fn plane(normal: Vec3<f64>, offset: f64) -> RenderGeometry<f64> {
+        RenderGeometry {
+            mat: Material::default(),
+            geom: Geometry {
+                max_steps: 64,
+                max_evaluations: None,
+                epsilon: 1e-6,
+                epsilon_scale: None,
+                cutoff: 100.0,
+                sample_size: 1e-6,
+                normal_method: NormalMethod::Central,
+                relaxation: None,
+                step_scale: None,
+                min_step: None,
+                refine_steps: None,
+                zoom_quality: None,
+                lod: None,
+                bounds: None,
+                de: Plane::new(normal, offset).into(),
+            },
+            checker: None,
+            instance_gradient: None,
+            orbit_trap: None,
+        }
+    }
+
+    #[test]
+    fn ambient_occlusion_test() {
+        // nothing above an open floor
+        let open = [plane(Vec3::unit_y(), 0.0)];
+        assert_eq!(
+            ambient_occlusion(&open, Vec3::zero(), Vec3::unit_y(), 4, 0.1),
+            1.0
+        );
+
+        // a ceiling just above the floor
+        let covered = [plane(Vec3::unit_y(), 0.0), plane(-Vec3::unit_y(), -0.15)];
+        let ao = ambient_occlusion(&covered, Vec3::zero(), Vec3::unit_y(), 4, 0.1);
+        assert!(ao < 1.0);
+
+        let mut cache = AoCache::new(0.5);
+        cache.get(&covered, Vec3::zero(), Vec3::unit_y(), 4, 0.1);
+        // same cell, same normal
+        assert_eq!(
+            cache.get(&covered, Vec3::new(0.2, 0.0, 0.1), Vec3::unit_y(), 4, 0.1),
+            ao
+        );
+        assert_eq!(cache.len(), 1);
+        cache.get(&covered, Vec3::new(2.0, 0.0, 0.0), Vec3::unit_y(), 4, 0.1);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn de_cache_test() {
+        let covered = [plane(Vec3::unit_y(), 0.0), plane(-Vec3::unit_y(), -0.15)];
+        let mut cache = DeCache::new(0.01, 2);
+        // samples 0.1 apart along the normal, so four cells per point
+        let exact = ambient_occlusion(&covered, Vec3::zero(), Vec3::unit_y(), 2, 0.1);
+        let cached =
+            ambient_occlusion_cached(&covered, &mut cache, Vec3::zero(), Vec3::unit_y(), 2, 0.1);
+        assert_eq!(cached, exact);
+        assert_eq!(cache.len(), 2);
+        // a neighboring point reuses both samples' cells
+        let nearby = Vec3::new(0.005, 0.0, 0.005);
+        ambient_occlusion_cached(&covered, &mut cache, nearby, Vec3::unit_y(), 2, 0.1);
+        assert_eq!(cache.stats(), DeCacheStats { hits: 2, misses: 2 });
+        assert_eq!(cache.stats().hit_rate(), 0.5);
+        // touch the first cell, so the second is the least recently used and makes room
+        cache.closest(&covered, Vec3::new(0.0, 0.1, 0.0));
+        cache.closest(&covered, Vec3::new(1.0, 0.05, 0.0));
+        assert_eq!(cache.len(), 2);
+        cache.closest(&covered, Vec3::new(0.0, 0.1, 0.0));
+        assert_eq!(cache.stats().hits, 4);
+        cache.closest(&covered, Vec3::new(0.0, 0.2, 0.0));
+        assert_eq!(cache.stats().misses, 4);
+    }
+
+    #[test]
+    fn normal_check_pass_test() {
+        let view = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 5.0, 0.0), -Vec3::unit_y()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            // narrow enough that every pixel hits the unit sphere below
+            focal_len: 10.0,
+            focus_dist: None,
+        };
+        let size = Extent2::new(2, 2);
+
+        let floor = [plane(Vec3::unit_y(), 0.0)];
+        let (problems, stats) = normal_check_pass(&floor, &view, size, 0.1);
+        assert_eq!(problems, vec![None; 4]);
+        assert_eq!(stats.hits, 4);
+
+        // the camera is already below this plane's surface
+        let flipped = [plane(-Vec3::unit_y(), 0.0)];
+        let (problems, _) = normal_check_pass(&flipped, &view, size, 0.1);
+        assert_eq!(problems, vec![Some(NormalProblem::Backfacing); 4]);
+
+        // a sample size much bigger than the shape flattens the gradient
+        let mut sphere = plane(Vec3::unit_y(), 0.0);
+        sphere.geom.de = Sphere::new(Vec3::zero(), 1.0).into();
+        sphere.geom.sample_size = 10.0;
+        let (_, stats) = normal_check_pass(&[sphere], &view, size, 0.1);
+        assert_eq!(
+            stats,
+            NormalStats {
+                hits: 4,
+                not_finite: 0,
+                denormalized: 4,
+                backfacing: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn normal_pass_test() {
+        let down = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 5.0, 0.0), -Vec3::unit_y()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 10.0,
+            focus_dist: None,
+        };
+        let size = Extent2::new(2, 2);
+        let floor = [plane(Vec3::unit_y(), 0.0)];
+        let normals = normal_pass(&floor, &down, size);
+        assert_eq!(normals.len(), 4);
+        for normal in normals {
+            assert!((normal.unwrap() - Vec3::unit_y()).magnitude() < 1e-6);
+        }
+        // looking up, away from the floor, every ray misses
+        let up = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::unit_y()),
+            ..down
+        };
+        assert_eq!(normal_pass(&floor, &up, size), vec![None; 4]);
+    }
+
+    #[test]
+    fn trap_coloring_test() {
+        let base = Material {
+            specular: 0.0,
+            diffuse: 0.0,
+            ambient: 0.0,
+            shininess: 0.0,
+        };
+        let glow = Material {
+            specular: 1.0,
+            diffuse: 1.0,
+            ambient: 1.0,
+            shininess: 1.0,
+        };
+        let mut fractal = plane(Vec3::unit_y(), 0.0);
+        fractal.mat = base;
+        fractal.geom.de = Mandelbox::new(2.0, 0.5, 1.0, 2).into();
+        let coloring = |falloff| {
+            Some(TrapColoring {
+                trap: OrbitTrap::Point {
+                    point: Vec3::zero(),
+                },
+                mat: glow,
+                falloff,
+            })
+        };
+        // the orbit from here comes within sqrt(5.18) of the origin; see distance's
+        // `orbit_trap_test`
+        let pos = Vec3::new(0.3, 0.2, 0.1);
+        fractal.orbit_trap = coloring(1.0);
+        let near = fractal.material_at(pos);
+        assert!((near.diffuse - (-5.18f64.sqrt()).exp()).abs() < 1e-12);
+        // a wider falloff blends further toward the trap's material
+        fractal.orbit_trap = coloring(10.0);
+        let far = fractal.material_at(pos);
+        assert!(near.diffuse < far.diffuse && far.diffuse < glow.diffuse);
+
+        // shapes without an orbit keep their own material
+        let mut floor = plane(Vec3::unit_y(), 0.0);
+        floor.orbit_trap = coloring(1.0);
+        assert_eq!(floor.material_at(pos), floor.mat);
+    }
+
+    #[test]
+    fn step_pass_test() {
+        let view = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 5.0, 0.0), -Vec3::unit_y()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 10.0,
+            focus_dist: None,
+        };
+        let size = Extent2::new(2, 2);
+
+        // straight down onto a floor: one step to the surface and one to confirm the hit
+        let floor = [plane(Vec3::unit_y(), 0.0)];
+        let (pixels, stats) = step_pass(&floor, &view, size);
+        assert!(pixels.iter().all(|p| p.closest.abs() < 1e-6));
+        assert_eq!(
+            stats,
+            StepStats {
+                rays: 4,
+                hits: 4,
+                total_steps: pixels.iter().map(|p| p.steps).sum(),
+                max_steps: pixels.iter().map(|p| p.steps).max().unwrap(),
+                floored_steps: 0,
+                out_of_evaluations: 0,
+            }
+        );
+
+        // a ceiling behind the camera adds its steps but no hits
+        let both = [plane(Vec3::unit_y(), 0.0), plane(-Vec3::unit_y(), -10.0)];
+        let (_, both_stats) = step_pass(&both, &view, size);
+        assert_eq!(both_stats.hits, 4);
+        assert!(both_stats.total_steps > stats.total_steps);
+        assert!(both_stats.mean_steps() > stats.mean_steps());
+    }
+
+    #[test]
+    fn turntable_test() {
+        let mut sphere = plane(Vec3::unit_y(), 0.0);
+        let center = Vec3::new(0.5, 0.0, 0.25);
+        sphere.geom.de = Sphere::new(center, 1.0).into();
+        let geometry = [sphere];
+        let found = bounding_center(&geometry, 10.0);
+        assert!((found - center).magnitude() < 1e-4, "{:?}", found);
+        // the x axis passes 0.25 from the sphere's center
+        let (start, end) = extent_along(&geometry, Vec3::unit_x(), 10.0).unwrap();
+        let half_chord = (1.0f64 - 0.25 * 0.25).sqrt();
+        assert!((start - (0.5 - half_chord)).abs() < 1e-4);
+        assert!((end - (0.5 + half_chord)).abs() < 1e-4);
+        // nothing to hit
+        assert_eq!(bounding_center::<f64>(&[], 10.0), Vec3::zero());
+
+        let view = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 1.0, -5.0), Vec3::unit_z()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 1.0,
+            focus_dist: None,
+        };
+        let quarter = view.orbit(center, std::f64::consts::FRAC_PI_2);
+        // a quarter turn counterclockwise, seen from above, at the same height
+        assert!((quarter.cam.origin - Vec3::new(-4.75, 1.0, 0.75)).magnitude() < 1e-9);
+        assert!((quarter.cam.direction - Vec3::unit_x()).magnitude() < 1e-9);
+        assert!((quarter.right - -Vec3::unit_z()).magnitude() < 1e-9);
+        // the center stays in the same place on screen
+        let on_screen = view.project(center).unwrap();
+        assert!((quarter.project(center).unwrap() - on_screen).magnitude() < 1e-9);
+        let full = view.orbit(center, 2.0 * std::f64::consts::PI);
+        assert!((full.cam.origin - view.cam.origin).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn cone_prepass_test() {
+        let view = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 5.0, 0.0), -Vec3::unit_y()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 1.0,
+            focus_dist: None,
+        };
+        let size = Extent2::new(8, 8);
+        let floor = [plane(Vec3::unit_y(), 0.0)];
+        let starts = cone_prepass(&floor, &view, size, 4);
+        for y in 0..size.h {
+            for x in 0..size.w {
+                let (pos, rot) = view.ray(pixel_location(x, y, size));
+                let start = starts[y * size.w + x];
+                // well into the empty space, but never past the surface
+                assert!(start > 2.0);
+                assert!(start < nearest_hit(&floor, pos, rot).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn clear_path_test() {
+        let mut ball = plane(Vec3::unit_y(), 0.0);
+        ball.geom.de = Sphere::new(Vec3::zero(), 1.0).into();
+        let geometry = [ball];
+        let clearance = 0.5;
+        // a pass just above the ball's center, through it and past either side
+        let pass: Vec<Vec3<f64>> = (-4..=4)
+            .map(|x| Vec3::new(f64::from(x) * 0.5, 0.5, 0.0))
+            .collect();
+        let dist = |pos| geometry[0].geom.de.estimate(pos);
+
+        // unsmoothed, every point ends up at least `clearance` from the ball
+        let mut path = pass.clone();
+        clear_path(&geometry, &mut path, clearance, 0);
+        let own: Vec<Vec3<f64>> = path.iter().zip(&pass).map(|(&a, &b)| a - b).collect();
+        assert!((path[4] - Vec3::new(0.0, 1.5, 0.0)).magnitude() < 1e-6);
+        for &pos in &path {
+            assert!(dist(pos) >= clearance - 1e-6);
+        }
+
+        // the point over the center needs the biggest push, so smoothing leaves it where it was
+        let mut path = pass.clone();
+        clear_path(&geometry, &mut path, clearance, 1);
+        assert!((path[4] - Vec3::new(0.0, 1.5, 0.0)).magnitude() < 1e-6);
+        // every point moves at least as far along its own push, even where its neighbors' pushes
+        // tilt the mean away from it
+        for ((&pos, &start), &own) in path.iter().zip(&pass).zip(&own) {
+            if own != Vec3::zero() {
+                let along = (pos - start).dot(own.normalized());
+                assert!(along >= own.magnitude() - 1e-9);
+            }
+        }
+        // and no point is pulled back inside by its neighbors
+        for pos in path {
+            assert!(dist(pos) > 0.0);
+        }
+    }
+
+    #[test]
+    fn above_surface_test() {
+        let floor = [plane(Vec3::unit_y(), 0.0)];
+        // a slanted ray hits the floor at (6, 0, 2), and the point sits straight up from there
+        let pos = Vec3::new(1.0, 5.0, 2.0);
+        let rot = Vec3::new(1.0, -1.0, 0.0).normalized();
+        let above = above_surface(&floor, pos, rot, 0.25).unwrap();
+        assert!((above - Vec3::new(6.0, 0.25, 2.0)).magnitude() < 1e-5);
+        // looking away from the floor, there's no surface to sit above
+        assert_eq!(above_surface(&floor, pos, Vec3::unit_y(), 0.25), None);
+    }
+
+    #[test]
+    fn depth_pass_test() {
+        let mut ring = plane(Vec3::unit_y(), 0.0);
+        ring.geom.max_steps = 256;
+        ring.geom.de = Torus::new(Vec3::zero(), 2.0, 0.5).into();
+        let geometry = [ring];
+        let down = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 5.0, 0.0), -Vec3::unit_y()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(6.0, 6.0),
+            focal_len: 10.0,
+            focus_dist: None,
+        };
+        let (coarse_size, size) = (Extent2::new(8, 8), Extent2::new(16, 16));
+        let coarse = depth_pass(&geometry, &down, coarse_size);
+        // the ring's hole and the corners miss, and the ring itself is hit
+        assert!(coarse.iter().any(Option::is_some));
+        assert!(coarse.iter().any(Option::is_none));
+
+        // warm-starting from the prepass finds the same hits as marching from the camera
+        let seeds = img::seed_distances(&coarse, coarse_size, size, 0.9);
+        for (i, &seed) in seeds.iter().enumerate() {
+            let (pos, rot) = down.ray(pixel_location(i % size.w, i / size.w, size));
+            let cold = nearest_hit(&geometry, pos, rot);
+            let warm = nearest_from(&geometry, pos, rot, seed).map(|(_, dist)| dist);
+            match (cold, warm) {
+                (Some(cold), Some(warm)) => assert!((cold - warm).abs() < 1e-5),
+                (cold, warm) => assert_eq!(cold, warm),
+            }
+            let cold = geometry[0].geom.estimate(pos, rot);
+            let warm = geometry[0].geom.estimate_from(pos, rot, seed);
+            match (cold, warm) {
+                (Some(cold), Some(warm)) => assert!((cold - warm).magnitude() < 1e-5),
+                (cold, warm) => assert_eq!(cold, warm),
+            }
+        }
+    }
+
+    #[test]
+    fn checker_test() {
+        let dark = Material {
+            specular: 0.0,
+            diffuse: 0.1,
+            ambient: 0.0,
+            shininess: 0.0,
+        };
+        let mut floor = plane(Vec3::unit_y(), 0.0);
+        floor.checker = Some(Checker {
+            mat: dark,
+            size: 2.0,
+        });
+        // stepping one cell along any axis, on either side of the origin, flips the material
+        for &(x, z) in &[(0.5, 0.5), (-0.5, 0.5), (-2.5, -3.5), (5.0, -1.0)] {
+            let here = floor.material_at(Vec3::new(x, 0.0, z));
+            assert!(here == floor.mat || here == dark);
+            let other = if here == dark { floor.mat } else { dark };
+            for &step in &[
+                Vec3::unit_x(),
+                -Vec3::unit_x(),
+                Vec3::unit_z(),
+                -Vec3::unit_z(),
+            ] {
+                assert_eq!(floor.material_at(Vec3::new(x, 0.0, z) + step * 2.0), other);
+            }
+        }
+        assert_eq!(floor.material_at(Vec3::new(0.5, 0.0, 0.5)), floor.mat);
+        assert_eq!(floor.material_at(Vec3::new(-0.5, 0.0, 0.5)), dark);
+    }
+
+    #[test]
+    fn albedo_pass_test() {
+        let mut floor = plane(Vec3::unit_y(), 0.0);
+        floor.mat.diffuse = 0.7;
+        let floor = [floor];
+        // looking out level with the horizon, the bottom row sees the floor and the top row sky
+        let level = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::unit_z()),
+            right: -Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 1.0,
+            focus_dist: None,
+        };
+        assert_eq!(
+            albedo_pass(&floor, &level, Extent2::new(2, 2)),
+            vec![None, None, Some(0.7), Some(0.7)]
+        );
+    }
+
+    #[test]
+    fn nearest_checked_test() {
+        let floor = plane(Vec3::unit_y(), 0.0);
+        let wall = plane(-Vec3::unit_z(), 3.0);
+        let geometry = [floor, wall];
+        let pos = Vec3::new(0.0, 1.0, 0.0);
+        let rot = Vec3::new(0.0, -0.5, 1.0).normalized();
+        let (g, dist) = nearest_checked(&geometry, pos, rot).unwrap().unwrap();
+        let (n, n_dist) = nearest(&geometry, pos, rot).unwrap();
+        assert!(std::ptr::eq(g, n));
+        assert!((dist - n_dist).abs() < 1e-3);
+        // a degenerate shape whose estimate is NaN everywhere fails the whole ray
+        let mut nan = plane(Vec3::unit_y(), 0.0);
+        nan.geom.de = Sphere::new(Vec3::zero(), f64::NAN).into();
+        assert!(nearest_checked(&[geometry[1].clone(), nan], pos, rot).is_err());
+    }
+}
@@ -1,26 +1,55 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
+use std::fmt;
 use std::iter::Sum;
+use std::path::Path;
+use std::sync::Arc;
 
 use color_processing::Color;
 use num::Float;
 use palette::{rgb::Rgb, rgb::RgbStandard, Alpha, Component};
-use serde::{Deserialize, Serialize};
-use vek::{Extent2, Quaternion, Ray, Vec3};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use vek::{Extent2, Quaternion, Ray, Vec2, Vec3};
 
 use crate::camera;
 use crate::camera::Viewport;
 use crate::distance;
+use crate::expr::Expr;
 use crate::light;
 use crate::light::Material;
+use crate::mesh;
+use crate::messages::{Locale, Message};
 use crate::render;
+use crate::volume;
 
 /// Errors caused by an incorrect schema found while deserializing a scene, typically from YAML.
 #[derive(Debug, Clone, PartialEq)]
 pub enum SceneDeserializeErr {
     UnknownMaterial(String),
     UnknownCamera(String),
+    UnknownRig(String),
     ColorParseErr(String),
+    /// A camera's `focus_point` probe ray didn't hit any geometry.
+    FocusPointMissed(String),
+    /// A camera's `above_surface` probe ray didn't hit any geometry.
+    SurfaceMissed(String),
+    /// The foveation of the render through a camera doesn't fall off over a positive distance.
+    FoveationFalloff(String),
+}
+
+impl SceneDeserializeErr {
+    /// a description of the error for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            SceneDeserializeErr::UnknownMaterial(m) => Message::UnknownMaterial(m),
+            SceneDeserializeErr::UnknownCamera(c) => Message::UnknownCamera(c),
+            SceneDeserializeErr::UnknownRig(r) => Message::UnknownRig(r),
+            SceneDeserializeErr::ColorParseErr(c) => Message::ColorParse(c),
+            SceneDeserializeErr::FocusPointMissed(c) => Message::FocusPointMissed(c),
+            SceneDeserializeErr::SurfaceMissed(c) => Message::SurfaceMissed(c),
+            SceneDeserializeErr::FoveationFalloff(c) => Message::FoveationFalloff(c),
+        }
+    }
 }
 
 /// Wrapper around color_processing's Color::new_string which bridges it together with the palette
@@ -110,7 +139,7 @@ where
     rot: Vec3<T>,
 
     #[serde(flatten)]
-    col: Material<String>,
+    pub(crate) col: Material<String>,
 }
 
 impl<T, S, A> TryFrom<Light<T>> for light::Light<T, Alpha<Rgb<S, T>, A>>
@@ -130,29 +159,51 @@ where
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct Render {
+pub struct Render<T> {
     pub camera: String,
     pub width: usize,
+    #[serde(default = "Option::default")]
+    pub foveation: Option<camera::Foveation<T>>,
+    #[serde(default = "Option::default")]
+    pub normal_smoothing: Option<camera::NormalSmoothing<T>>,
 }
 
-impl Render {
-    pub fn into_render<'a, T>(
+impl<T> Render<T> {
+    /// Resolves the render's camera; the resulting `camera::Render` owns a copy of the viewport,
+    /// so it doesn't borrow from `cameras`.
+    pub fn into_render(
         &self,
-        cameras: &'a HashMap<String, Viewport<T>>,
+        cameras: &HashMap<String, Viewport<T>>,
     ) -> Result<camera::Render<T>, SceneDeserializeErr>
     where
         T: Float + Sum + Default,
     {
+        if let Some(foveation) = self.foveation {
+            // `Foveation::quality` divides by it
+            if foveation.falloff.is_nan() || foveation.falloff <= T::zero() {
+                return Err(SceneDeserializeErr::FoveationFalloff(self.camera.clone()));
+            }
+        }
         Ok(camera::Render {
             width: self.width,
-            view: cameras
-                .get(&self.camera.clone())
-                .ok_or_else(|| SceneDeserializeErr::UnknownCamera(self.camera.clone()))?
-                .clone(),
+            foveation: self.foveation,
+            normal_smoothing: self.normal_smoothing,
+            view: *cameras
+                .get(&self.camera)
+                .ok_or_else(|| SceneDeserializeErr::UnknownCamera(self.camera.clone()))?,
         })
     }
 }
 
+/// Several cameras rendered together, like front, side, top, and perspective views of the same
+/// geometry, side by side in one contact sheet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rig {
+    pub cameras: Vec<String>,
+    /// the width of every view, in pixels
+    pub width: usize,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Camera<T> {
     facing: Vec3<T>,
@@ -161,6 +212,57 @@ pub struct Camera<T> {
     focal_len: T,
     width: T,
     height: T,
+    /// screen-space point (as in `Viewport::ray`) to autofocus on
+    #[serde(default)]
+    focus_point: Option<Vec2<T>>,
+    /// places the camera relative to the surface under a probe ray instead of at `pos`
+    #[serde(default)]
+    above_surface: Option<SurfacePlacement<T>>,
+    /// how many times zoomed in from `focal_len` the camera is at the last frame of an animation;
+    /// each frame zooms in by the same factor, so the zoom looks steady
+    #[serde(default)]
+    zoom_end: Option<T>,
+}
+
+/// Places a camera `offset` units above the surface hit by marching from the camera's `pos` along
+/// `direction`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SurfacePlacement<T> {
+    direction: Vec3<T>,
+    offset: T,
+}
+
+impl<T> Camera<T>
+where
+    T: Float + Sum + Default,
+{
+    /// Converts the camera into a viewport, placing it relative to the surface if
+    /// `above_surface` is given and marching a probe ray through `focus_point` (if given) to set
+    /// the viewport's focus distance.
+    fn to_viewport(
+        &self,
+        name: &str,
+        geometry: &[render::RenderGeometry<T>],
+    ) -> Result<Viewport<T>, SceneDeserializeErr> {
+        let mut view: Viewport<T> = self.into();
+        if let Some(placement) = &self.above_surface {
+            view.cam.origin = render::above_surface(
+                geometry,
+                self.pos,
+                placement.direction.normalized(),
+                placement.offset,
+            )
+            .ok_or_else(|| SceneDeserializeErr::SurfaceMissed(name.to_owned()))?;
+        }
+        if let Some(point) = self.focus_point {
+            let (pos, rot) = view.ray(point);
+            view.focus_dist = Some(
+                render::nearest_hit(geometry, pos, rot)
+                    .ok_or_else(|| SceneDeserializeErr::FocusPointMissed(name.to_owned()))?,
+            );
+        }
+        Ok(view)
+    }
 }
 
 impl<T> From<&Camera<T>> for Viewport<T>
@@ -173,6 +275,7 @@ where
             right: cam.right,
             size: Extent2::new(cam.width, cam.height),
             focal_len: cam.focal_len,
+            focus_dist: None,
         }
     }
 }
@@ -181,215 +284,3218 @@ where
 struct EstimatorBase<T> {
     material: String,
     epsilon: T,
+    /// growth of epsilon with distance from the camera; see `distance::Geometry::epsilon_scale`
+    #[serde(default = "Option::default")]
+    epsilon_scale: Option<T>,
     cutoff: T,
     max_steps: usize,
+    /// limit on estimates per ray; see `distance::Geometry::max_evaluations`
+    #[serde(default = "Option::default")]
+    max_evaluations: Option<usize>,
+    /// shortest step as a fraction of epsilon; see `distance::Geometry::min_step`
+    #[serde(default = "Option::default")]
+    min_step: Option<T>,
+    /// secant steps toward the surface after a hit; see `distance::Geometry::refine_steps`
+    #[serde(default = "Option::default")]
+    refine_steps: Option<usize>,
+    #[serde(default = "Option::default")]
+    zoom_quality: Option<distance::ZoomQuality<T>>,
+    /// fewer iterations far along rays; see `distance::DistanceLod`
+    #[serde(default = "Option::default")]
+    lod: Option<distance::DistanceLod<T>>,
+    /// central differences if omitted
+    #[serde(default = "Option::default")]
+    normal_method: Option<distance::NormalMethod>,
+    /// over-relaxation factor for sphere tracing; see `distance::Geometry::relaxation`
+    #[serde(default = "Option::default")]
+    relaxation: Option<T>,
+    /// safety factor for estimators which overestimate; see `distance::Geometry::step_scale`
+    #[serde(default = "Option::default")]
+    step_scale: Option<T>,
+    #[serde(default = "Option::default")]
+    checker: Option<Checker<T>>,
+    #[serde(default = "Option::default")]
+    instance_gradient: Option<InstanceGradient>,
+    #[serde(default = "Option::default")]
+    orbit_trap: Option<TrapColoring<T>>,
 }
 
+/// Alternates a geometry's surface with another material in a checkerboard
 #[derive(Serialize, Deserialize)]
-pub struct Julia<T> {
-    c: Quaternion<T>,
-    iterations: usize,
+struct Checker<T> {
+    material: String,
+    size: T,
+}
 
-    #[serde(flatten)]
-    est: EstimatorBase<T>,
+/// Shades the copies of a `repeat` shape from the geometry's material to another; see
+/// `render::InstanceGradient`
+#[derive(Serialize, Deserialize)]
+struct InstanceGradient {
+    material: String,
 }
 
+/// Colors a fractal by how close its orbits come to a trap; see `render::TrapColoring`
 #[derive(Serialize, Deserialize)]
-#[serde(tag = "type")]
-#[serde(rename_all = "lowercase")]
-pub enum Geometry<T> {
-    Julia(Julia<T>),
+struct TrapColoring<T> {
+    #[serde(flatten)]
+    trap: distance::OrbitTrap<T>,
+    material: String,
+    falloff: T,
 }
 
-impl<T> From<&Julia<T>> for distance::Geometry<T>
+impl<T> EstimatorBase<T>
 where
     T: Float + Sum,
 {
-    fn from(julia: &Julia<T>) -> distance::Geometry<T> {
+    fn geometry(&self, de: distance::GeometryEstimator<T>) -> distance::Geometry<T> {
         distance::Geometry {
-            max_steps: julia.est.max_steps,
-            epsilon: julia.est.epsilon,
-            cutoff: julia.est.cutoff,
-            sample_size: julia.est.epsilon,
-            de: distance::Julia::new(julia.c, julia.iterations).into(),
+            max_steps: self.max_steps,
+            max_evaluations: self.max_evaluations,
+            epsilon: self.epsilon,
+            epsilon_scale: self.epsilon_scale,
+            cutoff: self.cutoff,
+            sample_size: self.epsilon,
+            normal_method: self
+                .normal_method
+                .unwrap_or(distance::NormalMethod::Central),
+            relaxation: self.relaxation,
+            step_scale: self.step_scale,
+            min_step: self.min_step,
+            refine_steps: self.refine_steps,
+            zoom_quality: self.zoom_quality,
+            lod: self.lod,
+            // set for the whole scene by `Scene::bounds`
+            bounds: None,
+            de,
         }
     }
 }
 
-fn into_render_geoms<T>(
-    geom: &Vec<Geometry<T>>,
-    materials: &HashMap<String, Material<T>>,
-) -> Result<Vec<render::RenderGeometry<T>>, SceneDeserializeErr>
+#[derive(Serialize, Deserialize)]
+pub struct Julia<T> {
+    c: Quaternion<T>,
+    /// animates `c` from its value at the first frame to this one at the last; see
+    /// `Scene::animate`
+    #[serde(default = "Option::default")]
+    c_end: Option<Quaternion<T>>,
+    /// how `c` moves toward `c_end`, and the slice toward `slice_end`; linear if omitted
+    #[serde(default = "Option::default")]
+    interpolation: Option<Interpolation>,
+    iterations: usize,
+    /// p in q ← qᵖ + c, which may be fractional; 2, for the usual quaternion Julia sets, if
+    /// omitted. Also accepted as `degree`, as in `degree: 3` for the cubic variant.
+    #[serde(default = "Option::default", alias = "degree")]
+    power: Option<T>,
+    /// which 3D cross-section of the 4D set to view; the real component is 0 if omitted
+    #[serde(default = "Option::default")]
+    slice: Option<distance::QuaternionSlice<T>>,
+    /// sweeps the slice through the fourth dimension, moving its fixed component from
+    /// `slice.value` at the first frame to this at the last; see `Scene::animate`
+    #[serde(default = "Option::default")]
+    slice_end: Option<T>,
+    /// `burning_ship` to fold q into the positive orthant before raising it to the power; the
+    /// standard iteration if omitted
+    #[serde(default = "Option::default")]
+    variant: Option<distance::JuliaVariant>,
+}
+
+/// How an animated value moves from its start to its end over an animation
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    /// at a constant rate
+    Linear,
+    /// easing in and out, slowly at the ends and fastest in the middle
+    Smooth,
+    /// along the great arc between the quaternions' directions, their magnitude changing
+    /// linearly; for moving through the Julia sets of quaternions of about the same size. The same
+    /// as linear for numbers.
+    Slerp,
+}
+
+impl Interpolation {
+    /// the number `t` of the way from `from` to `to`, for `t` from 0 to 1
+    pub fn scalar<T: Float>(self, from: T, to: T, t: T) -> T {
+        let t = match self {
+            Interpolation::Linear | Interpolation::Slerp => t,
+            Interpolation::Smooth => t * t * (T::from(3).unwrap() - T::from(2).unwrap() * t),
+        };
+        from + (to - from) * t
+    }
+
+    /// the value `t` of the way from `from` to `to`, for `t` from 0 to 1
+    pub fn quaternion<T: Float + Sum>(
+        self,
+        from: Quaternion<T>,
+        to: Quaternion<T>,
+        t: T,
+    ) -> Quaternion<T> {
+        let lerp = |t: T| from * (T::one() - t) + to * t;
+        match self {
+            Interpolation::Linear => lerp(t),
+            Interpolation::Smooth => lerp(t * t * (T::from(3).unwrap() - T::from(2).unwrap() * t)),
+            Interpolation::Slerp => {
+                let (from_mag, to_mag) = (from.magnitude(), to.magnitude());
+                if from_mag == T::zero() || to_mag == T::zero() {
+                    return lerp(t);
+                }
+                let (a, b) = (from / from_mag, to / to_mag);
+                let angle = a.dot(b).max(-T::one()).min(T::one()).acos();
+                // nearly parallel or opposite directions have no single arc between them
+                if angle.sin().abs() < T::epsilon().sqrt() {
+                    return lerp(t);
+                }
+                let direction =
+                    (a * ((T::one() - t) * angle).sin() + b * (t * angle).sin()) / angle.sin();
+                direction * (from_mag * (T::one() - t) + to_mag * t)
+            }
+        }
+    }
+}
+
+impl<T> Julia<T>
 where
-    T: Float + Sum + Default,
+    T: Float + Sum,
 {
-    geom.iter()
-        .map(|g| match g {
-            Geometry::Julia(j) => (&j.est.material, j.into()),
-        })
-        .map(|(m, g)| {
-            Ok(render::RenderGeometry {
-                mat: materials
-                    .get(m)
-                    .ok_or_else(|| SceneDeserializeErr::UnknownMaterial(m.clone()))?
-                    .clone()
-                    .into(),
-                geom: g,
-            })
+    /// fixes `c` and the slice at their values at time `t`; see `Scene::animate`
+    fn animate(&mut self, t: T) {
+        let interpolation = self.interpolation.unwrap_or(Interpolation::Linear);
+        if let Some(end) = self.c_end.take() {
+            self.c = interpolation.quaternion(self.c, end, t);
+        }
+        if let Some(end) = self.slice_end.take() {
+            let slice = self
+                .slice
+                .get_or_insert_with(distance::QuaternionSlice::real_zero);
+            slice.value = interpolation.scalar(slice.value, end, t);
+        }
+    }
+}
+
+/// The quaternion Mandelbrot set
+#[derive(Serialize, Deserialize)]
+pub struct QuaternionMandelbrot<T> {
+    /// the quaternion every point starts iterating from; 0 if omitted
+    #[serde(default = "Option::default")]
+    start: Option<Quaternion<T>>,
+    iterations: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Mandelbox<T> {
+    scale: T,
+    min_radius: T,
+    fold_limit: T,
+    iterations: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Sphere<T> {
+    center: Vec3<T>,
+    radius: T,
+}
+
+/// An axis-aligned box; `size` is the full width, height, and depth
+#[derive(Serialize, Deserialize)]
+pub struct Cuboid<T> {
+    center: Vec3<T>,
+    size: Vec3<T>,
+    /// radius to round the box's edges off by
+    #[serde(default = "Option::default")]
+    rounding: Option<T>,
+}
+
+/// A torus around the y axis
+#[derive(Serialize, Deserialize)]
+pub struct Torus<T> {
+    center: Vec3<T>,
+    major_radius: T,
+    minor_radius: T,
+}
+
+/// An infinite plane
+#[derive(Serialize, Deserialize)]
+pub struct Plane<T> {
+    normal: Vec3<T>,
+    offset: T,
+}
+
+/// A triply periodic minimal surface, thickened into a sheet filling all of space
+#[derive(Serialize, Deserialize)]
+pub struct Tpms<T> {
+    /// the length of the repeating cell
+    period: T,
+    /// the sheet's total thickness
+    thickness: T,
+}
+
+/// A line segment from `start` to `end` with rounded thickness `radius`
+#[derive(Serialize, Deserialize)]
+pub struct Capsule<T> {
+    start: Vec3<T>,
+    end: Vec3<T>,
+    radius: T,
+}
+
+/// An upright capped cylinder
+#[derive(Serialize, Deserialize)]
+pub struct Cylinder<T> {
+    center: Vec3<T>,
+    radius: T,
+    height: T,
+}
+
+/// Terrain below `y = amplitude * height(x, z)`, with heights from noise or a grid
+#[derive(Serialize, Deserialize)]
+pub struct Heightfield<T> {
+    amplitude: T,
+    #[serde(flatten)]
+    source: HeightSource<T>,
+}
+
+/// The heights of a `Heightfield`: fractal noise, or a grid of samples like a heightmap image
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HeightSource<T> {
+    Grid {
+        /// rows of heights from -1 to 1, from -z to +z, each from -x to +x
+        heights: Vec<Vec<T>>,
+        /// the grid's extent in x and z, centered on the origin
+        size: Extent2<T>,
+    },
+    Noise {
+        frequency: T,
+        /// 5 if omitted
+        #[serde(default = "Option::default")]
+        octaves: Option<usize>,
+        #[serde(default = "u32::default")]
+        seed: u32,
+    },
+}
+
+/// A triangle mesh from an OBJ or STL file, baked into a signed distance grid
+#[derive(Serialize, Deserialize)]
+pub struct Mesh {
+    /// the mesh file, relative to the working directory
+    file: MeshFile,
+    /// grid samples along the mesh's longest side; 64 if omitted
+    #[serde(default = "Option::default")]
+    resolution: Option<usize>,
+}
+
+/// A mesh loaded when the scene is, so a missing or malformed file fails like a syntax error
+pub struct MeshFile {
+    path: String,
+    mesh: Arc<mesh::Mesh>,
+}
+
+impl Serialize for MeshFile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.path)
+    }
+}
+
+impl<'de> Deserialize<'de> for MeshFile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        let mesh = mesh::Mesh::load(Path::new(&path)).map_err(de::Error::custom)?;
+        Ok(MeshFile {
+            path,
+            mesh: Arc::new(mesh),
         })
-        .collect()
+    }
 }
 
-#[derive(Serialize, Deserialize, Default)]
-pub struct Scene<T>
-where
-    T: Float + Sum + Default + Clone,
-{
-    pub geometry: Vec<Geometry<T>>,
-    pub materials: HashMap<String, Material<T>>,
-    pub lights: Vec<Light<T>>,
-    pub cameras: HashMap<String, Camera<T>>,
-    pub renders: Vec<Render>,
+/// A signed distance volume from another program, loaded when the scene is like `MeshFile`; see
+/// `volume::Volume::load`
+pub struct Volume<T> {
+    spec: VolumeSpec<T>,
+    volume: Arc<volume::Volume>,
 }
 
-impl<T, S, A> TryFrom<&Scene<T>> for render::Scene<T, Alpha<Rgb<S, T>, A>>
-where
-    T: Float + Sum + Default + Clone + Component,
-    S: RgbStandard,
-    A: Component,
-{
-    type Error = SceneDeserializeErr;
+/// A volume as written in a scene file
+#[derive(Serialize, Deserialize)]
+pub struct VolumeSpec<T> {
+    /// an `.nrrd` file, or raw little-endian 32-bit floats; relative to the working directory
+    file: String,
+    /// the raw file's dimensions; NRRD files give their own
+    #[serde(default = "Option::default")]
+    samples: Option<Vec3<usize>>,
+    /// the distance between samples, overriding the file's; 1 if neither gives it
+    #[serde(default = "Option::default")]
+    spacing: Option<T>,
+    /// the grid's first corner, overriding the file's; centered on the origin if neither gives it
+    #[serde(default = "Option::default")]
+    origin: Option<Vec3<T>>,
+}
 
-    fn try_from(
-        scene: &Scene<T>,
-    ) -> Result<render::Scene<T, Alpha<Rgb<S, T>, A>>, SceneDeserializeErr> {
-        let viewports: HashMap<String, Viewport<T>> = scene
-            .cameras
-            .iter()
-            .map(|(s, c)| (s.to_owned(), c.into()))
-            .collect();
+impl<T: Serialize> Serialize for Volume<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.spec.serialize(serializer)
+    }
+}
 
-        Ok(render::Scene {
-            geometry: into_render_geoms(&scene.geometry, &scene.materials)?,
-            lights: scene
-                .lights
-                .iter()
-                .map(|l| l.clone().try_into())
-                .collect::<Result<Vec<light::Light<_, _>>, SceneDeserializeErr>>()?,
-            renders: scene
-                .renders
-                .iter()
-                .map(|r| r.into_render(&viewports))
-                .collect::<Result<Vec<camera::Render<T>>, SceneDeserializeErr>>()?,
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Volume<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let spec = VolumeSpec::deserialize(deserializer)?;
+        let volume =
+            volume::Volume::load(Path::new(&spec.file), spec.samples).map_err(de::Error::custom)?;
+        Ok(Volume {
+            spec,
+            volume: Arc::new(volume),
         })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use indoc::indoc;
-    use palette::Srgba;
-    use pretty_assertions::{assert_eq, assert_ne};
-    use serde_yaml;
-    use std::convert::{TryFrom, TryInto};
-    use vek::Vec3;
+/// A Sierpinski tetrahedron
+#[derive(Serialize, Deserialize)]
+pub struct Sierpinski<T> {
+    scale: T,
+    iterations: usize,
+}
 
-    use super::{Camera, Light, Render};
-    use crate::light;
+/// A pseudo-Kleinian limit set; omitted parameters take their standard values (see
+/// `distance::Kleinian::STANDARD_FOLD` and friends)
+#[derive(Serialize, Deserialize)]
+pub struct Kleinian<T> {
+    #[serde(default = "Option::default")]
+    fold: Option<Vec3<T>>,
+    #[serde(default = "Option::default")]
+    inversion_radius: Option<T>,
+    #[serde(default = "Option::default")]
+    core_radius: Option<T>,
+    iterations: usize,
+}
 
-    #[test]
-    fn render_deser_test() {
-        let render: Render = serde_yaml::from_str(indoc!(
-            "
-                camera: main
-                width: 300
-                "
-        ))
-        .unwrap();
-        assert_eq!(
-            render,
-            Render {
-                camera: "main".to_owned(),
-                width: 300,
+/// A rotation: a unit quaternion (x, y, z, then the real part w), an angle in radians around an
+/// axis, or Euler angles in radians
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Rotation<T> {
+    AxisAngle {
+        axis: Vec3<T>,
+        angle: T,
+    },
+    /// rotations around the x axis, then y, then z, each around the fixed world axes
+    Euler {
+        euler: Vec3<T>,
+    },
+    Quaternion(Quaternion<T>),
+}
+
+impl<T> Rotation<T>
+where
+    T: Float + Sum,
+{
+    /// how far a quaternion's magnitude may be from 1 before it's reported; hand-typed
+    /// quaternions are rarely off by more than rounding
+    pub const TOLERANCE: f64 = 1e-3;
+
+    /// the rotation as a unit quaternion; quaternions are normalized, and the zero quaternion,
+    /// which isn't a rotation at all, is treated as the identity
+    pub fn quaternion(&self) -> Quaternion<T> {
+        match *self {
+            Rotation::AxisAngle { axis, angle } => Quaternion::rotation_3d(angle, axis),
+            Rotation::Euler { euler } => {
+                Quaternion::rotation_z(euler.z)
+                    * Quaternion::rotation_y(euler.y)
+                    * Quaternion::rotation_x(euler.x)
             }
-        );
+            Rotation::Quaternion(q) if q.magnitude() == T::zero() => Quaternion::identity(),
+            Rotation::Quaternion(q) => q.normalized(),
+        }
     }
 
-    #[test]
-    fn render_vec_deser_test() {
-        let render: Vec<Render> = serde_yaml::from_str(indoc!(
-            "
-                - camera: main
-                  width: 300
-                - camera: xyz
-                  width: 20000
-                "
-        ))
-        .unwrap();
-        assert_eq!(
-            render,
-            vec!(
-                Render {
-                    camera: "main".to_owned(),
-                    width: 300,
-                },
-                Render {
-                    camera: "xyz".to_owned(),
-                    width: 20000,
+    /// the quaternion's magnitude, if it's further from 1 than `TOLERANCE`
+    fn deviation(&self) -> Option<T> {
+        match *self {
+            Rotation::AxisAngle { .. } | Rotation::Euler { .. } => None,
+            Rotation::Quaternion(q) => {
+                let magnitude = q.magnitude();
+                if (magnitude - T::one()).abs() > T::from(Self::TOLERANCE).unwrap() {
+                    Some(magnitude)
+                } else {
+                    None
                 }
-            )
-        );
+            }
+        }
     }
+}
 
-    #[test]
-    fn camera_deser_test() {
-        let cam: Camera<f64> = serde_yaml::from_str(indoc!(
-            "
-                facing: [1, 0, 0]
-                right: [0, 1, 0]
-                pos: [0, 0, 0]
-                focal_len: 10
-                width: 3
-                height: 2
-                "
-        ))
-        .unwrap();
-        assert_eq!(
-            cam,
-            Camera {
-                facing: Vec3::new(1.0, 0.0, 0.0),
-                right: Vec3::new(0.0, 1.0, 0.0),
-                pos: Vec3::new(0.0, 0.0, 0.0),
-                focal_len: 10.0,
-                width: 3.0,
-                height: 2.0,
-            }
-        );
+/// One map of an iterated function system
+#[derive(Serialize, Deserialize)]
+pub struct AffineMap<T> {
+    /// normals of planes through the origin to fold across before transforming
+    #[serde(default = "Vec::new")]
+    folds: Vec<Vec3<T>>,
+    #[serde(default = "Option::default")]
+    rotation: Option<Rotation<T>>,
+    scale: T,
+    #[serde(default = "Option::default")]
+    translation: Option<Vec3<T>>,
+}
+
+impl<T> From<&AffineMap<T>> for distance::AffineMap<T>
+where
+    T: Float + Sum,
+{
+    fn from(map: &AffineMap<T>) -> distance::AffineMap<T> {
+        distance::AffineMap {
+            folds: map.folds.clone(),
+            rotation: map
+                .rotation
+                .map_or_else(Quaternion::identity, |r| r.quaternion()),
+            scale: map.scale,
+            translation: map.translation.unwrap_or_else(Vec3::zero),
+        }
     }
+}
 
-    #[test]
-    fn light_deser_test() {
-        let light_unparsed: Light<f32> = serde_yaml::from_str(indoc!(
-            "
-            facing: [0, 0, 0]
-            specular: rgba(255, 255, 255, 1)
-            diffuse: rgba(255, 255, 255, 1)
-            ambient: rgba(255, 255, 127, 1)
-            "
-        ))
-        .unwrap();
+/// An iterated function system; every map is applied in order on each iteration
+#[derive(Serialize, Deserialize)]
+pub struct Ifs<T> {
+    maps: Vec<AffineMap<T>>,
+    iterations: usize,
+}
+
+/// One step of a hybrid fractal's iteration
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "formula", rename_all = "lowercase")]
+pub enum HybridFormula<T> {
+    Mandelbox {
+        scale: T,
+        min_radius: T,
+        fold_limit: T,
+    },
+    Bulb {
+        power: T,
+    },
+}
+
+impl<T> From<&HybridFormula<T>> for distance::HybridFormula<T>
+where
+    T: Float + Sum,
+{
+    fn from(formula: &HybridFormula<T>) -> distance::HybridFormula<T> {
+        match *formula {
+            HybridFormula::Mandelbox {
+                scale,
+                min_radius,
+                fold_limit,
+            } => distance::HybridFormula::Mandelbox {
+                scale,
+                min_radius,
+                fold_limit,
+            },
+            HybridFormula::Bulb { power } => distance::HybridFormula::Bulb { power },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HybridStep<T> {
+    #[serde(flatten)]
+    formula: HybridFormula<T>,
+    /// how many times in a row to apply the formula; 1 if omitted
+    #[serde(default = "Option::default")]
+    repeat: Option<usize>,
+}
+
+/// A hybrid fractal; every step's formula is applied in order on each iteration
+#[derive(Serialize, Deserialize)]
+pub struct Hybrid<T> {
+    formulas: Vec<HybridStep<T>>,
+    iterations: usize,
+}
+
+/// A fractal with a custom iteration step; see `distance::Formula` and `expr`
+#[derive(Serialize, Deserialize)]
+pub struct Formula<T> {
+    step: Expr,
+    /// the Julia set constant; the sample point if omitted
+    #[serde(default = "Option::default")]
+    c: Option<Quaternion<T>>,
+    iterations: usize,
+}
+
+/// A sphere to invert points through
+#[derive(Serialize, Deserialize)]
+pub struct InversionSphere<T> {
+    center: Vec3<T>,
+    radius: T,
+}
+
+/// An Apollonian-packing style fractal
+#[derive(Serialize, Deserialize)]
+pub struct Apollonian<T> {
+    spheres: Vec<InversionSphere<T>>,
+    period: T,
+    iterations: usize,
+}
+
+/// A shape; either one of the estimators or a combination of other shapes
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "lowercase")]
+pub enum Shape<T> {
+    Julia(Julia<T>),
+    Mandelbox(Mandelbox<T>),
+    Sphere(Sphere<T>),
+    #[serde(rename = "box")]
+    Cuboid(Cuboid<T>),
+    Torus(Torus<T>),
+    Plane(Plane<T>),
+    Gyroid(Tpms<T>),
+    #[serde(rename = "schwarz_p")]
+    SchwarzP(Tpms<T>),
+    Capsule(Capsule<T>),
+    Cylinder(Cylinder<T>),
+    Heightfield(Heightfield<T>),
+    Mesh(Mesh),
+    Volume(Volume<T>),
+    Sierpinski(Sierpinski<T>),
+    Kleinian(Kleinian<T>),
+    Ifs(Ifs<T>),
+    Apollonian(Apollonian<T>),
+    #[serde(rename = "quaternion_mandelbrot")]
+    QuaternionMandelbrot(QuaternionMandelbrot<T>),
+    Hybrid(Hybrid<T>),
+    Formula(Formula<T>),
+    Union(Combination<T>),
+    #[serde(rename = "smooth_union")]
+    SmoothUnion(SmoothUnion<T>),
+    #[serde(alias = "blend")]
+    Morph(Morph<T>),
+    Intersection(Combination<T>),
+    Difference(Combination<T>),
+    Twist(Twist<T>),
+    Bend(Bend<T>),
+    Taper(Taper<T>),
+    Elongate(Elongate<T>),
+    Mirror(Mirror<T>),
+    Symmetry(Symmetry<T>),
+    Repeat(Repeat<T>),
+    Onion(Onion<T>),
+    Round(Round<T>),
+    Displace(Displace<T>),
+    Warp(Warp<T>),
+    Bounded(Bounded<T>),
+}
+
+/// Two shapes combined by a union, intersection, or difference (`a` minus `b`)
+#[derive(Serialize, Deserialize)]
+pub struct Combination<T> {
+    a: Box<Node<T>>,
+    b: Box<Node<T>>,
+}
+
+/// A union of two shapes, blended together where they're within `k` of each other
+#[derive(Serialize, Deserialize)]
+pub struct SmoothUnion<T> {
+    a: Box<Node<T>>,
+    b: Box<Node<T>>,
+    k: T,
+}
+
+/// A shape `t` of the way from `a` to `b`; see `distance::Morph`. Also accepted as `type: blend`.
+#[derive(Serialize, Deserialize)]
+pub struct Morph<T> {
+    a: Box<Node<T>>,
+    b: Box<Node<T>>,
+    t: T,
+    /// animates `t` from its value at the first frame to this one at the last; see
+    /// `Scene::animate`
+    #[serde(default = "Option::default")]
+    t_end: Option<T>,
+    /// how `t` moves toward `t_end`; linear if omitted
+    #[serde(default = "Option::default")]
+    interpolation: Option<Interpolation>,
+}
+
+impl<T> Morph<T>
+where
+    T: Float + Sum,
+{
+    /// fixes `t` at its value at time `time`; see `Scene::animate`
+    fn animate(&mut self, time: T) {
+        if let Some(end) = self.t_end.take() {
+            self.t = self
+                .interpolation
+                .unwrap_or(Interpolation::Linear)
+                .scalar(self.t, end, time);
+        }
+    }
+}
+
+/// A shape twisted around an axis through the origin (y if omitted) by `amount` radians per unit
+#[derive(Serialize, Deserialize)]
+pub struct Twist<T> {
+    shape: Box<Node<T>>,
+    amount: T,
+    #[serde(default = "Option::default")]
+    axis: Option<Vec3<T>>,
+}
+
+/// A shape bent in the xy plane by `amount` radians per unit along x
+#[derive(Serialize, Deserialize)]
+pub struct Bend<T> {
+    shape: Box<Node<T>>,
+    amount: T,
+}
+
+/// A shape tapered along an axis through the origin; see `distance::Taper`
+#[derive(Serialize, Deserialize)]
+pub struct Taper<T> {
+    shape: Box<Node<T>>,
+    rate: T,
+    /// the y axis if omitted
+    #[serde(default = "Option::default")]
+    axis: Option<Vec3<T>>,
+}
+
+/// A shape stretched by `amount` along each axis from the origin; see `distance::Elongate`
+#[derive(Serialize, Deserialize)]
+pub struct Elongate<T> {
+    shape: Box<Node<T>>,
+    amount: Vec3<T>,
+}
+
+/// A deformation of a shape's space, like the `twist`, `bend`, `taper`, and `elongate` shapes but
+/// listed under a shape's `modifiers` instead of wrapping it
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "lowercase")]
+pub enum Modifier<T> {
+    Twist {
+        amount: T,
+        /// the y axis if omitted
+        #[serde(default = "Option::default")]
+        axis: Option<Vec3<T>>,
+    },
+    Bend {
+        amount: T,
+    },
+    Taper {
+        rate: T,
+        /// the y axis if omitted
+        #[serde(default = "Option::default")]
+        axis: Option<Vec3<T>>,
+    },
+    Elongate {
+        amount: Vec3<T>,
+    },
+}
+
+impl<T> Modifier<T>
+where
+    T: Float + Sum,
+{
+    /// `de` deformed by the modifier
+    fn apply(&self, de: distance::GeometryEstimator<T>) -> distance::GeometryEstimator<T> {
+        match *self {
+            Modifier::Twist { amount, axis } => {
+                distance::Twist::new(de, amount, axis.unwrap_or_else(Vec3::unit_y)).into()
+            }
+            Modifier::Bend { amount } => distance::Bend::new(de, amount).into(),
+            Modifier::Taper { rate, axis } => {
+                distance::Taper::new(de, rate, axis.unwrap_or_else(Vec3::unit_y)).into()
+            }
+            Modifier::Elongate { amount } => distance::Elongate::new(de, amount).into(),
+        }
+    }
+}
+
+/// A shape mirrored across a plane, keeping only the half in front of it (as in `Plane`)
+#[derive(Serialize, Deserialize)]
+pub struct Mirror<T> {
+    shape: Box<Node<T>>,
+    normal: Vec3<T>,
+    #[serde(default = "Option::default")]
+    offset: Option<T>,
+}
+
+/// A shape repeated `count` times around an axis through the origin; see
+/// `distance::Symmetry`
+#[derive(Serialize, Deserialize)]
+pub struct Symmetry<T> {
+    shape: Box<Node<T>>,
+    count: usize,
+    /// the y axis if omitted
+    #[serde(default = "Option::default")]
+    axis: Option<Vec3<T>>,
+}
+
+/// A shape repeated in a grid of `count` copies along each axis, `spacing` apart and centered on
+/// the origin; see `distance::Repeat`
+#[derive(Serialize, Deserialize)]
+pub struct Repeat<T> {
+    shape: Box<Node<T>>,
+    spacing: Vec3<T>,
+    count: Vec3<usize>,
+}
+
+/// A shape hollowed into a shell `thickness` thick around its surface
+#[derive(Serialize, Deserialize)]
+pub struct Onion<T> {
+    shape: Box<Node<T>>,
+    thickness: T,
+}
+
+/// A shape with its edges rounded by `radius`, which also grows it by `radius`
+#[derive(Serialize, Deserialize)]
+pub struct Round<T> {
+    shape: Box<Node<T>>,
+    radius: T,
+}
+
+/// A shape melted by moving its sample points with noise; see `distance::Warp`
+#[derive(Serialize, Deserialize)]
+pub struct Warp<T> {
+    shape: Box<Node<T>>,
+    amplitude: T,
+    frequency: T,
+    /// layers of noise, each finer than the last; 1 if omitted
+    #[serde(default = "Option::default")]
+    octaves: Option<usize>,
+    /// picks the noise pattern; 0 if omitted
+    #[serde(default = "u32::default")]
+    seed: u32,
+}
+
+/// A shape with its surface roughened by noise; see `distance::Displace`
+#[derive(Serialize, Deserialize)]
+pub struct Displace<T> {
+    shape: Box<Node<T>>,
+    amplitude: T,
+    frequency: T,
+    /// picks the noise pattern; 0 if omitted
+    #[serde(default = "u32::default")]
+    seed: u32,
+}
+
+/// A shape with a cheap bounding shape to skip its estimator far away; see `distance::Bounded`
+#[derive(Serialize, Deserialize)]
+pub struct Bounded<T> {
+    shape: Box<Node<T>>,
+    /// a simple shape, like a sphere or box, containing all of `shape`
+    bound: Box<Node<T>>,
+    /// how close to the bound to start estimating `shape`; must be larger than `epsilon`
+    margin: T,
+}
+
+/// Scales a shape about the origin, rotates it, then translates it
+#[derive(Serialize, Deserialize)]
+pub struct Transform<T> {
+    #[serde(default = "Option::default")]
+    translate: Option<Vec3<T>>,
+    #[serde(default = "Option::default")]
+    rotate: Option<Rotation<T>>,
+    #[serde(default = "Option::default")]
+    scale: Option<T>,
+}
+
+impl<T> Shape<T> {
+    /// every rotation within the shape, including those of nested shapes' transforms
+    fn rotations(&self) -> Vec<&Rotation<T>> {
+        let nodes: Vec<&Node<T>> = match self {
+            Shape::Union(c) | Shape::Intersection(c) | Shape::Difference(c) => vec![&c.a, &c.b],
+            Shape::SmoothUnion(u) => vec![&u.a, &u.b],
+            Shape::Morph(m) => vec![&m.a, &m.b],
+            Shape::Twist(t) => vec![&t.shape],
+            Shape::Bend(b) => vec![&b.shape],
+            Shape::Taper(t) => vec![&t.shape],
+            Shape::Elongate(e) => vec![&e.shape],
+            Shape::Mirror(m) => vec![&m.shape],
+            Shape::Symmetry(s) => vec![&s.shape],
+            Shape::Repeat(r) => vec![&r.shape],
+            Shape::Onion(o) => vec![&o.shape],
+            Shape::Round(r) => vec![&r.shape],
+            Shape::Displace(d) => vec![&d.shape],
+            Shape::Warp(w) => vec![&w.shape],
+            Shape::Bounded(b) => vec![&b.shape, &b.bound],
+            Shape::Ifs(i) => {
+                return i.maps.iter().filter_map(|m| m.rotation.as_ref()).collect();
+            }
+            _ => vec![],
+        };
+        nodes
+            .into_iter()
+            .flat_map(|n| {
+                let mut rotations = n.shape.rotations();
+                rotations.extend(n.transform.as_ref().and_then(|t| t.rotate.as_ref()));
+                rotations
+            })
+            .collect()
+    }
+
+    /// the shapes this one combines or modifies
+    fn children_mut(&mut self) -> Vec<&mut Shape<T>> {
+        let nodes: Vec<&mut Node<T>> = match self {
+            Shape::Union(c) | Shape::Intersection(c) | Shape::Difference(c) => {
+                vec![&mut c.a, &mut c.b]
+            }
+            Shape::SmoothUnion(u) => vec![&mut u.a, &mut u.b],
+            Shape::Morph(m) => vec![&mut m.a, &mut m.b],
+            Shape::Twist(t) => vec![&mut t.shape],
+            Shape::Bend(b) => vec![&mut b.shape],
+            Shape::Taper(t) => vec![&mut t.shape],
+            Shape::Elongate(e) => vec![&mut e.shape],
+            Shape::Mirror(m) => vec![&mut m.shape],
+            Shape::Symmetry(s) => vec![&mut s.shape],
+            Shape::Repeat(r) => vec![&mut r.shape],
+            Shape::Onion(o) => vec![&mut o.shape],
+            Shape::Round(r) => vec![&mut r.shape],
+            Shape::Displace(d) => vec![&mut d.shape],
+            Shape::Warp(w) => vec![&mut w.shape],
+            Shape::Bounded(b) => vec![&mut b.shape, &mut b.bound],
+            _ => vec![],
+        };
+        nodes.into_iter().map(|n| &mut n.shape).collect()
+    }
+
+    /// Halves the iterations of this shape and every shape inside it, and the resolution meshes
+    /// are baked at; see `Scene::proxy`
+    fn simplify(&mut self) {
+        let halve = |n: usize| n.div_ceil(2).max(1);
+        match self {
+            Shape::Julia(j) => j.iterations = halve(j.iterations),
+            Shape::Mandelbox(m) => m.iterations = halve(m.iterations),
+            Shape::Sierpinski(s) => s.iterations = halve(s.iterations),
+            Shape::Kleinian(k) => k.iterations = halve(k.iterations),
+            Shape::Ifs(i) => i.iterations = halve(i.iterations),
+            Shape::Apollonian(a) => a.iterations = halve(a.iterations),
+            Shape::QuaternionMandelbrot(q) => q.iterations = halve(q.iterations),
+            Shape::Hybrid(h) => h.iterations = halve(h.iterations),
+            Shape::Formula(f) => f.iterations = halve(f.iterations),
+            Shape::Mesh(m) => m.resolution = Some(halve(m.resolution.unwrap_or(64))),
+            _ => {}
+        }
+        for child in self.children_mut() {
+            child.simplify();
+        }
+    }
+
+    /// fixes every animated value of this shape and the shapes inside it at time `t`
+    fn animate(&mut self, t: T)
+    where
+        T: Float + Sum,
+    {
+        match self {
+            Shape::Julia(j) => j.animate(t),
+            Shape::Morph(m) => m.animate(t),
+            _ => {}
+        }
+        for child in self.children_mut() {
+            child.animate(t);
+        }
+    }
+
+    /// the shape's `type` in scene files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::Julia(_) => "julia",
+            Shape::Mandelbox(_) => "mandelbox",
+            Shape::Sphere(_) => "sphere",
+            Shape::Cuboid(_) => "box",
+            Shape::Torus(_) => "torus",
+            Shape::Plane(_) => "plane",
+            Shape::Gyroid(_) => "gyroid",
+            Shape::SchwarzP(_) => "schwarz_p",
+            Shape::Capsule(_) => "capsule",
+            Shape::Cylinder(_) => "cylinder",
+            Shape::Heightfield(_) => "heightfield",
+            Shape::Mesh(_) => "mesh",
+            Shape::Volume(_) => "volume",
+            Shape::Sierpinski(_) => "sierpinski",
+            Shape::Kleinian(_) => "kleinian",
+            Shape::Ifs(_) => "ifs",
+            Shape::Apollonian(_) => "apollonian",
+            Shape::QuaternionMandelbrot(_) => "quaternion_mandelbrot",
+            Shape::Hybrid(_) => "hybrid",
+            Shape::Formula(_) => "formula",
+            Shape::Union(_) => "union",
+            Shape::SmoothUnion(_) => "smooth_union",
+            Shape::Morph(_) => "morph",
+            Shape::Intersection(_) => "intersection",
+            Shape::Difference(_) => "difference",
+            Shape::Twist(_) => "twist",
+            Shape::Bend(_) => "bend",
+            Shape::Taper(_) => "taper",
+            Shape::Elongate(_) => "elongate",
+            Shape::Mirror(_) => "mirror",
+            Shape::Symmetry(_) => "symmetry",
+            Shape::Repeat(_) => "repeat",
+            Shape::Onion(_) => "onion",
+            Shape::Round(_) => "round",
+            Shape::Displace(_) => "displace",
+            Shape::Warp(_) => "warp",
+            Shape::Bounded(_) => "bounded",
+        }
+    }
+}
+
+/// A shape nested inside another, with its own transform
+#[derive(Serialize, Deserialize)]
+pub struct Node<T> {
+    #[serde(flatten)]
+    shape: Shape<T>,
+    /// deformations of the shape, applied in order before its transform
+    #[serde(default = "Vec::new")]
+    modifiers: Vec<Modifier<T>>,
+    #[serde(default = "Option::default")]
+    transform: Option<Transform<T>>,
+}
+
+/// A shape in the scene, with the material and marching parameters to render it with
+#[derive(Serialize, Deserialize)]
+pub struct Geometry<T> {
+    #[serde(flatten)]
+    shape: Shape<T>,
+    /// deformations of the shape, applied in order before its transform
+    #[serde(default = "Vec::new")]
+    modifiers: Vec<Modifier<T>>,
+    #[serde(default = "Option::default")]
+    transform: Option<Transform<T>>,
+
+    #[serde(flatten)]
+    est: EstimatorBase<T>,
+}
+
+impl<T> Geometry<T> {
+    /// the `type` of the geometry's shape
+    pub fn name(&self) -> &'static str {
+        self.shape.name()
+    }
+}
+
+impl<T> From<&Julia<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(julia: &Julia<T>) -> distance::GeometryEstimator<T> {
+        distance::Julia::new(
+            julia.c,
+            julia.iterations,
+            julia.power.unwrap_or_else(|| T::from(2).unwrap()),
+            julia
+                .slice
+                .unwrap_or_else(distance::QuaternionSlice::real_zero),
+        )
+        .with_variant(julia.variant.unwrap_or(distance::JuliaVariant::Standard))
+        .into()
+    }
+}
+
+impl<T> From<&Mandelbox<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(mandelbox: &Mandelbox<T>) -> distance::GeometryEstimator<T> {
+        distance::Mandelbox::new(
+            mandelbox.scale,
+            mandelbox.min_radius,
+            mandelbox.fold_limit,
+            mandelbox.iterations,
+        )
+        .into()
+    }
+}
+
+impl<T> From<&Sphere<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(sphere: &Sphere<T>) -> distance::GeometryEstimator<T> {
+        distance::Sphere::new(sphere.center, sphere.radius).into()
+    }
+}
+
+impl<T> From<&Cuboid<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(cuboid: &Cuboid<T>) -> distance::GeometryEstimator<T> {
+        distance::Cuboid::new(
+            cuboid.center,
+            cuboid.size / T::from(2).unwrap(),
+            cuboid.rounding.unwrap_or_else(T::zero),
+        )
+        .into()
+    }
+}
+
+impl<T> From<&Torus<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(torus: &Torus<T>) -> distance::GeometryEstimator<T> {
+        distance::Torus::new(torus.center, torus.major_radius, torus.minor_radius).into()
+    }
+}
+
+impl<T> From<&Plane<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(plane: &Plane<T>) -> distance::GeometryEstimator<T> {
+        distance::Plane::new(plane.normal, plane.offset).into()
+    }
+}
+
+impl<T> From<&Capsule<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(capsule: &Capsule<T>) -> distance::GeometryEstimator<T> {
+        distance::Capsule::new(capsule.start, capsule.end, capsule.radius).into()
+    }
+}
+
+impl<T> From<&Cylinder<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(cylinder: &Cylinder<T>) -> distance::GeometryEstimator<T> {
+        distance::Cylinder::new(cylinder.center, cylinder.radius, cylinder.height).into()
+    }
+}
+
+impl<T> From<&Mesh> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(mesh: &Mesh) -> distance::GeometryEstimator<T> {
+        mesh.file.mesh.bake(mesh.resolution.unwrap_or(64)).into()
+    }
+}
+
+impl<T> From<&Volume<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(v: &Volume<T>) -> distance::GeometryEstimator<T> {
+        let to_f64 = |c: T| c.to_f64().unwrap();
+        v.volume
+            .grid(
+                v.spec.spacing.map(|s| Vec3::broadcast(to_f64(s))),
+                v.spec.origin.map(|o| o.map(to_f64)),
+            )
+            .into()
+    }
+}
+
+impl<T> From<&Heightfield<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(heightfield: &Heightfield<T>) -> distance::GeometryEstimator<T> {
+        let source = match &heightfield.source {
+            HeightSource::Grid { heights, size } => {
+                // short rows are extended from their last height, like the grid's edges
+                let width = heights.first().map_or(0, Vec::len);
+                distance::HeightSource::Grid {
+                    heights: heights
+                        .iter()
+                        .flat_map(|row| {
+                            (0..width).map(move |x| {
+                                row.get(x)
+                                    .or_else(|| row.last())
+                                    .cloned()
+                                    .unwrap_or_else(T::zero)
+                            })
+                        })
+                        .collect(),
+                    resolution: Extent2::new(width, heights.len()),
+                    size: *size,
+                }
+            }
+            HeightSource::Noise {
+                frequency,
+                octaves,
+                seed,
+            } => distance::HeightSource::Noise {
+                frequency: *frequency,
+                octaves: octaves.unwrap_or(5),
+                seed: *seed,
+            },
+        };
+        distance::Heightfield::new(source, heightfield.amplitude).into()
+    }
+}
+
+impl<T> From<&Sierpinski<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(sierpinski: &Sierpinski<T>) -> distance::GeometryEstimator<T> {
+        distance::Sierpinski::new(sierpinski.scale, sierpinski.iterations).into()
+    }
+}
+
+impl<T> From<&Kleinian<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(kleinian: &Kleinian<T>) -> distance::GeometryEstimator<T> {
+        distance::Kleinian::new(
+            kleinian.fold.unwrap_or_else(|| {
+                Vec3::<f64>::from(distance::Kleinian::<T>::STANDARD_FOLD)
+                    .map(|c| T::from(c).unwrap())
+            }),
+            kleinian.inversion_radius.unwrap_or_else(|| {
+                T::from(distance::Kleinian::<T>::STANDARD_INVERSION_RADIUS).unwrap()
+            }),
+            kleinian
+                .core_radius
+                .unwrap_or_else(|| T::from(distance::Kleinian::<T>::STANDARD_CORE_RADIUS).unwrap()),
+            kleinian.iterations,
+        )
+        .into()
+    }
+}
+
+impl<T> From<&Ifs<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(ifs: &Ifs<T>) -> distance::GeometryEstimator<T> {
+        distance::Ifs::new(ifs.maps.iter().map(Into::into).collect(), ifs.iterations).into()
+    }
+}
+
+impl<T> From<&Apollonian<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(apollonian: &Apollonian<T>) -> distance::GeometryEstimator<T> {
+        distance::Apollonian::new(
+            apollonian
+                .spheres
+                .iter()
+                .map(|s| (s.center, s.radius))
+                .collect(),
+            apollonian.period,
+            apollonian.iterations,
+        )
+        .into()
+    }
+}
+
+impl<T> From<&QuaternionMandelbrot<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(mandelbrot: &QuaternionMandelbrot<T>) -> distance::GeometryEstimator<T> {
+        distance::QuaternionMandelbrot::new(
+            mandelbrot.start.unwrap_or_else(Quaternion::zero),
+            mandelbrot.iterations,
+        )
+        .into()
+    }
+}
+
+impl<T> From<&Hybrid<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(hybrid: &Hybrid<T>) -> distance::GeometryEstimator<T> {
+        let formulas = hybrid
+            .formulas
+            .iter()
+            .flat_map(|step| {
+                (0..step.repeat.unwrap_or(1))
+                    .map(move |_| distance::HybridFormula::from(&step.formula))
+            })
+            .collect();
+        distance::Hybrid::new(formulas, hybrid.iterations).into()
+    }
+}
+
+impl<T> From<&Shape<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(shape: &Shape<T>) -> distance::GeometryEstimator<T> {
+        match shape {
+            Shape::Julia(j) => j.into(),
+            Shape::Mandelbox(m) => m.into(),
+            Shape::Sphere(s) => s.into(),
+            Shape::Cuboid(c) => c.into(),
+            Shape::Torus(t) => t.into(),
+            Shape::Plane(p) => p.into(),
+            Shape::Gyroid(t) => {
+                distance::Tpms::new(distance::TpmsKind::Gyroid, t.period, t.thickness).into()
+            }
+            Shape::SchwarzP(t) => {
+                distance::Tpms::new(distance::TpmsKind::SchwarzP, t.period, t.thickness).into()
+            }
+            Shape::Capsule(c) => c.into(),
+            Shape::Cylinder(c) => c.into(),
+            Shape::Heightfield(h) => h.into(),
+            Shape::Mesh(m) => m.into(),
+            Shape::Volume(v) => v.into(),
+            Shape::Sierpinski(s) => s.into(),
+            Shape::Kleinian(k) => k.into(),
+            Shape::Ifs(i) => i.into(),
+            Shape::Apollonian(a) => a.into(),
+            Shape::QuaternionMandelbrot(m) => m.into(),
+            Shape::Hybrid(h) => h.into(),
+            Shape::Formula(f) => distance::Formula::new(f.step.clone(), f.c, f.iterations).into(),
+            Shape::Union(c) => distance::Union::new((&*c.a).into(), (&*c.b).into()).into(),
+            Shape::SmoothUnion(u) => {
+                distance::SmoothUnion::new((&*u.a).into(), (&*u.b).into(), u.k).into()
+            }
+            Shape::Morph(m) => distance::Morph::new((&*m.a).into(), (&*m.b).into(), m.t).into(),
+            Shape::Intersection(c) => {
+                distance::Intersection::new((&*c.a).into(), (&*c.b).into()).into()
+            }
+            Shape::Difference(c) => {
+                distance::Difference::new((&*c.a).into(), (&*c.b).into()).into()
+            }
+            Shape::Twist(t) => distance::Twist::new(
+                (&*t.shape).into(),
+                t.amount,
+                t.axis.unwrap_or_else(Vec3::unit_y),
+            )
+            .into(),
+            Shape::Bend(b) => distance::Bend::new((&*b.shape).into(), b.amount).into(),
+            Shape::Taper(t) => distance::Taper::new(
+                (&*t.shape).into(),
+                t.rate,
+                t.axis.unwrap_or_else(Vec3::unit_y),
+            )
+            .into(),
+            Shape::Repeat(r) => {
+                distance::Repeat::new((&*r.shape).into(), r.spacing, r.count).into()
+            }
+            Shape::Elongate(e) => distance::Elongate::new((&*e.shape).into(), e.amount).into(),
+            Shape::Onion(o) => distance::Onion::new((&*o.shape).into(), o.thickness).into(),
+            Shape::Round(r) => distance::Round::new((&*r.shape).into(), r.radius).into(),
+            Shape::Displace(d) => {
+                distance::Displace::new((&*d.shape).into(), d.amplitude, d.frequency, d.seed).into()
+            }
+            Shape::Warp(w) => distance::Warp::new(
+                (&*w.shape).into(),
+                w.amplitude,
+                w.frequency,
+                w.octaves.unwrap_or(1),
+                w.seed,
+            )
+            .into(),
+            Shape::Bounded(b) => {
+                distance::Bounded::new((&*b.shape).into(), (&*b.bound).into(), b.margin).into()
+            }
+            Shape::Mirror(m) => distance::Mirror::new(
+                (&*m.shape).into(),
+                m.normal,
+                m.offset.unwrap_or_else(T::zero),
+            )
+            .into(),
+            Shape::Symmetry(s) => distance::Symmetry::new(
+                (&*s.shape).into(),
+                s.count,
+                s.axis.unwrap_or_else(Vec3::unit_y),
+            )
+            .into(),
+        }
+    }
+}
+
+/// the estimator for `shape`, deformed by `modifiers` and then moved by `transform` if there is one
+fn transformed<T>(
+    shape: &Shape<T>,
+    modifiers: &[Modifier<T>],
+    transform: &Option<Transform<T>>,
+) -> distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    let de = modifiers
+        .iter()
+        .fold(shape.into(), |de, modifier| modifier.apply(de));
+    match transform {
+        Some(t) => distance::Transformed::new(
+            de,
+            t.translate.unwrap_or_else(Vec3::zero),
+            t.rotate
+                .map_or_else(Quaternion::identity, |r| r.quaternion()),
+            t.scale.unwrap_or_else(T::one),
+        )
+        .into(),
+        None => de,
+    }
+}
+
+impl<T> From<&Node<T>> for distance::GeometryEstimator<T>
+where
+    T: Float + Sum,
+{
+    fn from(node: &Node<T>) -> distance::GeometryEstimator<T> {
+        transformed(&node.shape, &node.modifiers, &node.transform)
+    }
+}
+
+impl<T> From<&Geometry<T>> for distance::Geometry<T>
+where
+    T: Float + Sum,
+{
+    fn from(geom: &Geometry<T>) -> distance::Geometry<T> {
+        geom.est
+            .geometry(transformed(&geom.shape, &geom.modifiers, &geom.transform))
+    }
+}
+
+fn into_render_geoms<T>(
+    geom: &Vec<Geometry<T>>,
+    materials: &HashMap<String, Material<T>>,
+) -> Result<Vec<render::RenderGeometry<T>>, SceneDeserializeErr>
+where
+    T: Float + Sum + Default,
+{
+    let material = |m: &String| {
+        materials
+            .get(m)
+            .cloned()
+            .ok_or_else(|| SceneDeserializeErr::UnknownMaterial(m.clone()))
+    };
+    geom.iter()
+        .map(|g| {
+            let est = &g.est;
+            Ok(render::RenderGeometry {
+                mat: material(&est.material)?,
+                geom: g.into(),
+                checker: match &est.checker {
+                    Some(c) => Some(render::Checker {
+                        mat: material(&c.material)?,
+                        size: c.size,
+                    }),
+                    None => None,
+                },
+                instance_gradient: match &est.instance_gradient {
+                    Some(g) => Some(render::InstanceGradient {
+                        mat: material(&g.material)?,
+                    }),
+                    None => None,
+                },
+                orbit_trap: match &est.orbit_trap {
+                    Some(t) => Some(render::TrapColoring {
+                        trap: t.trap,
+                        mat: material(&t.material)?,
+                        falloff: t.falloff,
+                    }),
+                    None => None,
+                },
+            })
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Scene<T>
+where
+    T: Float + Sum + Default + Clone,
+{
+    pub geometry: Vec<Geometry<T>>,
+    pub materials: HashMap<String, Material<T>>,
+    pub lights: Vec<Light<T>>,
+    pub cameras: HashMap<String, Camera<T>>,
+    pub renders: Vec<Render<T>>,
+    #[serde(default = "HashMap::default")]
+    pub rigs: HashMap<String, Rig>,
+    /// a sphere or box all of the geometry lies inside; rays are clipped to it before marching,
+    /// and those which miss it aren't marched at all
+    #[serde(default = "Option::default")]
+    pub bounds: Option<distance::Bounds<T>>,
+}
+
+impl<T, S, A> TryFrom<&Scene<T>> for render::Scene<T, Alpha<Rgb<S, T>, A>>
+where
+    T: Float + Sum + Default + Clone + Component,
+    S: RgbStandard,
+    A: Component,
+{
+    type Error = SceneDeserializeErr;
+
+    fn try_from(
+        scene: &Scene<T>,
+    ) -> Result<render::Scene<T, Alpha<Rgb<S, T>, A>>, SceneDeserializeErr> {
+        let mut geometry = into_render_geoms(&scene.geometry, &scene.materials)?;
+        for g in geometry.iter_mut() {
+            g.geom.bounds = scene.bounds;
+        }
+        for camera in scene.rigs.values().flat_map(|r| &r.cameras) {
+            if !scene.cameras.contains_key(camera) {
+                return Err(SceneDeserializeErr::UnknownCamera(camera.clone()));
+            }
+        }
+        let viewports: HashMap<String, Viewport<T>> = scene
+            .cameras
+            .iter()
+            .map(|(s, c)| Ok((s.to_owned(), c.to_viewport(s, &geometry)?)))
+            .collect::<Result<_, SceneDeserializeErr>>()?;
+
+        Ok(render::Scene {
+            geometry,
+            lights: scene
+                .lights
+                .iter()
+                .map(|l| l.clone().try_into())
+                .collect::<Result<Vec<light::Light<_, _>>, SceneDeserializeErr>>()?
+                .into(),
+            renders: scene
+                .renders
+                .iter()
+                .map(|r| r.into_render(&viewports))
+                .collect::<Result<Vec<camera::Render<T>>, SceneDeserializeErr>>()?,
+        })
+    }
+}
+
+/// Something in a scene which is probably a mistake, although the scene still loads
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneWarning {
+    /// a material no geometry, checker, or orbit trap uses
+    UnusedMaterial(String),
+    /// a camera no render uses
+    UnusedCamera(String),
+    /// a rotation quaternion within the geometry at index `geometry` whose magnitude is far from
+    /// 1; it's normalized, but the other components were probably meant to be different
+    DenormalizedRotation { geometry: usize, magnitude: f64 },
+}
+
+impl SceneWarning {
+    /// a description of the warning for the user, to be looked up in their locale
+    pub fn message(&self) -> Message<'_> {
+        match self {
+            SceneWarning::UnusedMaterial(m) => Message::UnusedMaterial(m),
+            SceneWarning::UnusedCamera(c) => Message::UnusedCamera(c),
+            SceneWarning::DenormalizedRotation {
+                geometry,
+                magnitude,
+            } => Message::DenormalizedRotation {
+                geometry: *geometry,
+                magnitude: *magnitude,
+            },
+        }
+    }
+}
+
+/// What a scene file contains, to check it parsed as intended before starting a long render
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneSummary {
+    /// how many top-level geometries there are of each `type`
+    pub geometry: BTreeMap<&'static str, usize>,
+    pub materials: usize,
+    pub lights: usize,
+    /// the resolution of each render, or `None` if its camera doesn't exist
+    pub resolutions: Vec<Option<Extent2<usize>>>,
+    /// bytes of 8-bit RGBA output buffers for every render together
+    /// (in u64, since it can overflow a 32-bit usize)
+    pub buffer_bytes: u64,
+    pub warnings: Vec<SceneWarning>,
+}
+
+impl<T> Scene<T>
+where
+    T: Float + Sum + Default + Clone,
+{
+    /// counts of everything in the scene, and warnings about anything that looks unintended
+    pub fn summary(&self) -> SceneSummary {
+        let mut geometry = BTreeMap::new();
+        for g in &self.geometry {
+            *geometry.entry(g.shape.name()).or_insert(0) += 1;
+        }
+
+        let resolutions: Vec<Option<Extent2<usize>>> = self
+            .renders
+            .iter()
+            .map(|r| {
+                let cam = self.cameras.get(&r.camera)?;
+                let height = T::from(r.width).unwrap() * cam.height / cam.width;
+                Some(Extent2::new(
+                    r.width,
+                    height.round().to_usize().unwrap_or(0),
+                ))
+            })
+            .collect();
+        // 4 bytes per pixel, as in `img::ImageData`
+        let buffer_bytes = resolutions
+            .iter()
+            .flatten()
+            .map(|size| size.w as u64 * size.h as u64 * 4)
+            .sum();
+
+        SceneSummary {
+            geometry,
+            materials: self.materials.len(),
+            lights: self.lights.len(),
+            resolutions,
+            buffer_bytes,
+            warnings: self.warnings(),
+        }
+    }
+
+    /// Makes this a cheap proxy of the scene for fast previews during look development: every
+    /// fractal gets half its iterations and meshes half their resolution, ε is doubled, and
+    /// every render is half as wide. Materials, lights, and cameras are left alone, so the proxy
+    /// looks like the scene, only coarser.
+    pub fn proxy(&mut self) {
+        let two = T::from(2).unwrap();
+        for g in self.geometry.iter_mut() {
+            g.shape.simplify();
+            g.est.epsilon = g.est.epsilon * two;
+        }
+        for render in self.renders.iter_mut() {
+            render.width = render.width.div_ceil(2).max(1);
+        }
+    }
+
+    /// Fixes every animated value in the scene (like a Julia set's `c` moving toward its `c_end`)
+    /// at time `t`, from 0 at the first frame of an animation to 1 at the last (see
+    /// `render::frame_time`), so the scene can be rendered as that frame. Unanimated scenes are
+    /// unchanged.
+    pub fn animate(&mut self, t: T) {
+        for g in self.geometry.iter_mut() {
+            g.shape.animate(t);
+        }
+        for camera in self.cameras.values_mut() {
+            if let Some(end) = camera.zoom_end.take() {
+                camera.focal_len = camera.focal_len * end.powf(t);
+            }
+        }
+    }
+
+    /// Cuts away every part of the scene's geometry in front of the plane through `normal * offset`
+    /// facing along `normal`, leaving the interior capped with a flat face where the plane crosses
+    /// solid geometry; for "scan" animations which sweep the plane through a fractal's insides.
+    pub fn clip(&mut self, normal: Vec3<T>, offset: T) {
+        for g in self.geometry.iter_mut() {
+            // the plane is in world space, so the geometry's modifiers and transform move inside
+            // the cut
+            let shape = std::mem::replace(
+                &mut g.shape,
+                Shape::Plane(Plane {
+                    normal,
+                    offset: T::zero(),
+                }),
+            );
+            g.shape = Shape::Intersection(Combination {
+                a: Box::new(Node {
+                    shape,
+                    modifiers: std::mem::take(&mut g.modifiers),
+                    transform: g.transform.take(),
+                }),
+                b: Box::new(Node {
+                    shape: Shape::Plane(Plane { normal, offset }),
+                    modifiers: Vec::new(),
+                    transform: None,
+                }),
+            });
+        }
+    }
+
+    /// Replaces the scene's renders with one for each camera of the rig `name`, in order, to be
+    /// rendered together into a contact sheet.
+    pub fn select_rig(&mut self, name: &str) -> Result<(), SceneDeserializeErr> {
+        let rig = self
+            .rigs
+            .get(name)
+            .ok_or_else(|| SceneDeserializeErr::UnknownRig(name.to_string()))?;
+        self.renders = rig
+            .cameras
+            .iter()
+            .map(|camera| Render {
+                camera: camera.clone(),
+                width: rig.width,
+                foveation: None,
+                normal_smoothing: None,
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Anything in the scene that looks unintended: unused materials and cameras, and rotation
+    /// quaternions far from unit length (which are normalized when the scene is loaded, but were
+    /// probably mistyped).
+    pub fn warnings(&self) -> Vec<SceneWarning> {
+        let used_materials: HashSet<&String> = self
+            .geometry
+            .iter()
+            .flat_map(|g| {
+                let est = &g.est;
+                Some(&est.material)
+                    .into_iter()
+                    .chain(est.checker.as_ref().map(|c| &c.material))
+                    .chain(est.instance_gradient.as_ref().map(|g| &g.material))
+                    .chain(est.orbit_trap.as_ref().map(|t| &t.material))
+            })
+            .collect();
+        let used_cameras: HashSet<&String> = self
+            .renders
+            .iter()
+            .map(|r| &r.camera)
+            .chain(self.rigs.values().flat_map(|r| &r.cameras))
+            .collect();
+        let mut unused_materials: Vec<&String> = self
+            .materials
+            .keys()
+            .filter(|m| !used_materials.contains(m))
+            .collect();
+        let mut unused_cameras: Vec<&String> = self
+            .cameras
+            .keys()
+            .filter(|c| !used_cameras.contains(c))
+            .collect();
+        // maps iterate in an arbitrary order
+        unused_materials.sort();
+        unused_cameras.sort();
+        let rotations = self.geometry.iter().enumerate().flat_map(|(i, g)| {
+            let mut rotations = g.shape.rotations();
+            rotations.extend(g.transform.as_ref().and_then(|t| t.rotate.as_ref()));
+            rotations.into_iter().filter_map(move |r| {
+                r.deviation()
+                    .map(|magnitude| SceneWarning::DenormalizedRotation {
+                        geometry: i,
+                        magnitude: magnitude.to_f64().unwrap_or(0.0),
+                    })
+            })
+        });
+        unused_materials
+            .into_iter()
+            .map(|m| SceneWarning::UnusedMaterial(m.clone()))
+            .chain(
+                unused_cameras
+                    .into_iter()
+                    .map(|c| SceneWarning::UnusedCamera(c.clone())),
+            )
+            .chain(rotations)
+            .collect()
+    }
+}
+
+impl fmt::Display for SceneSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let geometry: Vec<String> = self
+            .geometry
+            .iter()
+            .map(|(name, count)| format!("{} {}", count, name))
+            .collect();
+        writeln!(f, "geometry: {}", geometry.join(", "))?;
+        writeln!(f, "materials: {}", self.materials)?;
+        writeln!(f, "lights: {}", self.lights)?;
+        let resolutions: Vec<String> = self
+            .resolutions
+            .iter()
+            .map(|r| match r {
+                Some(size) => format!("{}x{}", size.w, size.h),
+                None => "?".to_string(),
+            })
+            .collect();
+        writeln!(
+            f,
+            "renders: {} ({})",
+            self.resolutions.len(),
+            resolutions.join(", ")
+        )?;
+        writeln!(
+            f,
+            "buffer memory: {:.1} MiB",
+            self.buffer_bytes as f64 / (1024.0 * 1024.0)
+        )?;
+        let locale = Locale::from_env();
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning.message().text(locale))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use palette::Srgba;
+    use pretty_assertions::{assert_eq, assert_ne};
+    use serde_yaml;
+    use std::convert::{TryFrom, TryInto};
+    use vek::{Extent2, Quaternion, Vec2, Vec3};
+
+    use super::{
+        Camera, Geometry, HeightSource, Heightfield, Interpolation, Light, Render, Rotation, Scene,
+        SceneDeserializeErr, SceneWarning, Shape, Transform,
+    };
+    use crate::camera;
+    use crate::distance::{self, Estimator};
+    use crate::light;
+    use crate::render;
+
+    #[test]
+    fn render_deser_test() {
+        let render: Render<f64> = serde_yaml::from_str(indoc!(
+            "
+                camera: main
+                width: 300
+                "
+        ))
+        .unwrap();
+        assert_eq!(
+            render,
+            Render {
+                camera: "main".to_owned(),
+                width: 300,
+                foveation: None,
+                normal_smoothing: None,
+            }
+        );
+    }
+
+    #[test]
+    fn render_vec_deser_test() {
+        let render: Vec<Render<f64>> = serde_yaml::from_str(indoc!(
+            "
+                - camera: main
+                  width: 300
+                - camera: xyz
+                  width: 20000
+                "
+        ))
+        .unwrap();
+        assert_eq!(
+            render,
+            vec!(
+                Render {
+                    camera: "main".to_owned(),
+                    width: 300,
+                    foveation: None,
+                    normal_smoothing: None,
+                },
+                Render {
+                    camera: "xyz".to_owned(),
+                    width: 20000,
+                    foveation: None,
+                    normal_smoothing: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn camera_deser_test() {
+        let cam: Camera<f64> = serde_yaml::from_str(indoc!(
+            "
+                facing: [1, 0, 0]
+                right: [0, 1, 0]
+                pos: [0, 0, 0]
+                focal_len: 10
+                width: 3
+                height: 2
+                "
+        ))
+        .unwrap();
+        assert_eq!(
+            cam,
+            Camera {
+                facing: Vec3::new(1.0, 0.0, 0.0),
+                right: Vec3::new(0.0, 1.0, 0.0),
+                pos: Vec3::new(0.0, 0.0, 0.0),
+                focal_len: 10.0,
+                width: 3.0,
+                height: 2.0,
+                focus_point: None,
+                above_surface: None,
+                zoom_end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn camera_focus_point_deser_test() {
+        let cam: Camera<f64> = serde_yaml::from_str(indoc!(
+            "
+                facing: [1, 0, 0]
+                right: [0, 1, 0]
+                pos: [0, 0, 0]
+                focal_len: 10
+                width: 3
+                height: 2
+                focus_point: [0.5, 0.25]
+                "
+        ))
+        .unwrap();
+        assert_eq!(cam.focus_point, Some(Vec2::new(0.5, 0.25)));
+
+        let wall: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: plane
+            normal: [-1, 0, 0]
+            offset: -4
+            material: plain
+            epsilon: 0.000001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geometry = [render::RenderGeometry {
+            mat: light::Material::default(),
+            geom: distance::Geometry::from(&wall),
+            checker: None,
+            instance_gradient: None,
+            orbit_trap: None,
+        }];
+        let view = cam.to_viewport("main", &geometry).unwrap();
+        // the probe ray through the focus point leaves the camera along (10, 0, 0.5) and meets
+        // the wall at x = 4
+        let (pos, rot) = view.ray(Vec2::new(0.5, 0.25));
+        let focus_dist = view.focus_dist.unwrap();
+        assert_eq!(Some(focus_dist), render::nearest_hit(&geometry, pos, rot));
+        assert!((focus_dist - 0.4 * 100.25f64.sqrt()).abs() < 1e-5);
+        assert!(matches!(
+            cam.to_viewport("main", &[]),
+            Err(SceneDeserializeErr::FocusPointMissed(_))
+        ));
+    }
+
+    #[test]
+    fn light_deser_test() {
+        let light_unparsed: Light<f32> = serde_yaml::from_str(indoc!(
+            "
+            facing: [0, 0, 0]
+            specular: rgba(255, 255, 255, 1)
+            diffuse: rgba(255, 255, 255, 1)
+            ambient: rgba(255, 255, 127, 1)
+            "
+        ))
+        .unwrap();
         let light_: light::Light<f32, Srgba> = light_unparsed.try_into().unwrap();
         assert_eq!(
-            light_,
-            light::Light {
-                rot: Vec3::new(0.0, 0.0, 0.0),
-                col: light::Material {
-                    specular: Srgba::new(1.0, 1.0, 1.0, 1.0),
-                    diffuse: Srgba::new(1.0, 1.0, 1.0, 1.0),
-                    ambient: Srgba::new(1.0, 1.0, 127.0/255.0, 1.0),
-                    shininess: Srgba::default(),
-                },
+            light_,
+            light::Light {
+                rot: Vec3::new(0.0, 0.0, 0.0),
+                col: light::Material {
+                    specular: Srgba::new(1.0, 1.0, 1.0, 1.0),
+                    diffuse: Srgba::new(1.0, 1.0, 1.0, 1.0),
+                    ambient: Srgba::new(1.0, 1.0, 127.0/255.0, 1.0),
+                    shininess: Srgba::default(),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn mandelbox_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: mandelbox
+            scale: -1.5
+            min_radius: 0.5
+            fold_limit: 1
+            iterations: 16
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        assert_eq!(geom.est.material, "plain");
+        match geom.shape {
+            Shape::Mandelbox(m) => {
+                assert_eq!(m.scale, -1.5);
+                assert_eq!(m.min_radius, 0.5);
+                assert_eq!(m.fold_limit, 1.0);
+                assert_eq!(m.iterations, 16);
+            }
+            _ => panic!("expected a mandelbox"),
+        }
+    }
+
+    #[test]
+    fn quaternion_mandelbrot_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: quaternion_mandelbrot
+            start: [0.1, 0.2, 0.3, 0.4]
+            iterations: 16
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        // and back, unchanged
+        let geom: Geometry<f64> =
+            serde_yaml::from_str(&serde_yaml::to_string(&geom).unwrap()).unwrap();
+        match geom.shape {
+            Shape::QuaternionMandelbrot(m) => {
+                assert_eq!(m.start, Some(Quaternion::from_xyzw(0.1, 0.2, 0.3, 0.4)));
+                assert_eq!(m.iterations, 16);
+            }
+            _ => panic!("expected a quaternion mandelbrot"),
+        }
+    }
+
+    #[test]
+    fn render_foveation_deser_test() {
+        let render: Render<f64> = serde_yaml::from_str(indoc!(
+            "
+                camera: main
+                width: 300
+                foveation:
+                    center: [0.5, 0.5]
+                    radius: 0.1
+                    falloff: 0.4
+                    min_quality: 0.25
+                "
+        ))
+        .unwrap();
+        assert_eq!(
+            render.foveation,
+            Some(camera::Foveation {
+                center: Vec2::new(0.5, 0.5),
+                radius: 0.1,
+                falloff: 0.4,
+                min_quality: 0.25,
+            })
+        );
+    }
+
+    #[test]
+    fn render_foveation_falloff_test() {
+        let render: Render<f64> = serde_yaml::from_str(indoc!(
+            "
+                camera: main
+                width: 300
+                foveation:
+                    center: [0.5, 0.5]
+                    radius: 0.1
+                    falloff: 0
+                    min_quality: 0.25
+                "
+        ))
+        .unwrap();
+        let cameras = vec![("main".to_string(), Default::default())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            render.into_render(&cameras).err(),
+            Some(SceneDeserializeErr::FoveationFalloff("main".to_string()))
+        );
+    }
+
+    #[test]
+    fn render_normal_smoothing_deser_test() {
+        let render: Render<f64> = serde_yaml::from_str(indoc!(
+            "
+                camera: main
+                width: 300
+                normal_smoothing:
+                    radius: 2
+                    depth_tolerance: 0.01
+                "
+        ))
+        .unwrap();
+        assert_eq!(
+            render.normal_smoothing,
+            Some(camera::NormalSmoothing {
+                radius: 2,
+                depth_tolerance: 0.01,
+            })
+        );
+    }
+
+    #[test]
+    fn box_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: box
+            center: [0, -1, 0]
+            size: [4, 0.5, 4]
+            rounding: 0.1
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        match geom.shape {
+            Shape::Cuboid(c) => {
+                assert_eq!(c.center, Vec3::new(0.0, -1.0, 0.0));
+                assert_eq!(c.size, Vec3::new(4.0, 0.5, 4.0));
+                assert_eq!(c.rounding, Some(0.1));
+            }
+            _ => panic!("expected a box"),
+        }
+    }
+
+    #[test]
+    fn tpms_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: schwarz_p
+            period: 2
+            thickness: 0.1
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        assert_eq!(geom.shape.name(), "schwarz_p");
+        match &geom.shape {
+            Shape::SchwarzP(t) => {
+                assert_eq!(t.period, 2.0);
+                assert_eq!(t.thickness, 0.1);
+            }
+            _ => panic!("expected a schwarz_p"),
+        }
+        // the origin is in the middle of a cell, away from the surface
+        let de: distance::GeometryEstimator<f64> = (&geom.shape).into();
+        assert!(de.estimate(Vec3::zero()) > 0.0);
+    }
+
+    #[test]
+    fn julia_power_deser_test() {
+        let yaml = indoc!(
+            "
+            type: julia
+            c: [-0.213, -0.0410, -0.563, -0.560]
+            iterations: 64
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        );
+        match serde_yaml::from_str::<Geometry<f64>>(yaml).unwrap().shape {
+            Shape::Julia(j) => assert_eq!(j.power, None),
+            _ => panic!("expected a julia set"),
+        }
+        match serde_yaml::from_str::<Geometry<f64>>(&format!("{}power: 2.5\n", yaml))
+            .unwrap()
+            .shape
+        {
+            Shape::Julia(j) => assert_eq!(j.power, Some(2.5)),
+            _ => panic!("expected a julia set"),
+        }
+        match serde_yaml::from_str::<Geometry<f64>>(&format!("{}degree: 3\n", yaml))
+            .unwrap()
+            .shape
+        {
+            Shape::Julia(j) => assert_eq!(j.power, Some(3.0)),
+            _ => panic!("expected a julia set"),
+        }
+        match serde_yaml::from_str::<Geometry<f64>>(&format!("{}variant: burning_ship\n", yaml))
+            .unwrap()
+            .shape
+        {
+            Shape::Julia(j) => assert_eq!(j.variant, Some(distance::JuliaVariant::BurningShip)),
+            _ => panic!("expected a julia set"),
+        }
+    }
+
+    #[test]
+    fn julia_slice_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: julia
+            c: [-0.213, -0.0410, -0.563, -0.560]
+            iterations: 64
+            slice:
+                fixed: k
+                value: 0.1
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        match geom.shape {
+            Shape::Julia(j) => assert_eq!(
+                j.slice,
+                Some(distance::QuaternionSlice {
+                    fixed: distance::QuaternionComponent::K,
+                    value: 0.1,
+                })
+            ),
+            _ => panic!("expected a julia set"),
+        }
+    }
+
+    #[test]
+    fn heightfield_deser_test() {
+        let grid: Shape<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: heightfield
+            amplitude: 2
+            heights:
+                - [0.5, 0.5]
+                - [0.5]
+            size: [4, 4]
+            "
+        ))
+        .unwrap();
+        let de: distance::GeometryEstimator<f64> = (&grid).into();
+        // the short row is extended from its last height, so the terrain is flat at y = 1
+        assert_eq!(de.estimate(Vec3::new(2.0, 3.0, 2.0)), 2.0);
+        assert_eq!(de.estimate(Vec3::new(-2.0, 0.5, -2.0)), -0.5);
+
+        let noise: Shape<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: heightfield
+            amplitude: 1
+            frequency: 0.25
+            "
+        ))
+        .unwrap();
+        match &noise {
+            Shape::Heightfield(Heightfield {
+                source: HeightSource::Noise { octaves, seed, .. },
+                ..
+            }) => assert_eq!((*octaves, *seed), (None, 0)),
+            _ => panic!("expected a noise heightfield"),
+        }
+        let de: distance::GeometryEstimator<f64> = (&noise).into();
+        assert!(de.estimate(Vec3::new(0.0, 2.0, 0.0)) > 0.0);
+    }
+
+    #[test]
+    fn mesh_deser_test() {
+        let path = std::env::temp_dir().join("ray-marcher-mesh-deser-test.obj");
+        std::fs::write(
+            &path,
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 3 2\nf 1 2 4\nf 1 4 3\nf 2 3 4\n",
+        )
+        .unwrap();
+        let yaml = format!("type: mesh\nfile: {}\nresolution: 8\n", path.display());
+        let shape: Shape<f64> = serde_yaml::from_str(&yaml).unwrap();
+        let de: distance::GeometryEstimator<f64> = (&shape).into();
+        assert!(de.estimate(Vec3::broadcast(0.1)) < 0.0);
+        assert!(de.estimate(Vec3::broadcast(2.0)) > 0.0);
+        std::fs::remove_file(&path).unwrap();
+
+        let missing: Result<Shape<f64>, _> =
+            serde_yaml::from_str("type: mesh\nfile: does-not-exist.stl\n");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn volume_deser_test() {
+        let path = std::env::temp_dir().join("ray-marcher-volume-deser-test.raw");
+        // a 2-sample cube, all inside
+        std::fs::write(&path, vec![0; 8 * 4]).unwrap();
+        let yaml = format!(
+            "type: volume\nfile: {}\nsamples: [2, 2, 2]\nspacing: 2\n",
+            path.display()
+        );
+        let shape: Shape<f64> = serde_yaml::from_str(&yaml).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let de: distance::GeometryEstimator<f64> = (&shape).into();
+        // the grid's corners are at ±1, so this point is 2 units past one
+        assert_eq!(de.estimate(Vec3::new(3.0, 0.0, 0.0)), 2.0);
+        // written back as it was given
+        assert!(serde_yaml::to_string(&shape)
+            .unwrap()
+            .contains("spacing: 2"));
+
+        let missing_samples = format!("type: volume\nfile: {}\n", path.display());
+        assert!(serde_yaml::from_str::<Shape<f64>>(&missing_samples).is_err());
+    }
+
+    #[test]
+    fn kleinian_example_scene_test() {
+        let scene: Scene<f64> = serde_yaml::from_str(include_str!("../data/kleinian.yml")).unwrap();
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        assert_eq!(rendered.geometry.len(), 1);
+        assert_eq!(rendered.renders.len(), 1);
+        match &scene.geometry[0].shape {
+            Shape::Kleinian(k) => {
+                assert_eq!(k.fold, None);
+                assert_eq!(k.iterations, 12);
+            }
+            _ => panic!("expected a kleinian"),
+        }
+    }
+
+    #[test]
+    fn ifs_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: ifs
+            maps:
+                - folds: [[1, 1, 0], [1, 0, 1], [0, 1, 1]]
+                  scale: 2
+                  translation: [-1, -1, -1]
+                - rotation: [0, 0, 0.3826834, 0.9238795]
+                  scale: 1
+            iterations: 10
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        match geom.shape {
+            Shape::Ifs(i) => {
+                assert_eq!(i.maps.len(), 2);
+                assert_eq!(i.maps[0].folds.len(), 3);
+                assert_eq!(i.maps[0].rotation, None);
+                assert_eq!(i.maps[1].translation, None);
+            }
+            _ => panic!("expected an ifs"),
+        }
+    }
+
+    #[test]
+    fn hybrid_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: hybrid
+            iterations: 8
+            formulas:
+              - formula: mandelbox
+                scale: 2
+                min_radius: 0.5
+                fold_limit: 1
+                repeat: 2
+              - formula: bulb
+                power: 8
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        match geom.shape {
+            Shape::Hybrid(h) => {
+                assert_eq!(h.iterations, 8);
+                assert_eq!(h.formulas.len(), 2);
+                assert_eq!(h.formulas[0].repeat, Some(2));
+                assert_eq!(h.formulas[1].repeat, None);
+            }
+            _ => panic!("expected a hybrid"),
+        }
+    }
+
+    #[test]
+    fn union_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: union
+            a:
+                type: sphere
+                center: [-2, 0, 0]
+                radius: 1
+            b:
+                type: sphere
+                center: [2, 0, 0]
+                radius: 1
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geom = distance::Geometry::from(&geom);
+        // inside either sphere, and between them, nearest to both
+        assert_eq!(geom.de.estimate(Vec3::new(-2.0, 0.0, 0.0)), -1.0);
+        assert_eq!(geom.de.estimate(Vec3::new(2.0, 0.0, 0.0)), -1.0);
+        assert_eq!(geom.de.estimate(Vec3::zero()), 1.0);
+        assert_eq!(geom.de.estimate(Vec3::new(5.0, 0.0, 0.0)), 2.0);
+    }
+
+    #[test]
+    fn intersection_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: intersection
+            a:
+                type: box
+                center: [0, 0, 0]
+                size: [2, 2, 2]
+            b:
+                type: sphere
+                center: [0, 0, 0]
+                radius: 1.2
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geom = distance::Geometry::from(&geom);
+        // inside both, bounded by the box's faces and the sphere past its corners
+        assert_eq!(geom.de.estimate(Vec3::zero()), -1.0);
+        assert!((geom.de.estimate(Vec3::new(1.1, 0.0, 0.0)) - 0.1).abs() < 1e-12);
+        assert!(geom.de.estimate(Vec3::broadcast(0.95)) > 0.0);
+    }
+
+    #[test]
+    fn difference_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: difference
+            a:
+                type: box
+                center: [0, 0, 0]
+                size: [2, 2, 2]
+            b:
+                type: sphere
+                center: [0, 0, 0]
+                radius: 1.2
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        assert_eq!(geom.est.material, "plain");
+        let geom = distance::Geometry::from(&geom);
+        // the box's center is carved out by the sphere, but its corners remain
+        assert!(geom.de.estimate(Vec3::zero()) > 0.0);
+        assert!(geom.de.estimate(Vec3::broadcast(0.95)) < 0.0);
+    }
+
+    #[test]
+    fn symmetry_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: symmetry
+            count: 6
+            shape:
+                type: mirror
+                normal: [0, 0, 1]
+                shape:
+                    type: sphere
+                    center: [2, 0, 0.5]
+                    radius: 0.25
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geom = distance::Geometry::from(&geom);
+        // twelve spheres: six around y, each mirrored across the wedge's middle
+        let turn = std::f64::consts::PI / 3.0;
+        let copy = Vec3::new(
+            2.0 * turn.cos() - 0.5 * turn.sin(),
+            0.0,
+            -2.0 * turn.sin() - 0.5 * turn.cos(),
+        );
+        assert!((geom.de.estimate(copy) + 0.25).abs() < 1e-9);
+        assert!((geom.de.estimate(Vec3::new(2.0, 0.0, -0.5)) + 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn modifiers_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: sphere
+            center: [0, 0, 0]
+            radius: 1
+            modifiers:
+              - type: taper
+                rate: 0.5
+              - type: twist
+                amount: 0
+            transform:
+              translate: [0, 0, 3]
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geom = distance::Geometry::from(&geom);
+        // the modifiers are applied in order, before the translation
+        let expected: distance::GeometryEstimator<f64> = distance::Twist::new(
+            distance::Taper::new(
+                distance::Sphere::new(Vec3::zero(), 1.0).into(),
+                0.5,
+                Vec3::unit_y(),
+            )
+            .into(),
+            0.0,
+            Vec3::unit_y(),
+        )
+        .into();
+        for &p in &[Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 1.5, 0.5)] {
+            let found = geom.de.estimate(p + Vec3::new(0.0, 0.0, 3.0));
+            assert!((found - expected.estimate(p)).abs() < 1e-9);
+        }
+        assert!((expected.estimate(Vec3::new(2.0, 0.0, 0.0)) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn elongate_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: elongate
+            amount: [4, 0, 0]
+            shape:
+                type: torus
+                center: [0, 0, 0]
+                major_radius: 1
+                minor_radius: 0.25
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geom = distance::Geometry::from(&geom);
+        // a torus stretched into a racetrack, its straights 4 long
+        assert!((geom.de.estimate(Vec3::new(0.0, 0.0, 1.0)) + 0.25).abs() < 1e-9);
+        assert!((geom.de.estimate(Vec3::new(3.0, 0.0, 0.0)) + 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transform_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: union
+            a:
+                type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                transform:
+                    translate: [-2, 0, 0]
+            b:
+                type: sphere
+                center: [0, 0, 0]
+                radius: 1
+            transform:
+                translate: [0, 3, 0]
+                scale: 2
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let geom = distance::Geometry::from(&geom);
+        // the first sphere is at x = -2 within the union, then everything is doubled and raised
+        assert_eq!(geom.de.estimate(Vec3::new(-4.0, 3.0, 0.0)), -2.0);
+        assert_eq!(geom.de.estimate(Vec3::new(0.0, 3.0, 0.0)), -2.0);
+        assert_eq!(geom.de.estimate(Vec3::new(-2.0, 3.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn rotation_deser_test() {
+        let scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: box
+                center: [0, 0, 0]
+                size: [2, 1, 1]
+                transform:
+                    rotate: {axis: [0, 0, 1], angle: 1.5707963}
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                transform:
+                    rotate: [0, 0, 1, 1]
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        let rotated = distance::Geometry::from(&scene.geometry[0]);
+        // the box's long side now runs along y
+        assert!((rotated.de.estimate(Vec3::new(0.0, 2.0, 0.0)) - 1.0).abs() < 1e-6);
+        assert!((rotated.de.estimate(Vec3::new(2.0, 0.0, 0.0)) - 1.5).abs() < 1e-6);
+        assert_eq!(
+            scene.warnings(),
+            vec![SceneWarning::DenormalizedRotation {
+                geometry: 1,
+                magnitude: 2f64.sqrt(),
+            }]
+        );
+    }
+
+    #[test]
+    fn euler_rotation_deser_test() {
+        let geom: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: julia
+            c: [-0.213, -0.0410, -0.563, -0.560]
+            iterations: 8
+            transform:
+                rotate: {euler: [1.5707963, 1.5707963, 0]}
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let rotation = match &geom.transform {
+            Some(Transform {
+                rotate: Some(r), ..
+            }) => *r,
+            _ => panic!("expected a rotation"),
+        };
+        // y goes to z around x, then z goes to x around y
+        let q = rotation.quaternion();
+        assert!((q * Vec3::unit_y() - Vec3::unit_x()).magnitude() < 1e-6);
+        // a single angle is the same as that axis and angle
+        let z: Rotation<f64> = Rotation::Euler {
+            euler: Vec3::new(0.0, 0.0, 0.5),
+        };
+        let axis_angle = Rotation::AxisAngle {
+            axis: Vec3::unit_z(),
+            angle: 0.5,
+        };
+        assert!(
+            (z.quaternion().into_vec4() - axis_angle.quaternion().into_vec4()).magnitude() < 1e-9
+        );
+        // the julia set is sampled in its own rotated frame
+        let rotated = distance::Geometry::from(&geom);
+        let julia = distance::Geometry::from(
+            &serde_yaml::from_str::<Geometry<f64>>(indoc!(
+                "
+                type: julia
+                c: [-0.213, -0.0410, -0.563, -0.560]
+                iterations: 8
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+                "
+            ))
+            .unwrap(),
+        );
+        let p = Vec3::new(0.3, -0.2, 0.4);
+        assert!((rotated.de.estimate(q * p) - julia.de.estimate(p)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn interpolation_test() {
+        let from = Quaternion::from_xyzw(0.0, 0.0, 0.0, 1.0);
+        let to = Quaternion::from_xyzw(0.0, 2.0, 0.0, 0.0);
+        let close = |a: Quaternion<f64>, b: Quaternion<f64>| {
+            (a.into_vec4() - b.into_vec4()).magnitude() < 1e-9
+        };
+        for &mode in &[
+            Interpolation::Linear,
+            Interpolation::Smooth,
+            Interpolation::Slerp,
+        ] {
+            assert!(close(mode.quaternion(from, to, 0.0), from));
+            assert!(close(mode.quaternion(from, to, 1.0), to));
+        }
+        let half = Quaternion::from_xyzw(0.0, 1.0, 0.0, 0.5);
+        assert!(close(Interpolation::Linear.quaternion(from, to, 0.5), half));
+        // symmetric easing passes through the middle at the same time
+        assert!(close(Interpolation::Smooth.quaternion(from, to, 0.5), half));
+        assert!(close(
+            Interpolation::Smooth.quaternion(from, to, 0.25),
+            Interpolation::Linear.quaternion(from, to, 0.15625)
+        ));
+        // halfway around the arc, and halfway between the magnitudes
+        let arc = Interpolation::Slerp.quaternion(from, to, 0.5);
+        let diagonal = 1.5 / 2f64.sqrt();
+        assert!(close(
+            arc,
+            Quaternion::from_xyzw(0.0, diagonal, 0.0, diagonal)
+        ));
+    }
+
+    #[test]
+    fn julia_animation_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: julia
+                c: [0, 0, 0, -1]
+                c_end: [0, 0, 0, 1]
+                iterations: 8
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: round
+                radius: 0.1
+                shape:
+                    type: julia
+                    c: [0, 0, 0, 0]
+                    c_end: [0.4, 0, 0, 0]
+                    interpolation: smooth
+                    iterations: 8
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        scene.animate(render::frame_time(15, 10, 30));
+        match &scene.geometry[0].shape {
+            Shape::Julia(j) => {
+                assert_eq!(j.c, Quaternion::from_xyzw(0.0, 0.0, 0.0, -0.5));
+                assert_eq!(j.c_end, None);
             }
+            _ => panic!("expected a julia set"),
+        }
+        match &scene.geometry[1].shape {
+            Shape::Round(r) => match &r.shape.shape {
+                Shape::Julia(j) => assert!((j.c.x - 0.4 * 0.15625).abs() < 1e-9),
+                _ => panic!("expected a julia set"),
+            },
+            _ => panic!("expected a round"),
+        }
+    }
+
+    #[test]
+    fn camera_zoom_animation_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry: []
+            materials: {}
+            lights: []
+            cameras:
+                zooming:
+                    facing: [1, 0, 0]
+                    right: [0, 1, 0]
+                    pos: [0, 0, 0]
+                    focal_len: 2
+                    width: 3
+                    height: 2
+                    zoom_end: 16
+                still:
+                    facing: [1, 0, 0]
+                    right: [0, 1, 0]
+                    pos: [0, 0, 0]
+                    focal_len: 2
+                    width: 3
+                    height: 2
+            renders: []
+            "
+        ))
+        .unwrap();
+        // halfway through the zoom in octaves, not in focal length
+        scene.animate(0.5);
+        assert_eq!(scene.cameras["zooming"].focal_len, 8.0);
+        assert_eq!(scene.cameras["zooming"].zoom_end, None);
+        assert_eq!(scene.cameras["still"].focal_len, 2.0);
+    }
+
+    #[test]
+    fn julia_slice_animation_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: julia
+                c: [0, 0, 0, -1]
+                iterations: 8
+                slice: {fixed: k, value: -1}
+                slice_end: 1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: julia
+                c: [0, 0, 0, -1]
+                iterations: 8
+                slice_end: 0.5
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        scene.animate(0.75);
+        let slices: Vec<_> = scene
+            .geometry
+            .iter()
+            .map(|g| match &g.shape {
+                Shape::Julia(j) => {
+                    assert_eq!(j.slice_end, None);
+                    j.slice.unwrap()
+                }
+                _ => panic!("expected a julia set"),
+            })
+            .collect();
+        assert_eq!(
+            slices[0],
+            distance::QuaternionSlice {
+                fixed: distance::QuaternionComponent::K,
+                value: 0.5,
+            }
+        );
+        // without a slice, the sweep starts from the real component at 0
+        assert_eq!(
+            slices[1],
+            distance::QuaternionSlice {
+                fixed: distance::QuaternionComponent::Real,
+                value: 0.375,
+            }
+        );
+    }
+
+    #[test]
+    fn morph_animation_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: morph
+                a: {type: sphere, center: [0, 0, 0], radius: 1}
+                b: {type: box, center: [0, 0, 0], size: [2, 2, 2]}
+                t: 0
+                t_end: 1
+                interpolation: smooth
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        scene.animate(0.25);
+        match &scene.geometry[0].shape {
+            Shape::Morph(m) => {
+                assert!((m.t - 0.15625).abs() < 1e-9);
+                assert_eq!(m.t_end, None);
+            }
+            _ => panic!("expected a morph"),
+        }
+        let blend: Geometry<f64> = serde_yaml::from_str(indoc!(
+            "
+            type: blend
+            a: {type: sphere, center: [0, 0, 0], radius: 1}
+            b: {type: sphere, center: [0, 0, 0], radius: 3}
+            t: 0.5
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        ))
+        .unwrap();
+        let blend = distance::Geometry::from(&blend);
+        assert!((blend.de.estimate(Vec3::new(4.0, 0.0, 0.0)) - 2.0).abs() < 1e-9);
+        let geom = distance::Geometry::from(&scene.geometry[0]);
+        // a quarter of the way from the sphere to the box, by the smooth easing
+        let p = Vec3::new(2.0, 2.0, 0.0);
+        let expected = 0.84375 * (8f64.sqrt() - 1.0) + 0.15625 * 2f64.sqrt();
+        assert!((geom.de.estimate(p) - expected).abs() < 1e-9);
+    }
+    #[test]
+    fn formula_deser_test() {
+        let yaml = indoc!(
+            "
+            type: formula
+            step: abs(q) ^ 2 + c
+            iterations: 12
+            material: plain
+            epsilon: 0.001
+            cutoff: 100
+            max_steps: 64
+            "
+        );
+        let geom: Geometry<f64> = serde_yaml::from_str(yaml).unwrap();
+        match geom.shape {
+            Shape::Formula(f) => {
+                assert_eq!(f.step, "(abs(q) ^ 2) + c".parse().unwrap());
+                assert_eq!(f.c, None);
+            }
+            _ => panic!("expected a formula"),
+        }
+        // parse errors are reported when the scene is loaded
+        let err = serde_yaml::from_str::<Geometry<f64>>(&yaml.replace("abs(q)", "abs(q"));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn mixed_estimators_scene_test() {
+        // fractals and primitives side by side, each marched with its own estimator
+        let scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: julia
+                c: [-0.213, -0.0410, -0.563, -0.560]
+                iterations: 16
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: hybrid
+                iterations: 8
+                formulas:
+                  - formula: bulb
+                    power: 8
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: sphere
+                center: [0, -101, 0]
+                radius: 100
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        let de: Vec<_> = rendered.geometry.iter().map(|g| &g.geom.de).collect();
+        match (de[0], de[1], de[2]) {
+            (
+                distance::GeometryEstimator::Julia(_),
+                distance::GeometryEstimator::Hybrid(_),
+                distance::GeometryEstimator::Sphere(sphere),
+            ) => assert_eq!(sphere.estimate(Vec3::zero()), 1.0),
+            _ => panic!("expected a julia set, a mandelbulb, and a sphere"),
+        }
+    }
+
+    #[test]
+    fn proxy_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: julia
+                c: [-0.213, -0.0410, -0.563, -0.560]
+                iterations: 15
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+              - type: twist
+                amount: 0.5
+                shape:
+                    type: mandelbox
+                    scale: 2
+                    min_radius: 0.5
+                    fold_limit: 1
+                    iterations: 8
+                material: plain
+                epsilon: 0.002
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders:
+              - camera: main
+                width: 801
+            "
+        ))
+        .unwrap();
+        scene.proxy();
+        match &scene.geometry[0].shape {
+            Shape::Julia(j) => assert_eq!(j.iterations, 8),
+            _ => panic!("expected a julia set"),
+        }
+        match &scene.geometry[1].shape {
+            Shape::Twist(t) => match &t.shape.shape {
+                Shape::Mandelbox(m) => assert_eq!(m.iterations, 4),
+                _ => panic!("expected a mandelbox"),
+            },
+            _ => panic!("expected a twist"),
+        }
+        assert_eq!(scene.geometry[0].est.epsilon, 0.002);
+        assert_eq!(scene.geometry[1].est.epsilon, 0.004);
+        assert_eq!(scene.renders[0].width, 401);
+    }
+
+    #[test]
+    fn orbit_trap_deser_test() {
+        let scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: mandelbox
+                scale: 2
+                min_radius: 0.5
+                fold_limit: 1
+                iterations: 8
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+                orbit_trap:
+                    trap: axis
+                    direction: [0, 1, 0]
+                    material: glow
+                    falloff: 0.5
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+                glow: {specular: 0, diffuse: 1, ambient: 0.5}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        let coloring = rendered.geometry[0].orbit_trap.as_ref().unwrap();
+        assert_eq!(
+            coloring.trap,
+            distance::OrbitTrap::Axis {
+                direction: Vec3::unit_y()
+            }
+        );
+        assert_eq!(coloring.mat.diffuse, 1.0);
+    }
+
+    #[test]
+    fn instance_gradient_deser_test() {
+        let scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: repeat
+                spacing: [3, 3, 3]
+                count: [3, 1, 1]
+                shape:
+                    type: sphere
+                    center: [0, 0, 0]
+                    radius: 1
+                transform:
+                    translate: [0, 0, 5]
+                material: dark
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+                instance_gradient:
+                    material: light
+            materials:
+                dark: {specular: 0, diffuse: 0, ambient: 0}
+                light: {specular: 0, diffuse: 1, ambient: 0.5}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        assert_eq!(scene.warnings(), vec![]);
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        let geometry = &rendered.geometry[0];
+        // the tops of the three spheres, from the first to the last
+        let diffuse = |x: f64| geometry.material_at(Vec3::new(x, 1.0, 5.0)).diffuse;
+        assert_eq!(diffuse(-3.0), 0.0);
+        assert_eq!(diffuse(0.0), 0.5);
+        assert_eq!(diffuse(3.0), 1.0);
+    }
+
+    #[test]
+    fn summary_test() {
+        let mut scene: Scene<f64> =
+            serde_yaml::from_str(include_str!("../data/scene.yml")).unwrap();
+        let summary = scene.summary();
+        assert_eq!(summary.geometry.get("julia"), Some(&1));
+        assert_eq!(summary.materials, 1);
+        assert_eq!(summary.lights, 1);
+        assert_eq!(summary.resolutions, vec![Some(Extent2::new(500, 333))]);
+        assert_eq!(summary.buffer_bytes, 500 * 333 * 4);
+        assert_eq!(summary.warnings, vec![]);
+
+        scene.renders.clear();
+        assert_eq!(
+            scene.summary().warnings,
+            vec![SceneWarning::UnusedCamera("main".to_string())]
+        );
+    }
+
+    #[test]
+    fn clip_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                transform:
+                    translate: [2, 0, 0]
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            "
+        ))
+        .unwrap();
+        // keep x ≥ 2.5
+        scene.clip(-Vec3::unit_x(), -2.5);
+        assert!(scene.geometry[0].transform.is_none());
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        let de = &rendered.geometry[0].geom.de;
+        // the cap, in the middle of the translated sphere
+        assert!(de.estimate(Vec3::new(2.5, 0.0, 0.0)).abs() < 1e-9);
+        assert!(de.estimate(Vec3::new(2.75, 0.0, 0.0)) < 0.0);
+        // cut away
+        assert!(de.estimate(Vec3::new(2.0, 0.0, 0.0)) > 0.0);
+    }
+
+    #[test]
+    fn bounds_deser_test() {
+        let scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry:
+              - type: sphere
+                center: [0, 0, 0]
+                radius: 1
+                material: plain
+                epsilon: 0.001
+                cutoff: 100
+                max_steps: 64
+            materials:
+                plain: {specular: 0, diffuse: 0.5, ambient: 0}
+            lights: []
+            cameras: {}
+            renders: []
+            bounds:
+                type: box
+                center: [0, 0, 0]
+                size: [2, 2, 2]
+            "
+        ))
+        .unwrap();
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        let geom = &rendered.geometry[0].geom;
+        assert_eq!(
+            geom.bounds,
+            Some(distance::Bounds::Box {
+                center: Vec3::zero(),
+                size: Vec3::new(2.0, 2.0, 2.0),
+            })
+        );
+        let down = -Vec3::unit_z();
+        assert_eq!(geom.hit_distance(Vec3::new(0.0, 0.0, 5.0), down), Some(4.0));
+        assert!(geom.march_trace(Vec3::new(1.5, 0.0, 5.0), down).is_empty());
+    }
+
+    #[test]
+    fn rig_test() {
+        let mut scene: Scene<f64> = serde_yaml::from_str(indoc!(
+            "
+            geometry: []
+            materials: {}
+            lights: []
+            cameras:
+                front:
+                    facing: [0, 0, 1]
+                    right: [1, 0, 0]
+                    pos: [0, 0, -5]
+                    focal_len: 1
+                    width: 1
+                    height: 1
+                top:
+                    facing: [0, -1, 0]
+                    right: [1, 0, 0]
+                    pos: [0, 5, 0]
+                    focal_len: 1
+                    width: 2
+                    height: 1
+            renders: []
+            rigs:
+                canonical:
+                    cameras: [top, front]
+                    width: 64
+            "
+        ))
+        .unwrap();
+        // cameras in rigs are used
+        assert_eq!(scene.warnings(), vec![]);
+        assert_eq!(
+            scene.select_rig("turntable"),
+            Err(SceneDeserializeErr::UnknownRig("turntable".to_string()))
+        );
+        scene.select_rig("canonical").unwrap();
+        assert_eq!(
+            scene.summary().resolutions,
+            vec![Some(Extent2::new(64, 32)), Some(Extent2::new(64, 64))]
+        );
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        assert_eq!(rendered.renders.len(), 2);
+
+        scene
+            .rigs
+            .get_mut("canonical")
+            .unwrap()
+            .cameras
+            .push("side".to_string());
+        let rendered: Result<render::Scene<f64, Srgba<f64>>, _> = (&scene).try_into();
+        assert_eq!(
+            rendered.err(),
+            Some(SceneDeserializeErr::UnknownCamera("side".to_string()))
         );
     }
+
+    #[test]
+    fn apollonian_example_scene_test() {
+        let scene: Scene<f64> =
+            serde_yaml::from_str(include_str!("../data/apollonian.yml")).unwrap();
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        assert_eq!(rendered.geometry.len(), 1);
+        match &scene.geometry[0].shape {
+            Shape::Apollonian(a) => assert_eq!(a.spheres.len(), 1),
+            _ => panic!("expected an apollonian"),
+        }
+    }
+
+    #[test]
+    fn ifs_example_scene_test() {
+        let scene: Scene<f64> = serde_yaml::from_str(include_str!("../data/ifs.yml")).unwrap();
+        let rendered: render::Scene<f64, Srgba<f64>> = (&scene).try_into().unwrap();
+        assert_eq!(rendered.geometry.len(), 1);
+        match &scene.geometry[0].shape {
+            Shape::Ifs(i) => {
+                assert_eq!(i.maps.len(), 1);
+                assert_eq!(i.maps[0].folds.len(), 6);
+            }
+            _ => panic!("expected an ifs"),
+        }
+        assert_eq!(scene.warnings(), vec![]);
+    }
 }
@@ -0,0 +1,108 @@
+//! Scheduling of render work onto threads.
+use vek::Rect;
+
+/// Splits `tiles` into `groups` runs of neighboring tiles, one per NUMA node (or other set of
+/// threads sharing memory), so each group's threads write to one contiguous region of the
+/// accumulation buffers. Tiles should be in row order, as from `img::Tiling::tiles`.
+pub fn partition_tiles(
+    tiles: Vec<Rect<usize, usize>>,
+    groups: usize,
+) -> Vec<Vec<Rect<usize, usize>>> {
+    let groups = groups.max(1);
+    let len = tiles.len();
+    let mut tiles = tiles.into_iter();
+    (0..groups)
+        .map(|i| {
+            let size = (i + 1) * len / groups - i * len / groups;
+            tiles.by_ref().take(size).collect()
+        })
+        .collect()
+}
+
+/// Pins the calling thread to core `cpu`; returns whether it was pinned, which is never the case
+/// without the `affinity` feature or off Linux.
+#[cfg(all(feature = "affinity", target_os = "linux"))]
+pub fn pin_current_thread(cpu: usize) -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+/// Pins the calling thread to core `cpu`; returns whether it was pinned, which is never the case
+/// without the `affinity` feature or off Linux.
+#[cfg(not(all(feature = "affinity", target_os = "linux")))]
+pub fn pin_current_thread(_cpu: usize) -> bool {
+    false
+}
+
+/// Lowers the calling thread's scheduling priority to the minimum, so a long render leaves the
+/// rest of the system responsive; returns whether it was lowered, which is never the case off
+/// Unix or without the `nice` feature.
+#[cfg(all(feature = "nice", unix))]
+pub fn lower_priority() -> bool {
+    // on Linux, `who = 0` means the calling thread rather than the whole process
+    unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, 19) == 0 }
+}
+
+/// Lowers the calling thread's scheduling priority to the minimum, so a long render leaves the
+/// rest of the system responsive; returns whether it was lowered, which is never the case off
+/// Unix or without the `nice` feature.
+#[cfg(not(all(feature = "nice", unix)))]
+pub fn lower_priority() -> bool {
+    false
+}
+
+/// Gives up the rest of the thread's time slice once every `interval` units of work (e.g.
+/// tiles), so a background render doesn't hog the CPU even where priorities can't be lowered
+pub struct Yielder {
+    interval: usize,
+    count: usize,
+}
+
+impl Yielder {
+    pub fn new(interval: usize) -> Self {
+        Self { interval, count: 0 }
+    }
+
+    /// records a unit of work, yielding if it's the `interval`th; returns whether it yielded
+    pub fn tick(&mut self) -> bool {
+        self.count += 1;
+        if self.count >= self.interval {
+            self.count = 0;
+            std::thread::yield_now();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vek::{Extent2, Rect};
+
+    use super::{partition_tiles, Yielder};
+    use crate::img::Tiling;
+
+    #[test]
+    fn partition_tiles_test() {
+        let tiles = Tiling::Scanline(1).tiles(Extent2::new(10, 10));
+        let groups = partition_tiles(tiles, 3);
+        assert_eq!(
+            groups.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 3, 4]
+        );
+        // each group is a contiguous band of the image
+        assert_eq!(groups[1][0], Rect::new(0, 3, 10, 1));
+        assert_eq!(groups[1][2], Rect::new(0, 5, 10, 1));
+    }
+
+    #[test]
+    fn yielder_test() {
+        let mut yielder = Yielder::new(3);
+        let ticks: Vec<bool> = (0..6).map(|_| yielder.tick()).collect();
+        assert_eq!(ticks, vec![false, false, true, false, false, true]);
+    }
+}
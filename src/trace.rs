@@ -0,0 +1,248 @@
+//! Full traces of the primary ray through a single pixel, for debugging estimators: every march
+//! step against every geometry plus the inputs to shading the hit, exportable as JSON or as an
+//! OBJ polyline to view alongside the scene.
+use std::iter::Sum;
+
+use num::Float;
+use vek::{Extent2, Vec3};
+
+use crate::camera::Viewport;
+use crate::distance::MarchStep;
+use crate::light::Material;
+use crate::render::{self, RenderGeometry};
+
+/// The march of the ray against one geometry
+pub struct GeometryTrace<T> {
+    /// index of the geometry in the scene
+    pub geometry: usize,
+    pub steps: Vec<MarchStep<T>>,
+    pub hit: bool,
+}
+
+/// What the pixel's color is computed from
+pub struct ShadingInputs<T: Default> {
+    /// index of the geometry hit
+    pub geometry: usize,
+    pub pos: Vec3<T>,
+    pub normal: Vec3<T>,
+    pub material: Material<T>,
+}
+
+pub struct PixelTrace<T: Default> {
+    pub x: usize,
+    pub y: usize,
+    pub origin: Vec3<T>,
+    pub direction: Vec3<T>,
+    pub geometries: Vec<GeometryTrace<T>>,
+    /// `None` if the ray missed everything
+    pub shading: Option<ShadingInputs<T>>,
+}
+
+/// Traces the primary ray through pixel (`x`, `y`) of an image of size `size`.
+pub fn trace_pixel<T>(
+    geometry: &[RenderGeometry<T>],
+    view: &Viewport<T>,
+    size: Extent2<usize>,
+    x: usize,
+    y: usize,
+) -> PixelTrace<T>
+where
+    T: Float + Sum + Default,
+{
+    let (origin, direction) = view.ray(render::pixel_location(x, y, size));
+    let geometries: Vec<GeometryTrace<T>> = geometry
+        .iter()
+        .enumerate()
+        .map(|(i, g)| {
+            let steps = g.geom.march_trace(origin, direction);
+            let hit = steps.last().into_iter().any(|s| s.dist <= g.geom.epsilon);
+            GeometryTrace {
+                geometry: i,
+                steps,
+                hit,
+            }
+        })
+        .collect();
+
+    // the nearest hit is the one that's shaded, as in `render::nearest`
+    let shading = geometries
+        .iter()
+        .filter(|t| t.hit)
+        .filter_map(|t| t.steps.last().map(|s| (t.geometry, s)))
+        .fold(
+            None,
+            |nearest: Option<(usize, &MarchStep<T>)>, (i, s)| match nearest {
+                Some((n, n_step)) if n_step.total <= s.total => Some((n, n_step)),
+                _ => Some((i, s)),
+            },
+        )
+        .map(|(i, s)| ShadingInputs {
+            geometry: i,
+            pos: s.pos,
+            normal: geometry[i].geom.normal(s.pos),
+            material: geometry[i].material_at(s.pos),
+        });
+
+    PixelTrace {
+        x,
+        y,
+        origin,
+        direction,
+        geometries,
+        shading,
+    }
+}
+
+/// `x` as a JSON number; JSON has no infinities or NaN, so those are null
+fn json_num<T: Float>(x: T) -> String {
+    match x.to_f64() {
+        Some(x) if x.is_finite() => x.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+fn json_vec<T: Float>(v: Vec3<T>) -> String {
+    format!("[{},{},{}]", json_num(v.x), json_num(v.y), json_num(v.z))
+}
+
+impl<T> PixelTrace<T>
+where
+    T: Float + Default,
+{
+    pub fn to_json(&self) -> String {
+        let geometries: Vec<String> = self
+            .geometries
+            .iter()
+            .map(|g| {
+                let steps: Vec<String> = g
+                    .steps
+                    .iter()
+                    .map(|s| {
+                        format!(
+                            r#"{{"pos":{},"total":{},"dist":{},"step":{},"floored":{}}}"#,
+                            json_vec(s.pos),
+                            json_num(s.total),
+                            json_num(s.dist),
+                            json_num(s.step),
+                            s.floored
+                        )
+                    })
+                    .collect();
+                format!(
+                    r#"{{"geometry":{},"hit":{},"steps":[{}]}}"#,
+                    g.geometry,
+                    g.hit,
+                    steps.join(",")
+                )
+            })
+            .collect();
+        let shading = match &self.shading {
+            Some(s) => format!(
+                r#"{{"geometry":{},"pos":{},"normal":{},"material":{{"specular":{},"diffuse":{},"ambient":{},"shininess":{}}}}}"#,
+                s.geometry,
+                json_vec(s.pos),
+                json_vec(s.normal),
+                json_num(s.material.specular),
+                json_num(s.material.diffuse),
+                json_num(s.material.ambient),
+                json_num(s.material.shininess)
+            ),
+            None => "null".to_string(),
+        };
+        format!(
+            r#"{{"x":{},"y":{},"origin":{},"direction":{},"geometries":[{}],"shading":{}}}"#,
+            self.x,
+            self.y,
+            json_vec(self.origin),
+            json_vec(self.direction),
+            geometries.join(","),
+            shading
+        )
+    }
+
+    /// The march against each geometry as an OBJ polyline through its sample points, one object
+    /// per geometry.
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        // OBJ vertex indices are global and 1-based
+        let mut next = 1;
+        for g in &self.geometries {
+            obj.push_str(&format!("o geometry{}\n", g.geometry));
+            for s in &g.steps {
+                obj.push_str(&format!(
+                    "v {} {} {}\n",
+                    s.pos.x.to_f64().unwrap_or(0.0),
+                    s.pos.y.to_f64().unwrap_or(0.0),
+                    s.pos.z.to_f64().unwrap_or(0.0)
+                ));
+            }
+            if g.steps.len() > 1 {
+                let indices: Vec<String> = (next..next + g.steps.len())
+                    .map(|i| i.to_string())
+                    .collect();
+                obj.push_str(&format!("l {}\n", indices.join(" ")));
+            }
+            next += g.steps.len();
+        }
+        obj
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vek::{Extent2, Ray, Vec3};
+
+    use super::trace_pixel;
+    use crate::camera::Viewport;
+    use crate::distance::{Geometry, NormalMethod, Sphere};
+    use crate::light::Material;
+    use crate::render::RenderGeometry;
+
+    #[test]
+    fn trace_pixel_test() {
+        let geometry = vec![RenderGeometry {
+            mat: Material {
+                specular: 0.0,
+                diffuse: 0.5,
+                ambient: 0.0,
+                shininess: 0.0,
+            },
+            geom: Geometry {
+                max_steps: 64,
+                max_evaluations: None,
+                epsilon: 1e-6,
+                epsilon_scale: None,
+                cutoff: 100.0,
+                sample_size: 1e-6,
+                normal_method: NormalMethod::Central,
+                relaxation: None,
+                step_scale: None,
+                min_step: None,
+                refine_steps: None,
+                zoom_quality: None,
+                lod: None,
+                bounds: None,
+                de: Sphere::new(Vec3::zero(), 1.0).into(),
+            },
+            checker: None,
+            instance_gradient: None,
+            orbit_trap: None,
+        }];
+        let view = Viewport {
+            cam: Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::unit_z()),
+            right: Vec3::unit_x(),
+            size: Extent2::new(1.0, 1.0),
+            focal_len: 1.0,
+            focus_dist: None,
+        };
+        // the middle pixel of a 3x3 image looks straight at the sphere
+        let trace = trace_pixel(&geometry, &view, Extent2::new(3, 3), 1, 1);
+        assert!(trace.geometries[0].hit);
+        let shading = trace.shading.as_ref().unwrap();
+        assert!((shading.pos - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-5);
+        assert!(trace.to_json().contains(r#""hit":true"#));
+        let obj = trace.to_obj();
+        assert!(obj.starts_with("o geometry0\nv 0 0 -5\n"));
+        assert!(obj.contains("\nl 1 2"));
+    }
+}
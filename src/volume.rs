@@ -0,0 +1,350 @@
+//! Signed distance volumes generated by other programs, loaded from NRRD files or raw arrays of
+//! floats, to be ray marched as `distance::SdfGrid`s.
+use std::fmt;
+use std::fs;
+use std::iter::Sum;
+use std::path::Path;
+use std::sync::Arc;
+
+use num::Float;
+use vek::Vec3;
+
+use crate::distance::SdfGrid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VolumeError {
+    /// the file couldn't be read
+    Io(String),
+    /// an NRRD header field that's missing, malformed, or unsupported
+    Header(String),
+    /// a raw file without its dimensions given
+    MissingSamples,
+    /// the data isn't as long as the dimensions say, in bytes
+    Length { expected: usize, found: usize },
+}
+
+impl fmt::Display for VolumeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VolumeError::Io(e) => write!(f, "couldn't read volume: {}", e),
+            VolumeError::Header(field) => write!(f, "unsupported or invalid NRRD field: {}", field),
+            VolumeError::MissingSamples => {
+                write!(f, "raw volumes need their dimensions given as `samples`")
+            }
+            VolumeError::Length { expected, found } => write!(
+                f,
+                "volume data should be {} bytes, but is {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+/// A grid of signed distances, with its placement if the file gave it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    pub samples: Vec3<usize>,
+    pub spacing: Option<Vec3<f64>>,
+    pub origin: Option<Vec3<f64>>,
+    /// in x-major order
+    pub distances: Vec<f64>,
+}
+
+/// How the samples of a volume are stored
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Sample {
+    F32,
+    F64,
+}
+
+/// how many samples a grid `samples` wide holds, if it holds any and they can be counted
+fn sample_count(samples: Vec3<usize>) -> Result<usize, VolumeError> {
+    samples
+        .iter()
+        .try_fold(1usize, |count, &s| count.checked_mul(s))
+        .filter(|&count| count > 0)
+        .ok_or_else(|| VolumeError::Header("sizes".to_string()))
+}
+
+/// the samples of `data`, `kind` values in little- or big-endian order
+fn decode(
+    data: &[u8],
+    kind: Sample,
+    big_endian: bool,
+    count: usize,
+) -> Result<Vec<f64>, VolumeError> {
+    let width = match kind {
+        Sample::F32 => 4,
+        Sample::F64 => 8,
+    };
+    let expected = count
+        .checked_mul(width)
+        .ok_or(VolumeError::Header("sizes".to_string()))?;
+    if data.len() != expected {
+        return Err(VolumeError::Length {
+            expected,
+            found: data.len(),
+        });
+    }
+    Ok(data
+        .chunks(width)
+        .map(|bytes| match (kind, big_endian) {
+            (Sample::F32, false) => {
+                f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            (Sample::F32, true) => {
+                f64::from(f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            (Sample::F64, _) => {
+                let mut b = [0; 8];
+                b.copy_from_slice(bytes);
+                if big_endian {
+                    f64::from_be_bytes(b)
+                } else {
+                    f64::from_le_bytes(b)
+                }
+            }
+        })
+        .collect())
+}
+
+/// the numbers in a vector like `(1,0,0)`
+fn parse_vector(s: &str) -> Option<Vec3<f64>> {
+    let mut coords = s
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|c| c.trim().parse().ok());
+    Some(Vec3::new(coords.next()??, coords.next()??, coords.next()??))
+}
+
+impl Volume {
+    /// Reads a volume from an NRRD file, or, for any other extension, a raw array of
+    /// little-endian 32-bit floats with dimensions `samples`
+    pub fn load(path: &Path, samples: Option<Vec3<usize>>) -> Result<Self, VolumeError> {
+        let bytes = fs::read(path).map_err(|e| VolumeError::Io(e.to_string()))?;
+        let nrrd = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("nrrd"));
+        if nrrd {
+            Self::parse_nrrd(&bytes)
+        } else {
+            let samples = samples.ok_or(VolumeError::MissingSamples)?;
+            Ok(Volume {
+                samples,
+                spacing: None,
+                origin: None,
+                distances: decode(&bytes, Sample::F32, false, sample_count(samples)?)?,
+            })
+        }
+    }
+
+    /// Parses a three-dimensional NRRD file with its data attached, raw and uncompressed, as
+    /// `float` or `double` samples. `spacings`, or axis-aligned `space directions`, and
+    /// `space origin` place the grid.
+    pub fn parse_nrrd(bytes: &[u8]) -> Result<Self, VolumeError> {
+        let header_err = |field: &str| VolumeError::Header(field.to_string());
+        if !bytes.starts_with(b"NRRD") {
+            return Err(header_err("magic"));
+        }
+        // the header ends at the first blank line
+        let end = bytes
+            .windows(2)
+            .position(|w| w == b"\n\n")
+            .ok_or_else(|| header_err("data"))?;
+        let header = String::from_utf8_lossy(&bytes[..end]);
+
+        let mut kind = None;
+        let mut sizes = None;
+        let mut big_endian = false;
+        let mut spacing = None;
+        let mut origin = None;
+        // the magic line, then `field: value` lines and `#` comments
+        for line in header.lines().skip(1).filter(|l| !l.starts_with('#')) {
+            let mut parts = line.splitn(2, ':');
+            let (field, value) = match (parts.next(), parts.next()) {
+                // `key:=value` pairs are metadata
+                (Some(field), Some(value)) if !value.starts_with('=') => {
+                    (field.trim(), value.trim())
+                }
+                _ => continue,
+            };
+            match field {
+                "type" => {
+                    kind = Some(match value {
+                        "float" => Sample::F32,
+                        "double" => Sample::F64,
+                        _ => return Err(header_err(field)),
+                    })
+                }
+                "dimension" if value != "3" => return Err(header_err(field)),
+                "encoding" if value != "raw" => return Err(header_err(field)),
+                "data file" | "datafile" => return Err(header_err(field)),
+                "endian" => big_endian = value == "big",
+                "sizes" => {
+                    let s: Vec<usize> = value
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| header_err(field))?;
+                    if s.len() != 3 {
+                        return Err(header_err(field));
+                    }
+                    sizes = Some(Vec3::new(s[0], s[1], s[2]));
+                }
+                "spacings" => {
+                    spacing = Some(
+                        parse_vector(&value.split_whitespace().collect::<Vec<_>>().join(","))
+                            .ok_or_else(|| header_err(field))?,
+                    );
+                }
+                "space directions" => {
+                    // each axis's step, which must lie along that axis
+                    let steps: Vec<Vec3<f64>> = value
+                        .split_whitespace()
+                        .map(parse_vector)
+                        .collect::<Option<_>>()
+                        .filter(|steps: &Vec<_>| steps.len() == 3)
+                        .ok_or_else(|| header_err(field))?;
+                    let diagonal = Vec3::new(steps[0].x, steps[1].y, steps[2].z);
+                    let off_diagonal = steps.iter().map(|s| s.magnitude()).sum::<f64>()
+                        - diagonal.map(f64::abs).sum();
+                    if off_diagonal.abs() > 1e-9 {
+                        return Err(header_err(field));
+                    }
+                    spacing = Some(diagonal);
+                }
+                "space origin" => {
+                    origin = Some(parse_vector(value).ok_or_else(|| header_err(field))?);
+                }
+                _ => {}
+            }
+        }
+
+        let samples = sizes.ok_or_else(|| header_err("sizes"))?;
+        let kind = kind.ok_or_else(|| header_err("type"))?;
+        Ok(Volume {
+            samples,
+            spacing,
+            origin,
+            distances: decode(&bytes[end + 2..], kind, big_endian, sample_count(samples)?)?,
+        })
+    }
+
+    /// The volume as an estimator. `spacing` and `origin` override the file's; without either,
+    /// samples are 1 unit apart and the grid is centered on the origin.
+    pub fn grid<T>(&self, spacing: Option<Vec3<f64>>, origin: Option<Vec3<f64>>) -> SdfGrid<T>
+    where
+        T: Float + Sum,
+    {
+        let spacing = spacing.or(self.spacing).unwrap_or_else(Vec3::one);
+        let origin = origin
+            .or(self.origin)
+            .unwrap_or_else(|| -self.samples.map(|s| s.max(1) as f64 - 1.0) * spacing / 2.0);
+        SdfGrid::new(
+            origin.map(|c| T::from(c).unwrap()),
+            spacing.map(|c| T::from(c).unwrap()),
+            self.samples,
+            Arc::new(
+                self.distances
+                    .iter()
+                    .map(|&d| T::from(d).unwrap())
+                    .collect(),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use vek::Vec3;
+
+    use super::{Volume, VolumeError};
+    use crate::distance::Estimator;
+
+    /// a sphere of radius 1 sampled on a 5-sample cube from -2 to 2
+    fn sphere() -> Vec<f64> {
+        let mut distances = Vec::new();
+        for z in 0..5 {
+            for y in 0..5 {
+                for x in 0..5 {
+                    let p = Vec3::new(x, y, z).map(|c| f64::from(c) - 2.0);
+                    distances.push(p.magnitude() - 1.0);
+                }
+            }
+        }
+        distances
+    }
+
+    #[test]
+    fn nrrd_test() {
+        let mut nrrd = b"NRRD0004\n# a sphere\ntype: double\ndimension: 3\nsizes: 5 5 5\n\
+            endian: big\nencoding: raw\nspace directions: (1,0,0) (0,1,0) (0,0,1)\n\
+            space origin: (-2,-2,-2)\nunit:=mm\n\n"
+            .to_vec();
+        for d in sphere() {
+            nrrd.extend_from_slice(&d.to_be_bytes());
+        }
+        let volume = Volume::parse_nrrd(&nrrd).unwrap();
+        assert_eq!(volume.samples, Vec3::broadcast(5));
+        assert_eq!(volume.spacing, Some(Vec3::one()));
+        assert_eq!(volume.origin, Some(Vec3::broadcast(-2.0)));
+        assert_eq!(volume.distances, sphere());
+
+        let grid = volume.grid::<f64>(None, None);
+        assert_eq!(grid.estimate(Vec3::new(1.0, 0.0, 0.0)), 0.0);
+        // halfway between samples at distances 0 and 1
+        assert_eq!(grid.estimate(Vec3::new(1.5, 0.0, 0.0)), 0.5);
+        // placed by the scene instead
+        let moved = volume.grid::<f64>(None, Some(Vec3::zero()));
+        assert_eq!(moved.estimate(Vec3::broadcast(2.0)), -1.0);
+
+        nrrd.truncate(nrrd.len() - 1);
+        assert_eq!(
+            Volume::parse_nrrd(&nrrd),
+            Err(VolumeError::Length {
+                expected: 1000,
+                found: 999
+            })
+        );
+        let sized = |sizes: &str| {
+            let header = format!("NRRD0004\ntype: double\nsizes: {}\n\n", sizes);
+            Volume::parse_nrrd(header.as_bytes())
+        };
+        assert_eq!(
+            sized("5 0 5"),
+            Err(VolumeError::Header("sizes".to_string()))
+        );
+        assert_eq!(
+            sized(&format!("{} {} 2", usize::MAX, usize::MAX)),
+            Err(VolumeError::Header("sizes".to_string()))
+        );
+        assert_eq!(
+            Volume::parse_nrrd(b"NRRD0004\ntype: short\n\n"),
+            Err(VolumeError::Header("type".to_string()))
+        );
+    }
+
+    #[test]
+    fn raw_test() {
+        let path = std::env::temp_dir().join("ray-marcher-raw-volume-test.raw");
+        let bytes: Vec<u8> = sphere()
+            .iter()
+            .flat_map(|&d| (d as f32).to_le_bytes().to_vec())
+            .collect();
+        std::fs::write(&path, bytes).unwrap();
+        assert_eq!(Volume::load(&path, None), Err(VolumeError::MissingSamples));
+        assert_eq!(
+            Volume::load(&path, Some(Vec3::new(5, 0, 5))),
+            Err(VolumeError::Header("sizes".to_string()))
+        );
+        let volume = Volume::load(&path, Some(Vec3::broadcast(5))).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // centered on the origin
+        let grid = volume.grid::<f64>(Some(Vec3::broadcast(0.5)), None);
+        assert_eq!(grid.estimate(Vec3::zero()), -1.0);
+    }
+}